@@ -200,9 +200,158 @@ fn split_ola_asm_pieces(
     }
 }
 
+pub(crate) const PSEUDO_MNEMONICS: [&str; 7] = ["sub", "neg", "push", "pop", "lt", "le", "shl"];
+
+// expand a pseudo-instruction into canonical instructions, each keeping the
+// pseudo line as its asm so debug info points back to what was written
+fn expand_pseudo_instruction(asm_line: &str) -> Option<Result<Vec<OlaAsmInstruction>, String>> {
+    let instruction_without_comment = &asm_line[0..asm_line.find(';').unwrap_or(asm_line.len())];
+    let pieces: Vec<&str> = instruction_without_comment.split_whitespace().collect();
+    let mnemonic = *pieces.first()?;
+    if !PSEUDO_MNEMONICS.contains(&mnemonic) {
+        return None;
+    }
+    Some(
+        expand_pseudo_pieces(mnemonic, &pieces[1..]).and_then(|lines| {
+            lines
+                .iter()
+                .map(|line| {
+                    OlaAsmInstruction::from_str(line).map(|mut instruction| {
+                        instruction.asm = asm_line.to_string();
+                        instruction
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()
+                .map_err(|err| format!("expand pseudo instruction {} error: {}", asm_line, err))
+        }),
+    )
+}
+
+fn expand_pseudo_pieces(mnemonic: &str, ops: &[&str]) -> Result<Vec<String>, String> {
+    let expected = match mnemonic {
+        "push" | "pop" => 1,
+        "neg" => 2,
+        _ => 3,
+    };
+    if ops.len() != expected {
+        return Err(format!("invalid operand size for {}", mnemonic));
+    }
+    let is_reg = |op: &str| {
+        matches!(
+            OlaAsmOperand::from_str(op),
+            Ok(OlaAsmOperand::RegisterOperand { .. })
+        )
+    };
+    let is_imm = |op: &str| {
+        matches!(
+            OlaAsmOperand::from_str(op),
+            Ok(OlaAsmOperand::ImmediateOperand { .. })
+        )
+    };
+    let require_reg = |op: &str| {
+        if is_reg(op) {
+            Ok(())
+        } else {
+            Err(format!("{} operand {} must be a register", mnemonic, op))
+        }
+    };
+
+    match mnemonic {
+        // dst = op0 + (-1) * op1, restoring op1 when it has to be clobbered
+        "sub" => {
+            let (dst, op0, op1) = (ops[0], ops[1], ops[2]);
+            require_reg(dst)?;
+            require_reg(op0)?;
+            if is_imm(op1) {
+                return Ok(vec![format!("add {} {} {}", dst, op0, negate_decimal(op1))]);
+            }
+            require_reg(op1)?;
+            if dst != op0 {
+                Ok(vec![
+                    format!("mul {} {} -1", dst, op1),
+                    format!("add {} {} {}", dst, op0, dst),
+                ])
+            } else if dst != op1 {
+                Ok(vec![
+                    format!("mul {} {} -1", op1, op1),
+                    format!("add {} {} {}", dst, op0, op1),
+                    format!("mul {} {} -1", op1, op1),
+                ])
+            } else {
+                Ok(vec![format!("mov {} 0", dst)])
+            }
+        }
+        "neg" => {
+            let (dst, src) = (ops[0], ops[1]);
+            require_reg(dst)?;
+            if is_imm(src) {
+                return Ok(vec![format!("mov {} {}", dst, negate_decimal(src))]);
+            }
+            require_reg(src)?;
+            Ok(vec![format!("mul {} {} -1", dst, src)])
+        }
+        // r9 points past the top of the stack, like a function frame
+        "push" => {
+            require_reg(ops[0])?;
+            Ok(vec![
+                "add r9 r9 1".to_string(),
+                format!("mstore [r9,-1] {}", ops[0]),
+            ])
+        }
+        "pop" => {
+            require_reg(ops[0])?;
+            Ok(vec![
+                format!("mload {} [r9,-1]", ops[0]),
+                "add r9 r9 -1".to_string(),
+            ])
+        }
+        // op0 < op1 is !(op0 >= op1)
+        "lt" => {
+            let (dst, op0, op1) = (ops[0], ops[1], ops[2]);
+            require_reg(dst)?;
+            require_reg(op0)?;
+            Ok(vec![
+                format!("gte {} {} {}", dst, op0, op1),
+                format!("eq {} {} 0", dst, dst),
+            ])
+        }
+        // op0 <= op1 is op1 >= op0
+        "le" => {
+            let (dst, op0, op1) = (ops[0], ops[1], ops[2]);
+            require_reg(dst)?;
+            require_reg(op1)?;
+            Ok(vec![format!("gte {} {} {}", dst, op1, op0)])
+        }
+        "shl" => {
+            let (dst, src, bits) = (ops[0], ops[1], ops[2]);
+            require_reg(dst)?;
+            require_reg(src)?;
+            let bits = bits
+                .parse::<u32>()
+                .ok()
+                .filter(|bits| *bits < 64)
+                .ok_or(format!("shl shift {} must be an immediate in 0..64", bits))?;
+            Ok(vec![format!("mul {} {} {}", dst, src, 1u64 << bits)])
+        }
+        _ => Err(format!("unknown pseudo instruction {}", mnemonic)),
+    }
+}
+
+fn negate_decimal(value: &str) -> String {
+    let digits = value.trim_start_matches(['+', '-']);
+    if digits.trim_start_matches('0').is_empty() {
+        "0".to_string()
+    } else if value.starts_with('-') {
+        digits.to_string()
+    } else {
+        format!("-{}", digits)
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub(crate) enum AsmRow {
     Instruction(OlaAsmInstruction),
+    PseudoInstruction(Vec<OlaAsmInstruction>),
     LabelCall(String),
     LabelJmp(String),
     LabelProphet(String),
@@ -214,6 +363,10 @@ impl Display for AsmRow {
             AsmRow::Instruction(instruction) => {
                 write!(f, "Instruction({})", instruction)
             }
+            AsmRow::PseudoInstruction(instructions) => {
+                let expanded: Vec<String> = instructions.iter().map(|i| i.to_string()).collect();
+                write!(f, "PseudoInstruction([{}])", expanded.join(", "))
+            }
             AsmRow::LabelCall(value) => {
                 write!(f, "LabelCall({})", value)
             }
@@ -257,6 +410,10 @@ impl FromStr for AsmRow {
             return Ok(AsmRow::LabelProphet(label.to_string()));
         }
 
+        if let Some(expanded) = expand_pseudo_instruction(s) {
+            return expanded.map(AsmRow::PseudoInstruction);
+        }
+
         let instruction_res = OlaAsmInstruction::from_str(s);
         return if instruction_res.is_ok() {
             Ok(AsmRow::Instruction(instruction_res.unwrap()))
@@ -268,7 +425,7 @@ impl FromStr for AsmRow {
 
 #[cfg(test)]
 mod tests {
-    use crate::asm::{split_ola_asm_pieces, AsmRow, OlaAsmInstruction};
+    use crate::asm::{expand_pseudo_instruction, split_ola_asm_pieces, AsmRow, OlaAsmInstruction};
 
     use crate::operands::OlaAsmOperand;
     use core::vm::hardware::OlaRegister;
@@ -382,4 +539,43 @@ mod tests {
             AsmRow::LabelProphet(String::from(".PROPHET1_3"))
         );
     }
+
+    #[test]
+    fn test_pseudo_instruction_expand() {
+        let expand = |line: &str| -> Vec<String> {
+            let expanded = expand_pseudo_instruction(line).unwrap().unwrap();
+            assert!(expanded.iter().all(|i| i.asm == line));
+            expanded.iter().map(|i| i.opcode.token()).collect()
+        };
+        assert_eq!(expand("sub r0 r1 5"), vec!["add"]);
+        assert_eq!(expand("sub r0 r1 r2"), vec!["mul", "add"]);
+        assert_eq!(expand("sub r0 r0 r2"), vec!["mul", "add", "mul"]);
+        assert_eq!(expand("sub r0 r0 r0"), vec!["mov"]);
+        assert_eq!(expand("neg r0 r1"), vec!["mul"]);
+        assert_eq!(expand("push r3"), vec!["add", "mstore"]);
+        assert_eq!(expand("pop r3"), vec!["mload", "add"]);
+        assert_eq!(expand("lt r0 r1 r2"), vec!["gte", "eq"]);
+        assert_eq!(expand("le r0 3 r2"), vec!["gte"]);
+        assert_eq!(expand("shl r0 r1 4"), vec!["mul"]);
+
+        let sub_imm = expand_pseudo_instruction("sub r0 r1 5").unwrap().unwrap();
+        assert_eq!(
+            sub_imm[0].op1,
+            Some(OlaAsmOperand::ImmediateOperand {
+                value: ImmediateValue::from_str("-5").unwrap()
+            })
+        );
+        let shl = expand_pseudo_instruction("shl r0 r1 4").unwrap().unwrap();
+        assert_eq!(
+            shl[0].op1,
+            Some(OlaAsmOperand::ImmediateOperand {
+                value: ImmediateValue::from_str("16").unwrap()
+            })
+        );
+
+        assert!(expand_pseudo_instruction("add r0 r1 r2").is_none());
+        assert!(expand_pseudo_instruction("push 5").unwrap().is_err());
+        assert!(expand_pseudo_instruction("shl r0 r1 64").unwrap().is_err());
+        assert!(expand_pseudo_instruction("sub r0 r1").unwrap().is_err());
+    }
 }
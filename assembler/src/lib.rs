@@ -1,7 +1,8 @@
 mod asm;
 pub mod encoder;
+mod macros;
 pub mod operands;
 mod relocate;
 mod test_binary_program_print;
-mod test_data_generator;
+pub mod test_data_generator;
 mod tests;
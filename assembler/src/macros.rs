@@ -0,0 +1,213 @@
+use crate::asm::PSEUDO_MNEMONICS;
+use crate::relocate::line_pre_process;
+use core::vm::opcodes::OlaOpcode;
+use regex::Regex;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const MAX_MACRO_DEPTH: usize = 16;
+
+/// A source line after macro expansion. `origin` is the macro invocation a
+/// line was expanded from, used as its asm in debug info.
+#[derive(Debug, Clone)]
+pub(crate) struct AsmLine {
+    pub(crate) line_num: usize,
+    pub(crate) text: String,
+    pub(crate) origin: Option<String>,
+}
+
+// .macro name p0, p1
+//   add \p0 \p0 \p1
+// .endm
+#[derive(Debug, Clone)]
+struct AsmMacro {
+    params: Vec<String>,
+    body: Vec<String>,
+}
+
+impl AsmMacro {
+    fn substitute(&self, args: &[&str]) -> Result<Vec<String>, String> {
+        let regex_param = Regex::new(r"\\(?P<param>\w+)").unwrap();
+        let bindings: HashMap<&str, &str> = self
+            .params
+            .iter()
+            .map(|param| param.as_str())
+            .zip(args.iter().cloned())
+            .collect();
+        self.body
+            .iter()
+            .map(|line| {
+                let mut unknown: Option<String> = None;
+                let substituted = regex_param.replace_all(line, |caps: &regex::Captures| {
+                    let param = caps.name("param").unwrap().as_str();
+                    match bindings.get(param) {
+                        Some(arg) => arg.to_string(),
+                        None => {
+                            unknown = Some(param.to_string());
+                            String::new()
+                        }
+                    }
+                });
+                match unknown {
+                    Some(param) => Err(format!("unknown macro parameter \\{}", param)),
+                    None => Ok(substituted.to_string()),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Collects `.macro` definitions from the program and expands every
+/// invocation, returning comment-free, trimmed, non-empty lines.
+pub(crate) fn expand_macros(program: &str) -> Result<Vec<AsmLine>, String> {
+    let mut macros: HashMap<String, AsmMacro> = HashMap::new();
+    let mut lines: Vec<(usize, String)> = vec![];
+    let mut defining: Option<(usize, String, AsmMacro)> = None;
+
+    for (line_num, line) in program.lines().enumerate() {
+        let processed_line = line_pre_process(line);
+        if processed_line.is_empty() {
+            continue;
+        }
+        let mut pieces = processed_line.split_whitespace();
+        let head = pieces.next().unwrap();
+        match (head, defining.as_mut()) {
+            (".macro", Some(_)) => {
+                return Err(format!(
+                    "line {}: {} ==> nested macro definition",
+                    line_num, line
+                ));
+            }
+            (".macro", None) => {
+                let header: Vec<&str> = pieces
+                    .flat_map(|piece| piece.split(','))
+                    .filter(|piece| !piece.is_empty())
+                    .collect();
+                let (name, params) = header.split_first().ok_or(format!(
+                    "line {}: {} ==> macro name missing",
+                    line_num, line
+                ))?;
+                check_macro_header(name, params, &macros)
+                    .map_err(|err| format!("line {}: {} ==> {}", line_num, line, err))?;
+                defining = Some((
+                    line_num,
+                    name.to_string(),
+                    AsmMacro {
+                        params: params.iter().map(|param| param.to_string()).collect(),
+                        body: vec![],
+                    },
+                ));
+            }
+            (".endm", Some(_)) => {
+                let (_, name, asm_macro) = defining.take().unwrap();
+                macros.insert(name, asm_macro);
+            }
+            (".endm", None) => {
+                return Err(format!(
+                    "line {}: {} ==> .endm without .macro",
+                    line_num, line
+                ));
+            }
+            (_, Some((_, _, asm_macro))) => {
+                if processed_line.ends_with(':') {
+                    return Err(format!(
+                        "line {}: {} ==> labels are not allowed in macro body",
+                        line_num, line
+                    ));
+                }
+                asm_macro.body.push(processed_line.to_string());
+            }
+            (_, None) => lines.push((line_num, processed_line.to_string())),
+        }
+    }
+    if let Some((line_num, name, _)) = defining {
+        return Err(format!("line {}: macro {} has no .endm", line_num, name));
+    }
+
+    let mut expanded: Vec<AsmLine> = vec![];
+    for (line_num, text) in lines {
+        if is_invocation(&text, &macros) {
+            let mut body: Vec<String> = vec![];
+            expand_invocation(&text, &macros, 0, &mut body)
+                .map_err(|err| format!("line {}: {} ==> {}", line_num, text, err))?;
+            expanded.extend(body.into_iter().map(|line| AsmLine {
+                line_num,
+                text: line,
+                origin: Some(text.clone()),
+            }));
+        } else {
+            expanded.push(AsmLine {
+                line_num,
+                text,
+                origin: None,
+            });
+        }
+    }
+    Ok(expanded)
+}
+
+fn check_macro_header(
+    name: &str,
+    params: &[&str],
+    macros: &HashMap<String, AsmMacro>,
+) -> Result<(), String> {
+    let regex_word = Regex::new(r"^\w+$").unwrap();
+    if !regex_word.is_match(name) {
+        return Err(format!("invalid macro name {}", name));
+    }
+    if OlaOpcode::from_str(name).is_ok() || PSEUDO_MNEMONICS.contains(&name) {
+        return Err(format!("macro {} shadows an instruction", name));
+    }
+    if macros.contains_key(name) {
+        return Err(format!("macro {} defined twice", name));
+    }
+    for (i, param) in params.iter().enumerate() {
+        if !regex_word.is_match(param) {
+            return Err(format!("invalid macro parameter {}", param));
+        }
+        if params[..i].contains(param) {
+            return Err(format!("macro parameter {} declared twice", param));
+        }
+    }
+    Ok(())
+}
+
+fn is_invocation(line: &str, macros: &HashMap<String, AsmMacro>) -> bool {
+    line.split_whitespace()
+        .next()
+        .map_or(false, |head| macros.contains_key(head))
+}
+
+fn expand_invocation(
+    line: &str,
+    macros: &HashMap<String, AsmMacro>,
+    depth: usize,
+    expanded: &mut Vec<String>,
+) -> Result<(), String> {
+    if depth >= MAX_MACRO_DEPTH {
+        return Err(format!(
+            "macro expansion deeper than {}, recursive macro?",
+            MAX_MACRO_DEPTH
+        ));
+    }
+    let mut pieces = line.split_whitespace();
+    let name = pieces.next().unwrap();
+    let args: Vec<&str> = pieces.collect();
+    let asm_macro = &macros[name];
+    if args.len() != asm_macro.params.len() {
+        return Err(format!(
+            "macro {} expects {} arguments, got {}",
+            name,
+            asm_macro.params.len(),
+            args.len()
+        ));
+    }
+    for body_line in asm_macro.substitute(&args)? {
+        if is_invocation(&body_line, macros) {
+            expand_invocation(&body_line, macros, depth + 1, expanded)?;
+        } else {
+            expanded.push(body_line);
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use crate::asm::{AsmRow, OlaAsmInstruction};
+use crate::macros::{expand_macros, AsmLine};
 use core::program::binary_program::{OlaProphetInput, OlaProphetOutput};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
@@ -14,57 +15,49 @@ pub(crate) struct AsmBundle {
 #[derive(Debug, Clone)]
 struct AsmScope {
     label: String,
-    lines: Vec<String>,
+    lines: Vec<AsmLine>,
 }
 
 impl AsmBundle {
     fn generate_sorted_asm_scopes(&self) -> Result<Vec<AsmScope>, String> {
-        let mut lines = self.program.lines();
+        let lines = expand_macros(self.program.as_str())?;
         let mut scopes: Vec<AsmScope> = vec![];
         let mut current_scope_label: String = String::new();
-        let mut current_scope_lines: Vec<String> = vec![];
-        let mut line_num = 0;
-        loop {
-            if let Some(line) = lines.next() {
-                let processed_line = line_pre_process(line);
-                if processed_line.is_empty() {
-                    continue;
-                }
-
-                let row_res = AsmRow::from_str(processed_line);
-                if row_res.is_err() {
-                    let err_msg = row_res.err().unwrap();
-                    return Err(format!("line {}: {} ==> {}", line_num, line, err_msg));
-                }
-                let row = row_res.unwrap();
-                match row {
-                    AsmRow::LabelCall(label) => {
-                        if !current_scope_lines.is_empty() {
-                            let scope = AsmScope {
-                                label: current_scope_label.clone(),
-                                lines: current_scope_lines.clone(),
-                            };
-                            scopes.push(scope);
-                        }
-                        current_scope_label = label;
-                        current_scope_lines.clear();
-                        current_scope_lines.push(processed_line.to_string())
-                    }
-                    _ => {
-                        current_scope_lines.push(processed_line.to_string());
+        let mut current_scope_lines: Vec<AsmLine> = vec![];
+        for line in lines {
+            let row_res = AsmRow::from_str(line.text.as_str());
+            if row_res.is_err() {
+                let err_msg = row_res.err().unwrap();
+                return Err(format!(
+                    "line {}: {} ==> {}",
+                    line.line_num, line.text, err_msg
+                ));
+            }
+            let row = row_res.unwrap();
+            match row {
+                AsmRow::LabelCall(label) => {
+                    if !current_scope_lines.is_empty() {
+                        let scope = AsmScope {
+                            label: current_scope_label.clone(),
+                            lines: current_scope_lines.clone(),
+                        };
+                        scopes.push(scope);
                     }
-                };
-            } else {
-                if !current_scope_lines.is_empty() {
-                    let scope = AsmScope {
-                        label: current_scope_label.clone(),
-                        lines: current_scope_lines.clone(),
-                    };
-                    scopes.push(scope);
+                    current_scope_label = label;
+                    current_scope_lines.clear();
+                    current_scope_lines.push(line)
                 }
-                break;
-            }
-            line_num += 1;
+                _ => {
+                    current_scope_lines.push(line);
+                }
+            };
+        }
+        if !current_scope_lines.is_empty() {
+            let scope = AsmScope {
+                label: current_scope_label.clone(),
+                lines: current_scope_lines.clone(),
+            };
+            scopes.push(scope);
         }
         scopes.sort_by(|a, b| {
             if a.label == "main" {
@@ -117,8 +110,6 @@ pub(crate) fn asm_relocate(bundle: AsmBundle) -> Result<RelocatedAsmBundle, Stri
         ));
     }
     let scopes = scopes_res.unwrap();
-    let scopes_codes: Vec<String> = scopes.iter().map(|scope| scope.lines.join("\n")).collect();
-    let resorted_program = scopes_codes.join("\n");
 
     let mut instructions: Vec<OlaAsmInstruction> = vec![];
     let mut mapper_label_call: HashMap<String, usize> = HashMap::new();
@@ -129,18 +120,18 @@ pub(crate) fn asm_relocate(bundle: AsmBundle) -> Result<RelocatedAsmBundle, Stri
     let mut ori_counter: usize = 0;
     let mut label_stack: Vec<AsmRow> = vec![];
 
-    let mut lines = resorted_program.lines();
+    let mut lines = scopes.iter().flat_map(|scope| scope.lines.iter());
 
     loop {
         if let Some(line) = lines.next() {
-            let row_res = AsmRow::from_str(line);
+            let row_res = AsmRow::from_str(line.text.as_str());
             if row_res.is_err() {
                 let err_msg = row_res.err().unwrap();
-                return Err(format!("{} ==> {}", line, err_msg));
+                return Err(format!("{} ==> {}", line.text, err_msg));
             }
             let row = row_res.unwrap();
             match row {
-                AsmRow::Instruction(instruction) => {
+                AsmRow::Instruction(_) | AsmRow::PseudoInstruction(_) => {
                     label_stack.iter().for_each(|cached_row| match cached_row {
                         AsmRow::LabelCall(label) => {
                             mapper_label_call.insert(label.clone(), counter);
@@ -154,9 +145,19 @@ pub(crate) fn asm_relocate(bundle: AsmBundle) -> Result<RelocatedAsmBundle, Stri
                         _ => {}
                     });
                     label_stack.clear();
-                    instructions.push(instruction.clone());
-                    ori_counter = counter;
-                    counter += instruction.binary_length() as usize;
+                    let expanded = match row {
+                        AsmRow::PseudoInstruction(expanded) => expanded,
+                        AsmRow::Instruction(instruction) => vec![instruction],
+                        _ => unreachable!(),
+                    };
+                    for mut instruction in expanded {
+                        if let Some(origin) = &line.origin {
+                            instruction.asm = origin.clone();
+                        }
+                        ori_counter = counter;
+                        counter += instruction.binary_length() as usize;
+                        instructions.push(instruction);
+                    }
                 }
                 AsmRow::LabelCall(_) => {
                     // for cached_label in &label_stack {
@@ -228,7 +229,7 @@ pub(crate) fn asm_relocate(bundle: AsmBundle) -> Result<RelocatedAsmBundle, Stri
 }
 
 // remove comments and trim
-fn line_pre_process(line: &str) -> &str {
+pub(crate) fn line_pre_process(line: &str) -> &str {
     let comment_start = line.find(";");
     let without_comment: &str = if comment_start.is_some() {
         &line[..comment_start.unwrap()]
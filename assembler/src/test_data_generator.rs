@@ -1,3 +1,183 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+const GENERAL_REGS: usize = 9;
+const WORK_SLOTS: u64 = 4;
+
+/// What a random program knows about a register or frame slot, ordered from
+/// most to least specific.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Known {
+    Bool,
+    U32,
+    Felt,
+    Unset,
+}
+
+#[derive(Clone)]
+struct GenState {
+    regs: [Known; GENERAL_REGS],
+    slots: [Known; WORK_SLOTS as usize],
+}
+
+impl GenState {
+    fn merge(&mut self, other: &GenState) {
+        for (known, other) in self.regs.iter_mut().zip(other.regs.iter()) {
+            *known = (*known).max(*other);
+        }
+        for (known, other) in self.slots.iter_mut().zip(other.slots.iter()) {
+            *known = (*known).max(*other);
+        }
+    }
+
+    fn pick_reg(&self, rng: &mut StdRng, at_most: Known) -> Option<usize> {
+        let regs: Vec<usize> = (0..GENERAL_REGS)
+            .filter(|reg| self.regs[*reg] <= at_most)
+            .collect();
+        regs.choose(rng).cloned()
+    }
+}
+
+/// Generates a straight-line program with forward branches in the asm json
+/// format, from a fixed seed. It only uses instructions whose operands are
+/// in range, ends by returning a few computed values through the tape and
+/// never faults, so every executor should run it to `end`.
+pub fn random_asm_program(seed: u64, len: usize) -> String {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let ret_len: u64 = rng.gen_range(1..=3);
+    let ret_base = ret_len + 1;
+    let frame = ret_base + WORK_SLOTS;
+    let mut state = GenState {
+        regs: [Known::Unset; GENERAL_REGS],
+        slots: [Known::Unset; WORK_SLOTS as usize],
+    };
+    let mut lines = vec![
+        "main:".to_string(),
+        ".LBL0_0:".to_string(),
+        format!("add r9 r9 {}", frame),
+    ];
+    // cjmp targets still open: (label, instructions left, state at the jump)
+    let mut pending: Vec<(usize, usize, GenState)> = vec![];
+    let mut labels = 0;
+
+    for _ in 0..len {
+        if let Some(line) = random_instruction(&mut rng, &mut state, ret_base) {
+            lines.push(line);
+        } else {
+            let dst = rng.gen_range(0..GENERAL_REGS);
+            lines.push(format!("mov r{} {}", dst, rng.gen::<u32>()));
+            state.regs[dst] = Known::U32;
+        }
+        for (_, left, _) in pending.iter_mut() {
+            *left -= 1;
+        }
+        while let Some(index) = pending.iter().position(|(_, left, _)| *left == 0) {
+            let (label, _, jumped) = pending.remove(index);
+            lines.push(format!(".LBL0_{}:", label));
+            state.merge(&jumped);
+        }
+        if rng.gen_ratio(1, 6) {
+            if let Some(cond) = state.pick_reg(&mut rng, Known::Bool) {
+                labels += 1;
+                lines.push(format!("cjmp r{} .LBL0_{}", cond, labels));
+                pending.push((labels, rng.gen_range(1..=4), state.clone()));
+            }
+        }
+    }
+    for (label, _, jumped) in pending {
+        lines.push(format!(".LBL0_{}:", label));
+        state.merge(&jumped);
+    }
+
+    for offset in 0..ret_len {
+        let src = match state.pick_reg(&mut rng, Known::Felt) {
+            Some(src) => src,
+            None => {
+                lines.push(format!("mov r0 {}", rng.gen::<u32>()));
+                state.regs[0] = Known::U32;
+                0
+            }
+        };
+        lines.push(format!("mstore [r9,-{}] r{}", ret_base - offset, src));
+    }
+    lines.push(format!("mov r8 {}", ret_len));
+    lines.push("mstore [r9,-1] r8".to_string());
+    lines.push(format!("add r8 r9 -{}", ret_base));
+    lines.push(format!("tstore r8 {}", ret_base));
+    lines.push("end".to_string());
+
+    serde_json::json!({ "program": lines.join("\n"), "prophets": [] }).to_string()
+}
+
+fn random_instruction(rng: &mut StdRng, state: &mut GenState, ret_base: u64) -> Option<String> {
+    let dst = rng.gen_range(0..GENERAL_REGS);
+    let (line, known) = match rng.gen_range(0..12) {
+        0 => (format!("mov r{} {}", dst, rng.gen::<u32>()), Known::U32),
+        1 => {
+            let src = state.pick_reg(rng, Known::Felt)?;
+            (format!("mov r{} r{}", dst, src), state.regs[src])
+        }
+        2 | 3 => {
+            let op = ["add", "mul"].choose(rng)?;
+            let op0 = state.pick_reg(rng, Known::Felt)?;
+            let op1 = random_operand(rng, state, Known::Felt);
+            (format!("{} r{} r{} {}", op, dst, op0, op1), Known::Felt)
+        }
+        4 => {
+            let op = ["eq", "neq"].choose(rng)?;
+            let op0 = state.pick_reg(rng, Known::Felt)?;
+            let op1 = random_operand(rng, state, Known::Felt);
+            (format!("{} r{} r{} {}", op, dst, op0, op1), Known::Bool)
+        }
+        5 => {
+            let op0 = state.pick_reg(rng, Known::U32)?;
+            let op1 = random_operand(rng, state, Known::U32);
+            (format!("gte r{} r{} {}", dst, op0, op1), Known::Bool)
+        }
+        6 | 7 => {
+            let op = ["and", "or", "xor"].choose(rng)?;
+            let op0 = state.pick_reg(rng, Known::U32)?;
+            let op1 = random_operand(rng, state, Known::U32);
+            (format!("{} r{} r{} {}", op, dst, op0, op1), Known::U32)
+        }
+        8 => {
+            let src = state.pick_reg(rng, Known::Felt)?;
+            (format!("not r{} r{}", dst, src), Known::Felt)
+        }
+        9 => {
+            let src = state.pick_reg(rng, Known::U32)?;
+            return Some(format!("range r{}", src));
+        }
+        10 => {
+            let src = state.pick_reg(rng, Known::Felt)?;
+            let slot = rng.gen_range(0..WORK_SLOTS);
+            state.slots[slot as usize] = state.regs[src];
+            return Some(format!("mstore [r9,-{}] r{}", ret_base + 1 + slot, src));
+        }
+        _ => {
+            let slot = (0..WORK_SLOTS)
+                .filter(|slot| state.slots[*slot as usize] != Known::Unset)
+                .collect::<Vec<_>>()
+                .choose(rng)
+                .cloned()?;
+            (
+                format!("mload r{} [r9,-{}]", dst, ret_base + 1 + slot),
+                state.slots[slot as usize],
+            )
+        }
+    };
+    state.regs[dst] = known;
+    Some(line)
+}
+
+fn random_operand(rng: &mut StdRng, state: &GenState, at_most: Known) -> String {
+    match state.pick_reg(rng, at_most) {
+        Some(reg) if rng.gen_bool(0.7) => format!("r{}", reg),
+        _ => rng.gen_range(0..1u64 << 16).to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::encoder::encode_to_binary;
@@ -3,8 +3,16 @@ mod tests {
     use core::program::binary_program::BinaryProgram;
     use std::path::PathBuf;
 
-    use crate::encoder::encode_asm_from_json_file;
-    use core::program::decoder::decode_binary_program_to_instructions;
+    use crate::encoder::{encode_asm_from_json_file, encode_asm_from_json_string};
+    use core::program::analysis::{FrameIssue, ProgramAnalysis};
+    use core::program::compact::CompactProgram;
+    use core::program::decoder::{
+        decode_binary_program_to_instructions, decode_compact_program_to_instructions,
+        disassemble_binary_program,
+    };
+    use core::program::prophet_check::{check_binary_prophet_outputs, ProphetOutputWarning};
+    use core::program::verifier::{verify_binary_program, verify_program, ProgramViolation};
+    use core::vm::opcodes::OlaOpcode;
 
     #[test]
     fn test_decode_malloc() {
@@ -80,4 +88,351 @@ mod tests {
         let regenerated_binary = regenerated_binary_vec.join("\n");
         assert_eq!(regenerated_binary, encoded_program.bytecode);
     }
+
+    #[test]
+    fn test_disassemble_round_trip() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("test_data/asm/");
+        for file_name in [
+            "call.json",
+            "fibo_recursive.json",
+            "sqrt_prophet_asm.json",
+            "global.json",
+        ] {
+            let mut path = dir.clone();
+            path.push(file_name);
+            let encoded_program = encode_asm_from_json_file(path.display().to_string()).unwrap();
+            test_disassemble(file_name, encoded_program.clone());
+
+            let mut stripped = encoded_program;
+            stripped.debug_info = None;
+            test_disassemble(file_name, stripped);
+        }
+    }
+
+    fn test_disassemble(file_name: &str, program: BinaryProgram) {
+        let disassembled = disassemble_binary_program(program.clone()).unwrap();
+        let json = serde_json::to_string(&disassembled).unwrap();
+        let reassembled = encode_asm_from_json_string(json).unwrap();
+        assert_eq!(reassembled.bytecode, program.bytecode, "{}", file_name);
+        let hosts = |p: &BinaryProgram| -> Vec<(usize, String)> {
+            let mut hosts: Vec<(usize, String)> = p
+                .prophets
+                .iter()
+                .map(|p| (p.host, p.code.clone()))
+                .collect();
+            hosts.sort();
+            hosts
+        };
+        assert_eq!(hosts(&reassembled), hosts(&program), "{}", file_name);
+    }
+
+    #[test]
+    fn test_compact_round_trip() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/sqrt_prophet_asm.json");
+        let program = encode_asm_from_json_file(path.display().to_string()).unwrap();
+        test_compact(program);
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/bin/books_bin.json");
+        let json = std::fs::read_to_string(path).unwrap();
+        let program: BinaryProgram = serde_json::from_str(json.as_str()).unwrap();
+        test_compact(program);
+    }
+
+    #[test]
+    fn test_compact_truncated_immediate() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/sqrt_prophet_asm.json");
+        let program = encode_asm_from_json_file(path.display().to_string()).unwrap();
+        let mut compact = CompactProgram::from_binary_program(&program).unwrap();
+        // drop everything after the opcode word of the last instruction
+        // carrying an immediate
+        let instructions = decode_compact_program_to_instructions(&compact).unwrap();
+        let mut pc = 0;
+        let mut last_imm = None;
+        for instruction in &instructions {
+            if instruction.binary_length() == 2 {
+                last_imm = Some(pc);
+            }
+            pc += instruction.binary_length() as usize;
+        }
+        let pc = last_imm.unwrap();
+        compact.words.truncate(pc + 1);
+
+        let decoded = CompactProgram::decode(&compact.encode()).unwrap();
+        let err = decoded.to_binary_program().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("pc {}: immediate word missing at end of program", pc)
+        );
+    }
+
+    fn test_compact(program: BinaryProgram) {
+        let compact = CompactProgram::from_binary_program(&program).unwrap();
+        let bytes = compact.encode();
+        let decoded = CompactProgram::decode(&bytes).unwrap();
+        assert_eq!(decoded, compact);
+
+        let restored = decoded.to_binary_program().unwrap();
+        assert_eq!(restored.bytecode, program.bytecode);
+        assert_eq!(restored.prophets, program.prophets);
+        assert_eq!(restored.debug_info, program.debug_info);
+
+        let from_words = decode_compact_program_to_instructions(&decoded).unwrap();
+        let from_json = decode_binary_program_to_instructions(program).unwrap();
+        assert_eq!(from_words.len(), from_json.len());
+        for (a, b) in from_words.iter().zip(from_json.iter()) {
+            assert_eq!(a.encode().unwrap(), b.encode().unwrap());
+            assert_eq!(a.prophet, b.prophet);
+        }
+
+        let mut stripped = compact.clone();
+        stripped.debug_info = None;
+        assert_eq!(stripped.hash(), compact.hash());
+
+        let mut tampered = bytes.clone();
+        tampered[12] ^= 1;
+        assert!(CompactProgram::decode(&tampered).is_err());
+        let mut future_version = bytes;
+        future_version[4] = 2;
+        assert!(CompactProgram::decode(&future_version).is_err());
+    }
+
+    #[test]
+    fn test_verify_deployable_programs() {
+        for dir in ["test_data/bin", "test_data/bin/sys"] {
+            let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            path.push(dir);
+            for entry in std::fs::read_dir(path).unwrap() {
+                let path = entry.unwrap().path();
+                if path.extension().map_or(true, |ext| ext != "json") {
+                    continue;
+                }
+                let json = std::fs::read_to_string(&path).unwrap();
+                let program: BinaryProgram = serde_json::from_str(json.as_str()).unwrap();
+                assert_eq!(verify_binary_program(&program), Ok(()), "{:?}", path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_reports_all_violations() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/sqrt_prophet_asm.json");
+        let program = encode_asm_from_json_file(path.display().to_string()).unwrap();
+        let compact = CompactProgram::from_binary_program(&program).unwrap();
+        assert_eq!(verify_program(&compact), Ok(()));
+
+        let instructions = decode_compact_program_to_instructions(&compact).unwrap();
+        let mut pcs: Vec<usize> = vec![];
+        let mut pc = 0;
+        for instruction in &instructions {
+            pcs.push(pc);
+            pc += instruction.binary_length() as usize;
+        }
+        let pc_of = |opcode: OlaOpcode| {
+            pcs.iter()
+                .zip(instructions.iter())
+                .filter(|(_, i)| i.opcode == opcode && i.binary_length() == 2)
+                .map(|(pc, _)| *pc)
+                .collect::<Vec<usize>>()
+        };
+        let jmp = pc_of(OlaOpcode::JMP)[0];
+        let call = pc_of(OlaOpcode::CALL)[0];
+        let mov = pc_of(OlaOpcode::MOV)[0];
+
+        let mut broken = compact.clone();
+        broken.words[jmp + 1] = broken.words.len() as u64 + 5;
+        broken.words[call + 1] = mov as u64 + 1;
+        broken.prophets[0].host = mov + 1;
+        broken.prophets[0].outputs[0].length = 0;
+        for (pc, instruction) in pcs.iter().zip(instructions.iter()) {
+            if instruction.opcode == OlaOpcode::END {
+                broken.words[*pc] = OlaOpcode::RET.binary_bit_mask();
+            }
+        }
+
+        let violations = verify_program(&broken).unwrap_err();
+        assert!(violations.contains(&ProgramViolation::TargetOutOfRange {
+            pc: jmp,
+            opcode: OlaOpcode::JMP.token(),
+            target: broken.words.len() + 5,
+        }));
+        assert!(
+            violations.contains(&ProgramViolation::TargetInsideImmediate {
+                pc: call,
+                opcode: OlaOpcode::CALL.token(),
+                target: mov + 1,
+            })
+        );
+        assert!(
+            violations.contains(&ProgramViolation::ProphetHostInsideImmediate { host: mov + 1 })
+        );
+        assert!(violations.contains(&ProgramViolation::ProphetShapeError {
+            host: mov + 1,
+            err: format!(
+                "output {} has zero length",
+                broken.prophets[0].outputs[0].name
+            ),
+        }));
+        assert!(violations.contains(&ProgramViolation::NoReachableEnd));
+    }
+
+    #[test]
+    fn test_analysis_fibo_recursive() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/fibo_recursive.json");
+        let program = encode_asm_from_json_file(path.display().to_string()).unwrap();
+        let compact = CompactProgram::from_binary_program(&program).unwrap();
+        let analysis = ProgramAnalysis::from_compact_program(&compact).unwrap();
+
+        assert_eq!(analysis.functions.len(), 2);
+        assert!(analysis.unreachable.is_empty());
+        let frames: Vec<(String, Option<u64>)> = analysis
+            .functions
+            .values()
+            .map(|f| (f.name.clone(), f.frame_size))
+            .collect();
+        assert_eq!(frames[0], ("main".to_string(), Some(4)));
+        assert_eq!(frames[1].1, Some(9));
+        for function in analysis.functions.values() {
+            assert!(function.frame_issues.is_empty(), "{}", function.name);
+        }
+
+        let fib = analysis.functions.keys().last().cloned().unwrap();
+        assert_eq!(analysis.functions[&fib].blocks.len(), 7);
+        let dot = analysis.function_to_dot(fib).unwrap();
+        assert!(dot.starts_with(&format!("digraph \"func_{}\"", fib)));
+        assert!(dot.contains(&format!("calls func_{}", fib)));
+        assert_eq!(dot.matches(" -> ").count(), 6);
+        assert!(analysis.function_to_dot(fib + 1).is_none());
+    }
+
+    #[test]
+    fn test_analysis_frame_issues() {
+        let asm = r#"{
+            "program": "main:\n.LBL0_0:\n  add r9 r9 4\n  call foo\n  add r9 r9 -4\n  end\nfoo:\n.LBL1_0:\n  add r9 r9 2\n  mov r0 1\n  cjmp r0 .LBL1_1\n  ret\n.LBL1_1:\n  add r9 r9 -2\n  ret\n.LBL1_2:\n  mov r0 3\n  ret\n",
+            "prophets": []
+        }"#;
+        let program = encode_asm_from_json_string(asm.to_string()).unwrap();
+        let compact = CompactProgram::from_binary_program(&program).unwrap();
+        let analysis = ProgramAnalysis::from_compact_program(&compact).unwrap();
+
+        let main = &analysis.functions[&0];
+        assert_eq!(main.frame_size, Some(4));
+        assert!(main.frame_issues.is_empty());
+
+        let foo = analysis.functions.values().last().unwrap();
+        assert_eq!(foo.frame_size, Some(2));
+        assert_eq!(foo.frame_issues.len(), 1);
+        assert!(matches!(
+            foo.frame_issues[0],
+            FrameIssue::UnbalancedExit { delta: 2, .. }
+        ));
+        assert_eq!(analysis.unreachable.len(), 1);
+        let dead = analysis.unreachable.iter().next().unwrap();
+        assert_eq!(analysis.blocks[dead].instructions.len(), 2);
+    }
+
+    #[test]
+    fn test_prophet_check_deployable_programs() {
+        for dir in ["test_data/bin", "test_data/bin/sys"] {
+            let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            path.push(dir);
+            for entry in std::fs::read_dir(path).unwrap() {
+                let path = entry.unwrap().path();
+                if path.extension().map_or(true, |ext| ext != "json") {
+                    continue;
+                }
+                let json = std::fs::read_to_string(&path).unwrap();
+                let program: BinaryProgram = serde_json::from_str(json.as_str()).unwrap();
+                let warnings = check_binary_prophet_outputs(&program).unwrap();
+                assert!(warnings.is_empty(), "{:?}: {:?}", path, warnings);
+            }
+        }
+    }
+
+    #[test]
+    fn test_prophet_check_sqrt() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/sqrt_prophet_asm.json");
+        let program = encode_asm_from_json_file(path.display().to_string()).unwrap();
+        let warnings = check_binary_prophet_outputs(&program).unwrap();
+
+        // sqrt is range checked and squared back, the malloc addresses are
+        // used as pointers straight away
+        let sqrt = program
+            .prophets
+            .iter()
+            .find(|prophet| prophet.code.contains("sqrt"))
+            .unwrap();
+        assert!(warnings.iter().all(|warning| warning.host != sqrt.host));
+        assert!(!warnings.is_empty());
+        assert!(warnings.iter().all(|warning| warning.output == "cid.addr"));
+    }
+
+    #[test]
+    fn test_prophet_check_unchecked_output() {
+        let asm = r#"{
+            "program": "main:\n.LBL0_0:\n  add r9 r9 2\n  mov r1 5\n.PROPHET0_0:\n  mov r0 psp\n  mload r0 [r0]\n  mstore [r9,-1] r0\n  mload r2 [r9,-1]\n  range r2\n  mov r3 psp\n  mload r4 [r3,1]\n  eq r5 r4 7\n  cjmp r5 .LBL0_1\n  jmp .LBL0_1\n.LBL0_1:\n  mload r6 [r3,2]\n  mstore [r1] r6\n  add r9 r9 -2\n  end\n",
+            "prophets": [
+                {
+                    "label": ".PROPHET0_0",
+                    "code": "%{\n    entry() {\n        cid.x = 1;\n    }\n%}",
+                    "inputs": [],
+                    "outputs": [
+                        {"name": "cid.x", "length": 1, "is_ref": false, "is_input_output": false},
+                        {"name": "cid.y", "length": 2, "is_ref": false, "is_input_output": false}
+                    ]
+                }
+            ]
+        }"#;
+        let program = encode_asm_from_json_string(asm.to_string()).unwrap();
+        let warnings = check_binary_prophet_outputs(&program).unwrap();
+        assert_eq!(
+            warnings,
+            vec![ProphetOutputWarning {
+                host: 2,
+                output: "cid.y".to_string(),
+                offset: 1,
+                pc: 23,
+                instruction: "mstore [r1,0] r6".to_string(),
+            }]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "prophet at 2: output cid.y[1] used unchecked by `mstore [r1,0] r6` at pc 23"
+        );
+    }
+
+    #[test]
+    fn test_pseudo_instructions_and_macros() {
+        let asm = r#"{
+            "program": ".macro swap a, b\n  push \\a\n  mov \\a \\b\n  pop \\b\n.endm\n.macro dec r\n  sub \\r \\r 1\n.endm\nmain:\n.LBL0_0:\n  mov r0 1\n  mov r1 2\n  swap r0 r1 ; keeps both\n  dec r0\n  end\n",
+            "prophets": []
+        }"#;
+        let expected = r#"{
+            "program": "main:\n.LBL0_0:\n  mov r0 1\n  mov r1 2\n  add r9 r9 1\n  mstore [r9,-1] r0\n  mov r0 r1\n  mload r1 [r9,-1]\n  add r9 r9 -1\n  add r0 r0 -1\n  end\n",
+            "prophets": []
+        }"#;
+        let program = encode_asm_from_json_string(asm.to_string()).unwrap();
+        let hand_written = encode_asm_from_json_string(expected.to_string()).unwrap();
+        assert_eq!(program.bytecode, hand_written.bytecode);
+
+        let debug_info = program.debug_info.unwrap();
+        let swap_lines: Vec<usize> = debug_info
+            .iter()
+            .filter(|(_, asm)| asm.as_str() == "swap r0 r1")
+            .map(|(pc, _)| *pc)
+            .collect();
+        assert_eq!(swap_lines.len(), 5);
+        let dec_lines = debug_info
+            .values()
+            .filter(|asm| asm.as_str() == "dec r0")
+            .count();
+        assert_eq!(dec_lines, 1);
+        assert_eq!(debug_info.values().last().unwrap(), "end");
+    }
 }
@@ -4,7 +4,9 @@ use circuits::stark::config::StarkConfig;
 use circuits::stark::ola_stark::OlaStark;
 use circuits::stark::proof::PublicValues;
 use circuits::stark::prover::prove_with_traces;
+use circuits::stark::serialization::Buffer;
 use circuits::stark::verifier::verify_proof;
+use circuits::stark::verifying_key::{max_degree_bits, OlaProvingKey};
 use core::program::Program;
 use core::state::state_storage::StateStorage;
 use core::types::{Field, GoldilocksField};
@@ -26,7 +28,13 @@ const D: usize = 2;
 type C = Blake3GoldilocksConfig;
 type F = <C as GenericConfig<D>>::F;
 
-pub fn test_by_asm_json(path: String) {
+/// Proves the program at `path`, with the proving key built on the first
+/// call and reused after.
+pub fn test_by_asm_json(
+    path: String,
+    config: &StarkConfig,
+    proving_key: &mut Option<OlaProvingKey<F, C, D>>,
+) {
     let program = encode_asm_from_json_file(path).unwrap();
     let instructions = program.bytecode.split("\n");
     let mut prophets = HashMap::new();
@@ -83,12 +91,22 @@ pub fn test_by_asm_json(path: String) {
         now.elapsed().as_millis(),
         traces[0].get(0).unwrap().values.len()
     );
+    let proving_key = proving_key.get_or_insert_with(|| {
+        let now = Instant::now();
+        let proving_key = OlaProvingKey::<F, C, D>::new(
+            config,
+            max_degree_bits(&traces),
+            &mut TimingTree::default(),
+        );
+        info!("keygen time:{}", now.elapsed().as_millis());
+        proving_key
+    });
     let now = Instant::now();
 
-    let config = StarkConfig::standard_fast_config();
     let proof = prove_with_traces::<F, C, D>(
         &ola_stark,
-        &config,
+        proving_key,
+        config,
         traces,
         public_values,
         &mut TimingTree::default(),
@@ -96,8 +114,15 @@ pub fn test_by_asm_json(path: String) {
     info!("prove_with_traces time:{}", now.elapsed().as_millis());
 
     if let Ok(proof) = proof {
+        let mut buffer = Buffer::new(Vec::new());
+        buffer.write_all_proof(&proof).unwrap();
+        println!(
+            "proof size:{} bytes, batch_fri:{}",
+            buffer.len(),
+            config.batch_fri
+        );
         let ola_stark = OlaStark::default();
-        let verify_res = verify_proof(ola_stark, proof, &config);
+        let verify_res = verify_proof(ola_stark, &proving_key.verifying_key, proof, config);
         println!("verify result:{:?}", verify_res);
     } else {
         println!("proof err:{:?}", proof);
@@ -111,12 +136,22 @@ fn fib_loop_benchmark(c: &mut Criterion) {
     let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("benches/asm/fib_asm.json");
     let mut group = c.benchmark_group("fibo_loop");
-    let input = 0;
-    group.bench_with_input(BenchmarkId::from_parameter(1), &input, |b, _| {
-        b.iter(|| {
-            test_by_asm_json(path.display().to_string());
-        });
-    });
+    for batch_fri in [false, true] {
+        let config = StarkConfig {
+            batch_fri,
+            ..StarkConfig::standard_fast_config()
+        };
+        group.bench_with_input(
+            BenchmarkId::new("batch_fri", batch_fri),
+            &config,
+            |b, config| {
+                let mut proving_key = None;
+                b.iter(|| {
+                    test_by_asm_json(path.display().to_string(), config, &mut proving_key);
+                });
+            },
+        );
+    }
     group.finish();
 }
 
@@ -0,0 +1,99 @@
+//! Compares the lookup arguments of the bitwise and rangecheck tables on a
+//! bitwise program. Run it as is for logUp, then with
+//! `--features bitwise_permuted_lookup,rangecheck_permuted_lookup` for
+//! sorted permuted columns.
+use assembler::encoder::encode_asm_from_json_file;
+use circuits::builtins::bitwise::bitwise_stark::BitwiseStark;
+use circuits::builtins::rangecheck::rangecheck_stark::RangeCheckStark;
+use circuits::generation::{generate_traces, GenerationInputs};
+use circuits::stark::config::StarkConfig;
+use circuits::stark::ola_stark::OlaStark;
+use circuits::stark::prover::prove_with_traces;
+use circuits::stark::stark::Stark;
+use circuits::stark::verifying_key::{max_degree_bits, OlaProvingKey};
+use core::program::Program;
+use core::state::state_storage::StateStorage;
+use core::types::account::Address;
+use criterion::{criterion_group, criterion_main, Criterion};
+use executor::{BatchCacheManager, Process};
+use log::{info, LevelFilter};
+use plonky2::plonk::config::{Blake3GoldilocksConfig, GenericConfig};
+use plonky2::util::timing::TimingTree;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const D: usize = 2;
+type C = Blake3GoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+/// The trace columns of a table, then the columns its permutation and
+/// lookup arguments add.
+fn committed_columns<S: Stark<F, D>>(stark: &S, config: &StarkConfig) -> (usize, usize) {
+    (
+        S::COLUMNS,
+        stark.num_permutation_batches(config) + stark.num_lookup_helper_columns(config),
+    )
+}
+
+fn lookups_benchmark(c: &mut Criterion) {
+    let _ = env_logger::builder()
+        .filter_level(LevelFilter::Info)
+        .try_init();
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("../assembler/test_data/asm/bitwise.json");
+    let program = encode_asm_from_json_file(path.display().to_string()).unwrap();
+    let mut prophets = HashMap::new();
+    for item in program.prophets {
+        prophets.insert(item.host as u64, item);
+    }
+    let mut program = Program {
+        instructions: program.bytecode.split('\n').map(String::from).collect(),
+        prophets,
+        ..Program::default()
+    };
+    let mut process = Process::new();
+    process.addr_storage = Address::default();
+    let _ = process.execute(
+        &mut program,
+        &StateStorage::new_test(),
+        &mut BatchCacheManager::default(),
+    );
+
+    let config = StarkConfig::standard_fast_config();
+    let mut ola_stark = OlaStark::default();
+    let (traces, public_values) =
+        generate_traces(program, &mut ola_stark, GenerationInputs::default());
+    let bitwise = committed_columns::<BitwiseStark<F, D>>(&ola_stark.bitwise_stark, &config);
+    let rangecheck =
+        committed_columns::<RangeCheckStark<F, D>>(&ola_stark.rangecheck_stark, &config);
+    info!(
+        "bitwise: {} trace + {} auxiliary columns, rangecheck: {} trace + {} auxiliary columns",
+        bitwise.0, bitwise.1, rangecheck.0, rangecheck.1
+    );
+    let proving_key = OlaProvingKey::<F, C, D>::new(
+        &config,
+        max_degree_bits(&traces),
+        &mut TimingTree::default(),
+    );
+
+    c.bench_function("prove_bitwise", |b| {
+        b.iter(|| {
+            prove_with_traces::<F, C, D>(
+                &ola_stark,
+                &proving_key,
+                &config,
+                traces.clone(),
+                public_values.clone(),
+                &mut TimingTree::default(),
+            )
+            .unwrap()
+        });
+    });
+}
+
+criterion_group![
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = lookups_benchmark
+];
+criterion_main!(benches);
@@ -1,16 +1,23 @@
+use core::trace::trace::Trace;
 use crate::builtins::bitwise::columns::*;
 use anyhow::Result;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 //use crate::var::{StarkEvaluationTargets, StarkEvaluationVars};
+use crate::cpu::cpu_stark;
+use crate::generation::builtin::{bitwise_fixed_columns, generate_bitwise_trace};
 use crate::stark::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
-use crate::stark::cross_table_lookup::Column;
-use crate::stark::lookup::*;
-use crate::stark::permutation::*;
+use crate::stark::cross_table_lookup::{Column, CrossTableLookup, TableWithColumns};
+use crate::stark::lookup::{eval_lookups, eval_lookups_circuit, Lookup};
+use crate::stark::ola_stark::Table;
+use crate::stark::permutation::PermutationPair;
 use crate::stark::stark::Stark;
+use crate::stark::table::{OlaTable, TraceJob};
+use crate::stark::util::trace_to_poly_values;
 use crate::stark::vars::{StarkEvaluationTargets, StarkEvaluationVars};
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
 use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
 //use plonky2::iop::ext_target::ExtensionTarget;
@@ -85,104 +92,19 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for BitwiseStark<
                     - lv[COMPRESS_LIMBS.start + i],
             );
         }
-
-        eval_lookups(
-            vars,
-            yield_constr,
-            OP0_LIMBS_PERMUTED.start,
-            FIX_RANGE_CHECK_U8_PERMUTED.start,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            OP0_LIMBS_PERMUTED.start + 1,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 1,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            OP0_LIMBS_PERMUTED.start + 2,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 2,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            OP0_LIMBS_PERMUTED.start + 3,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 3,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            OP1_LIMBS_PERMUTED.start,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 4,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            OP1_LIMBS_PERMUTED.start + 1,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 5,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            OP1_LIMBS_PERMUTED.start + 2,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 6,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            OP1_LIMBS_PERMUTED.start + 3,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 7,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            RES_LIMBS_PERMUTED.start,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 8,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            RES_LIMBS_PERMUTED.start + 1,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 9,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            RES_LIMBS_PERMUTED.start + 2,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 10,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            RES_LIMBS_PERMUTED.start + 3,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 11,
-        );
-
-        eval_lookups(
-            vars,
-            yield_constr,
-            COMPRESS_PERMUTED.start,
-            FIX_COMPRESS_PERMUTED.start,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            COMPRESS_PERMUTED.start + 1,
-            FIX_COMPRESS_PERMUTED.start + 1,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            COMPRESS_PERMUTED.start + 2,
-            FIX_COMPRESS_PERMUTED.start + 2,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            COMPRESS_PERMUTED.start + 3,
-            FIX_COMPRESS_PERMUTED.start + 3,
-        );
+        // The compressed table is not preprocessed as it depends on beta, so
+        // tie it to the preprocessed bitwise table.
+        yield_constr.constraint(
+            lv[FIX_TAG]
+                + lv[FIX_BITWSIE_OP0] * beta
+                + lv[FIX_BITWSIE_OP1] * beta * beta
+                + lv[FIX_BITWSIE_RES] * beta * beta * beta
+                - lv[FIX_COMPRESS],
+        );
+
+        for (input, table) in permuted_lookups() {
+            eval_lookups(vars, yield_constr, input, table);
+        }
     }
 
     fn eval_ext_circuit(
@@ -230,120 +152,16 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for BitwiseStark<
             let cs = builder.sub_extension(cs, lv[COMPRESS_LIMBS.start + i]);
             yield_constr.constraint(builder, cs);
         }
-
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            OP0_LIMBS_PERMUTED.start,
-            FIX_RANGE_CHECK_U8_PERMUTED.start,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            OP0_LIMBS_PERMUTED.start + 1,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 1,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            OP0_LIMBS_PERMUTED.start + 2,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 2,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            OP0_LIMBS_PERMUTED.start + 3,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 3,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            OP1_LIMBS_PERMUTED.start,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 4,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            OP1_LIMBS_PERMUTED.start + 1,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 5,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            OP1_LIMBS_PERMUTED.start + 2,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 6,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            OP1_LIMBS_PERMUTED.start + 3,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 7,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            RES_LIMBS_PERMUTED.start,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 8,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            RES_LIMBS_PERMUTED.start + 1,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 9,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            RES_LIMBS_PERMUTED.start + 2,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 10,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            RES_LIMBS_PERMUTED.start + 3,
-            FIX_RANGE_CHECK_U8_PERMUTED.start + 11,
-        );
-
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            COMPRESS_PERMUTED.start,
-            FIX_COMPRESS_PERMUTED.start,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            COMPRESS_PERMUTED.start + 1,
-            FIX_COMPRESS_PERMUTED.start + 1,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            COMPRESS_PERMUTED.start + 2,
-            FIX_COMPRESS_PERMUTED.start + 2,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            COMPRESS_PERMUTED.start + 3,
-            FIX_COMPRESS_PERMUTED.start + 3,
-        );
+        let op0_cs = builder.mul_extension(lv[FIX_BITWSIE_OP0], beta);
+        let op1_cs = builder.mul_extension(lv[FIX_BITWSIE_OP1], beta_2);
+        let res_cs = builder.mul_extension(lv[FIX_BITWSIE_RES], beta_3);
+        let cs = builder.add_many_extension([lv[FIX_TAG], op0_cs, op1_cs, res_cs]);
+        let cs = builder.sub_extension(cs, lv[FIX_COMPRESS]);
+        yield_constr.constraint(builder, cs);
+
+        for (input, table) in permuted_lookups() {
+            eval_lookups_circuit(builder, vars, yield_constr, input, table);
+        }
     }
 
     fn constraint_degree(&self) -> usize {
@@ -351,17 +169,63 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for BitwiseStark<
     }
 
     fn permutation_pairs(&self) -> Vec<PermutationPair> {
+        let u8_limbs = OP0_LIMBS.chain(OP1_LIMBS).chain(RES_LIMBS);
+        let inputs = u8_limbs.chain(COMPRESS_LIMBS);
+        let tables = [FIX_RANGE_CHECK_U8; 12]
+            .into_iter()
+            .chain([FIX_COMPRESS; 4]);
+        inputs
+            .zip(tables)
+            .zip(permuted_lookups())
+            .flat_map(|((input, table), (permuted_input, permuted_table))| {
+                [
+                    PermutationPair::singletons(input, permuted_input),
+                    PermutationPair::singletons(table, permuted_table),
+                ]
+            })
+            .collect()
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        if PERMUTED_LOOKUP {
+            return vec![];
+        }
         vec![
-            PermutationPair::singletons(COMPRESS_LIMBS.start, COMPRESS_PERMUTED.start),
-            PermutationPair::singletons(COMPRESS_LIMBS.start + 1, COMPRESS_PERMUTED.start + 1),
-            PermutationPair::singletons(COMPRESS_LIMBS.start + 2, COMPRESS_PERMUTED.start + 2),
-            PermutationPair::singletons(COMPRESS_LIMBS.start + 3, COMPRESS_PERMUTED.start + 3),
-            PermutationPair::singletons(FIX_COMPRESS, FIX_COMPRESS_PERMUTED.start),
-            PermutationPair::singletons(FIX_COMPRESS, FIX_COMPRESS_PERMUTED.start + 1),
-            PermutationPair::singletons(FIX_COMPRESS, FIX_COMPRESS_PERMUTED.start + 2),
-            PermutationPair::singletons(FIX_COMPRESS, FIX_COMPRESS_PERMUTED.start + 3),
+            Lookup {
+                columns: OP0_LIMBS.chain(OP1_LIMBS).chain(RES_LIMBS).collect(),
+                table_column: FIX_RANGE_CHECK_U8,
+                frequencies_column: FIX_RANGE_CHECK_U8_FREQ,
+            },
+            Lookup {
+                columns: COMPRESS_LIMBS.collect(),
+                table_column: FIX_COMPRESS,
+                frequencies_column: FIX_COMPRESS_FREQ,
+            },
         ]
     }
+
+    fn preprocessed_columns(&self) -> Vec<usize> {
+        PREPROCESSED_COLS.to_vec()
+    }
+
+    fn preprocessed_values(&self, degree_bits: usize) -> Option<Vec<PolynomialValues<F>>> {
+        // The trace has at least `BITWISE_U8_SIZE` rows.
+        let len = 1 << degree_bits;
+        (len >= BITWISE_U8_SIZE).then(|| {
+            bitwise_fixed_columns(len)
+                .into_iter()
+                .map(PolynomialValues::new)
+                .collect()
+        })
+    }
+}
+
+/// The permuted input and permuted table columns of each limb, the u8 limbs
+/// then the compressed ones. Empty without `PERMUTED_LOOKUP`.
+fn permuted_lookups() -> impl Iterator<Item = (usize, usize)> {
+    LIMBS_PERMUTED
+        .zip(FIX_RANGE_CHECK_U8_PERMUTED)
+        .chain(COMPRESS_PERMUTED.zip(FIX_COMPRESS_PERMUTED))
 }
 
 // Get the column info for Cross_Lookup<Cpu_table, Bitwise_table>
@@ -410,6 +274,66 @@ pub fn ctl_data_with_bitwise_fixed<F: Field>() -> Vec<Column<F>> {
 pub fn ctl_filter_with_bitwise_fixed<F: Field>() -> Column<F> {
     Column::one()
 }*/
+
+impl<F: RichField + Extendable<D>, const D: usize> OlaTable<F, D> for BitwiseStark<F, D> {
+    fn table(&self) -> Table {
+        Table::Bitwise
+    }
+
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F> {
+        let cells = std::mem::take(&mut trace.builtin_bitwise_combined);
+        Box::new(move || {
+            let (rows, beta) = generate_bitwise_trace(&cells);
+            (trace_to_poly_values(rows), Some(beta))
+        })
+    }
+
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        vec![ctl_bitwise_cpu()]
+    }
+
+    fn compress_challenge(&self) -> Option<F> {
+        self.get_compress_challenge()
+    }
+
+    fn set_compress_challenge(&mut self, challenge: F) -> Result<()> {
+        self.set_compress_challenge(challenge)
+    }
+}
+
+// add bitwise rangecheck instance
+// Cpu table
+// +-----+-----+-----+---------+--------+---------+-----+-----+-----+-----+----
+// | clk | ins | ... | sel_and | sel_or | sel_xor | ... | op0 | op1 | dst | ...
+// +-----+-----+-----+---------+--------+---------+-----+-----+----+----+----
+//
+// Bitwise table
+// +-----+-----+-----+-----+------------+------------+-----------+------------+---
+// | tag | op0 | op1 | res | op0_limb_0 | op0_limb_1 |res_limb_2 | op0_limb_3
+// |...
+// +-----+-----+-----+-----+------------+------------+-----------+------------+---
+//
+// Filter bitwise from CPU Table
+// 1. (sel_add + sel_or + sel_xor) * (op0, op1, dst) = looking_table
+// Filter bitwise from Bitwsie Table
+// 1. (op0, op1, res) = looked_table
+
+// Cross_Lookup_Table(looking_table, looked_table)
+fn ctl_bitwise_cpu<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new_logup(
+        vec![TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_with_bitwise(),
+            Some(cpu_stark::ctl_filter_with_bitwise()),
+        )],
+        TableWithColumns::new(
+            Table::Bitwise,
+            ctl_data_with_cpu(),
+            Some(ctl_filter_with_cpu()),
+        ),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use crate::builtins::bitwise::bitwise_stark::BitwiseStark;
@@ -417,11 +341,14 @@ mod tests {
     use crate::generation::builtin::generate_bitwise_trace;
     use crate::stark::constraint_consumer::ConstraintConsumer;
     use crate::stark::stark::Stark;
+    use crate::stark::util::trace_to_poly_values;
     use crate::stark::vars::StarkEvaluationVars;
     use assembler::encoder::encode_asm_from_json_file;
 
+    use core::program::instruction::Opcode;
     use core::program::Program;
     use core::state::state_storage::StateStorage;
+    use core::trace::trace::BitwiseCombinedRow;
     use core::types::account::Address;
     use executor::{BatchCacheManager, Process};
     use plonky2::field::goldilocks_field::GoldilocksField;
@@ -526,6 +453,81 @@ mod tests {
         }
     }
 
+    /// The bitwise row of `op0 AND op1`.
+    fn and_row(op0: u64, op1: u64) -> BitwiseCombinedRow {
+        type F = GoldilocksField;
+        let [op0_limbs, op1_limbs, res_limbs] = [op0, op1, op0 & op1].map(|value| {
+            [0, 8, 16, 24].map(|shift| F::from_canonical_u64((value >> shift) & 0xff))
+        });
+        BitwiseCombinedRow {
+            opcode: 1 << Opcode::AND as u8,
+            op0: F::from_canonical_u64(op0),
+            op1: F::from_canonical_u64(op1),
+            res: F::from_canonical_u64(op0 & op1),
+            op0_0: op0_limbs[0],
+            op0_1: op0_limbs[1],
+            op0_2: op0_limbs[2],
+            op0_3: op0_limbs[3],
+            op1_0: op1_limbs[0],
+            op1_1: op1_limbs[1],
+            op1_2: op1_limbs[2],
+            op1_3: op1_limbs[3],
+            res_0: res_limbs[0],
+            res_1: res_limbs[1],
+            res_2: res_limbs[2],
+            res_3: res_limbs[3],
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "bitwise_permuted_lookup"))]
+    fn test_bitwise_lookup_rejects_bad_limbs() {
+        use crate::builtins::bitwise::columns::{FIX_RANGE_CHECK_U8_FREQ, OP0_LIMBS};
+        use crate::stark::lookup::lookups_hold;
+
+        type F = GoldilocksField;
+        let (rows, beta) = generate_bitwise_trace::<F>(&[and_row(0x12_3456, 0x0f_0f0f)]);
+        let mut stark = BitwiseStark::<F, 2>::default();
+        stark.set_compress_challenge(beta).unwrap();
+        let trace = trace_to_poly_values(rows);
+        assert!(lookups_hold(&stark, &trace));
+
+        // The lowest limb of op0, 0x56, lifted out of the u8 range and no
+        // longer counted.
+        let mut out_of_range = trace.clone();
+        out_of_range[OP0_LIMBS.start].values[0] += F::from_canonical_u64(1 << 8);
+        out_of_range[FIX_RANGE_CHECK_U8_FREQ].values[0x56] -= F::ONE;
+        assert!(!lookups_hold(&stark, &out_of_range));
+
+        // One match of 0x56 counted against 0x57 instead.
+        let mut wrong_frequencies = trace;
+        wrong_frequencies[FIX_RANGE_CHECK_U8_FREQ].values[0x56] -= F::ONE;
+        wrong_frequencies[FIX_RANGE_CHECK_U8_FREQ].values[0x57] += F::ONE;
+        assert!(!lookups_hold(&stark, &wrong_frequencies));
+    }
+
+    #[test]
+    fn test_bitwise_fix_compress_is_constrained() {
+        use crate::builtins::bitwise::columns::FIX_COMPRESS;
+        use crate::stark::constraint_check::check_table;
+        use crate::stark::ola_stark::Table;
+
+        type F = GoldilocksField;
+        let (rows, beta) = generate_bitwise_trace::<F>(&[and_row(0x12_3456, 0x0f_0f0f)]);
+        let mut stark = BitwiseStark::<F, 2>::default();
+        stark.set_compress_challenge(beta).unwrap();
+        let mut trace = trace_to_poly_values(rows);
+        let len = trace[0].len();
+        assert!(check_table(&stark, Table::Bitwise, &trace, &mut (0..len)).is_empty());
+
+        // Swapping two entries of the compressed table keeps its multiset but
+        // lets limbs look up tuples outside the bitwise table.
+        trace[FIX_COMPRESS].values.swap(1, 2);
+        let failures = check_table(&stark, Table::Bitwise, &trace, &mut (0..len));
+        let failing_rows: Vec<_> = failures.iter().map(|failure| failure.row).collect();
+        assert_eq!(failing_rows, vec![1, 2]);
+    }
+
     #[test]
     fn test_bitwise_with_program() {
         let file_name = "bitwise.json";
@@ -45,31 +45,62 @@ pub(crate) const RES_LIMBS: Range<usize> = OP1_LIMBS.end..OP1_LIMBS.end + 4;
 //pub(crate) const RES_LIMB_2: usize  = RES_LIMB_1 + 1;
 //pub(crate) const RES_LIMB_3: usize  = RES_LIMB_2 + 1;
 
-pub(crate) const OP0_LIMBS_PERMUTED: Range<usize> = RES_LIMBS.end..RES_LIMBS.end + 4; //16
-pub(crate) const OP1_LIMBS_PERMUTED: Range<usize> =
-    OP0_LIMBS_PERMUTED.end..OP0_LIMBS_PERMUTED.end + 4; //20
-pub(crate) const RES_LIMBS_PERMUTED: Range<usize> =
-    OP1_LIMBS_PERMUTED.end..OP1_LIMBS_PERMUTED.end + 4; //24
+// Whether the limbs are looked up with sorted permuted columns rather than a
+// logUp argument, see the `bitwise_permuted_lookup` feature.
+pub(crate) const PERMUTED_LOOKUP: bool = cfg!(feature = "bitwise_permuted_lookup");
+const NUM_PERMUTED_U8: usize = if PERMUTED_LOOKUP { 12 } else { 0 };
+const NUM_PERMUTED_COMPRESS: usize = if PERMUTED_LOOKUP { 4 } else { 0 };
 
-pub(crate) const COMPRESS_LIMBS: Range<usize> = RES_LIMBS_PERMUTED.end..RES_LIMBS_PERMUTED.end + 4; //28
-pub(crate) const COMPRESS_PERMUTED: Range<usize> = COMPRESS_LIMBS.end..COMPRESS_LIMBS.end + 4; //32
+// OP0_LIMBS, OP1_LIMBS and RES_LIMBS permuted, only with PERMUTED_LOOKUP.
+pub(crate) const LIMBS_PERMUTED: Range<usize> = RES_LIMBS.end..RES_LIMBS.end + NUM_PERMUTED_U8;
 
-// [0...2^8-1]
-pub(crate) const FIX_RANGE_CHECK_U8: usize = COMPRESS_PERMUTED.end; //36
+pub(crate) const COMPRESS_LIMBS: Range<usize> = LIMBS_PERMUTED.end..LIMBS_PERMUTED.end + 4;
+
+// COMPRESS_LIMBS permuted, only with PERMUTED_LOOKUP.
+pub(crate) const COMPRESS_PERMUTED: Range<usize> =
+    COMPRESS_LIMBS.end..COMPRESS_LIMBS.end + NUM_PERMUTED_COMPRESS;
+
+// [0...2^8-1], permuted along each of LIMBS_PERMUTED with PERMUTED_LOOKUP,
+// along with how many limbs each row matches otherwise
+pub(crate) const FIX_RANGE_CHECK_U8: usize = COMPRESS_PERMUTED.end;
 pub(crate) const FIX_RANGE_CHECK_U8_PERMUTED: Range<usize> =
-    FIX_RANGE_CHECK_U8 + 1..FIX_RANGE_CHECK_U8 + 13; //37~48
-                                                     // 1 => AND TABLE
-                                                     // 2 => OR  TABLE
-                                                     // 3 => XOR TABLE
-pub(crate) const FIX_TAG: usize = FIX_RANGE_CHECK_U8_PERMUTED.end; //49
-pub(crate) const FIX_BITWSIE_OP0: usize = FIX_TAG + 1; //50
-pub(crate) const FIX_BITWSIE_OP1: usize = FIX_BITWSIE_OP0 + 1; //51
-pub(crate) const FIX_BITWSIE_RES: usize = FIX_BITWSIE_OP1 + 1; //52
+    FIX_RANGE_CHECK_U8 + 1..FIX_RANGE_CHECK_U8 + 1 + NUM_PERMUTED_U8;
+pub(crate) const FIX_RANGE_CHECK_U8_FREQ: usize = FIX_RANGE_CHECK_U8_PERMUTED.end;
+
+// 1 => AND TABLE
+// 2 => OR  TABLE
+// 3 => XOR TABLE
+pub(crate) const FIX_TAG: usize = if PERMUTED_LOOKUP {
+    FIX_RANGE_CHECK_U8_PERMUTED.end
+} else {
+    FIX_RANGE_CHECK_U8_FREQ + 1
+};
+pub(crate) const FIX_BITWSIE_OP0: usize = FIX_TAG + 1;
+pub(crate) const FIX_BITWSIE_OP1: usize = FIX_BITWSIE_OP0 + 1;
+pub(crate) const FIX_BITWSIE_RES: usize = FIX_BITWSIE_OP1 + 1;
+
+// compressed fixed rows, permuted along each of COMPRESS_PERMUTED with
+// PERMUTED_LOOKUP, along with how many compressed limbs each row matches
+// otherwise
+pub(crate) const FIX_COMPRESS: usize = FIX_BITWSIE_RES + 1;
+pub(crate) const FIX_COMPRESS_PERMUTED: Range<usize> =
+    FIX_COMPRESS + 1..FIX_COMPRESS + 1 + NUM_PERMUTED_COMPRESS;
+pub(crate) const FIX_COMPRESS_FREQ: usize = FIX_COMPRESS_PERMUTED.end;
 
-pub(crate) const FIX_COMPRESS: usize = FIX_BITWSIE_RES + 1; //53
-pub(crate) const FIX_COMPRESS_PERMUTED: Range<usize> = FIX_COMPRESS + 1..FIX_COMPRESS + 5; //54~57
+pub(crate) const COL_NUM_BITWISE: usize = if PERMUTED_LOOKUP {
+    FIX_COMPRESS_PERMUTED.end //59
+} else {
+    FIX_COMPRESS_FREQ + 1 //29
+};
 
-pub(crate) const COL_NUM_BITWISE: usize = FIX_COMPRESS_PERMUTED.end; //58
+// fixed columns only depending on the number of rows, committed in the keys
+pub(crate) const PREPROCESSED_COLS: [usize; 5] = [
+    FIX_RANGE_CHECK_U8,
+    FIX_TAG,
+    FIX_BITWSIE_OP0,
+    FIX_BITWSIE_OP1,
+    FIX_BITWSIE_RES,
+];
 
 pub(crate) const RANGE_CHECK_U8_SIZE: usize = 1 << 8;
 //pub(crate) const BITWISE_U8_SIZE_PER: usize = (1 << 15) + (1 << 7);
@@ -96,16 +127,8 @@ pub(crate) fn get_bitwise_col_name_map() -> BTreeMap<usize, String> {
         let name = format!("RES_LIMB_{}", index);
         m.insert(col, name);
     }
-    for (index, col) in OP0_LIMBS_PERMUTED.into_iter().enumerate() {
-        let name = format!("OP0_LIMB_{}_PERMUTED", index);
-        m.insert(col, name);
-    }
-    for (index, col) in OP1_LIMBS_PERMUTED.into_iter().enumerate() {
-        let name = format!("OP1_LIMB_{}_PERMUTED", index);
-        m.insert(col, name);
-    }
-    for (index, col) in RES_LIMBS_PERMUTED.into_iter().enumerate() {
-        let name = format!("RES_LIMB_{}_PERMUTED", index);
+    for (index, col) in LIMBS_PERMUTED.enumerate() {
+        let name = format!("LIMB_{}_PERMUTED", index);
         m.insert(col, name);
     }
     for (index, col) in COMPRESS_LIMBS.into_iter().enumerate() {
@@ -121,6 +144,12 @@ pub(crate) fn get_bitwise_col_name_map() -> BTreeMap<usize, String> {
         let name = format!("FIX_RANGE_CHECK_U8_PERMUTED_{}", index);
         m.insert(col, name);
     }
+    if !PERMUTED_LOOKUP {
+        m.insert(
+            FIX_RANGE_CHECK_U8_FREQ,
+            String::from("FIX_RANGE_CHECK_U8_FREQ"),
+        );
+    }
     m.insert(FIX_TAG, String::from("FIX_TAG"));
     m.insert(FIX_BITWSIE_OP0, String::from("FIX_BITWSIE_OP0"));
     m.insert(FIX_BITWSIE_OP1, String::from("FIX_BITWSIE_OP1"));
@@ -130,6 +159,9 @@ pub(crate) fn get_bitwise_col_name_map() -> BTreeMap<usize, String> {
         let name = format!("FIX_COMPRESS_PERMUTED_{}", index);
         m.insert(col, name);
     }
+    if !PERMUTED_LOOKUP {
+        m.insert(FIX_COMPRESS_FREQ, String::from("FIX_COMPRESS_FREQ"));
+    }
     m
 }
 
@@ -1,10 +1,17 @@
+use core::trace::trace::Trace;
 use crate::builtins::cmp::columns::*;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+use crate::builtins::rangecheck::rangecheck_stark;
+use crate::cpu::cpu_stark;
+use crate::generation::builtin::generate_cmp_trace;
 use crate::stark::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
-use crate::stark::cross_table_lookup::Column;
+use crate::stark::cross_table_lookup::{Column, CrossTableLookup, TableWithColumns};
+use crate::stark::ola_stark::Table;
 use crate::stark::stark::Stark;
+use crate::stark::table::{OlaTable, TraceJob};
+use crate::stark::util::trace_to_poly_values;
 use crate::stark::vars::{StarkEvaluationTargets, StarkEvaluationVars};
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
@@ -97,6 +104,48 @@ pub fn ctl_data_with_cpu<F: Field>() -> Vec<Column<F>> {
 pub fn ctl_filter_with_cpu<F: Field>() -> Column<F> {
     Column::single(COL_CMP_FILTER_LOOKING_RC)
 }
+impl<F: RichField + Extendable<D>, const D: usize> OlaTable<F, D> for CmpStark<F, D> {
+    fn table(&self) -> Table {
+        Table::Cmp
+    }
+
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F> {
+        let cells = std::mem::take(&mut trace.builtin_cmp);
+        Box::new(move || (trace_to_poly_values(generate_cmp_trace(&cells)), None))
+    }
+
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        vec![ctl_cmp_cpu(), ctl_cmp_rangecheck()]
+    }
+}
+
+// add CMP cross lookup instance
+fn ctl_cmp_cpu<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new_logup(
+        vec![TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_with_cmp(),
+            Some(cpu_stark::ctl_filter_with_cmp()),
+        )],
+        TableWithColumns::new(Table::Cmp, ctl_data_with_cpu(), Some(ctl_filter_with_cpu())),
+    )
+}
+
+fn ctl_cmp_rangecheck<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new_logup(
+        vec![TableWithColumns::new(
+            Table::RangeCheck,
+            rangecheck_stark::ctl_data_with_cmp(),
+            Some(rangecheck_stark::ctl_filter_with_cmp()),
+        )],
+        TableWithColumns::new(
+            Table::Cmp,
+            ctl_data_with_rangecheck(),
+            Some(ctl_filter_with_rangecheck()),
+        ),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use crate::builtins::cmp::cmp_stark::CmpStark;
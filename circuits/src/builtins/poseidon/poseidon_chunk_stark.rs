@@ -1,3 +1,4 @@
+use core::trace::trace::Trace;
 use core::types::Field;
 use std::marker::PhantomData;
 
@@ -12,10 +13,15 @@ use plonky2::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::cpu::cpu_stark;
+use crate::generation::poseidon_chunk::generate_poseidon_chunk_trace;
 use crate::stark::{
     constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer},
-    cross_table_lookup::Column,
+    cross_table_lookup::{Column, CrossTableLookup, TableWithColumns},
+    ola_stark::Table,
     stark::Stark,
+    table::{OlaTable, TraceJob},
+    util::trace_to_poly_values,
     vars::{StarkEvaluationTargets, StarkEvaluationVars},
 };
 
@@ -285,6 +291,39 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for PoseidonChunk
     }
 }
 
+impl<F: RichField + Extendable<D>, const D: usize> OlaTable<F, D> for PoseidonChunkStark<F, D> {
+    fn table(&self) -> Table {
+        Table::PoseidonChunk
+    }
+
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F> {
+        let cells = std::mem::take(&mut trace.builtin_poseidon_chunk);
+        Box::new(move || {
+            let rows = generate_poseidon_chunk_trace(&cells);
+            (trace_to_poly_values(rows), None)
+        })
+    }
+
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        vec![ctl_cpu_poseidon_chunk()]
+    }
+}
+
+fn ctl_cpu_poseidon_chunk<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new(
+        vec![TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_with_poseidon_chunk(),
+            Some(cpu_stark::ctl_filter_with_poseidon_chunk()),
+        )],
+        TableWithColumns::new(
+            Table::PoseidonChunk,
+            ctl_data_with_cpu(),
+            Some(ctl_filter_with_cpu()),
+        ),
+    )
+}
+
 mod test {
     use core::trace::trace::{PoseidonChunkRow, Trace};
     use core::types::Field;
@@ -1,8 +1,17 @@
 use crate::builtins::poseidon::columns::*;
+use crate::builtins::poseidon::poseidon_chunk_stark;
+use crate::builtins::storage::storage_access_stark;
+use crate::cpu::cpu_stark;
+use crate::generation::poseidon::generate_poseidon_trace;
+use crate::program::prog_chunk_stark;
 use crate::stark::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
-use crate::stark::cross_table_lookup::Column;
+use crate::stark::cross_table_lookup::{Column, CrossTableLookup, TableWithColumns};
+use crate::stark::ola_stark::Table;
 use crate::stark::stark::Stark;
+use crate::stark::table::{OlaTable, TraceJob};
+use crate::stark::util::trace_to_poly_values;
 use crate::stark::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+use core::trace::trace::Trace;
 use core::util::poseidon_utils::{
     constant_layer_field, mds_layer_field, mds_partial_layer_fast_field, mds_partial_layer_init,
     partial_first_constant_layer, sbox_layer_field, sbox_monomial, POSEIDON_STATE_WIDTH,
@@ -180,6 +189,92 @@ pub fn ctl_filter_with_storage<F: Field>() -> Column<F> {
     Column::sum([FILTER_LOOKED_STORAGE_LEAF, FILTER_LOOKED_STORAGE_BRANCH])
 }
 
+impl<F: RichField + Extendable<D>, const D: usize> OlaTable<F, D> for PoseidonStark<F, D> {
+    fn table(&self) -> Table {
+        Table::Poseidon
+    }
+
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F> {
+        let cells = std::mem::take(&mut trace.builtin_poseidon);
+        Box::new(move || (trace_to_poly_values(generate_poseidon_trace(&cells)), None))
+    }
+
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        vec![
+            ctl_chunk_poseidon(),
+            ctl_cpu_poseidon_tree_key(),
+            ctl_storage_access_poseidon(),
+        ]
+    }
+}
+
+fn ctl_chunk_poseidon<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new(
+        vec![
+            TableWithColumns::new(
+                Table::PoseidonChunk,
+                poseidon_chunk_stark::ctl_data_with_poseidon(),
+                Some(poseidon_chunk_stark::ctl_filter_with_poseidon()),
+            ),
+            TableWithColumns::new(
+                Table::ProgChunk,
+                prog_chunk_stark::ctl_data_to_poseidon(),
+                Some(prog_chunk_stark::ctl_filter_to_poseidon()),
+            ),
+        ],
+        TableWithColumns::new(
+            Table::Poseidon,
+            ctl_data_with_poseidon_chunk(),
+            Some(ctl_filter_with_poseidon_chunk()),
+        ),
+    )
+}
+
+fn ctl_cpu_poseidon_tree_key<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new(
+        vec![TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_poseidon_treekey(),
+            Some(cpu_stark::ctl_filter_poseidon_treekey()),
+        )],
+        TableWithColumns::new(
+            Table::Poseidon,
+            ctl_data_cpu_tree_key(),
+            Some(ctl_filter_cpu_tree_key()),
+        ),
+    )
+}
+
+fn ctl_storage_access_poseidon<F: Field>() -> CrossTableLookup<F> {
+    let looker_bit0 = TableWithColumns::new(
+        Table::StorageAccess,
+        storage_access_stark::ctl_data_with_poseidon_bit0(),
+        Some(storage_access_stark::ctl_filter_with_poseidon_bit0()),
+    );
+    let looker_bit0_pre = TableWithColumns::new(
+        Table::StorageAccess,
+        storage_access_stark::ctl_data_with_poseidon_bit0_pre(),
+        Some(storage_access_stark::ctl_filter_with_poseidon_bit0()),
+    );
+    let looker_bit1 = TableWithColumns::new(
+        Table::StorageAccess,
+        storage_access_stark::ctl_data_with_poseidon_bit1(),
+        Some(storage_access_stark::ctl_filter_with_poseidon_bit1()),
+    );
+    let looker_bit1_pre = TableWithColumns::new(
+        Table::StorageAccess,
+        storage_access_stark::ctl_data_with_poseidon_bit1_pre(),
+        Some(storage_access_stark::ctl_filter_with_poseidon_bit1()),
+    );
+    let all_lookers = vec![looker_bit0, looker_bit0_pre, looker_bit1, looker_bit1_pre];
+    let looked = TableWithColumns::new(
+        Table::Poseidon,
+        ctl_data_with_storage(),
+        Some(ctl_filter_with_storage()),
+    );
+    CrossTableLookup::new(all_lookers, looked)
+}
+
 mod test {
     use core::trace::trace::{PoseidonRow, Trace};
     use core::types::Field;
@@ -1,5 +1,8 @@
 // 2022-12-19: written by xb
 
+use std::collections::BTreeMap;
+use std::ops::Range;
+
 /* RC_Table construction as follows:
 +-----+---------+---------+------+
 | val | limb_lo | limb_hi | TAG
@@ -32,13 +35,56 @@ pub(crate) const CMP_FILTER: usize = MEMORY_REGION_FILTER + 1;
 pub(crate) const VAL: usize = CMP_FILTER + 1;
 pub(crate) const LIMB_LO: usize = VAL + 1;
 pub(crate) const LIMB_HI: usize = LIMB_LO + 1;
-pub(crate) const LIMB_LO_PERMUTED: usize = LIMB_HI + 1;
-pub(crate) const LIMB_HI_PERMUTED: usize = LIMB_LO_PERMUTED + 1;
 
-pub(crate) const FIX_RANGE_CHECK_U16: usize = LIMB_HI_PERMUTED + 1;
-pub(crate) const FIX_RANGE_CHECK_U16_PERMUTED_LO: usize = FIX_RANGE_CHECK_U16 + 1;
-pub(crate) const FIX_RANGE_CHECK_U16_PERMUTED_HI: usize = FIX_RANGE_CHECK_U16_PERMUTED_LO + 1;
+// Whether the limbs are looked up with sorted permuted columns rather than a
+// logUp argument, see the `rangecheck_permuted_lookup` feature.
+pub(crate) const PERMUTED_LOOKUP: bool = cfg!(feature = "rangecheck_permuted_lookup");
+const NUM_PERMUTED: usize = if PERMUTED_LOOKUP { 2 } else { 0 };
+
+// LIMB_LO and LIMB_HI permuted, only with PERMUTED_LOOKUP.
+pub(crate) const LIMBS_PERMUTED: Range<usize> = LIMB_HI + 1..LIMB_HI + 1 + NUM_PERMUTED;
+
+pub(crate) const FIX_RANGE_CHECK_U16: usize = LIMBS_PERMUTED.end;
+// FIX_RANGE_CHECK_U16 permuted along each of LIMBS_PERMUTED, only with
+// PERMUTED_LOOKUP.
+pub(crate) const FIX_RANGE_CHECK_U16_PERMUTED: Range<usize> =
+    FIX_RANGE_CHECK_U16 + 1..FIX_RANGE_CHECK_U16 + 1 + NUM_PERMUTED;
+// How many limbs each row of FIX_RANGE_CHECK_U16 matches, only without
+// PERMUTED_LOOKUP.
+pub(crate) const FIX_RANGE_CHECK_U16_FREQ: usize = FIX_RANGE_CHECK_U16_PERMUTED.end;
+
+pub(crate) const COL_NUM_RC: usize = if PERMUTED_LOOKUP {
+    FIX_RANGE_CHECK_U16_PERMUTED.end //11
+} else {
+    FIX_RANGE_CHECK_U16_FREQ + 1 //10
+};
 
-pub(crate) const COL_NUM_RC: usize = FIX_RANGE_CHECK_U16_PERMUTED_HI + 1; //11
+pub(crate) fn get_rangecheck_col_name_map() -> BTreeMap<usize, String> {
+    let mut m: BTreeMap<usize, String> = BTreeMap::new();
+    m.insert(CPU_FILTER, "cpu_filter".to_string());
+    m.insert(MEMORY_SORT_FILTER, "memory_sort_filter".to_string());
+    m.insert(MEMORY_REGION_FILTER, "memory_region_filter".to_string());
+    m.insert(CMP_FILTER, "cmp_filter".to_string());
+    m.insert(VAL, "val".to_string());
+    m.insert(LIMB_LO, "limb_lo".to_string());
+    m.insert(LIMB_HI, "limb_hi".to_string());
+    for (col, name) in LIMBS_PERMUTED.zip(["limb_lo_permuted", "limb_hi_permuted"]) {
+        m.insert(col, name.to_string());
+    }
+    m.insert(FIX_RANGE_CHECK_U16, "fix_range_check_u16".to_string());
+    for (col, name) in FIX_RANGE_CHECK_U16_PERMUTED.zip([
+        "fix_range_check_u16_permuted_lo",
+        "fix_range_check_u16_permuted_hi",
+    ]) {
+        m.insert(col, name.to_string());
+    }
+    if !PERMUTED_LOOKUP {
+        m.insert(
+            FIX_RANGE_CHECK_U16_FREQ,
+            "fix_range_check_u16_freq".to_string(),
+        );
+    }
+    m
+}
 
 pub(crate) const RANGE_CHECK_U16_SIZE: usize = 1 << 16; //4
@@ -1,15 +1,23 @@
+use core::trace::trace::Trace;
 use crate::builtins::rangecheck::columns::*;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+use crate::cpu::cpu_stark;
+use crate::generation::builtin::{generate_rc_trace, rangecheck_fixed_column};
+use crate::memory::memory_stark;
 use crate::stark::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
-use crate::stark::cross_table_lookup::Column;
-use crate::stark::lookup::*;
-use crate::stark::permutation::*;
+use crate::stark::cross_table_lookup::{Column, CrossTableLookup, TableWithColumns};
+use crate::stark::lookup::{eval_lookups, eval_lookups_circuit, Lookup};
+use crate::stark::ola_stark::Table;
+use crate::stark::permutation::PermutationPair;
 use crate::stark::stark::Stark;
+use crate::stark::table::{OlaTable, TraceJob};
+use crate::stark::util::trace_to_poly_values;
 use crate::stark::vars::{StarkEvaluationTargets, StarkEvaluationVars};
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
 use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
@@ -48,18 +56,9 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for RangeCheckSta
 
         yield_constr.constraint(val - sum);
 
-        eval_lookups(
-            vars,
-            yield_constr,
-            LIMB_LO_PERMUTED,
-            FIX_RANGE_CHECK_U16_PERMUTED_LO,
-        );
-        eval_lookups(
-            vars,
-            yield_constr,
-            LIMB_HI_PERMUTED,
-            FIX_RANGE_CHECK_U16_PERMUTED_HI,
-        );
+        for (input, table) in LIMBS_PERMUTED.zip(FIX_RANGE_CHECK_U16_PERMUTED) {
+            eval_lookups(vars, yield_constr, input, table);
+        }
     }
 
     fn eval_ext_circuit(
@@ -78,20 +77,9 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for RangeCheckSta
         let val_sum_diff = builder.sub_extension(val, sum);
         yield_constr.constraint(builder, val_sum_diff);
 
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            LIMB_LO_PERMUTED,
-            FIX_RANGE_CHECK_U16_PERMUTED_LO,
-        );
-        eval_lookups_circuit(
-            builder,
-            vars,
-            yield_constr,
-            LIMB_HI_PERMUTED,
-            FIX_RANGE_CHECK_U16_PERMUTED_HI,
-        );
+        for (input, table) in LIMBS_PERMUTED.zip(FIX_RANGE_CHECK_U16_PERMUTED) {
+            eval_lookups_circuit(builder, vars, yield_constr, input, table);
+        }
     }
 
     fn constraint_degree(&self) -> usize {
@@ -99,12 +87,38 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for RangeCheckSta
     }
 
     fn permutation_pairs(&self) -> Vec<PermutationPair> {
-        vec![
-            PermutationPair::singletons(LIMB_LO, LIMB_LO_PERMUTED),
-            PermutationPair::singletons(LIMB_HI, LIMB_HI_PERMUTED),
-            PermutationPair::singletons(FIX_RANGE_CHECK_U16, FIX_RANGE_CHECK_U16_PERMUTED_LO),
-            PermutationPair::singletons(FIX_RANGE_CHECK_U16, FIX_RANGE_CHECK_U16_PERMUTED_HI),
-        ]
+        [LIMB_LO, LIMB_HI]
+            .into_iter()
+            .zip(LIMBS_PERMUTED.zip(FIX_RANGE_CHECK_U16_PERMUTED))
+            .flat_map(|(limb, (input, table))| {
+                [
+                    PermutationPair::singletons(limb, input),
+                    PermutationPair::singletons(FIX_RANGE_CHECK_U16, table),
+                ]
+            })
+            .collect()
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        if PERMUTED_LOOKUP {
+            return vec![];
+        }
+        vec![Lookup {
+            columns: vec![LIMB_LO, LIMB_HI],
+            table_column: FIX_RANGE_CHECK_U16,
+            frequencies_column: FIX_RANGE_CHECK_U16_FREQ,
+        }]
+    }
+
+    fn preprocessed_columns(&self) -> Vec<usize> {
+        vec![FIX_RANGE_CHECK_U16]
+    }
+
+    fn preprocessed_values(&self, degree_bits: usize) -> Option<Vec<PolynomialValues<F>>> {
+        // The trace has at least `RANGE_CHECK_U16_SIZE` rows.
+        let len = 1 << degree_bits;
+        (len >= RANGE_CHECK_U16_SIZE)
+            .then(|| vec![PolynomialValues::new(rangecheck_fixed_column(len))])
     }
 }
 
@@ -146,6 +160,71 @@ pub fn ctl_filter_with_cpu<F: Field>() -> Column<F> {
 pub fn ctl_filter_with_rangecheck_fixed<F: Field>() -> Column<F> {
     Column::one()
 }*/
+impl<F: RichField + Extendable<D>, const D: usize> OlaTable<F, D> for RangeCheckStark<F, D> {
+    fn table(&self) -> Table {
+        Table::RangeCheck
+    }
+
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F> {
+        let cells = std::mem::take(&mut trace.builtin_rangecheck);
+        Box::new(move || (trace_to_poly_values(generate_rc_trace(&cells)), None))
+    }
+
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        vec![
+            ctl_memory_rc_sort(),
+            ctl_memory_rc_region(),
+            ctl_rangecheck_cpu(),
+        ]
+    }
+}
+
+fn ctl_memory_rc_sort<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new_logup(
+        vec![TableWithColumns::new(
+            Table::Memory,
+            memory_stark::ctl_data_mem_sort_rc(),
+            Some(memory_stark::ctl_filter_mem_sort_rc()),
+        )],
+        TableWithColumns::new(
+            Table::RangeCheck,
+            ctl_data_memory(),
+            Some(ctl_filter_memory_sort()),
+        ),
+    )
+}
+
+fn ctl_memory_rc_region<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new_logup(
+        vec![TableWithColumns::new(
+            Table::Memory,
+            memory_stark::ctl_data_mem_rc_diff_cond(),
+            Some(memory_stark::ctl_filter_mem_rc_diff_cond()),
+        )],
+        TableWithColumns::new(
+            Table::RangeCheck,
+            ctl_data_memory(),
+            Some(ctl_filter_memory_region()),
+        ),
+    )
+}
+
+// add Rangecheck cross lookup instance
+fn ctl_rangecheck_cpu<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new_logup(
+        vec![TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_with_rangecheck(),
+            Some(cpu_stark::ctl_filter_with_rangecheck()),
+        )],
+        TableWithColumns::new(
+            Table::RangeCheck,
+            ctl_data_with_cpu(),
+            Some(ctl_filter_with_cpu()),
+        ),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use crate::builtins::rangecheck::rangecheck_stark::RangeCheckStark;
@@ -252,6 +331,42 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(not(feature = "rangecheck_permuted_lookup"))]
+    fn test_rangecheck_lookup_rejects_bad_limbs() {
+        use crate::builtins::rangecheck::columns::{FIX_RANGE_CHECK_U16_FREQ, LIMB_LO};
+        use crate::stark::lookup::lookups_hold;
+        use crate::stark::util::trace_to_poly_values;
+        use core::trace::trace::RangeCheckRow;
+
+        type F = GoldilocksField;
+        let stark = RangeCheckStark::<F, 2>::default();
+        let cells = [5u64, 70_000, 1 << 20].map(|val| RangeCheckRow {
+            val: F::from_canonical_u64(val),
+            limb_lo: F::from_canonical_u64(val & 0xffff),
+            limb_hi: F::from_canonical_u64(val >> 16),
+            filter_looked_for_mem_sort: F::ZERO,
+            filter_looked_for_mem_region: F::ZERO,
+            filter_looked_for_cpu: F::ONE,
+            filter_looked_for_comparison: F::ZERO,
+            filter_looked_for_storage: F::ZERO,
+        });
+        let trace = trace_to_poly_values(generate_rc_trace::<F>(&cells));
+        assert!(lookups_hold(&stark, &trace));
+
+        // The low limb of 5 lifted out of the u16 range, and no longer counted.
+        let mut out_of_range = trace.clone();
+        out_of_range[LIMB_LO].values[0] += F::from_canonical_u64(1 << 16);
+        out_of_range[FIX_RANGE_CHECK_U16_FREQ].values[5] -= F::ONE;
+        assert!(!lookups_hold(&stark, &out_of_range));
+
+        // One match of 5 counted against 6 instead.
+        let mut wrong_frequencies = trace;
+        wrong_frequencies[FIX_RANGE_CHECK_U16_FREQ].values[5] -= F::ONE;
+        wrong_frequencies[FIX_RANGE_CHECK_U16_FREQ].values[6] += F::ONE;
+        assert!(!lookups_hold(&stark, &wrong_frequencies));
+    }
+
     #[test]
     fn test_rangecheck_with_program() {
         let program_path = "range_check.json";
@@ -1,3 +1,4 @@
+use core::trace::trace::Trace;
 use core::types::Field;
 use std::marker::PhantomData;
 
@@ -12,10 +13,15 @@ use plonky2::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::cpu::cpu_stark;
+use crate::generation::sccall::generate_sccall_trace;
 use crate::stark::{
     constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer},
-    cross_table_lookup::Column,
+    cross_table_lookup::{Column, CrossTableLookup, TableWithColumns},
+    ola_stark::Table,
     stark::Stark,
+    table::{OlaTable, TraceJob},
+    util::trace_to_poly_values,
     vars::{StarkEvaluationTargets, StarkEvaluationVars},
 };
 
@@ -75,11 +81,21 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for SCCallStark<F
         FE: FieldExtension<D2, BaseField = F>,
         P: PackedField<Scalar = FE>,
     {
+        let lv = vars.local_values;
+        let nv = vars.next_values;
         yield_constr.constraint(
-            vars.local_values[COL_SCCALL_CLK_CALLER_RET]
-                - vars.local_values[COL_SCCALL_CLK_CALLER_CALL]
-                - vars.local_values[COL_SCCALL_CALLER_OP1_IMM],
+            lv[COL_SCCALL_CLK_CALLER_RET]
+                - lv[COL_SCCALL_CLK_CALLER_CALL]
+                - lv[COL_SCCALL_CALLER_OP1_IMM],
         );
+
+        // padding rows are all zero and only at the end
+        let is_padding = lv[COL_SCCALL_IS_PADDING];
+        yield_constr.constraint(is_padding * (P::ONES - is_padding));
+        for col in lv.iter().take(COL_SCCALL_IS_PADDING) {
+            yield_constr.constraint(is_padding * *col);
+        }
+        yield_constr.constraint_transition(is_padding * (P::ONES - nv[COL_SCCALL_IS_PADDING]));
     }
 
     fn eval_ext_circuit(
@@ -91,8 +107,49 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for SCCallStark<F
     }
 
     fn constraint_degree(&self) -> usize {
-        1
+        2
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> OlaTable<F, D> for SCCallStark<F, D> {
+    fn table(&self) -> Table {
+        Table::SCCall
+    }
+
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F> {
+        let cells = std::mem::take(&mut trace.sc_call);
+        Box::new(move || (trace_to_poly_values(generate_sccall_trace(&cells)), None))
     }
+
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        vec![ctl_cpu_sccall(), ctl_cpu_sccall_end()]
+    }
+}
+
+fn ctl_cpu_sccall<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new(
+        vec![TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_cpu_sccall(),
+            Some(cpu_stark::ctl_filter_cpu_sccall()),
+        )],
+        TableWithColumns::new(Table::SCCall, ctl_data_sccall(), Some(ctl_filter_sccall())),
+    )
+}
+
+fn ctl_cpu_sccall_end<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new(
+        vec![TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_cpu_sccall_end(),
+            Some(cpu_stark::ctl_filter_cpu_sccall_end()),
+        )],
+        TableWithColumns::new(
+            Table::SCCall,
+            ctl_data_sccall_end(),
+            Some(ctl_filter_sccall_end()),
+        ),
+    )
 }
 
 #[cfg(test)]
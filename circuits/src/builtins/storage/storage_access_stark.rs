@@ -1,3 +1,4 @@
+use core::trace::trace::Trace;
 use core::types::Field;
 use std::marker::PhantomData;
 
@@ -12,10 +13,16 @@ use plonky2::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::cpu::cpu_stark;
+use crate::generation::storage::generate_storage_access_trace;
+use crate::program::prog_chunk_stark;
 use crate::stark::{
     constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer},
-    cross_table_lookup::Column,
+    cross_table_lookup::{Column, CrossTableLookup, TableWithColumns},
+    ola_stark::Table,
     stark::Stark,
+    table::{OlaTable, TraceJob},
+    util::trace_to_poly_values,
     vars::{StarkEvaluationTargets, StarkEvaluationVars},
 };
 
@@ -335,6 +342,55 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for StorageAccess
     }
 }
 
+impl<F: RichField + Extendable<D>, const D: usize> OlaTable<F, D> for StorageAccessStark<F, D> {
+    fn table(&self) -> Table {
+        Table::StorageAccess
+    }
+
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F> {
+        let accesses = std::mem::take(&mut trace.builtin_storage_hash);
+        let prog_hash_reads = std::mem::take(&mut trace.builtin_program_hash);
+        Box::new(move || {
+            let rows = generate_storage_access_trace(&accesses, &prog_hash_reads);
+            (trace_to_poly_values(rows), None)
+        })
+    }
+
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        vec![ctl_cpu_storage_access(), ctl_prog_chunk_storage()]
+    }
+}
+
+fn ctl_cpu_storage_access<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new(
+        vec![TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_cpu_storage_access(),
+            Some(cpu_stark::ctl_filter_cpu_storage_access()),
+        )],
+        TableWithColumns::new(
+            Table::StorageAccess,
+            ctl_data_with_cpu(),
+            Some(ctl_filter_with_cpu_sstore()),
+        ),
+    )
+}
+
+fn ctl_prog_chunk_storage<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new(
+        vec![TableWithColumns::new(
+            Table::ProgChunk,
+            prog_chunk_stark::ctl_data_to_storage_access(),
+            Some(prog_chunk_stark::ctl_filter_to_storage_access()),
+        )],
+        TableWithColumns::new(
+            Table::StorageAccess,
+            ctl_data_for_prog_chunk(),
+            Some(ctl_filter_for_prog_chunk()),
+        ),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -1,4 +1,5 @@
-use core::{types::Field, vm::opcodes::OlaOpcode};
+use core::{trace::trace::Trace, types::Field, vm::opcodes::OlaOpcode};
+use std::iter;
 use std::marker::PhantomData;
 
 use itertools::Itertools;
@@ -12,10 +13,15 @@ use plonky2::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::cpu::cpu_stark;
+use crate::generation::tape::generate_tape_trace;
 use crate::stark::{
     constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer},
-    cross_table_lookup::Column,
+    cross_table_lookup::{Column, CrossTableLookup, TableWithColumns},
+    ola_stark::Table,
     stark::Stark,
+    table::{OlaTable, TraceJob},
+    util::trace_to_poly_values,
     vars::{StarkEvaluationTargets, StarkEvaluationVars},
 };
 
@@ -144,6 +150,59 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for TapeStark<F,
     }
 }
 
+impl<F: RichField + Extendable<D>, const D: usize> OlaTable<F, D> for TapeStark<F, D> {
+    fn table(&self) -> Table {
+        Table::Tape
+    }
+
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F> {
+        let cells = std::mem::take(&mut trace.tape);
+        Box::new(move || (trace_to_poly_values(generate_tape_trace(&cells)), None))
+    }
+
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        vec![ctl_cpu_tape()]
+    }
+}
+
+fn ctl_cpu_tape<F: Field>() -> CrossTableLookup<F> {
+    let cpu_tape_tload_tstore = TableWithColumns::new(
+        Table::Cpu,
+        cpu_stark::ctl_data_cpu_tape_load_store(),
+        Some(cpu_stark::ctl_filter_cpu_tape_load_store()),
+    );
+    let cpu_tape_sccall_caller = (0..4).map(|i: usize| {
+        TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_cpu_tape_sccall_caller(i),
+            Some(cpu_stark::ctl_filter_cpu_is_sccall_ext()),
+        )
+    });
+    let cpu_tape_sccall_callee_code = (0..4).map(|i: usize| {
+        TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_cpu_tape_sccall_callee_code(i),
+            Some(cpu_stark::ctl_filter_cpu_is_sccall_ext()),
+        )
+    });
+    let cpu_tape_sccall_callee_storage = (0..4).map(|i: usize| {
+        TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_cpu_tape_sccall_callee_storage(i),
+            Some(cpu_stark::ctl_filter_cpu_is_sccall_ext()),
+        )
+    });
+
+    let all_lookers = iter::once(cpu_tape_tload_tstore)
+        .chain(cpu_tape_sccall_caller)
+        .chain(cpu_tape_sccall_callee_code)
+        .chain(cpu_tape_sccall_callee_storage)
+        .collect();
+
+    let tape_looked = TableWithColumns::new(Table::Tape, ctl_data_tape(), Some(ctl_filter_tape()));
+    CrossTableLookup::new(all_lookers, tape_looked)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::stark::stark::Stark;
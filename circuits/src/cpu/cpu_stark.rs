@@ -1,12 +1,16 @@
-use core::{program::CTX_REGISTER_NUM, vm::opcodes::OlaOpcode};
+use core::{program::CTX_REGISTER_NUM, trace::trace::Trace, vm::opcodes::OlaOpcode};
 
 use serde::{Deserialize, Serialize};
 
 use {
     super::{columns::*, *},
+    crate::generation::cpu::generate_cpu_trace,
     crate::stark::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer},
     crate::stark::cross_table_lookup::Column,
+    crate::stark::ola_stark::Table,
     crate::stark::stark::Stark,
+    crate::stark::table::{OlaTable, TraceJob},
+    crate::stark::util::trace_to_poly_values,
     crate::stark::vars::{StarkEvaluationTargets, StarkEvaluationVars},
     core::program::REGISTER_NUM,
     itertools::izip,
@@ -960,6 +964,17 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for CpuStark<F, D
     }
 }
 
+impl<F: RichField + Extendable<D>, const D: usize> OlaTable<F, D> for CpuStark<F, D> {
+    fn table(&self) -> Table {
+        Table::Cpu
+    }
+
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F> {
+        let exec = trace.exec.clone();
+        Box::new(move || (trace_to_poly_values(generate_cpu_trace(&exec)), None))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{generation::cpu::generate_cpu_trace, test_utils::test_stark_with_asm_path};
@@ -12,7 +12,113 @@ use crate::builtins::cmp::columns::{
     COL_CMP_OP1,
 };
 use crate::builtins::rangecheck::columns as rangecheck;
-use crate::stark::lookup::permuted_cols;
+use crate::stark::lookup::{lookup_frequencies, permuted_cols};
+
+/// The fixed columns of the bitwise table at `len` rows, in the order of
+/// `bitwise::PREPROCESSED_COLS`: the u8 range, then the AND, OR and XOR tables
+/// of all pairs of u8 operands, zero-padded.
+pub fn bitwise_fixed_columns<F: RichField>(len: usize) -> Vec<Vec<F>> {
+    let mut range_check_u8 = (0..bitwise::RANGE_CHECK_U8_SIZE)
+        .map(F::from_canonical_usize)
+        .collect::<Vec<_>>();
+    range_check_u8.resize(len, F::ZERO);
+    let mut tag = vec![F::ZERO; len];
+    let mut op0_col = vec![F::ZERO; len];
+    let mut op1_col = vec![F::ZERO; len];
+    let mut res_col = vec![F::ZERO; len];
+    // for 2^8 case, the row is 2^15 + 2^7
+    // fixed at 2023-1-16, for 2^8 case, row number is 2^16
+    let mut index = 0;
+    for op0 in 0..bitwise::RANGE_CHECK_U8_SIZE {
+        for op1 in 0..bitwise::RANGE_CHECK_U8_SIZE {
+            for (i, (opcode, res)) in [
+                (Opcode::AND, op0 & op1),
+                (Opcode::OR, op0 | op1),
+                (Opcode::XOR, op0 ^ op1),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let row = bitwise::BITWISE_U8_SIZE_PER * i + index;
+                op0_col[row] = F::from_canonical_usize(op0);
+                op1_col[row] = F::from_canonical_usize(op1);
+                res_col[row] = F::from_canonical_usize(res);
+                tag[row] = F::from_canonical_u64(1_u64 << opcode as u8);
+            }
+            index += 1;
+        }
+    }
+    vec![range_check_u8, tag, op0_col, op1_col, res_col]
+}
+
+/// The fixed column of the rangecheck table at `len` rows: the u16 range,
+/// padded with its last value.
+pub fn rangecheck_fixed_column<F: RichField>(len: usize) -> Vec<F> {
+    (0..len)
+        .map(|i| F::from_canonical_usize(i.min(rangecheck::RANGE_CHECK_U16_SIZE - 1)))
+        .collect()
+}
+
+/// Fills the columns the limb and compress lookups of the bitwise table need:
+/// the permuted columns with the `bitwise_permuted_lookup` feature, the
+/// multiplicities otherwise.
+fn fill_bitwise_lookup_columns<F: RichField>(trace: &mut [Vec<F>]) {
+    let u8_limbs = bitwise::OP0_LIMBS
+        .chain(bitwise::OP1_LIMBS)
+        .chain(bitwise::RES_LIMBS);
+    if bitwise::PERMUTED_LOOKUP {
+        let lookups = u8_limbs
+            .zip(bitwise::LIMBS_PERMUTED.zip(bitwise::FIX_RANGE_CHECK_U8_PERMUTED))
+            .map(|(limb, permuted)| (limb, bitwise::FIX_RANGE_CHECK_U8, permuted))
+            .chain(
+                bitwise::COMPRESS_LIMBS
+                    .zip(bitwise::COMPRESS_PERMUTED.zip(bitwise::FIX_COMPRESS_PERMUTED))
+                    .map(|(limb, permuted)| (limb, bitwise::FIX_COMPRESS, permuted)),
+            )
+            .collect::<Vec<_>>();
+        for (limb, table_column, (permuted_input, permuted_table)) in lookups {
+            let (inputs, table) = permuted_cols(&trace[limb], &trace[table_column]);
+            trace[permuted_input] = inputs;
+            trace[permuted_table] = table;
+        }
+        return;
+    }
+
+    let u8_limbs = u8_limbs
+        .map(|col| trace[col].as_slice())
+        .collect::<Vec<_>>();
+    let u8_frequencies = lookup_frequencies(&u8_limbs, &trace[bitwise::FIX_RANGE_CHECK_U8]);
+    let compress_limbs = bitwise::COMPRESS_LIMBS
+        .map(|col| trace[col].as_slice())
+        .collect::<Vec<_>>();
+    let compress_frequencies = lookup_frequencies(&compress_limbs, &trace[bitwise::FIX_COMPRESS]);
+    trace[bitwise::FIX_RANGE_CHECK_U8_FREQ] = u8_frequencies;
+    trace[bitwise::FIX_COMPRESS_FREQ] = compress_frequencies;
+}
+
+/// Fills the columns the limb lookups of the rangecheck table need: the
+/// permuted columns with the `rangecheck_permuted_lookup` feature, the
+/// multiplicities otherwise.
+fn fill_rangecheck_lookup_columns<F: RichField>(trace: &mut [Vec<F>]) {
+    if rangecheck::PERMUTED_LOOKUP {
+        let permuted = rangecheck::LIMBS_PERMUTED.zip(rangecheck::FIX_RANGE_CHECK_U16_PERMUTED);
+        for (limb, (permuted_input, permuted_table)) in [rangecheck::LIMB_LO, rangecheck::LIMB_HI]
+            .into_iter()
+            .zip(permuted)
+        {
+            let (inputs, table) =
+                permuted_cols(&trace[limb], &trace[rangecheck::FIX_RANGE_CHECK_U16]);
+            trace[permuted_input] = inputs;
+            trace[permuted_table] = table;
+        }
+        return;
+    }
+
+    trace[rangecheck::FIX_RANGE_CHECK_U16_FREQ] = lookup_frequencies(
+        &[&trace[rangecheck::LIMB_LO], &trace[rangecheck::LIMB_HI]],
+        &trace[rangecheck::FIX_RANGE_CHECK_U16],
+    );
+}
 
 // add by xb 2023-1-5
 // case 1:
@@ -76,44 +182,11 @@ pub fn generate_bitwise_trace<F: RichField>(
     }
 
     // add fix bitwise info
-    // for 2^8 case, the row is 2^15 + 2^7
-    // fixed at 2023-1-16, for 2^8 case, row number is 2^16
-    let mut index = 0;
-    for op0 in 0..bitwise::RANGE_CHECK_U8_SIZE {
-        // add fix rangecheck info
-        trace[bitwise::FIX_RANGE_CHECK_U8][op0] = F::from_canonical_usize(op0);
-
-        //for op1 in op0..bitwise::RANGE_CHECK_U8_SIZE {
-        for op1 in 0..bitwise::RANGE_CHECK_U8_SIZE {
-            // exe the AND OPE ration
-            let res_and = op0 & op1;
-            trace[bitwise::FIX_BITWSIE_OP0][index] = F::from_canonical_usize(op0);
-            trace[bitwise::FIX_BITWSIE_OP1][index] = F::from_canonical_usize(op1);
-            trace[bitwise::FIX_BITWSIE_RES][index] = F::from_canonical_usize(res_and);
-            trace[bitwise::FIX_TAG][index] = F::from_canonical_u64(1_u64 << Opcode::AND as u8);
-
-            let res_or = op0 | op1;
-            trace[bitwise::FIX_BITWSIE_OP0][bitwise::BITWISE_U8_SIZE_PER + index] =
-                F::from_canonical_usize(op0);
-            trace[bitwise::FIX_BITWSIE_OP1][bitwise::BITWISE_U8_SIZE_PER + index] =
-                F::from_canonical_usize(op1);
-            trace[bitwise::FIX_BITWSIE_RES][bitwise::BITWISE_U8_SIZE_PER + index] =
-                F::from_canonical_usize(res_or);
-            trace[bitwise::FIX_TAG][bitwise::BITWISE_U8_SIZE_PER + index] =
-                F::from_canonical_u64(1_u64 << Opcode::OR as u8);
-
-            let res_xor = op0 ^ op1;
-            trace[bitwise::FIX_BITWSIE_OP0][bitwise::BITWISE_U8_SIZE_PER * 2 + index] =
-                F::from_canonical_usize(op0);
-            trace[bitwise::FIX_BITWSIE_OP1][bitwise::BITWISE_U8_SIZE_PER * 2 + index] =
-                F::from_canonical_usize(op1);
-            trace[bitwise::FIX_BITWSIE_RES][bitwise::BITWISE_U8_SIZE_PER * 2 + index] =
-                F::from_canonical_usize(res_xor);
-            trace[bitwise::FIX_TAG][bitwise::BITWISE_U8_SIZE_PER * 2 + index] =
-                F::from_canonical_u64(1_u64 << Opcode::XOR as u8);
-
-            index += 1;
-        }
+    for (col, values) in bitwise::PREPROCESSED_COLS
+        .into_iter()
+        .zip(bitwise_fixed_columns(ext_trace_len))
+    {
+        trace[col] = values;
     }
 
     // TODO: We should choose proper columns for oracle.
@@ -157,42 +230,7 @@ pub fn generate_bitwise_trace<F: RichField>(
             + trace[bitwise::FIX_BITWSIE_RES][i] * beta * beta * beta;
     }
 
-    // add the permutation information
-    for i in 0..4 {
-        // permuted for rangecheck
-        let (permuted_inputs, permuted_table) = permuted_cols(
-            &trace[bitwise::OP0_LIMBS.start + i],
-            &trace[bitwise::FIX_RANGE_CHECK_U8],
-        );
-
-        trace[bitwise::OP0_LIMBS_PERMUTED.start + i] = permuted_inputs;
-        trace[bitwise::FIX_RANGE_CHECK_U8_PERMUTED.start + i] = permuted_table;
-
-        let (permuted_inputs, permuted_table) = permuted_cols(
-            &trace[bitwise::OP1_LIMBS.start + i],
-            &trace[bitwise::FIX_RANGE_CHECK_U8],
-        );
-
-        trace[bitwise::OP1_LIMBS_PERMUTED.start + i] = permuted_inputs;
-        trace[bitwise::FIX_RANGE_CHECK_U8_PERMUTED.start + 4 + i] = permuted_table;
-
-        let (permuted_inputs, permuted_table) = permuted_cols(
-            &trace[bitwise::RES_LIMBS.start + i],
-            &trace[bitwise::FIX_RANGE_CHECK_U8],
-        );
-
-        trace[bitwise::RES_LIMBS_PERMUTED.start + i] = permuted_inputs;
-        trace[bitwise::FIX_RANGE_CHECK_U8_PERMUTED.start + 8 + i] = permuted_table;
-
-        // permutation for bitwise
-        let (permuted_inputs, permuted_table) = permuted_cols(
-            &trace[bitwise::COMPRESS_LIMBS.start + i],
-            &trace[bitwise::FIX_COMPRESS],
-        );
-
-        trace[bitwise::COMPRESS_PERMUTED.start + i] = permuted_inputs;
-        trace[bitwise::FIX_COMPRESS_PERMUTED.start + i] = permuted_table;
-    }
+    fill_bitwise_lookup_columns(&mut trace);
 
     let trace_row_vecs = trace.try_into().unwrap_or_else(|v: Vec<Vec<F>>| {
         panic!(
@@ -275,36 +313,9 @@ pub fn generate_rc_trace<F: RichField>(
         trace[rangecheck::LIMB_HI][i] = F::from_canonical_u64(c.limb_hi.to_canonical_u64());
     }
     // add fix rangecheck info
-    trace[rangecheck::FIX_RANGE_CHECK_U16] = (0..rangecheck::RANGE_CHECK_U16_SIZE)
-        .map(|i| F::from_canonical_usize(i))
-        .collect();
-    if trace[rangecheck::FIX_RANGE_CHECK_U16].len() < ext_trace_len {
-        let append_start = trace[rangecheck::FIX_RANGE_CHECK_U16].len();
-        let append_end_exclusive = ext_trace_len;
-        let append_value = trace[rangecheck::FIX_RANGE_CHECK_U16]
-            .last()
-            .unwrap()
-            .clone();
-        (append_start..append_end_exclusive).for_each(|_| {
-            trace[rangecheck::FIX_RANGE_CHECK_U16].push(append_value.clone());
-        });
-    }
+    trace[rangecheck::FIX_RANGE_CHECK_U16] = rangecheck_fixed_column(ext_trace_len);
 
-    let (permuted_inputs, permuted_table) = permuted_cols(
-        &trace[rangecheck::LIMB_LO],
-        &trace[rangecheck::FIX_RANGE_CHECK_U16],
-    );
-
-    trace[rangecheck::LIMB_LO_PERMUTED] = permuted_inputs;
-    trace[rangecheck::FIX_RANGE_CHECK_U16_PERMUTED_LO] = permuted_table;
-
-    let (permuted_inputs, permuted_table) = permuted_cols(
-        &trace[rangecheck::LIMB_HI],
-        &trace[rangecheck::FIX_RANGE_CHECK_U16],
-    );
-
-    trace[rangecheck::LIMB_HI_PERMUTED] = permuted_inputs;
-    trace[rangecheck::FIX_RANGE_CHECK_U16_PERMUTED_HI] = permuted_table;
+    fill_rangecheck_lookup_columns(&mut trace);
 
     trace.try_into().unwrap_or_else(|v: Vec<Vec<F>>| {
         panic!(
@@ -368,44 +379,11 @@ pub fn gen_bitwise_trace<F: RichField>(
     }
 
     // add fix bitwise info
-    // for 2^8 case, the row is 2^15 + 2^7
-    // fixed at 2023-1-16, for 2^8 case, row number is 2^16
-    let mut index = 0;
-    for op0 in 0..bitwise::RANGE_CHECK_U8_SIZE {
-        // add fix rangecheck info
-        trace[bitwise::FIX_RANGE_CHECK_U8][op0] = F::from_canonical_usize(op0);
-
-        //for op1 in op0..bitwise::RANGE_CHECK_U8_SIZE {
-        for op1 in 0..bitwise::RANGE_CHECK_U8_SIZE {
-            // exe the AND OPE ration
-            let res_and = op0 & op1;
-            trace[bitwise::FIX_BITWSIE_OP0][index] = F::from_canonical_usize(op0);
-            trace[bitwise::FIX_BITWSIE_OP1][index] = F::from_canonical_usize(op1);
-            trace[bitwise::FIX_BITWSIE_RES][index] = F::from_canonical_usize(res_and);
-            trace[bitwise::FIX_TAG][index] = F::from_canonical_u64(1_u64 << Opcode::AND as u8);
-
-            let res_or = op0 | op1;
-            trace[bitwise::FIX_BITWSIE_OP0][bitwise::BITWISE_U8_SIZE_PER + index] =
-                F::from_canonical_usize(op0);
-            trace[bitwise::FIX_BITWSIE_OP1][bitwise::BITWISE_U8_SIZE_PER + index] =
-                F::from_canonical_usize(op1);
-            trace[bitwise::FIX_BITWSIE_RES][bitwise::BITWISE_U8_SIZE_PER + index] =
-                F::from_canonical_usize(res_or);
-            trace[bitwise::FIX_TAG][bitwise::BITWISE_U8_SIZE_PER + index] =
-                F::from_canonical_u64(1_u64 << Opcode::OR as u8);
-
-            let res_xor = op0 ^ op1;
-            trace[bitwise::FIX_BITWSIE_OP0][bitwise::BITWISE_U8_SIZE_PER * 2 + index] =
-                F::from_canonical_usize(op0);
-            trace[bitwise::FIX_BITWSIE_OP1][bitwise::BITWISE_U8_SIZE_PER * 2 + index] =
-                F::from_canonical_usize(op1);
-            trace[bitwise::FIX_BITWSIE_RES][bitwise::BITWISE_U8_SIZE_PER * 2 + index] =
-                F::from_canonical_usize(res_xor);
-            trace[bitwise::FIX_TAG][bitwise::BITWISE_U8_SIZE_PER * 2 + index] =
-                F::from_canonical_u64(1_u64 << Opcode::XOR as u8);
-
-            index += 1;
-        }
+    for (col, values) in bitwise::PREPROCESSED_COLS
+        .into_iter()
+        .zip(bitwise_fixed_columns(ext_trace_len))
+    {
+        trace[col] = values;
     }
 
     // TODO: We should choose proper columns for oracle.
@@ -449,42 +427,7 @@ pub fn gen_bitwise_trace<F: RichField>(
             + trace[bitwise::FIX_BITWSIE_RES][i] * beta * beta * beta;
     }
 
-    // add the permutation information
-    for i in 0..4 {
-        // permuted for rangecheck
-        let (permuted_inputs, permuted_table) = permuted_cols(
-            &trace[bitwise::OP0_LIMBS.start + i],
-            &trace[bitwise::FIX_RANGE_CHECK_U8],
-        );
-
-        trace[bitwise::OP0_LIMBS_PERMUTED.start + i] = permuted_inputs;
-        trace[bitwise::FIX_RANGE_CHECK_U8_PERMUTED.start + i] = permuted_table;
-
-        let (permuted_inputs, permuted_table) = permuted_cols(
-            &trace[bitwise::OP1_LIMBS.start + i],
-            &trace[bitwise::FIX_RANGE_CHECK_U8],
-        );
-
-        trace[bitwise::OP1_LIMBS_PERMUTED.start + i] = permuted_inputs;
-        trace[bitwise::FIX_RANGE_CHECK_U8_PERMUTED.start + 4 + i] = permuted_table;
-
-        let (permuted_inputs, permuted_table) = permuted_cols(
-            &trace[bitwise::RES_LIMBS.start + i],
-            &trace[bitwise::FIX_RANGE_CHECK_U8],
-        );
-
-        trace[bitwise::RES_LIMBS_PERMUTED.start + i] = permuted_inputs;
-        trace[bitwise::FIX_RANGE_CHECK_U8_PERMUTED.start + 8 + i] = permuted_table;
-
-        // permutation for bitwise
-        let (permuted_inputs, permuted_table) = permuted_cols(
-            &trace[bitwise::COMPRESS_LIMBS.start + i],
-            &trace[bitwise::FIX_COMPRESS],
-        );
-
-        trace[bitwise::COMPRESS_PERMUTED.start + i] = permuted_inputs;
-        trace[bitwise::FIX_COMPRESS_PERMUTED.start + i] = permuted_table;
-    }
+    fill_bitwise_lookup_columns(&mut trace);
 
     let trace_row_vecs = trace.try_into().unwrap_or_else(|v: Vec<Vec<F>>| {
         panic!(
@@ -575,36 +518,9 @@ pub fn gen_rc_trace<F: RichField>(
         }
     }
     // add fix rangecheck info
-    trace[rangecheck::FIX_RANGE_CHECK_U16] = (0..rangecheck::RANGE_CHECK_U16_SIZE)
-        .map(|i| F::from_canonical_usize(i))
-        .collect();
-    if trace[rangecheck::FIX_RANGE_CHECK_U16].len() < ext_trace_len {
-        let append_start = trace[rangecheck::FIX_RANGE_CHECK_U16].len();
-        let append_end_exclusive = ext_trace_len;
-        let append_value = trace[rangecheck::FIX_RANGE_CHECK_U16]
-            .last()
-            .unwrap()
-            .clone();
-        (append_start..append_end_exclusive).for_each(|_| {
-            trace[rangecheck::FIX_RANGE_CHECK_U16].push(append_value.clone());
-        });
-    }
-
-    let (permuted_inputs, permuted_table) = permuted_cols(
-        &trace[rangecheck::LIMB_LO],
-        &trace[rangecheck::FIX_RANGE_CHECK_U16],
-    );
-
-    trace[rangecheck::LIMB_LO_PERMUTED] = permuted_inputs;
-    trace[rangecheck::FIX_RANGE_CHECK_U16_PERMUTED_LO] = permuted_table;
-
-    let (permuted_inputs, permuted_table) = permuted_cols(
-        &trace[rangecheck::LIMB_HI],
-        &trace[rangecheck::FIX_RANGE_CHECK_U16],
-    );
+    trace[rangecheck::FIX_RANGE_CHECK_U16] = rangecheck_fixed_column(ext_trace_len);
 
-    trace[rangecheck::LIMB_HI_PERMUTED] = permuted_inputs;
-    trace[rangecheck::FIX_RANGE_CHECK_U16_PERMUTED_HI] = permuted_table;
+    fill_rangecheck_lookup_columns(&mut trace);
 
     trace.try_into().unwrap_or_else(|v: Vec<Vec<F>>| {
         panic!(
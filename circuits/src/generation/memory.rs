@@ -131,11 +131,9 @@ pub fn generate_memory_trace<F: RichField>(
             } else {
                 F::ONE
             };
-            trace[memory::COL_MEM_DIFF_ADDR_INV][i] = trace[memory::COL_MEM_DIFF_ADDR][i].inverse();
             trace[memory::COL_MEM_DIFF_ADDR_COND][i] = p - addr;
             trace[memory::COL_MEM_REGION_PROPHET][i] = F::ONE;
             trace[memory::COL_MEM_RC_VALUE][i] = trace[memory::COL_MEM_DIFF_ADDR_COND][i];
-            trace[memory::COL_MEM_DIFF_ADDR_INV][i] = trace[memory::COL_MEM_DIFF_ADDR][i].inverse();
             trace[memory::COL_MEM_DIFF_ADDR_COND][i] = p - addr;
             trace[memory::COL_MEM_REGION_PROPHET][i] = F::ONE;
             trace[memory::COL_MEM_RC_VALUE][i] = trace[memory::COL_MEM_DIFF_ADDR_COND][i];
@@ -289,11 +287,9 @@ pub fn gen_memory_trace<F: RichField>(
             } else {
                 F::ONE
             };
-            trace[memory::COL_MEM_DIFF_ADDR_INV][i] = trace[memory::COL_MEM_DIFF_ADDR][i].inverse();
             trace[memory::COL_MEM_DIFF_ADDR_COND][i] = p - addr;
             trace[memory::COL_MEM_REGION_PROPHET][i] = F::ONE;
             trace[memory::COL_MEM_RC_VALUE][i] = trace[memory::COL_MEM_DIFF_ADDR_COND][i];
-            trace[memory::COL_MEM_DIFF_ADDR_INV][i] = trace[memory::COL_MEM_DIFF_ADDR][i].inverse();
             trace[memory::COL_MEM_DIFF_ADDR_COND][i] = p - addr;
             trace[memory::COL_MEM_REGION_PROPHET][i] = F::ONE;
             trace[memory::COL_MEM_RC_VALUE][i] = trace[memory::COL_MEM_DIFF_ADDR_COND][i];
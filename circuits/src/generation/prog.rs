@@ -1,10 +1,11 @@
 use core::{
     crypto::poseidon_trace::calculate_poseidon,
     trace::trace::Step,
-    types::{Field, GoldilocksField},
+    types::{merkle_tree::decode_addr, Field, GoldilocksField},
     vm::opcodes::OlaOpcode,
 };
 use std::cmp::max;
+use std::collections::HashMap;
 
 use itertools::Itertools;
 use plonky2::{
@@ -15,6 +16,16 @@ use plonky2::{
 
 use crate::{program::columns::*, stark::lookup::permuted_cols};
 
+/// The programs of the execution trace, keyed by their decoded address.
+pub(crate) fn decode_progs(
+    addr_program_hash: HashMap<String, Vec<GoldilocksField>>,
+) -> Vec<([GoldilocksField; 4], Vec<GoldilocksField>)> {
+    addr_program_hash
+        .into_iter()
+        .map(|(addr, hash)| (decode_addr(addr), hash))
+        .collect()
+}
+
 pub fn generate_prog_trace<F: RichField>(
     execs: &[Step],
     progs: Vec<([GoldilocksField; 4], Vec<GoldilocksField>)>,
@@ -1,14 +1,21 @@
+use core::trace::trace::Trace;
 use core::vm::opcodes::OlaOpcode;
 use std::ops::Sub;
 
 use plonky2::field::types::Field;
 use serde::{Deserialize, Serialize};
 
-use crate::stark::cross_table_lookup::Column;
+use crate::stark::cross_table_lookup::{Column, CrossTableLookup, TableWithColumns};
 use {
     super::columns::*,
+    crate::builtins::poseidon::poseidon_chunk_stark,
+    crate::cpu::cpu_stark,
+    crate::generation::memory::generate_memory_trace,
     crate::stark::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer},
+    crate::stark::ola_stark::Table,
     crate::stark::stark::Stark,
+    crate::stark::table::{OlaTable, TraceJob},
+    crate::stark::util::trace_to_poly_values,
     crate::stark::vars::{StarkEvaluationTargets, StarkEvaluationVars},
     plonky2::field::extension::{Extendable, FieldExtension},
     plonky2::field::packed::PackedField,
@@ -136,6 +143,7 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
         let value = lv[COL_MEM_VALUE];
         let nv_value = nv[COL_MEM_VALUE];
         let diff_clk = lv[COL_MEM_DIFF_CLK];
+        let nv_diff_clk = nv[COL_MEM_DIFF_CLK];
         let rc_value = lv[COL_MEM_RC_VALUE];
         let filter_looking_rc = lv[COL_MEM_FILTER_LOOKING_RC];
         let lv_filter_looking_rc_cond = lv[COL_MEM_FILTER_LOOKING_RC_COND];
@@ -197,6 +205,10 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
         yield_constr.constraint(
             (P::ONES - lv[COL_MEM_IS_RW]) * (P::ONES - lv[COL_MEM_S_PROPHET] - lv[COL_MEM_S_MLOAD]),
         );
+        // only prophet region is write once, and prophets are written at clk 0
+        yield_constr.constraint(is_rw + region_prophet - P::ONES);
+        yield_constr.constraint(lv[COL_MEM_S_PROPHET] * lv[COL_MEM_CLK]);
+        yield_constr.constraint((P::ONES - is_rw) * rw_addr_unchanged);
         // constraint is_write
         yield_constr.constraint(
             lv[COL_MEM_IS_WRITE]
@@ -229,6 +241,7 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
         // make sure region addr is right
         yield_constr.constraint(region_prophet * (p - addr - diff_addr_cond));
         yield_constr.constraint(region_heap * (p - span - addr - diff_addr_cond));
+        yield_constr.constraint(region_stack * diff_addr_cond);
 
         // if not prophet write, must be looked up.
         // yield_constr
@@ -250,6 +263,22 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
                 * nv_region_stack
                 * (P::ONES - nv_rw_addr_unchanged - nv_diff_addr * nv_diff_addr_inv),
         );
+        yield_constr.constraint_transition(
+            (P::ONES - nv[COL_MEM_TX_IDX] + lv[COL_MEM_TX_IDX])
+                * (P::ONES - nv[COL_MEM_ENV_IDX] + lv[COL_MEM_ENV_IDX])
+                * region_stack
+                * nv_region_stack
+                * nv_diff_addr
+                * nv_rw_addr_unchanged,
+        );
+        yield_constr.constraint_transition(
+            (P::ONES - nv[COL_MEM_TX_IDX] + lv[COL_MEM_TX_IDX])
+                * (P::ONES - nv[COL_MEM_ENV_IDX] + lv[COL_MEM_ENV_IDX])
+                * region_stack
+                * nv_region_stack
+                * nv_diff_addr_inv
+                * nv_rw_addr_unchanged,
+        );
         yield_constr.constraint_transition(
             (P::ONES - nv[COL_MEM_TX_IDX] + lv[COL_MEM_TX_IDX])
                 * (P::ONES - nv[COL_MEM_ENV_IDX] + lv[COL_MEM_ENV_IDX])
@@ -257,6 +286,22 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
                 * nv_region_heap
                 * (P::ONES - nv_rw_addr_unchanged - nv_diff_addr * nv_diff_addr_inv),
         );
+        yield_constr.constraint_transition(
+            (P::ONES - nv[COL_MEM_TX_IDX] + lv[COL_MEM_TX_IDX])
+                * (P::ONES - nv[COL_MEM_ENV_IDX] + lv[COL_MEM_ENV_IDX])
+                * region_heap
+                * nv_region_heap
+                * nv_diff_addr
+                * nv_rw_addr_unchanged,
+        );
+        yield_constr.constraint_transition(
+            (P::ONES - nv[COL_MEM_TX_IDX] + lv[COL_MEM_TX_IDX])
+                * (P::ONES - nv[COL_MEM_ENV_IDX] + lv[COL_MEM_ENV_IDX])
+                * region_heap
+                * nv_region_heap
+                * nv_diff_addr_inv
+                * nv_rw_addr_unchanged,
+        );
 
         // for write once:
         // 1. addr doesn't change or increase by 1 in prophet region;
@@ -267,6 +312,23 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
         yield_constr.constraint(
             region_prophet * nv_region_prophet * (nv_addr - addr - P::ONES) * nv_is_write,
         );
+        yield_constr.constraint(region_prophet * lv[COL_MEM_DIFF_ADDR_INV]);
+
+        // diff_clk is clk' - clk when addr is unchanged and zero for a new addr.
+        yield_constr.constraint_transition(
+            (P::ONES - nv[COL_MEM_TX_IDX] + lv[COL_MEM_TX_IDX])
+                * (P::ONES - nv[COL_MEM_ENV_IDX] + lv[COL_MEM_ENV_IDX])
+                * nv_rw_addr_unchanged
+                * (nv[COL_MEM_CLK] - lv[COL_MEM_CLK] - nv_diff_clk),
+        );
+        yield_constr.constraint(is_rw * (P::ONES - rw_addr_unchanged) * diff_clk);
+        yield_constr.constraint_transition(
+            region_prophet
+                * nv_region_prophet
+                * (nv_addr - addr - P::ONES)
+                * (nv[COL_MEM_CLK] - lv[COL_MEM_CLK] - nv_diff_clk),
+        );
+        yield_constr.constraint_transition(nv_region_prophet * (nv_addr - addr) * nv_diff_clk);
 
         // (P::ONES - nv[COL_MEM_TX_IDX] + lv[COL_MEM_TX_IDX])
         // * (nv[COL_MEM_ENV_IDX] - lv[COL_MEM_ENV_IDX]
@@ -325,6 +387,8 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
                 * (P::ONES - filter_looking_rc),
         );
 
+        yield_constr.constraint((P::ONES - is_rw) * (rc_value - diff_addr_cond));
+
         // heap and prophet read, diff_cond must rc
         yield_constr.constraint((P::ONES - lv_filter_looking_rc_cond) * region_heap);
         yield_constr.constraint(
@@ -344,6 +408,102 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
         8
     }
 }
+
+impl<F: RichField + Extendable<D>, const D: usize> OlaTable<F, D> for MemoryStark<F, D> {
+    fn table(&self) -> Table {
+        Table::Memory
+    }
+
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F> {
+        let memory = std::mem::take(&mut trace.memory);
+        Box::new(move || (trace_to_poly_values(generate_memory_trace(&memory)), None))
+    }
+
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        vec![ctl_cpu_memory(), ctl_poseidon_chunk_mem()]
+    }
+}
+
+fn ctl_cpu_memory<F: Field>() -> CrossTableLookup<F> {
+    let cpu_mem_store_load = TableWithColumns::new(
+        Table::Cpu,
+        cpu_stark::ctl_data_cpu_mem_store_load(),
+        Some(cpu_stark::ctl_filter_cpu_mem_store_load()),
+    );
+    let cpu_mem_call_ret_pc = TableWithColumns::new(
+        Table::Cpu,
+        cpu_stark::ctl_data_cpu_mem_call_ret_pc(),
+        Some(cpu_stark::ctl_filter_cpu_mem_call_ret()),
+    );
+    let cpu_mem_call_ret_fp = TableWithColumns::new(
+        Table::Cpu,
+        cpu_stark::ctl_data_cpu_mem_call_ret_fp(),
+        Some(cpu_stark::ctl_filter_cpu_mem_call_ret()),
+    );
+    let cpu_mem_tload_tstore = TableWithColumns::new(
+        Table::Cpu,
+        cpu_stark::ctl_data_cpu_mem_tload_tstore(),
+        Some(cpu_stark::ctl_filter_cpu_mem_tload_tstore()),
+    );
+    let cpu_sccall_mems = (0..4).map(|i: usize| {
+        TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_cpu_mem_sccall(i),
+            Some(cpu_stark::ctl_filter_cpu_mem_sccall()),
+        )
+    });
+    let cpu_storage_addr = (0..4).map(|i: usize| {
+        TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_cpu_mem_for_storage_addr(i),
+            Some(cpu_stark::ctl_filter_cpu_storage_access()),
+        )
+    });
+    let cpu_storage_value = (0..4).map(|i: usize| {
+        TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_cpu_mem_for_storage_value(i),
+            Some(cpu_stark::ctl_filter_cpu_storage_access()),
+        )
+    });
+
+    let mut all_cpu_lookers = vec![
+        cpu_mem_store_load,
+        cpu_mem_call_ret_pc,
+        cpu_mem_call_ret_fp,
+        cpu_mem_tload_tstore,
+    ];
+    all_cpu_lookers.extend(cpu_sccall_mems);
+    all_cpu_lookers.extend(cpu_storage_addr);
+    all_cpu_lookers.extend(cpu_storage_value);
+    let memory_looked = TableWithColumns::new(Table::Memory, ctl_data(), Some(ctl_filter()));
+    CrossTableLookup::new(all_cpu_lookers, memory_looked)
+}
+
+fn ctl_poseidon_chunk_mem<F: Field>() -> CrossTableLookup<F> {
+    let looker_src = (0..8).map(|i: usize| {
+        TableWithColumns::new(
+            Table::PoseidonChunk,
+            poseidon_chunk_stark::ctl_data_with_mem_src(i),
+            Some(poseidon_chunk_stark::ctl_filter_with_mem_src(i)),
+        )
+    });
+    let looker_dst = (0..4).map(|i: usize| {
+        TableWithColumns::new(
+            Table::PoseidonChunk,
+            poseidon_chunk_stark::ctl_data_with_mem_dst(i),
+            Some(poseidon_chunk_stark::ctl_filter_with_mem_dst()),
+        )
+    });
+    let all_lookers = looker_src.into_iter().chain(looker_dst).collect();
+    let mem_looked = TableWithColumns::new(
+        Table::Memory,
+        ctl_data_with_poseidon_chunk(),
+        Some(ctl_filter_with_poseidon_chunk()),
+    );
+    CrossTableLookup::new(all_lookers, mem_looked)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::generation::memory::generate_memory_trace;
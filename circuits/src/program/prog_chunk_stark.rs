@@ -1,3 +1,4 @@
+use core::trace::trace::Trace;
 use core::types::Field;
 use std::marker::PhantomData;
 
@@ -12,10 +13,14 @@ use plonky2::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::generation::prog::{decode_progs, generate_prog_chunk_trace};
 use crate::stark::{
     constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer},
     cross_table_lookup::Column,
+    ola_stark::Table,
     stark::Stark,
+    table::{OlaTable, TraceJob},
+    util::trace_to_poly_values,
     vars::{StarkEvaluationTargets, StarkEvaluationVars},
 };
 
@@ -176,6 +181,17 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ProgChunkStar
     }
 }
 
+impl<F: RichField + Extendable<D>, const D: usize> OlaTable<F, D> for ProgChunkStark<F, D> {
+    fn table(&self) -> Table {
+        Table::ProgChunk
+    }
+
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F> {
+        let progs = decode_progs(std::mem::take(&mut trace.addr_program_hash));
+        Box::new(move || (trace_to_poly_values(generate_prog_chunk_trace(progs)), None))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
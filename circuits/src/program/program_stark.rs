@@ -1,3 +1,4 @@
+use core::trace::trace::Trace;
 use core::types::Field;
 use std::{marker::PhantomData, vec};
 
@@ -13,12 +14,18 @@ use plonky2::{
 use serde::{Deserialize, Serialize};
 
 use super::columns::*;
+use crate::cpu::cpu_stark;
+use crate::generation::prog::{decode_progs, generate_prog_trace};
+use crate::program::prog_chunk_stark;
 use crate::stark::{
     constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer},
-    cross_table_lookup::Column,
+    cross_table_lookup::{Column, CrossTableLookup, TableWithColumns},
     lookup::eval_lookups,
+    ola_stark::Table,
     permutation::PermutationPair,
     stark::Stark,
+    table::{OlaTable, TraceJob},
+    util::trace_to_poly_values,
     vars::{StarkEvaluationTargets, StarkEvaluationVars},
 };
 use anyhow::Result;
@@ -116,6 +123,71 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ProgramStark<
     }
 }
 
+impl<F: RichField + Extendable<D>, const D: usize> OlaTable<F, D> for ProgramStark<F, D> {
+    fn table(&self) -> Table {
+        Table::Program
+    }
+
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F> {
+        let exec = std::mem::take(&mut trace.exec);
+        let progs = decode_progs(trace.addr_program_hash.clone());
+        let start_end_roots = trace.start_end_roots;
+        Box::new(move || {
+            let (rows, beta) = generate_prog_trace(&exec, progs, start_end_roots);
+            (trace_to_poly_values(rows), Some(beta))
+        })
+    }
+
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        vec![ctl_cpu_program(), ctl_prog_chunk_prog()]
+    }
+
+    fn compress_challenge(&self) -> Option<F> {
+        self.get_compress_challenge()
+    }
+
+    fn set_compress_challenge(&mut self, challenge: F) -> Result<()> {
+        self.set_compress_challenge(challenge)
+    }
+}
+
+fn ctl_cpu_program<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new(
+        vec![
+            TableWithColumns::new(
+                Table::Cpu,
+                cpu_stark::ctl_data_inst_to_program(),
+                Some(cpu_stark::ctl_filter_with_program_inst()),
+            ),
+            TableWithColumns::new(
+                Table::Cpu,
+                cpu_stark::ctl_data_imm_to_program(),
+                Some(cpu_stark::ctl_filter_with_program_imm()),
+            ),
+        ],
+        TableWithColumns::new(Table::Program, ctl_data_by_cpu(), Some(ctl_filter_by_cpu())),
+    )
+}
+
+fn ctl_prog_chunk_prog<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new(
+        (0..8)
+            .map(|i: usize| {
+                TableWithColumns::new(
+                    Table::ProgChunk,
+                    prog_chunk_stark::ctl_data_to_program(i),
+                    Some(prog_chunk_stark::ctl_filter_to_program(i)),
+                )
+            })
+            .collect(),
+        TableWithColumns::new(
+            Table::Program,
+            ctl_data_by_program_chunk(),
+            Some(ctl_filter_by_program_chunk()),
+        ),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use crate::generation::prog::generate_prog_trace;
@@ -0,0 +1,186 @@
+//! Proving the openings of several tables with one FRI proof.
+//!
+//! Tables are grouped by degree, since the oracles of one FRI instance must
+//! all have the same height. The instance of a group is the concatenation of
+//! the instances of its tables, each table keeping its own opening points.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use plonky2::field::extension::Extendable;
+use plonky2::fri::oracle::PolynomialBatch;
+use plonky2::fri::proof::FriChallenges;
+use plonky2::fri::structure::{FriBatchInfo, FriInstanceInfo, FriOpenings, FriPolynomialInfo};
+use plonky2::fri::verifier::verify_fri_proof;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::challenger::Challenger;
+use plonky2::plonk::config::{GenericConfig, Hasher};
+use plonky2::timed;
+use plonky2::util::timing::TimingTree;
+
+use super::config::StarkConfig;
+use super::proof::{BatchOpeningProof, StarkProof, StarkProofChallenges};
+use super::table::TableConstraints;
+use super::verifying_key::OlaVerifyingKey;
+
+/// The commitments of a table whose openings are left to a batch FRI proof.
+pub struct PendingOpenings<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+{
+    pub(crate) degree_bits: usize,
+    pub(crate) instance: FriInstanceInfo<F, D>,
+    pub(crate) trace_commitment: PolynomialBatch<F, C, D>,
+    pub(crate) permutation_ctl_zs_commitment: PolynomialBatch<F, C, D>,
+    pub(crate) quotient_commitment: PolynomialBatch<F, C, D>,
+}
+
+/// The proof of a table, and its commitments when its openings are left to
+/// a batch FRI proof.
+pub type TableProof<F, C, const D: usize> = (StarkProof<F, C, D>, Option<PendingOpenings<F, C, D>>);
+
+/// Concatenates FRI instances, shifting the oracle indices of each instance
+/// past the oracles of the previous ones.
+fn concat_fri_instances<F: RichField + Extendable<D>, const D: usize>(
+    instances: Vec<FriInstanceInfo<F, D>>,
+) -> FriInstanceInfo<F, D> {
+    let mut oracles = vec![];
+    let mut batches = vec![];
+    for instance in instances {
+        let offset = oracles.len();
+        oracles.extend(instance.oracles);
+        batches.extend(instance.batches.into_iter().map(|batch| {
+            FriBatchInfo {
+                point: batch.point,
+                polynomials: batch
+                    .polynomials
+                    .into_iter()
+                    .map(|poly| FriPolynomialInfo {
+                        oracle_index: poly.oracle_index + offset,
+                        polynomial_index: poly.polynomial_index,
+                    })
+                    .collect(),
+            }
+        }));
+    }
+    FriInstanceInfo { oracles, batches }
+}
+
+/// Proves the pending openings of the tables with one FRI proof per degree.
+/// `pending` holds an entry per table, `None` for tables whose openings are
+/// already proven.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn prove_batch_openings<F, C, const D: usize>(
+    pending: Vec<Option<PendingOpenings<F, C, D>>>,
+    preprocessed_commitments: &[Option<&PolynomialBatch<F, C, D>>],
+    challenger: &mut Challenger<F, C::Hasher>,
+    config: &StarkConfig,
+    timing: &mut TimingTree,
+    twiddle_map: &mut BTreeMap<usize, Vec<F>>,
+) -> Vec<BatchOpeningProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let mut by_degree = BTreeMap::<usize, Vec<(usize, PendingOpenings<F, C, D>)>>::new();
+    for (table, openings) in pending.into_iter().enumerate() {
+        if let Some(openings) = openings {
+            by_degree
+                .entry(openings.degree_bits)
+                .or_default()
+                .push((table, openings));
+        }
+    }
+
+    let mut batch_opening_proofs = Vec::with_capacity(by_degree.len());
+    for (degree_bits, group) in by_degree {
+        let mut tables = Vec::with_capacity(group.len());
+        let mut instances = Vec::with_capacity(group.len());
+        let mut commitments = Vec::with_capacity(group.len());
+        for (table, openings) in group {
+            tables.push(table);
+            instances.push(openings.instance);
+            commitments.push([
+                openings.trace_commitment,
+                openings.permutation_ctl_zs_commitment,
+                openings.quotient_commitment,
+            ]);
+        }
+        let oracles = tables
+            .iter()
+            .zip(&commitments)
+            .flat_map(|(&table, commitments)| {
+                commitments.iter().chain(preprocessed_commitments[table])
+            })
+            .collect::<Vec<_>>();
+
+        let opening_proof = timed!(
+            timing,
+            &format!("compute batch openings proof of degree {}", degree_bits),
+            PolynomialBatch::prove_openings(
+                &concat_fri_instances(instances),
+                &oracles,
+                challenger,
+                &config.fri_params(degree_bits),
+                timing,
+                twiddle_map,
+            )
+        );
+        batch_opening_proofs.push(BatchOpeningProof {
+            tables,
+            opening_proof,
+        });
+    }
+    batch_opening_proofs
+}
+
+/// Verifies the FRI proof of a batch against the openings of its tables.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn verify_batch_opening_proof<F, C, const D: usize>(
+    tables: &[&dyn TableConstraints<F, D>],
+    stark_proofs: &[StarkProof<F, C, D>],
+    stark_challenges: &[StarkProofChallenges<F, D>],
+    batch: &BatchOpeningProof<F, C, D>,
+    fri_challenges: &FriChallenges<F, D>,
+    degree_bits: usize,
+    verifying_key: &OlaVerifyingKey<F, C, D>,
+    config: &StarkConfig,
+) -> Result<()>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let g = F::primitive_root_of_unity(degree_bits);
+
+    let mut instances = Vec::with_capacity(batch.tables.len());
+    let mut openings = FriOpenings { batches: vec![] };
+    let mut merkle_caps = Vec::with_capacity(3 * batch.tables.len());
+    for &table in &batch.tables {
+        let proof = &stark_proofs[table];
+        instances.push(tables[table].fri_instance(
+            stark_challenges[table].stark_zeta,
+            g,
+            degree_bits,
+            proof.num_ctl_zs(),
+            config,
+        ));
+        openings
+            .batches
+            .extend(proof.openings.to_fri_openings().batches);
+        merkle_caps.extend([
+            proof.trace_cap.clone(),
+            proof.permutation_ctl_zs_cap.clone(),
+            proof.quotient_polys_cap.clone(),
+        ]);
+        merkle_caps.extend(verifying_key.preprocessed_cap(table, degree_bits)?.cloned());
+    }
+
+    verify_fri_proof::<F, C, D>(
+        &concat_fri_instances(instances),
+        &openings,
+        fri_challenges,
+        &merkle_caps,
+        &batch.opening_proof,
+        &config.fri_params(degree_bits),
+    )
+}
@@ -1,37 +1,341 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, ensure, Result};
+use plonky2::field::extension::Extendable;
 use plonky2::fri::reduction_strategies::FriReductionStrategy;
 use plonky2::fri::{FriConfig, FriParams};
+use plonky2::hash::hash_types::RichField;
+use plonky2_util::log2_ceil;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StarkConfig {
+    /// The bits of security proofs must achieve, see `StarkConfig::validate`.
     pub security_bits: usize,
 
+    /// Hold `security_bits` against the proven soundness of FRI rather than
+    /// the conjectured one.
+    #[serde(default)]
+    pub proven_security: bool,
+
     /// The number of challenge points to generate, for IOPs that have soundness
     /// errors of (roughly) `degree / |F|`.
     pub num_challenges: usize,
 
     pub fri_config: FriConfig,
+
+    /// Prove the openings of all tables of the same degree with one FRI
+    /// proof, instead of one FRI proof per table.
+    #[serde(default)]
+    pub batch_fri: bool,
+
+    /// Hide the traces: the committed polynomials are randomized off the
+    /// trace domain, the quotient chunks and the FRI batch are masked and the
+    /// Merkle leaves are salted. Raises the degree of the committed
+    /// polynomials by `num_blinding_values`, rounded up to a power of two.
+    #[serde(default)]
+    pub zero_knowledge: bool,
+
+    /// Keep at most one trace LDE in memory at a time: only the caps of the
+    /// trace commitments are kept, the traces are committed again when their
+    /// tables are proven, consuming them, and twiddles are not cached across
+    /// tables. Saves the LDEs and Merkle trees of all but one trace, for
+    /// twice the trace commitment time, and leaves the proofs unchanged.
+    /// Can't be combined with `batch_fri`, whose openings need the
+    /// commitments of all tables of a degree at once, nor with
+    /// `zero_knowledge`, whose randomized commitments can't be computed
+    /// again with the same caps.
+    #[serde(default)]
+    pub low_memory: bool,
 }
 
 impl StarkConfig {
-    /// A typical configuration with a rate of 2, resulting in fast but large
-    /// proofs. Targets ~100 bit conjectured security.
-    // TODO: check the params
+    /// A typical configuration with a blowup factor of 8, resulting in fast but
+    /// large proofs. Targets ~100 bit conjectured security.
     pub fn standard_fast_config() -> Self {
-        Self {
-            security_bits: 100,
-            num_challenges: 2,
+        SecurityPreset::Conjectured100.config()
+    }
+
+    /// The soundness of proofs of tables of up to `2^degree_bits` rows.
+    pub fn security_level<F: RichField + Extendable<D>, const D: usize>(
+        &self,
+        degree_bits: usize,
+    ) -> SecurityLevel {
+        let degree_bits = degree_bits + self.blinding_bits(degree_bits);
+        let lde_bits = degree_bits + self.fri_config.rate_bits;
+        let query_bits = self.fri_config.rate_bits * self.fri_config.num_query_rounds;
+        let extension_bits = F::BITS * D;
+        SecurityLevel {
+            fri_conjectured_bits: query_bits,
+            fri_proven_bits: (query_bits / 2).min(extension_bits.saturating_sub(2 * lde_bits)),
+            proof_of_work_bits: self.fri_config.proof_of_work_bits as usize,
+            extension_bits: extension_bits.saturating_sub(lde_bits),
+            challenge_bits: self.num_challenges * F::BITS.saturating_sub(degree_bits),
+        }
+    }
+
+    /// Checks that proofs of tables of up to `2^degree_bits` rows achieve
+    /// `security_bits`, and that the proving options can be combined.
+    pub fn validate<F: RichField + Extendable<D>, const D: usize>(
+        &self,
+        degree_bits: usize,
+    ) -> Result<()> {
+        ensure!(
+            self.num_challenges > 0 && self.fri_config.num_query_rounds > 0,
+            "The config draws no challenges or FRI queries."
+        );
+        ensure!(
+            !(self.batch_fri && self.low_memory),
+            "Batched FRI openings keep the commitments of all tables, they can't be proven in low memory mode."
+        );
+        ensure!(
+            !(self.zero_knowledge && self.low_memory),
+            "Randomized trace commitments can't be recomputed, zero-knowledge proofs can't be proven in low memory mode."
+        );
+        let level = self.security_level::<F, D>(degree_bits);
+        let (achieved, kind) = if self.proven_security {
+            (level.proven_bits(), "proven")
+        } else {
+            (level.conjectured_bits(), "conjectured")
+        };
+        ensure!(
+            achieved >= self.security_bits,
+            "The config achieves {} bits of {} security for degree 2^{}, below the {} bits required: {:?}.",
+            achieved,
+            kind,
+            degree_bits,
+            self.security_bits,
+            level
+        );
+        Ok(())
+    }
+
+    /// The number of evaluations off the trace domain a proof reveals of
+    /// each committed polynomial, and so of random coefficients hiding it:
+    /// the openings at `zeta` and `g zeta`, and a coset of up to
+    /// `2^max_arity_bits` points per FRI query.
+    pub(crate) fn num_blinding_values(&self) -> usize {
+        if !self.zero_knowledge {
+            return 0;
+        }
+        let max_arity_bits = self.fri_config.reduction_strategy.max_arity_bits();
+        2 + (self.fri_config.num_query_rounds << max_arity_bits)
+    }
+
+    /// The number of degree bits the committed polynomials of a trace of
+    /// `2^degree_bits` rows have on top of `degree_bits`, room for
+    /// `num_blinding_values` more coefficients. Tables of fewer rows than
+    /// that have their degree raised the most.
+    pub(crate) fn blinding_bits(&self, degree_bits: usize) -> usize {
+        log2_ceil((1 << degree_bits) + self.num_blinding_values()) - degree_bits
+    }
+
+    /// The number of coefficients of each chunk of a quotient. In
+    /// zero-knowledge mode the chunks leave room below the committed degree
+    /// for the `num_blinding_values` coefficients masking them.
+    pub(crate) fn quotient_chunk_len(&self, degree_bits: usize) -> usize {
+        ((1 << degree_bits) << self.blinding_bits(degree_bits)) - self.num_blinding_values()
+    }
+
+    pub(crate) fn fri_params(&self, degree_bits: usize) -> FriParams {
+        self.fri_config.fri_params(
+            degree_bits + self.blinding_bits(degree_bits),
+            self.zero_knowledge,
+        )
+    }
+}
+
+/// Bits of soundness of a `StarkConfig` for a given degree, per source of
+/// soundness error. These are the usual estimates, not a full analysis of
+/// the protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecurityLevel {
+    /// FRI queries, conjectured to each have a soundness error of the rate.
+    pub fri_conjectured_bits: usize,
+    /// FRI in the Johnson bound regime: each query has a soundness error of
+    /// the square root of the rate, and the commit phase an error of
+    /// `|LDE|^2 / |F^D|`.
+    pub fri_proven_bits: usize,
+    /// Grinding done before drawing the FRI queries.
+    pub proof_of_work_bits: usize,
+    /// Out-of-domain sampling in the extension field, `|LDE| / |F^D|`.
+    pub extension_bits: usize,
+    /// The `num_challenges` repetitions of the base field challenges of the
+    /// permutation, lookup and cross-table lookup arguments and of the
+    /// constraint combination, each `degree / |F|`.
+    pub challenge_bits: usize,
+}
+
+impl SecurityLevel {
+    pub fn conjectured_bits(&self) -> usize {
+        (self.fri_conjectured_bits + self.proof_of_work_bits)
+            .min(self.extension_bits)
+            .min(self.challenge_bits)
+    }
+
+    pub fn proven_bits(&self) -> usize {
+        (self.fri_proven_bits + self.proof_of_work_bits)
+            .min(self.extension_bits)
+            .min(self.challenge_bits)
+    }
+}
+
+/// Named parameter sets. All use a blowup factor of 8, the smallest one fitting
+/// the degree 8 constraints of the memory table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityPreset {
+    /// Few queries and no grinding, for development only.
+    FastDev,
+    /// ~100 bits of conjectured security, for tables of up to `2^25` rows.
+    Conjectured100,
+    /// ~128 bits of conjectured security. Needs an extension field larger
+    /// than `2^128`, such as the quartic extension of Goldilocks, which the
+    /// prover does not support yet.
+    Conjectured128,
+    /// ~128 bits of proven security. Needs an extension field larger than
+    /// `2^128`, such as the quartic extension of Goldilocks, which the prover
+    /// does not support yet.
+    Proven128,
+}
+
+impl SecurityPreset {
+    pub const ALL: [SecurityPreset; 4] = [
+        SecurityPreset::FastDev,
+        SecurityPreset::Conjectured100,
+        SecurityPreset::Conjectured128,
+        SecurityPreset::Proven128,
+    ];
+
+    /// The presets `OlaStark<GoldilocksField, 2>`, the only instance the
+    /// prover is built for, passes `StarkConfig::validate` with.
+    pub const PROVABLE: [SecurityPreset; 2] =
+        [SecurityPreset::FastDev, SecurityPreset::Conjectured100];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SecurityPreset::FastDev => "fast-dev",
+            SecurityPreset::Conjectured100 => "100-bit",
+            SecurityPreset::Conjectured128 => "128-bit",
+            SecurityPreset::Proven128 => "128-bit-proven",
+        }
+    }
+
+    pub fn config(self) -> StarkConfig {
+        let (security_bits, proven_security, num_challenges, num_query_rounds, proof_of_work_bits) =
+            match self {
+                SecurityPreset::FastDev => (20, false, 1, 8, 0),
+                SecurityPreset::Conjectured100 => (100, false, 3, 28, 16),
+                SecurityPreset::Conjectured128 => (128, false, 4, 38, 16),
+                SecurityPreset::Proven128 => (128, true, 4, 72, 20),
+            };
+        StarkConfig {
+            security_bits,
+            proven_security,
+            num_challenges,
             fri_config: FriConfig {
                 rate_bits: 3,
                 cap_height: 4,
-                proof_of_work_bits: 16,
+                proof_of_work_bits,
                 reduction_strategy: FriReductionStrategy::ConstantArityBits(4, 5),
-                num_query_rounds: 28,
+                num_query_rounds,
             },
+            batch_fri: false,
+            zero_knowledge: false,
+            low_memory: false,
         }
     }
+}
 
-    pub(crate) fn fri_params(&self, degree_bits: usize) -> FriParams {
-        self.fri_config.fri_params(degree_bits, false)
+impl fmt::Display for SecurityPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for SecurityPreset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.to_string() == s)
+            .ok_or_else(|| anyhow!("Unknown security preset {}.", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    use super::*;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_presets() {
+        for preset in SecurityPreset::ALL {
+            assert_eq!(
+                preset.to_string().parse::<SecurityPreset>().unwrap(),
+                preset
+            );
+        }
+
+        let config = SecurityPreset::Conjectured100.config();
+        assert_eq!(config.security_level::<F, 2>(20).conjectured_bits(), 100);
+        config.validate::<F, 2>(25).unwrap();
+        assert!(config.validate::<F, 2>(26).is_err());
+        let zk_config = StarkConfig {
+            zero_knowledge: true,
+            ..config.clone()
+        };
+        assert!(zk_config.validate::<F, 2>(25).is_err());
+        // The blinding covers the two openings and a coset of 2^4 points per
+        // FRI query.
+        assert_eq!(config.blinding_bits(2), 0);
+        assert_eq!(zk_config.num_blinding_values(), 2 + 28 * 16);
+        assert_eq!(zk_config.blinding_bits(2), 7);
+        assert_eq!(zk_config.blinding_bits(20), 1);
+        assert_eq!(config.quotient_chunk_len(2), 4);
+        assert_eq!(zk_config.quotient_chunk_len(2), 512 - 450);
+        let batch_low_memory_config = StarkConfig {
+            batch_fri: true,
+            low_memory: true,
+            ..config.clone()
+        };
+        assert!(batch_low_memory_config.validate::<F, 2>(20).is_err());
+        let zk_low_memory_config = StarkConfig {
+            zero_knowledge: true,
+            low_memory: true,
+            ..config.clone()
+        };
+        assert!(zk_low_memory_config.validate::<F, 2>(16).is_err());
+
+        for preset in SecurityPreset::PROVABLE {
+            preset.config().validate::<F, 2>(20).unwrap();
+        }
+        // The quadratic extension of Goldilocks caps the field bounds below
+        // 128 bits.
+        for preset in [SecurityPreset::Conjectured128, SecurityPreset::Proven128] {
+            let config = preset.config();
+            assert!(config.validate::<F, 2>(16).is_err());
+            config.validate::<F, 4>(24).unwrap();
+        }
+        assert!(
+            SecurityPreset::Conjectured128
+                .config()
+                .security_level::<F, 4>(24)
+                .proven_bits()
+                < 128
+        );
+
+        let weak_config = StarkConfig {
+            fri_config: FriConfig {
+                num_query_rounds: 20,
+                ..config.fri_config.clone()
+            },
+            ..config
+        };
+        assert!(weak_config.validate::<F, 2>(16).is_err());
     }
 }
@@ -0,0 +1,136 @@
+//! Evaluates every stark's constraints on each row of a generated trace.
+//! An unsatisfiable trace still proves, the proof just fails to verify, so
+//! this is the way to find out which table, row and constraint is at fault.
+
+use std::fmt::{Display, Formatter};
+use std::panic::Location;
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+
+use super::constraint_consumer::{ConstraintConsumer, ConstraintKind};
+use super::ola_stark::{OlaStark, Table, TableRegistry};
+use super::stark::Stark;
+use super::vars::StarkEvaluationVars;
+
+/// A constraint that does not vanish on a trace row.
+#[derive(Debug, Clone)]
+pub struct ConstraintFailure<F: Field> {
+    pub table: Table,
+    pub row: usize,
+    /// Position among the constraints the stark emits for each row.
+    pub constraint: usize,
+    pub kind: ConstraintKind,
+    pub location: &'static Location<'static>,
+    pub value: F,
+    /// Named columns of the failing row.
+    pub local_values: Vec<(String, F)>,
+    /// Named columns of the following row, which constraints of any kind
+    /// may refer to.
+    pub next_values: Vec<(String, F)>,
+}
+
+impl<F: Field> Display for ConstraintFailure<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} row {}: constraint {} ({:?}, {}) evaluates to {}",
+            self.table, self.row, self.constraint, self.kind, self.location, self.value
+        )?;
+        write!(f, "\n  local: {}", format_columns(&self.local_values))?;
+        write!(f, "\n  next: {}", format_columns(&self.next_values))
+    }
+}
+
+fn format_columns<F: Field>(columns: &[(String, F)]) -> String {
+    columns
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Checks every table's constraints, first-row, last-row and transition
+/// ones included, on the trace the prover would commit to. Cross-table
+/// lookups, in-table lookups and permutation arguments are not covered.
+pub fn check_constraints<F, const D: usize>(
+    ola_stark: &OlaStark<F, D>,
+    traces: &[Vec<PolynomialValues<F>>],
+) -> Vec<ConstraintFailure<F>>
+where
+    F: RichField + Extendable<D>,
+    OlaStark<F, D>: TableRegistry<F, D>,
+{
+    ola_stark
+        .tables()
+        .into_iter()
+        .zip(traces)
+        .flat_map(|(table, trace)| {
+            let len = trace.first().map_or(0, |column| column.len());
+            table.check_rows(trace, &mut (0..len))
+        })
+        .collect()
+}
+
+pub(crate) fn check_table<F, S, const D: usize>(
+    stark: &S,
+    table: Table,
+    trace: &[PolynomialValues<F>],
+    rows: &mut dyn Iterator<Item = usize>,
+) -> Vec<ConstraintFailure<F>>
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+{
+    let col_names = table.col_names();
+    let len = trace.first().map_or(0, |column| column.len());
+    let row_values =
+        |row: usize| -> [F; S::COLUMNS] { std::array::from_fn(|col| trace[col].values[row]) };
+    let named = |values: &[F; S::COLUMNS]| -> Vec<(String, F)> {
+        col_names
+            .iter()
+            .map(|(col, name)| (name.clone(), values[*col]))
+            .collect()
+    };
+
+    let mut failures = vec![];
+    for row in rows {
+        let local_values = row_values(row);
+        let next_values = row_values((row + 1) % len);
+        let is_first = row == 0;
+        let is_last = row == len - 1;
+        // On the trace domain only whether these vanish matters, ones keep
+        // the reported values unscaled.
+        let mut consumer = ConstraintConsumer::new_recording(
+            if is_last { F::ZERO } else { F::ONE },
+            if is_first { F::ONE } else { F::ZERO },
+            if is_last { F::ONE } else { F::ZERO },
+        );
+        stark.eval_packed_base(
+            StarkEvaluationVars {
+                local_values: &local_values,
+                next_values: &next_values,
+            },
+            &mut consumer,
+        );
+        for (index, constraint) in consumer.recorded().iter().enumerate() {
+            if constraint.value.is_zero() {
+                continue;
+            }
+            failures.push(ConstraintFailure {
+                table,
+                row,
+                constraint: index,
+                kind: constraint.kind,
+                location: constraint.location,
+                value: constraint.value,
+                local_values: named(&local_values),
+                next_values: named(&next_values),
+            });
+        }
+    }
+    failures
+}
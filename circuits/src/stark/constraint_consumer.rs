@@ -1,4 +1,5 @@
 use std::marker::PhantomData;
+use std::panic::Location;
 
 use plonky2::field::extension::Extendable;
 use plonky2::field::packed::PackedField;
@@ -29,6 +30,28 @@ pub struct ConstraintConsumer<P: PackedField> {
     /// point associated with the last trace row, and zero at other points
     /// in the subgroup.
     lagrange_basis_last: P,
+
+    /// Every constraint emitted so far, kept only by consumers built with
+    /// `new_recording`.
+    recorded: Option<Vec<RecordedConstraint<P>>>,
+}
+
+/// Which rows a constraint applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    AllRows,
+    Transition,
+    FirstRow,
+    LastRow,
+}
+
+/// One emitted constraint, already multiplied by its row filter.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordedConstraint<P: PackedField> {
+    pub value: P,
+    pub kind: ConstraintKind,
+    /// Where the stark emitted it, the only name a constraint has.
+    pub location: &'static Location<'static>,
 }
 
 impl<P: PackedField> ConstraintConsumer<P> {
@@ -44,6 +67,16 @@ impl<P: PackedField> ConstraintConsumer<P> {
             z_last,
             lagrange_basis_first,
             lagrange_basis_last,
+            recorded: None,
+        }
+    }
+
+    /// A consumer that keeps each constraint apart instead of combining
+    /// them, to find out which ones a row violates.
+    pub fn new_recording(z_last: P, lagrange_basis_first: P, lagrange_basis_last: P) -> Self {
+        Self {
+            recorded: Some(vec![]),
+            ..Self::new(vec![], z_last, lagrange_basis_first, lagrange_basis_last)
         }
     }
 
@@ -51,29 +84,57 @@ impl<P: PackedField> ConstraintConsumer<P> {
         self.constraint_accs
     }
 
+    /// Constraints emitted so far, in order. Empty unless built with
+    /// `new_recording`.
+    pub fn recorded(&self) -> &[RecordedConstraint<P>] {
+        self.recorded.as_deref().unwrap_or_default()
+    }
+
     /// Add one constraint valid on all rows except the last.
+    #[track_caller]
     pub fn constraint_transition(&mut self, constraint: P) {
-        self.constraint(constraint * self.z_last);
+        self.emit(constraint * self.z_last, ConstraintKind::Transition);
     }
 
     /// Add one constraint on all rows.
+    #[track_caller]
     pub fn constraint(&mut self, constraint: P) {
-        for (&alpha, acc) in self.alphas.iter().zip(&mut self.constraint_accs) {
-            *acc *= alpha;
-            *acc += constraint;
-        }
+        self.emit(constraint, ConstraintKind::AllRows);
     }
 
     /// Add one constraint, but first multiply it by a filter such that it will
     /// only apply to the first row of the trace.
+    #[track_caller]
     pub fn constraint_first_row(&mut self, constraint: P) {
-        self.constraint(constraint * self.lagrange_basis_first);
+        self.emit(
+            constraint * self.lagrange_basis_first,
+            ConstraintKind::FirstRow,
+        );
     }
 
     /// Add one constraint, but first multiply it by a filter such that it will
     /// only apply to the last row of the trace.
+    #[track_caller]
     pub fn constraint_last_row(&mut self, constraint: P) {
-        self.constraint(constraint * self.lagrange_basis_last);
+        self.emit(
+            constraint * self.lagrange_basis_last,
+            ConstraintKind::LastRow,
+        );
+    }
+
+    #[track_caller]
+    fn emit(&mut self, constraint: P, kind: ConstraintKind) {
+        if let Some(recorded) = &mut self.recorded {
+            recorded.push(RecordedConstraint {
+                value: constraint,
+                kind,
+                location: Location::caller(),
+            });
+        }
+        for (&alpha, acc) in self.alphas.iter().zip(&mut self.constraint_accs) {
+            *acc *= alpha;
+            *acc += constraint;
+        }
     }
 }
 
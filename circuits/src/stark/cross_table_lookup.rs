@@ -1,5 +1,6 @@
 use core::types::GoldilocksField;
 use std::borrow::Borrow;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::iter::repeat;
 
@@ -21,7 +22,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::config::StarkConfig;
 use super::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
-use super::ola_stark::{Table, NUM_TABLES};
+use super::ola_stark::Table;
 use super::permutation::{
     get_grand_product_challenge_set, GrandProductChallenge, GrandProductChallengeSet,
 };
@@ -221,6 +222,35 @@ impl<F: Field> Column<F> {
             + self.constant
     }
 
+    /// Trace columns the combination reads.
+    pub(crate) fn column_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.linear_combination.iter().map(|&(c, _)| c)
+    }
+
+    /// Writes the combination out with the given column names, e.g.
+    /// `op0 + 2*op1 + 1`. Columns without a name show as `col{index}`.
+    pub fn describe(&self, col_names: &BTreeMap<usize, String>) -> String {
+        let mut terms: Vec<String> = self
+            .linear_combination
+            .iter()
+            .map(|(c, f)| {
+                let name = col_names
+                    .get(c)
+                    .cloned()
+                    .unwrap_or_else(|| format!("col{}", c));
+                if f.is_one() {
+                    name
+                } else {
+                    format!("{}*{}", f, name)
+                }
+            })
+            .collect();
+        if !self.constant.is_zero() || terms.is_empty() {
+            terms.push(self.constant.to_string());
+        }
+        terms.join(" + ")
+    }
+
     pub fn eval_circuit<const D: usize>(
         &self,
         builder: &mut CircuitBuilder<F, D>,
@@ -242,13 +272,23 @@ impl<F: Field> Column<F> {
         let constant = builder.constant_extension(F::Extension::from_basefield(self.constant));
         builder.inner_product_extension(F::ONE, constant, pairs)
     }
+
+    /// The column as field elements, for digests.
+    pub(crate) fn flatten(&self) -> Vec<F> {
+        let mut elements = vec![F::from_canonical_usize(self.linear_combination.len())];
+        for &(col, coeff) in &self.linear_combination {
+            elements.extend([F::from_canonical_usize(col), coeff]);
+        }
+        elements.push(self.constant);
+        elements
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct TableWithColumns<F: Field> {
-    table: Table,
-    columns: Vec<Column<F>>,
-    filter_column: Option<Column<F>>,
+    pub(crate) table: Table,
+    pub(crate) columns: Vec<Column<F>>,
+    pub(crate) filter_column: Option<Column<F>>,
 }
 
 impl Serialize for TableWithColumns<GoldilocksField> {
@@ -374,12 +414,41 @@ impl<F: Field> TableWithColumns<F> {
             filter_column,
         }
     }
+
+    pub(crate) fn flatten(&self) -> Vec<F> {
+        let mut elements = vec![
+            F::from_canonical_usize(self.table as usize),
+            F::from_canonical_usize(self.columns.len()),
+        ];
+        elements.extend(self.columns.iter().flat_map(Column::flatten));
+        match &self.filter_column {
+            Some(filter_column) => {
+                elements.push(F::ONE);
+                elements.extend(filter_column.flatten());
+            }
+            None => elements.push(F::ZERO),
+        }
+        elements
+    }
+}
+
+/// How the Z polynomials of a cross-table lookup are built.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CtlArgument {
+    /// Running product of the combined rows the filter selects.
+    #[default]
+    GrandProduct,
+    /// Running sum of `filter / combined row`. The filter does not have to be
+    /// binary, so a looked table can hold a multiplicity instead of repeating
+    /// rows.
+    LogUp,
 }
 
 #[derive(Clone, Debug)]
 pub struct CrossTableLookup<F: Field> {
-    looking_tables: Vec<TableWithColumns<F>>,
-    looked_table: TableWithColumns<F>,
+    pub(crate) looking_tables: Vec<TableWithColumns<F>>,
+    pub(crate) looked_table: TableWithColumns<F>,
+    pub(crate) argument: CtlArgument,
 }
 
 impl Serialize for CrossTableLookup<GoldilocksField> {
@@ -387,9 +456,10 @@ impl Serialize for CrossTableLookup<GoldilocksField> {
     where
         S: Serializer,
     {
-        let mut s = serializer.serialize_struct("CrossTableLookup", 2)?;
+        let mut s = serializer.serialize_struct("CrossTableLookup", 3)?;
         s.serialize_field("looking_tables", &self.looking_tables)?;
         s.serialize_field("looked_table", &self.looked_table)?;
+        s.serialize_field("argument", &self.argument)?;
         s.end()
     }
 }
@@ -402,6 +472,7 @@ impl<'de> Deserialize<'de> for CrossTableLookup<GoldilocksField> {
         enum Field {
             LookingTables,
             LookedTable,
+            Argument,
         }
 
         impl<'de> Deserialize<'de> for Field {
@@ -415,7 +486,7 @@ impl<'de> Deserialize<'de> for CrossTableLookup<GoldilocksField> {
                     type Value = Field;
 
                     fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                        formatter.write_str("`looking_tables` or `looked_table`")
+                        formatter.write_str("`looking_tables`, `looked_table` or `argument`")
                     }
 
                     fn visit_str<E>(self, value: &str) -> Result<Field, E>
@@ -425,6 +496,7 @@ impl<'de> Deserialize<'de> for CrossTableLookup<GoldilocksField> {
                         match value {
                             "looking_tables" => Ok(Field::LookingTables),
                             "looked_table" => Ok(Field::LookedTable),
+                            "argument" => Ok(Field::Argument),
                             _ => Err(de::Error::unknown_field(value, FIELDS)),
                         }
                     }
@@ -449,6 +521,7 @@ impl<'de> Deserialize<'de> for CrossTableLookup<GoldilocksField> {
             {
                 let mut looking_tables = None;
                 let mut looked_table = None;
+                let mut argument = None;
                 while let Some(key) = map.next_key()? {
                     match key {
                         Field::LookingTables => {
@@ -463,20 +536,29 @@ impl<'de> Deserialize<'de> for CrossTableLookup<GoldilocksField> {
                             }
                             looked_table = Some(map.next_value()?);
                         }
+                        Field::Argument => {
+                            if argument.is_some() {
+                                return Err(de::Error::duplicate_field("argument"));
+                            }
+                            argument = Some(map.next_value()?);
+                        }
                     }
                 }
                 let looking_tables =
                     looking_tables.ok_or_else(|| de::Error::missing_field("looking_tables"))?;
                 let looked_table =
                     looked_table.ok_or_else(|| de::Error::missing_field("looked_table"))?;
+                // Optional, lookups serialized before it existed are grand products.
+                let argument = argument.unwrap_or_default();
                 Ok(CrossTableLookup {
                     looking_tables,
                     looked_table,
+                    argument,
                 })
             }
         }
 
-        const FIELDS: &'static [&'static str] = &["looking_tables", "looked_table"];
+        const FIELDS: &[&str] = &["looking_tables", "looked_table", "argument"];
         deserializer.deserialize_struct(
             "CrossTableLookup",
             FIELDS,
@@ -496,9 +578,37 @@ impl<F: Field> CrossTableLookup<F> {
         Self {
             looking_tables,
             looked_table,
+            argument: CtlArgument::GrandProduct,
+        }
+    }
+
+    /// A lookup proven with a logarithmic derivative argument rather than a
+    /// grand product.
+    pub fn new_logup(
+        looking_tables: Vec<TableWithColumns<F>>,
+        looked_table: TableWithColumns<F>,
+    ) -> Self {
+        Self {
+            argument: CtlArgument::LogUp,
+            ..Self::new(looking_tables, looked_table)
         }
     }
 
+    /// The lookup as field elements, for digests.
+    pub(crate) fn flatten(&self) -> Vec<F> {
+        let mut elements = vec![
+            F::from_canonical_usize(self.argument as usize),
+            F::from_canonical_usize(self.looking_tables.len()),
+        ];
+        elements.extend(
+            self.looking_tables
+                .iter()
+                .chain([&self.looked_table])
+                .flat_map(TableWithColumns::flatten),
+        );
+        elements
+    }
+
     #[allow(unused)]
     pub(crate) fn num_ctl_zs(ctls: &[Self], table: Table, num_challenges: usize) -> usize {
         let mut num_ctls = 0;
@@ -523,6 +633,7 @@ pub(crate) struct CtlZData<F: Field> {
     pub(crate) challenge: GrandProductChallenge<F>,
     pub(crate) columns: Vec<Column<F>>,
     pub(crate) filter_column: Option<Column<F>>,
+    pub(crate) argument: CtlArgument,
 }
 
 impl<F: Field> CtlData<F> {
@@ -544,38 +655,48 @@ impl<F: Field> CtlData<F> {
 
 pub fn cross_table_lookup_data<F: RichField, C: GenericConfig<D, F = F>, const D: usize>(
     config: &StarkConfig,
-    trace_poly_values: &[Vec<PolynomialValues<F>>; NUM_TABLES],
+    trace_poly_values: &[Vec<PolynomialValues<F>>],
     cross_table_lookups: &[CrossTableLookup<F>],
     challenger: &mut Challenger<F, C::Hasher>,
-) -> [CtlData<F>; NUM_TABLES] {
+) -> Vec<CtlData<F>> {
     let challenges = get_grand_product_challenge_set(challenger, config.num_challenges);
-    let mut ctl_data_per_table = [0; NUM_TABLES].map(|_| CtlData::default());
+    let mut ctl_data_per_table = trace_poly_values
+        .iter()
+        .map(|_| CtlData::default())
+        .collect::<Vec<_>>();
     for CrossTableLookup {
         looking_tables,
         looked_table,
+        argument,
     } in cross_table_lookups
     {
+        let argument = *argument;
+        let z_poly = match argument {
+            CtlArgument::GrandProduct => partial_products,
+            CtlArgument::LogUp => partial_sums,
+        };
         for &challenge in &challenges.challenges {
             let zs_looking = looking_tables.iter().map(|table| {
-                partial_products(
+                z_poly(
                     &trace_poly_values[table.table as usize],
                     &table.columns,
                     &table.filter_column,
                     challenge,
                 )
             });
-            let z_looked = partial_products(
+            let z_looked = z_poly(
                 &trace_poly_values[looked_table.table as usize],
                 &looked_table.columns,
                 &looked_table.filter_column,
                 challenge,
             );
 
+            let looking_lasts = zs_looking.clone().map(|z| *z.values.last().unwrap());
             debug_assert_eq!(
-                zs_looking
-                    .clone()
-                    .map(|z| *z.values.last().unwrap())
-                    .product::<F>(),
+                match argument {
+                    CtlArgument::GrandProduct => looking_lasts.product::<F>(),
+                    CtlArgument::LogUp => looking_lasts.sum::<F>(),
+                },
                 *z_looked.values.last().unwrap()
             );
 
@@ -587,6 +708,7 @@ pub fn cross_table_lookup_data<F: RichField, C: GenericConfig<D, F = F>, const D
                         challenge,
                         columns: table.columns.clone(),
                         filter_column: table.filter_column.clone(),
+                        argument,
                     });
             }
             ctl_data_per_table[looked_table.table as usize]
@@ -596,6 +718,7 @@ pub fn cross_table_lookup_data<F: RichField, C: GenericConfig<D, F = F>, const D
                     challenge,
                     columns: looked_table.columns.clone(),
                     filter_column: looked_table.filter_column.clone(),
+                    argument,
                 });
         }
     }
@@ -631,6 +754,38 @@ fn partial_products<F: Field>(
     res.into()
 }
 
+fn partial_sums<F: Field>(
+    trace: &[PolynomialValues<F>],
+    columns: &[Column<F>],
+    filter_column: &Option<Column<F>>,
+    challenge: GrandProductChallenge<F>,
+) -> PolynomialValues<F> {
+    let degree = trace[0].len();
+    let combined = (0..degree)
+        .map(|i| {
+            let evals = columns
+                .iter()
+                .map(|c| c.eval_table(trace, i))
+                .collect::<Vec<_>>();
+            challenge.combine(evals.iter())
+        })
+        .collect::<Vec<_>>();
+    let inverses = F::batch_multiplicative_inverse(&combined);
+
+    let mut partial_sum = F::ZERO;
+    let mut res = Vec::with_capacity(degree);
+    for (i, inverse) in inverses.into_iter().enumerate() {
+        let filter = if let Some(column) = filter_column {
+            column.eval_table(trace, i)
+        } else {
+            F::ONE
+        };
+        partial_sum += filter * inverse;
+        res.push(partial_sum);
+    }
+    res.into()
+}
+
 #[derive(Clone)]
 pub struct CtlCheckVars<'a, F, FE, P, const D2: usize>
 where
@@ -643,35 +798,37 @@ where
     pub(crate) challenges: GrandProductChallenge<F>,
     pub(crate) columns: &'a [Column<F>],
     pub(crate) filter_column: &'a Option<Column<F>>,
+    pub(crate) argument: CtlArgument,
 }
 
 impl<'a, F: RichField + Extendable<D>, const D: usize>
     CtlCheckVars<'a, F, F::Extension, F::Extension, D>
 {
     pub(crate) fn from_proofs<C: GenericConfig<D, F = F>>(
-        proofs: &[StarkProof<F, C, D>; NUM_TABLES],
+        proofs: &[StarkProof<F, C, D>],
         cross_table_lookups: &'a [CrossTableLookup<F>],
         ctl_challenges: &'a GrandProductChallengeSet<F>,
-        num_permutation_zs: &[usize; NUM_TABLES],
-    ) -> [Vec<Self>; NUM_TABLES] {
+        num_aux_columns: &[usize],
+    ) -> Vec<Vec<Self>> {
         let mut ctl_zs = proofs
             .iter()
-            .zip(num_permutation_zs)
-            .map(|(p, &num_perms)| {
+            .zip(num_aux_columns)
+            .map(|(p, &num_aux)| {
                 let openings = &p.openings;
-                let ctl_zs = openings.permutation_ctl_zs.iter().skip(num_perms);
-                let ctl_zs_next = openings.permutation_ctl_zs_next.iter().skip(num_perms);
+                let ctl_zs = openings.permutation_ctl_zs.iter().skip(num_aux);
+                let ctl_zs_next = openings.permutation_ctl_zs_next.iter().skip(num_aux);
                 ctl_zs.zip(ctl_zs_next)
             })
             .collect::<Vec<_>>();
 
-        let mut ctl_vars_per_table = [0; NUM_TABLES].map(|_| vec![]);
+        let mut ctl_vars_per_table = proofs.iter().map(|_| vec![]).collect::<Vec<_>>();
         for CrossTableLookup {
             looking_tables,
             looked_table,
-            ..
+            argument,
         } in cross_table_lookups
         {
+            let argument = *argument;
             for &challenges in &ctl_challenges.challenges {
                 for table in looking_tables {
                     let (looking_z, looking_z_next) = ctl_zs[table.table as usize].next().unwrap();
@@ -681,6 +838,7 @@ impl<'a, F: RichField + Extendable<D>, const D: usize>
                         challenges,
                         columns: &table.columns,
                         filter_column: &table.filter_column,
+                        argument,
                     });
                 }
 
@@ -691,6 +849,7 @@ impl<'a, F: RichField + Extendable<D>, const D: usize>
                     challenges,
                     columns: &looked_table.columns,
                     filter_column: &looked_table.filter_column,
+                    argument,
                 });
             }
         }
@@ -716,6 +875,7 @@ pub(crate) fn eval_cross_table_lookup_checks<F, FE, P, C, S, const D: usize, con
             challenges,
             columns,
             filter_column,
+            argument,
         } = lookup_vars;
         let combine = |v: &[P]| -> P {
             let evals = columns.iter().map(|c| c.eval(v)).collect::<Vec<_>>();
@@ -732,12 +892,26 @@ pub(crate) fn eval_cross_table_lookup_checks<F, FE, P, C, S, const D: usize, con
         let next_filter = filter(vars.next_values);
         let select = |filter, x| filter * x + P::ONES - filter;
 
-        // Check value of `Z(1)`
-        consumer.constraint_first_row(*local_z - select(local_filter, combine(vars.local_values)));
-        // Check `Z(gw) = combination * Z(w)`
-        consumer.constraint_transition(
-            *next_z - *local_z * select(next_filter, combine(vars.next_values)),
-        );
+        match argument {
+            CtlArgument::GrandProduct => {
+                // Check value of `Z(1)`
+                consumer.constraint_first_row(
+                    *local_z - select(local_filter, combine(vars.local_values)),
+                );
+                // Check `Z(gw) = combination * Z(w)`
+                consumer.constraint_transition(
+                    *next_z - *local_z * select(next_filter, combine(vars.next_values)),
+                );
+            }
+            CtlArgument::LogUp => {
+                // Check `Z(1) * combination = filter`
+                consumer.constraint_first_row(*local_z * combine(vars.local_values) - local_filter);
+                // Check `(Z(gw) - Z(w)) * combination = filter`
+                consumer.constraint_transition(
+                    (*next_z - *local_z) * combine(vars.next_values) - next_filter,
+                );
+            }
+        }
     }
 }
 
@@ -748,6 +922,7 @@ pub struct CtlCheckVarsTarget<'a, F: Field, const D: usize> {
     pub(crate) challenges: GrandProductChallenge<Target>,
     pub(crate) columns: &'a [Column<F>],
     pub(crate) filter_column: &'a Option<Column<F>>,
+    pub(crate) argument: CtlArgument,
 }
 
 impl<'a, F: Field, const D: usize> CtlCheckVarsTarget<'a, F, D> {
@@ -757,15 +932,15 @@ impl<'a, F: Field, const D: usize> CtlCheckVarsTarget<'a, F, D> {
         proof: &StarkProofTarget<D>,
         cross_table_lookups: &'a [CrossTableLookup<F>],
         ctl_challenges: &'a GrandProductChallengeSet<Target>,
-        num_permutation_zs: usize,
+        num_aux_columns: usize,
     ) -> Vec<Self> {
         let mut ctl_zs = {
             let openings = &proof.openings;
-            let ctl_zs = openings.permutation_ctl_zs.iter().skip(num_permutation_zs);
+            let ctl_zs = openings.permutation_ctl_zs.iter().skip(num_aux_columns);
             let ctl_zs_next = openings
                 .permutation_ctl_zs_next
                 .iter()
-                .skip(num_permutation_zs);
+                .skip(num_aux_columns);
             ctl_zs.zip(ctl_zs_next)
         };
 
@@ -773,9 +948,10 @@ impl<'a, F: Field, const D: usize> CtlCheckVarsTarget<'a, F, D> {
         for CrossTableLookup {
             looking_tables,
             looked_table,
-            ..
+            argument,
         } in cross_table_lookups
         {
+            let argument = *argument;
             for &challenges in &ctl_challenges.challenges {
                 for looking_table in looking_tables {
                     if looking_table.table == table {
@@ -786,6 +962,7 @@ impl<'a, F: Field, const D: usize> CtlCheckVarsTarget<'a, F, D> {
                             challenges,
                             columns: &looking_table.columns,
                             filter_column: &looking_table.filter_column,
+                            argument,
                         });
                     }
                 }
@@ -798,6 +975,7 @@ impl<'a, F: Field, const D: usize> CtlCheckVarsTarget<'a, F, D> {
                         challenges,
                         columns: &looked_table.columns,
                         filter_column: &looked_table.filter_column,
+                        argument,
                     });
                 }
             }
@@ -824,6 +1002,7 @@ pub(crate) fn eval_cross_table_lookup_checks_circuit<
             challenges,
             columns,
             filter_column,
+            argument,
         } = lookup_vars;
 
         let one = builder.one_extension();
@@ -847,25 +1026,39 @@ pub(crate) fn eval_cross_table_lookup_checks_circuit<
             builder.mul_add_extension(filter, x, tmp) // filter * x + 1 - filter
         }
 
-        // Check value of `Z(1)`
         let local_columns_eval = columns
             .iter()
             .map(|c| c.eval_circuit(builder, vars.local_values))
             .collect::<Vec<_>>();
         let combined_local = challenges.combine_circuit(builder, &local_columns_eval);
-        let selected_local = select(builder, local_filter, combined_local);
-        let first_row = builder.sub_extension(*local_z, selected_local);
-        consumer.constraint_first_row(builder, first_row);
-        // Check `Z(gw) = combination * Z(w)`
         let next_columns_eval = columns
             .iter()
             .map(|c| c.eval_circuit(builder, vars.next_values))
             .collect::<Vec<_>>();
         let combined_next = challenges.combine_circuit(builder, &next_columns_eval);
-        let selected_next = select(builder, next_filter, combined_next);
-        let mut transition = builder.mul_extension(*local_z, selected_next);
-        transition = builder.sub_extension(*next_z, transition);
-        consumer.constraint_transition(builder, transition);
+
+        match argument {
+            CtlArgument::GrandProduct => {
+                // Check value of `Z(1)`
+                let selected_local = select(builder, local_filter, combined_local);
+                let first_row = builder.sub_extension(*local_z, selected_local);
+                consumer.constraint_first_row(builder, first_row);
+                // Check `Z(gw) = combination * Z(w)`
+                let selected_next = select(builder, next_filter, combined_next);
+                let mut transition = builder.mul_extension(*local_z, selected_next);
+                transition = builder.sub_extension(*next_z, transition);
+                consumer.constraint_transition(builder, transition);
+            }
+            CtlArgument::LogUp => {
+                // Check `Z(1) * combination = filter`
+                let first_row = builder.mul_sub_extension(*local_z, combined_local, local_filter);
+                consumer.constraint_first_row(builder, first_row);
+                // Check `(Z(gw) - Z(w)) * combination = filter`
+                let diff = builder.sub_extension(*next_z, *local_z);
+                let transition = builder.mul_sub_extension(diff, combined_next, next_filter);
+                consumer.constraint_transition(builder, transition);
+            }
+        }
     }
 }
 
@@ -875,7 +1068,7 @@ pub(crate) fn verify_cross_table_lookups<
     const D: usize,
 >(
     cross_table_lookups: Vec<CrossTableLookup<F>>,
-    ctl_zs_lasts: [Vec<F>; NUM_TABLES],
+    ctl_zs_lasts: Vec<Vec<F>>,
     ctl_extra_looking_products: Vec<Vec<F>>,
     config: &StarkConfig,
 ) -> Result<()> {
@@ -883,18 +1076,22 @@ pub(crate) fn verify_cross_table_lookups<
     for CrossTableLookup {
         looking_tables,
         looked_table,
+        argument,
     } in cross_table_lookups.iter()
     {
         let extra_product_vec = &ctl_extra_looking_products[looked_table.table as usize];
         for c in 0..config.num_challenges {
-            let looking_zs_prod = looking_tables
+            let looking_zs = looking_tables
                 .iter()
-                .map(|table| *ctl_zs_openings[table.table as usize].next().unwrap())
-                .product::<F>()
-                * extra_product_vec[c];
+                .map(|table| *ctl_zs_openings[table.table as usize].next().unwrap());
+            // Extra looking products only take part in grand products.
+            let looking_z = match argument {
+                CtlArgument::GrandProduct => looking_zs.product::<F>() * extra_product_vec[c],
+                CtlArgument::LogUp => looking_zs.sum::<F>(),
+            };
             let looked_z = *ctl_zs_openings[looked_table.table as usize].next().unwrap();
             ensure!(
-                looking_zs_prod == looked_z,
+                looking_z == looked_z,
                 "Cross-table lookup verification failed."
             );
         }
@@ -912,7 +1109,7 @@ pub(crate) fn verify_cross_table_lookups_circuit<
 >(
     builder: &mut CircuitBuilder<F, D>,
     cross_table_lookups: Vec<CrossTableLookup<F>>,
-    ctl_zs_lasts: [Vec<Target>; NUM_TABLES],
+    ctl_zs_lasts: Vec<Vec<Target>>,
     ctl_extra_looking_products: Vec<Vec<Target>>,
     inner_config: &StarkConfig,
 ) {
@@ -920,19 +1117,25 @@ pub(crate) fn verify_cross_table_lookups_circuit<
     for CrossTableLookup {
         looking_tables,
         looked_table,
+        argument,
     } in cross_table_lookups.into_iter()
     {
         let extra_product_vec = &ctl_extra_looking_products[looked_table.table as usize];
         for c in 0..inner_config.num_challenges {
-            let mut looking_zs_prod = builder.mul_many(
-                looking_tables
-                    .iter()
-                    .map(|table| *ctl_zs_openings[table.table as usize].next().unwrap()),
-            );
-            looking_zs_prod = builder.mul(looking_zs_prod, extra_product_vec[c]);
+            let looking_zs = looking_tables
+                .iter()
+                .map(|table| *ctl_zs_openings[table.table as usize].next().unwrap())
+                .collect::<Vec<_>>();
+            let looking_z = match argument {
+                CtlArgument::GrandProduct => {
+                    let looking_zs_prod = builder.mul_many(looking_zs);
+                    builder.mul(looking_zs_prod, extra_product_vec[c])
+                }
+                CtlArgument::LogUp => builder.add_many(looking_zs),
+            };
 
             let looked_z = *ctl_zs_openings[looked_table.table as usize].next().unwrap();
-            builder.connect(looked_z, looking_zs_prod);
+            builder.connect(looked_z, looking_z);
         }
     }
     debug_assert!(ctl_zs_openings.iter_mut().all(|iter| iter.next().is_none()));
@@ -969,6 +1172,7 @@ pub(crate) mod testutils {
         let CrossTableLookup {
             looking_tables,
             looked_table,
+            ..
         } = ctl;
 
         // Maps `m` with `(table, i) in m[row]` iff the `i`-th row of `table` is equal
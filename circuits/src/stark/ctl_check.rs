@@ -0,0 +1,182 @@
+//! Compares the looking and looked multisets of every cross-table lookup on
+//! generated traces. A mismatch only shows up as a failing grand product
+//! otherwise, this tells which tuples are unmatched and where they come from.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+
+use super::cross_table_lookup::{CrossTableLookup, TableWithColumns};
+use super::ola_stark::Table;
+
+/// A row taking part in a cross-table lookup.
+#[derive(Debug, Clone)]
+pub struct CtlRow<F: Field> {
+    pub table: Table,
+    pub row: usize,
+    /// Looked-up columns, named after the expression that computes them.
+    pub values: Vec<(String, F)>,
+}
+
+impl<F: Field> Display for CtlRow<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let values = self
+            .values
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "{:?} row {}: {}", self.table, self.row, values)
+    }
+}
+
+/// A tuple present a different number of times on the two sides of a
+/// cross-table lookup.
+#[derive(Debug, Clone)]
+pub struct CtlMismatch<F: Field> {
+    /// Index of the lookup in `OlaStark::cross_table_lookups`.
+    pub ctl: usize,
+    pub tuple: Vec<F>,
+    pub looking: Vec<CtlRow<F>>,
+    pub looked: Vec<CtlRow<F>>,
+}
+
+impl<F: Field> Display for CtlMismatch<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CTL #{}: {:?} is looked up {} times but present {} times",
+            self.ctl,
+            self.tuple,
+            self.looking.len(),
+            self.looked.len()
+        )?;
+        for row in &self.looking {
+            write!(f, "\n  looking {}", row)?;
+        }
+        for row in &self.looked {
+            write!(f, "\n  looked {}", row)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rows per tuple, along with the tuples in the order they first occur.
+struct MultiSet<F: Field> {
+    rows: HashMap<Vec<F>, Vec<CtlRow<F>>>,
+    order: Vec<Vec<F>>,
+}
+
+impl<F: Field> MultiSet<F> {
+    fn new() -> Self {
+        Self {
+            rows: HashMap::new(),
+            order: vec![],
+        }
+    }
+
+    fn insert(&mut self, tuple: Vec<F>, row: CtlRow<F>) {
+        let rows = self.rows.entry(tuple.clone()).or_default();
+        if rows.is_empty() {
+            self.order.push(tuple);
+        }
+        rows.push(row);
+    }
+}
+
+/// Checks every cross-table lookup on the traces the prover would commit
+/// to. Mismatches are ordered by lookup, then by where the tuple is first
+/// looked up, tuples that never are coming last.
+///
+/// Panics if a filter evaluates to something other than 0 or 1, as the
+/// prover does.
+pub fn check_ctls<F: Field>(
+    traces: &[Vec<PolynomialValues<F>>],
+    cross_table_lookups: &[CrossTableLookup<F>],
+) -> Vec<CtlMismatch<F>> {
+    let mut col_names = HashMap::new();
+    let mut mismatches = vec![];
+    for (ctl, lookup) in cross_table_lookups.iter().enumerate() {
+        let mut looking = MultiSet::new();
+        for table in &lookup.looking_tables {
+            collect_rows(traces, table, &mut col_names, &mut looking);
+        }
+        let mut looked = MultiSet::new();
+        collect_rows(traces, &lookup.looked_table, &mut col_names, &mut looked);
+
+        for tuple in looking.order {
+            let looking_rows = looking.rows.remove(&tuple).unwrap_or_default();
+            let looked_rows = looked.rows.remove(&tuple).unwrap_or_default();
+            if looking_rows.len() != looked_rows.len() {
+                mismatches.push(CtlMismatch {
+                    ctl,
+                    tuple,
+                    looking: looking_rows,
+                    looked: looked_rows,
+                });
+            }
+        }
+        // whatever is left is never looked up
+        for tuple in looked.order {
+            if let Some(looked_rows) = looked.rows.remove(&tuple) {
+                mismatches.push(CtlMismatch {
+                    ctl,
+                    tuple,
+                    looking: vec![],
+                    looked: looked_rows,
+                });
+            }
+        }
+    }
+    mismatches
+}
+
+fn collect_rows<F: Field>(
+    traces: &[Vec<PolynomialValues<F>>],
+    table: &TableWithColumns<F>,
+    col_names: &mut HashMap<usize, BTreeMap<usize, String>>,
+    multiset: &mut MultiSet<F>,
+) {
+    let names = col_names
+        .entry(table.table as usize)
+        .or_insert_with(|| table.table.col_names());
+    let names: Vec<String> = table
+        .columns
+        .iter()
+        .map(|column| column.describe(names))
+        .collect();
+    let trace = &traces[table.table as usize];
+    let len = trace.first().map_or(0, |column| column.len());
+    for row in 0..len {
+        let filter = table
+            .filter_column
+            .as_ref()
+            .map_or(F::ONE, |column| column.eval_table(trace, row));
+        if filter.is_zero() {
+            continue;
+        }
+        assert!(
+            filter.is_one(),
+            "{:?} row {}: non-binary filter {}",
+            table.table,
+            row,
+            filter
+        );
+        let tuple: Vec<F> = table
+            .columns
+            .iter()
+            .map(|column| column.eval_table(trace, row))
+            .collect();
+        let values = names.iter().cloned().zip(tuple.iter().copied()).collect();
+        multiset.insert(
+            tuple,
+            CtlRow {
+                table: table.table,
+                row,
+                values,
+            },
+        );
+    }
+}
@@ -1,26 +1,33 @@
 use plonky2::field::extension::Extendable;
-use plonky2::fri::proof::{FriProof, FriProofTarget};
+use plonky2::fri::proof::{FriChallenges, FriProofTarget};
 use plonky2::hash::hash_types::RichField;
 use plonky2::iop::challenger::{Challenger, RecursiveChallenger};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
-use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
 
 use super::config::StarkConfig;
-use super::ola_stark::{OlaStark, NUM_TABLES};
+use super::ola_stark::{OlaStark, TableRegistry};
 use super::permutation::{
     get_grand_product_challenge_set, get_n_grand_product_challenge_sets,
     get_n_grand_product_challenge_sets_target,
 };
 use super::proof::*;
+use super::verifying_key::OlaVerifyingKey;
 
 impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> AllProof<F, C, D> {
     /// Computes all Fiat-Shamir challenges used in the STARK proof.
     pub(crate) fn get_challenges(
         &self,
         ola_stark: &OlaStark<F, D>,
+        verifying_key: &OlaVerifyingKey<F, C, D>,
         config: &StarkConfig,
-    ) -> AllProofChallenges<F, D> {
+    ) -> AllProofChallenges<F, D>
+    where
+        OlaStark<F, D>: TableRegistry<F, D>,
+    {
         let mut challenger = Challenger::<F, C::Hasher>::new();
+        challenger.observe_hash::<C::Hasher>(verifying_key.digest());
+        observe_degree_bits(&mut challenger, &self.degree_bits);
 
         for proof in &self.stark_proofs {
             challenger.observe_cap(&proof.trace_cap);
@@ -33,18 +40,34 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> A
 
         let num_permutation_zs = ola_stark.nums_permutation_zs(config);
         let num_permutation_batch_sizes = ola_stark.permutation_batch_sizes();
+        let num_lookup_columns = ola_stark.nums_lookup_helper_columns(config);
 
-        AllProofChallenges {
-            stark_challenges: std::array::from_fn(|i| {
+        let stark_challenges = (0..self.stark_proofs.len())
+            .map(|i| {
                 challenger.compact();
                 self.stark_proofs[i].get_challenges(
                     &mut challenger,
                     num_permutation_zs[i] > 0,
                     num_permutation_batch_sizes[i],
+                    num_lookup_columns[i] > 0,
+                    self.degree_bits[i],
                     config,
                 )
-            }),
+            })
+            .collect();
+
+        let batch_fri_challenges = self
+            .batch_opening_proofs
+            .iter()
+            .map(|batch| {
+                batch.get_challenges(&mut challenger, self.degree_bits[batch.tables[0]], config)
+            })
+            .collect();
+
+        AllProofChallenges {
+            stark_challenges,
             ctl_challenges,
+            batch_fri_challenges,
         }
     }
 
@@ -52,9 +75,15 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> A
     pub(crate) fn get_challenger_states(
         &self,
         ola_stark: &OlaStark<F, D>,
+        verifying_key: &OlaVerifyingKey<F, C, D>,
         config: &StarkConfig,
-    ) -> AllChallengerState<F, D> {
+    ) -> AllChallengerState<F, D>
+    where
+        OlaStark<F, D>: TableRegistry<F, D>,
+    {
         let mut challenger = Challenger::<F, C::Hasher>::new();
+        challenger.observe_hash::<C::Hasher>(verifying_key.digest());
+        observe_degree_bits(&mut challenger, &self.degree_bits);
 
         for proof in &self.stark_proofs {
             challenger.observe_cap(&proof.trace_cap);
@@ -67,25 +96,39 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> A
 
         let num_permutation_zs = ola_stark.nums_permutation_zs(config);
         let num_permutation_batch_sizes = ola_stark.permutation_batch_sizes();
+        let num_lookup_columns = ola_stark.nums_lookup_helper_columns(config);
 
         let mut challenger_states = vec![challenger.compact()];
-        for i in 0..NUM_TABLES {
+        for i in 0..self.stark_proofs.len() {
             self.stark_proofs[i].get_challenges(
                 &mut challenger,
                 num_permutation_zs[i] > 0,
                 num_permutation_batch_sizes[i],
+                num_lookup_columns[i] > 0,
+                self.degree_bits[i],
                 config,
             );
             challenger_states.push(challenger.compact());
         }
 
         AllChallengerState {
-            states: challenger_states.try_into().unwrap(),
+            states: challenger_states,
             ctl_challenges,
         }
     }
 }
 
+/// Observes the heights of the tables, which the proof states rather than
+/// the commitments.
+pub(crate) fn observe_degree_bits<F: RichField, H: Hasher<F>>(
+    challenger: &mut Challenger<F, H>,
+    degree_bits: &[usize],
+) {
+    for &bits in degree_bits {
+        challenger.observe_element(F::from_canonical_usize(bits));
+    }
+}
+
 impl<F, C, const D: usize> StarkProof<F, C, D>
 where
     F: RichField + Extendable<D>,
@@ -97,21 +140,15 @@ where
         challenger: &mut Challenger<F, C::Hasher>,
         stark_use_permutation: bool,
         stark_permutation_batch_size: usize,
+        stark_use_lookups: bool,
+        degree_bits: usize,
         config: &StarkConfig,
     ) -> StarkProofChallenges<F, D> {
-        let degree_bits = self.recover_degree_bits(config);
-
         let StarkProof {
             permutation_ctl_zs_cap,
             quotient_polys_cap,
             openings,
-            opening_proof:
-                FriProof {
-                    commit_phase_merkle_caps,
-                    final_poly,
-                    pow_witness,
-                    ..
-                },
+            opening_proof,
             ..
         } = &self;
 
@@ -125,6 +162,9 @@ where
             )
         });
 
+        let lookup_challenges =
+            stark_use_lookups.then(|| challenger.get_n_challenges(num_challenges));
+
         challenger.observe_cap(permutation_ctl_zs_cap);
 
         let stark_alphas = challenger.get_n_challenges(num_challenges);
@@ -134,21 +174,49 @@ where
 
         challenger.observe_openings(&openings.to_fri_openings());
 
+        let fri_challenges = opening_proof.as_ref().map(|opening_proof| {
+            challenger.fri_challenges::<C, D>(
+                &opening_proof.commit_phase_merkle_caps,
+                &opening_proof.final_poly,
+                opening_proof.pow_witness,
+                config.fri_params(degree_bits).degree_bits,
+                &config.fri_config,
+            )
+        });
+
         StarkProofChallenges {
             permutation_challenge_sets,
+            lookup_challenges,
             stark_alphas,
             stark_zeta,
-            fri_challenges: challenger.fri_challenges::<C, D>(
-                commit_phase_merkle_caps,
-                final_poly,
-                *pow_witness,
-                degree_bits,
-                &config.fri_config,
-            ),
+            fri_challenges,
         }
     }
 }
 
+impl<F, C, const D: usize> BatchOpeningProof<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    /// Computes the FRI challenges of the batch, once the openings of all
+    /// tables have been observed.
+    pub(crate) fn get_challenges(
+        &self,
+        challenger: &mut Challenger<F, C::Hasher>,
+        degree_bits: usize,
+        config: &StarkConfig,
+    ) -> FriChallenges<F, D> {
+        challenger.fri_challenges::<C, D>(
+            &self.opening_proof.commit_phase_merkle_caps,
+            &self.opening_proof.final_poly,
+            self.opening_proof.pow_witness,
+            config.fri_params(degree_bits).degree_bits,
+            &config.fri_config,
+        )
+    }
+}
+
 impl<const D: usize> StarkProofTarget<D> {
     #[allow(unused)]
     pub(crate) fn get_challenges<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>>(
@@ -157,6 +225,7 @@ impl<const D: usize> StarkProofTarget<D> {
         challenger: &mut RecursiveChallenger<F, C::Hasher, D>,
         stark_use_permutation: bool,
         stark_permutation_batch_size: usize,
+        stark_use_lookups: bool,
         config: &StarkConfig,
     ) -> StarkProofChallengesTarget<D>
     where
@@ -187,6 +256,9 @@ impl<const D: usize> StarkProofTarget<D> {
             )
         });
 
+        let lookup_challenges =
+            stark_use_lookups.then(|| challenger.get_n_challenges(builder, num_challenges));
+
         challenger.observe_cap(permutation_ctl_zs_cap);
 
         let stark_alphas = challenger.get_n_challenges(builder, num_challenges);
@@ -198,6 +270,7 @@ impl<const D: usize> StarkProofTarget<D> {
 
         StarkProofChallengesTarget {
             permutation_challenge_sets,
+            lookup_challenges,
             stark_alphas,
             stark_zeta,
             fri_challenges: challenger.fri_challenges::<C>(
@@ -1,13 +1,19 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
 
 use itertools::Itertools;
-use plonky2::field::extension::Extendable;
+use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
 use plonky2::field::types::{Field, PrimeField64};
 use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::iop::target::Target;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2_util::ceil_div_usize;
 
 use crate::stark::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::stark::stark::Stark;
 use crate::stark::vars::{StarkEvaluationTargets, StarkEvaluationVars};
 
 pub(crate) fn eval_lookups<F: Field, P: PackedField<Scalar = F>, const COLS: usize>(
@@ -174,3 +180,372 @@ pub fn extend_to_power_of_2<F: PrimeField64>(inputs: &[F], table: &[F]) -> (Vec<
 
     (extend_input, extend_table)
 }
+
+/// A lookup of every cell of `columns` into `table_column`, proven with a
+/// logarithmic derivative argument rather than with permuted columns: for a
+/// random `alpha`, `sum_i sum_c 1 / (alpha + c_i) = sum_i m_i / (alpha + t_i)`,
+/// with the multiplicities `m_i` held in `frequencies_column`.
+#[derive(Clone, Debug)]
+pub struct Lookup {
+    pub columns: Vec<usize>,
+    pub table_column: usize,
+    pub frequencies_column: usize,
+}
+
+impl Lookup {
+    /// Helper columns the lookup needs per challenge: one per batch of looking
+    /// columns, then the running sum.
+    pub(crate) fn num_helper_columns(&self, batch_size: usize) -> usize {
+        ceil_div_usize(self.columns.len(), batch_size) + 1
+    }
+}
+
+/// Multiplicities of the values of `table` among the cells of `inputs`, each
+/// counted on the first row holding the value. Panics if a cell is not in
+/// the table.
+pub fn lookup_frequencies<F: PrimeField64>(inputs: &[&[F]], table: &[F]) -> Vec<F> {
+    let mut rows = HashMap::new();
+    for (row, value) in table.iter().enumerate() {
+        rows.entry(value.to_canonical_u64()).or_insert(row);
+    }
+    let mut frequencies = vec![0u64; table.len()];
+    for value in inputs.iter().flat_map(|column| column.iter()) {
+        let row = rows
+            .get(&value.to_canonical_u64())
+            .unwrap_or_else(|| panic!("{} is not in the lookup table", value));
+        frequencies[*row] += 1;
+    }
+    frequencies.into_iter().map(F::from_canonical_u64).collect()
+}
+
+/// Computes the helper columns of a lookup for one challenge. Each batch of
+/// looking columns gets the sum of their shifted inverses, and the last
+/// column is the running sum of those minus the table side, starting at 0.
+pub(crate) fn lookup_helper_columns<F: Field>(
+    lookup: &Lookup,
+    trace: &[PolynomialValues<F>],
+    challenge: F,
+    batch_size: usize,
+) -> Vec<PolynomialValues<F>> {
+    let (helper_columns, sum) = lookup_running_sum(lookup, trace, challenge, batch_size);
+    debug_assert!(
+        sum.is_zero(),
+        "Lookup into column {} does not balance",
+        lookup.table_column
+    );
+    helper_columns
+}
+
+/// The helper columns of a lookup, along with the value the running sum
+/// reaches past the last row, zero if and only if the lookup holds.
+fn lookup_running_sum<F: Field>(
+    lookup: &Lookup,
+    trace: &[PolynomialValues<F>],
+    challenge: F,
+    batch_size: usize,
+) -> (Vec<PolynomialValues<F>>, F) {
+    let degree = trace[0].len();
+    let inverses = |column: usize| {
+        let shifted = trace[column]
+            .values
+            .iter()
+            .map(|&value| value + challenge)
+            .collect_vec();
+        F::batch_multiplicative_inverse(&shifted)
+    };
+
+    let mut helper_columns = lookup
+        .columns
+        .chunks(batch_size)
+        .map(|batch| {
+            let mut sums = vec![F::ZERO; degree];
+            for &column in batch {
+                for (sum, inverse) in sums.iter_mut().zip(inverses(column)) {
+                    *sum += inverse;
+                }
+            }
+            PolynomialValues::new(sums)
+        })
+        .collect_vec();
+
+    let table_inverses = inverses(lookup.table_column);
+    let frequencies = &trace[lookup.frequencies_column].values;
+    let mut z = Vec::with_capacity(degree);
+    let mut sum = F::ZERO;
+    for row in 0..degree {
+        z.push(sum);
+        sum += helper_columns
+            .iter()
+            .map(|column| column.values[row])
+            .sum::<F>()
+            - frequencies[row] * table_inverses[row];
+    }
+    helper_columns.push(PolynomialValues::new(z));
+    (helper_columns, sum)
+}
+
+/// Whether the lookup constraints of `stark` vanish on every row of `trace`,
+/// with helper columns computed as an honest prover would. A trace whose
+/// lookups do not balance has no helper columns satisfying the constraints,
+/// these ones included.
+#[cfg(test)]
+pub(crate) fn lookups_hold<F, S, const D: usize>(stark: &S, trace: &[PolynomialValues<F>]) -> bool
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+{
+    let challenges = vec![F::rand()];
+    let batch_size = stark.lookup_batch_size();
+    let helper_columns = stark
+        .lookups()
+        .iter()
+        .flat_map(|lookup| lookup_running_sum(lookup, trace, challenges[0], batch_size).0)
+        .collect_vec();
+    let len = trace[0].len();
+    (0..len).all(|row| {
+        let next_row = (row + 1) % len;
+        let local_values: [F; S::COLUMNS] = std::array::from_fn(|col| trace[col].values[row]);
+        let next_values: [F; S::COLUMNS] = std::array::from_fn(|col| trace[col].values[next_row]);
+        let vars = StarkEvaluationVars {
+            local_values: &local_values,
+            next_values: &next_values,
+        };
+        let lookup_vars = LookupCheckVars {
+            local_values: helper_columns.iter().map(|c| c.values[row]).collect(),
+            next_values: helper_columns.iter().map(|c| c.values[next_row]).collect(),
+            challenges: challenges.clone(),
+        };
+        let mut consumer = ConstraintConsumer::new_recording(F::ONE, F::ZERO, F::ZERO);
+        eval_packed_lookups_generic::<F, F, F, S, D, 1>(stark, vars, lookup_vars, &mut consumer);
+        consumer
+            .recorded()
+            .iter()
+            .all(|constraint| constraint.value.is_zero())
+    })
+}
+
+pub struct LookupCheckVars<F, FE, P, const D2: usize>
+where
+    F: Field,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+{
+    pub(crate) local_values: Vec<P>,
+    pub(crate) next_values: Vec<P>,
+    pub(crate) challenges: Vec<F>,
+}
+
+/// Constrains the helper columns of every lookup of the stark. The running
+/// sum constraint wraps around from the last row to the first, so the sum
+/// over the whole trace has to vanish.
+pub(crate) fn eval_packed_lookups_generic<F, FE, P, S, const D: usize, const D2: usize>(
+    stark: &S,
+    vars: StarkEvaluationVars<FE, P, { S::COLUMNS }>,
+    lookup_vars: LookupCheckVars<F, FE, P, D2>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) where
+    F: RichField + Extendable<D>,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+    S: Stark<F, D>,
+{
+    let batch_size = stark.lookup_batch_size();
+    let mut start = 0;
+    for lookup in stark.lookups() {
+        let num_helper_columns = lookup.num_helper_columns(batch_size);
+        for &challenge in &lookup_vars.challenges {
+            let challenge = FE::from_basefield(challenge);
+            let helpers = &lookup_vars.local_values[start..start + num_helper_columns - 1];
+            for (&helper, batch) in helpers.iter().zip(lookup.columns.chunks(batch_size)) {
+                let shifted = batch
+                    .iter()
+                    .map(|&column| vars.local_values[column] + challenge)
+                    .collect_vec();
+                // helper = sum_k 1 / shifted_k, multiplied by all the denominators
+                let product = shifted.iter().fold(P::ONES, |acc, &x| acc * x);
+                let sum = (0..shifted.len())
+                    .map(|k| {
+                        shifted
+                            .iter()
+                            .enumerate()
+                            .filter(|&(l, _)| l != k)
+                            .fold(P::ONES, |acc, (_, &x)| acc * x)
+                    })
+                    .fold(P::ZEROS, |acc, x| acc + x);
+                yield_constr.constraint(helper * product - sum);
+            }
+
+            let z = lookup_vars.local_values[start + num_helper_columns - 1];
+            let next_z = lookup_vars.next_values[start + num_helper_columns - 1];
+            let helpers_sum = helpers.iter().fold(P::ZEROS, |acc, &x| acc + x);
+            let table = vars.local_values[lookup.table_column] + challenge;
+            let frequencies = vars.local_values[lookup.frequencies_column];
+            yield_constr.constraint((next_z - z - helpers_sum) * table + frequencies);
+            start += num_helper_columns;
+        }
+    }
+}
+
+pub struct LookupCheckVarsTarget<const D: usize> {
+    pub(crate) local_values: Vec<ExtensionTarget<D>>,
+    pub(crate) next_values: Vec<ExtensionTarget<D>>,
+    pub(crate) challenges: Vec<Target>,
+}
+
+#[allow(unused)]
+pub(crate) fn eval_ext_lookups_circuit<F, S, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    stark: &S,
+    vars: StarkEvaluationTargets<D, { S::COLUMNS }>,
+    lookup_vars: LookupCheckVarsTarget<D>,
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+{
+    let batch_size = stark.lookup_batch_size();
+    let mut start = 0;
+    for lookup in stark.lookups() {
+        let num_helper_columns = lookup.num_helper_columns(batch_size);
+        for &challenge in &lookup_vars.challenges {
+            let challenge = builder.convert_to_ext(challenge);
+            let helpers = &lookup_vars.local_values[start..start + num_helper_columns - 1];
+            for (&helper, batch) in helpers.iter().zip(lookup.columns.chunks(batch_size)) {
+                let shifted = batch
+                    .iter()
+                    .map(|&column| builder.add_extension(vars.local_values[column], challenge))
+                    .collect_vec();
+                let product = builder.mul_many_extension(shifted.iter().copied());
+                let mut sum = builder.zero_extension();
+                for k in 0..shifted.len() {
+                    let others = shifted
+                        .iter()
+                        .enumerate()
+                        .filter(|&(l, _)| l != k)
+                        .map(|(_, &x)| x);
+                    let term = builder.mul_many_extension(others);
+                    sum = builder.add_extension(sum, term);
+                }
+                let constraint = builder.mul_sub_extension(helper, product, sum);
+                yield_constr.constraint(builder, constraint);
+            }
+
+            let z = lookup_vars.local_values[start + num_helper_columns - 1];
+            let next_z = lookup_vars.next_values[start + num_helper_columns - 1];
+            let helpers_sum = builder.add_many_extension(helpers.iter().copied());
+            let diff = builder.sub_extension(next_z, z);
+            let diff = builder.sub_extension(diff, helpers_sum);
+            let table = builder.add_extension(vars.local_values[lookup.table_column], challenge);
+            let constraint = builder.mul_add_extension(
+                diff,
+                table,
+                vars.local_values[lookup.frequencies_column],
+            );
+            yield_constr.constraint(builder, constraint);
+            start += num_helper_columns;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::extension::FieldExtension;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::packed::PackedField;
+    use plonky2::field::polynomial::PolynomialValues;
+    use plonky2::field::types::Field;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+    use super::{lookup_frequencies, lookups_hold, Lookup};
+    use crate::stark::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+    use crate::stark::stark::Stark;
+    use crate::stark::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+    type F = GoldilocksField;
+    const D: usize = 2;
+
+    const LIMB: usize = 0;
+    const TABLE: usize = 1;
+    const FREQUENCIES: usize = 2;
+
+    /// A limb column looked up in the range `0..8`, and nothing else.
+    struct RangeStark;
+
+    impl Stark<F, D> for RangeStark {
+        const COLUMNS: usize = 3;
+
+        fn eval_packed_generic<FE, P, const D2: usize>(
+            &self,
+            _vars: StarkEvaluationVars<FE, P, 3>,
+            _yield_constr: &mut ConstraintConsumer<P>,
+        ) where
+            FE: FieldExtension<D2, BaseField = F>,
+            P: PackedField<Scalar = FE>,
+        {
+        }
+
+        fn eval_ext_circuit(
+            &self,
+            _builder: &mut CircuitBuilder<F, D>,
+            _vars: StarkEvaluationTargets<D, 3>,
+            _yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+        ) {
+        }
+
+        fn constraint_degree(&self) -> usize {
+            3
+        }
+
+        fn lookups(&self) -> Vec<Lookup> {
+            vec![Lookup {
+                columns: vec![LIMB],
+                table_column: TABLE,
+                frequencies_column: FREQUENCIES,
+            }]
+        }
+    }
+
+    fn range_trace(limbs: [u64; 8], frequencies: [u64; 8]) -> Vec<PolynomialValues<F>> {
+        [limbs, [0, 1, 2, 3, 4, 5, 6, 7], frequencies]
+            .into_iter()
+            .map(|column| PolynomialValues::new(column.map(F::from_canonical_u64).to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_logup_accepts_honest_trace() {
+        let limbs = [1, 3, 3, 0, 7, 2, 2, 2];
+        let table = (0..8).map(F::from_canonical_u64).collect::<Vec<_>>();
+        let limbs_column = limbs.map(F::from_canonical_u64);
+        let frequencies = lookup_frequencies(&[limbs_column.as_slice()], &table);
+        assert_eq!(
+            frequencies,
+            [1, 1, 3, 2, 0, 0, 0, 1].map(F::from_canonical_u64)
+        );
+
+        let trace = range_trace(limbs, [1, 1, 3, 2, 0, 0, 0, 1]);
+        assert!(lookups_hold::<F, _, D>(&RangeStark, &trace));
+    }
+
+    #[test]
+    fn test_logup_rejects_out_of_range_limb() {
+        // 8 is not in the table, every other limb is counted.
+        let trace = range_trace([1, 3, 3, 0, 8, 2, 2, 2], [1, 1, 3, 2, 0, 0, 0, 0]);
+        assert!(!lookups_hold::<F, _, D>(&RangeStark, &trace));
+        // Nor does counting it on any row of the table help.
+        let trace = range_trace([1, 3, 3, 0, 8, 2, 2, 2], [1, 1, 3, 2, 0, 0, 0, 1]);
+        assert!(!lookups_hold::<F, _, D>(&RangeStark, &trace));
+    }
+
+    #[test]
+    fn test_logup_rejects_wrong_frequencies() {
+        // As many matches in total, but 2 is counted once too often and 7
+        // once too few.
+        let trace = range_trace([1, 3, 3, 0, 7, 2, 2, 2], [1, 1, 4, 2, 0, 0, 0, 0]);
+        assert!(!lookups_hold::<F, _, D>(&RangeStark, &trace));
+        // Matches counted against the wrong table rows.
+        let trace = range_trace([1, 3, 3, 0, 7, 2, 2, 2], [1, 1, 1, 1, 1, 1, 1, 1]);
+        assert!(!lookups_hold::<F, _, D>(&RangeStark, &trace));
+    }
+}
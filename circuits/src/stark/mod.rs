@@ -1,15 +1,21 @@
+pub mod batch_fri;
 pub mod config;
+pub mod constraint_check;
 pub mod constraint_consumer;
 pub mod cross_table_lookup;
+pub mod ctl_check;
 mod get_challenges;
 pub mod lookup;
+pub mod mutation;
 pub mod ola_stark;
 pub mod permutation;
 pub mod proof;
 pub mod prover;
 pub mod serialization;
 pub mod stark;
+pub mod table;
 pub mod util;
 pub mod vanishing_poly;
 pub mod vars;
 pub mod verifier;
+pub mod verifying_key;
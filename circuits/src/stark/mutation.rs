@@ -0,0 +1,353 @@
+//! Negative testing of the starks. Cells and rows of an honest trace are
+//! altered one at a time, and every alteration has to make a constraint
+//! fire, or a lookup or cross-table lookup fail. The ones that go through
+//! point at under-constrained columns.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+
+use super::constraint_check::check_constraints;
+use super::cross_table_lookup::{CrossTableLookup, TableWithColumns};
+use super::ctl_check::check_ctls;
+use super::lookup::Lookup;
+use super::ola_stark::{OlaStark, Table, TableRegistry};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MutationKind {
+    /// Flips a cell of a column that only holds 0 and 1.
+    FlipSelector,
+    /// Adds one to a cell.
+    Perturb,
+    /// Swaps a row with the one after it.
+    SwapRows,
+    /// Adds one to a cell a cross-table lookup reads, on a row its filter
+    /// selects.
+    BreakCtl,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mutation {
+    pub table: Table,
+    pub kind: MutationKind,
+    pub row: usize,
+    /// Index and name of the altered column, none when rows are swapped.
+    pub column: Option<(usize, String)>,
+}
+
+impl Display for Mutation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {:?} row {}", self.table, self.kind, self.row)?;
+        if let Some((_, name)) = &self.column {
+            write!(f, " column {}", name)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MutationConfig {
+    /// How many rows each column, and each lookup, is altered on.
+    pub rows_per_column: usize,
+}
+
+impl Default for MutationConfig {
+    fn default() -> Self {
+        Self { rows_per_column: 4 }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MutationReport {
+    /// Number of mutations tried, per table.
+    pub tried: BTreeMap<Table, usize>,
+    /// Mutations neither the constraints nor the lookups caught.
+    pub survivors: Vec<Mutation>,
+}
+
+impl MutationReport {
+    /// Columns with surviving mutations, and how many survived on each.
+    pub fn under_constrained_columns(&self) -> Vec<(Table, String, usize)> {
+        let mut counts = BTreeMap::<(Table, String), usize>::new();
+        for mutation in &self.survivors {
+            if let Some((_, name)) = &mutation.column {
+                *counts.entry((mutation.table, name.clone())).or_default() += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .map(|((table, name), count)| (table, name, count))
+            .collect()
+    }
+}
+
+impl Display for MutationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (&table, tried) in &self.tried {
+            let survived = self
+                .survivors
+                .iter()
+                .filter(|mutation| mutation.table == table)
+                .count();
+            writeln!(f, "{:?}: {} mutations, {} survived", table, tried, survived)?;
+        }
+        for mutation in &self.survivors {
+            writeln!(f, "  {}", mutation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs mutations over every table of honest traces. Panics if the traces
+/// themselves violate a constraint or a cross-table lookup.
+pub fn run_mutations<F, const D: usize>(
+    ola_stark: &OlaStark<F, D>,
+    traces: &[Vec<PolynomialValues<F>>],
+    config: &MutationConfig,
+) -> MutationReport
+where
+    F: RichField + Extendable<D>,
+    OlaStark<F, D>: TableRegistry<F, D>,
+{
+    let ctls = &ola_stark.cross_table_lookups;
+    if let Some(failure) = check_constraints(ola_stark, traces).first() {
+        panic!("honest trace fails: {}", failure);
+    }
+    if let Some(mismatch) = check_ctls(traces, ctls).first() {
+        panic!("honest trace fails: {}", mismatch);
+    }
+
+    let mut traces = traces.to_vec();
+    let mut report = MutationReport::default();
+    for stark in ola_stark.tables() {
+        let table = stark.table();
+        report.tried.insert(table, 0);
+        let len = traces[table as usize]
+            .first()
+            .map_or(0, |column| column.len());
+        for mutation in mutations(table, &traces, ctls, config) {
+            let rows = match mutation.kind {
+                MutationKind::SwapRows => vec![mutation.row, mutation.row + 1],
+                _ => vec![mutation.row],
+            };
+            let saved: Vec<Vec<F>> = traces[table as usize]
+                .iter()
+                .map(|column| rows.iter().map(|&row| column.values[row]).collect())
+                .collect();
+            let before = ctl_tuples(&traces, ctls, table, &rows);
+            let lookups = stark.lookups();
+            let balance_before = lookup_balance(&traces[table as usize], &lookups, &rows);
+
+            apply(&mut traces[table as usize], &mutation);
+            // constraints reading an altered row as the next one included
+            let mut checked: Vec<usize> = rows
+                .iter()
+                .flat_map(|&row| [(row + len - 1) % len, row])
+                .collect();
+            checked.sort_unstable();
+            checked.dedup();
+            let caught = !stark
+                .check_rows(&traces[table as usize], &mut checked.into_iter())
+                .is_empty()
+                || ctl_tuples(&traces, ctls, table, &rows) != before
+                || lookup_balance(&traces[table as usize], &lookups, &rows) != balance_before;
+
+            for (column, values) in traces[table as usize].iter_mut().zip(saved) {
+                for (&row, value) in rows.iter().zip(values) {
+                    column.values[row] = value;
+                }
+            }
+            *report.tried.get_mut(&table).unwrap() += 1;
+            if !caught {
+                report.survivors.push(mutation);
+            }
+        }
+    }
+    report
+}
+
+fn mutations<F: Field>(
+    table: Table,
+    traces: &[Vec<PolynomialValues<F>>],
+    ctls: &[CrossTableLookup<F>],
+    config: &MutationConfig,
+) -> Vec<Mutation> {
+    let trace = &traces[table as usize];
+    let len = trace.first().map_or(0, |column| column.len());
+    if len == 0 {
+        return vec![];
+    }
+    let col_names = table.col_names();
+    let column = |col: usize| {
+        let name = col_names
+            .get(&col)
+            .cloned()
+            .unwrap_or_else(|| format!("col{}", col));
+        Some((col, name))
+    };
+    let mut mutations = vec![];
+
+    for (col, values) in trace.iter().enumerate() {
+        let is_selector = values
+            .values
+            .iter()
+            .all(|value| value.is_zero() || value.is_one());
+        let kind = if is_selector {
+            MutationKind::FlipSelector
+        } else {
+            MutationKind::Perturb
+        };
+        for row in spread(len, config.rows_per_column, col) {
+            mutations.push(Mutation {
+                table,
+                kind,
+                row,
+                column: column(col),
+            });
+        }
+    }
+
+    let differing: Vec<usize> = (0..len - 1)
+        .filter(|&row| {
+            trace
+                .iter()
+                .any(|column| column.values[row] != column.values[row + 1])
+        })
+        .collect();
+    for i in spread(differing.len(), config.rows_per_column, 0) {
+        mutations.push(Mutation {
+            table,
+            kind: MutationKind::SwapRows,
+            row: differing[i],
+            column: None,
+        });
+    }
+
+    for ctl in ctls {
+        let sides = ctl.looking_tables.iter().chain([&ctl.looked_table]);
+        for side in sides.filter(|side| side.table == table) {
+            let selected: Vec<usize> = (0..len)
+                .filter(|&row| filter(side, trace, row).is_one())
+                .collect();
+            for i in spread(selected.len(), config.rows_per_column, 0) {
+                for looked_up in &side.columns {
+                    if let Some(col) = looked_up.column_indices().next() {
+                        mutations.push(Mutation {
+                            table,
+                            kind: MutationKind::BreakCtl,
+                            row: selected[i],
+                            column: column(col),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    mutations
+}
+
+/// Up to `count` indexes spread evenly over `0..len`, shifted by `salt` so
+/// neighbouring columns are not altered on the same rows.
+fn spread(len: usize, count: usize, salt: usize) -> Vec<usize> {
+    if len == 0 {
+        return vec![];
+    }
+    let mut indexes: Vec<usize> = (0..count.min(len))
+        .map(|k| (k * len / count.min(len) + salt) % len)
+        .collect();
+    indexes.sort_unstable();
+    indexes.dedup();
+    indexes
+}
+
+fn apply<F: Field>(trace: &mut [PolynomialValues<F>], mutation: &Mutation) {
+    let row = mutation.row;
+    match (mutation.kind, &mutation.column) {
+        (MutationKind::SwapRows, _) => {
+            for column in trace.iter_mut() {
+                column.values.swap(row, row + 1);
+            }
+        }
+        (MutationKind::FlipSelector, Some((col, _))) => {
+            let value = &mut trace[*col].values[row];
+            *value = F::ONE - *value;
+        }
+        (MutationKind::Perturb | MutationKind::BreakCtl, Some((col, _))) => {
+            trace[*col].values[row] += F::ONE;
+        }
+        (_, None) => unreachable!("cell mutation without a column"),
+    }
+}
+
+fn filter<F: Field>(side: &TableWithColumns<F>, trace: &[PolynomialValues<F>], row: usize) -> F {
+    side.filter_column
+        .as_ref()
+        .map_or(F::ONE, |column| column.eval_table(trace, row))
+}
+
+/// What the given rows of a table contribute to each lookup, as tuple
+/// counts, looked-up tuples counting negatively. A filter that is neither
+/// 0 nor 1 gets a tuple of its own, the grand product does not balance with
+/// it either way.
+fn ctl_tuples<F: Field>(
+    traces: &[Vec<PolynomialValues<F>>],
+    ctls: &[CrossTableLookup<F>],
+    table: Table,
+    rows: &[usize],
+) -> HashMap<(usize, Vec<F>), i64> {
+    let trace = &traces[table as usize];
+    let mut tuples = HashMap::new();
+    for (index, ctl) in ctls.iter().enumerate() {
+        let looking = ctl.looking_tables.iter().map(|side| (side, 1));
+        for (side, sign) in looking.chain([(&ctl.looked_table, -1)]) {
+            if side.table != table {
+                continue;
+            }
+            for &row in rows {
+                let filter = filter(side, trace, row);
+                let tuple = if filter.is_zero() {
+                    continue;
+                } else if filter.is_one() {
+                    side.columns
+                        .iter()
+                        .map(|column| column.eval_table(trace, row))
+                        .collect()
+                } else {
+                    vec![filter]
+                };
+                *tuples.entry((index, tuple)).or_default() += sign;
+            }
+        }
+    }
+    tuples.retain(|_, count| *count != 0);
+    tuples
+}
+
+/// What the given rows contribute to each in-table lookup, per value: one
+/// for every looking cell holding it, minus the frequency the table column
+/// gives it. The lookup argument only holds if these sum to zero over the
+/// trace.
+fn lookup_balance<F: PrimeField64>(
+    trace: &[PolynomialValues<F>],
+    lookups: &[Lookup],
+    rows: &[usize],
+) -> HashMap<(usize, u64), F> {
+    let mut balance = HashMap::new();
+    for (index, lookup) in lookups.iter().enumerate() {
+        for &row in rows {
+            for &column in &lookup.columns {
+                let value = trace[column].values[row].to_canonical_u64();
+                *balance.entry((index, value)).or_insert(F::ZERO) += F::ONE;
+            }
+            let value = trace[lookup.table_column].values[row].to_canonical_u64();
+            *balance.entry((index, value)).or_insert(F::ZERO) -=
+                trace[lookup.frequencies_column].values[row];
+        }
+    }
+    balance.retain(|_, count| !count.is_zero());
+    balance
+}
@@ -1,924 +1,189 @@
 use core::types::GoldilocksField;
-use std::iter;
+use std::collections::BTreeMap;
 
 use super::config::StarkConfig;
-use super::cross_table_lookup::{CrossTableLookup, TableWithColumns};
-use super::stark::Stark;
-use crate::builtins::bitwise::bitwise_stark::{self, BitwiseStark};
-use crate::builtins::cmp::cmp_stark::{self, CmpStark};
-use crate::builtins::poseidon::poseidon_chunk_stark::{self, PoseidonChunkStark};
-use crate::builtins::poseidon::poseidon_stark::{self, PoseidonStark};
-use crate::builtins::rangecheck::rangecheck_stark::{self, RangeCheckStark};
-use crate::builtins::sccall::sccall_stark::{self, SCCallStark};
-use crate::builtins::storage::storage_access_stark::{self, StorageAccessStark};
-use crate::builtins::tape::tape_stark::{self, TapeStark};
-use crate::cpu::cpu_stark;
-use crate::cpu::cpu_stark::CpuStark;
-use crate::memory::memory_stark::{
-    self, ctl_data as mem_ctl_data, ctl_data_mem_rc_diff_cond, ctl_data_mem_sort_rc,
-    ctl_filter as mem_ctl_filter, ctl_filter_mem_rc_diff_cond, ctl_filter_mem_sort_rc, MemoryStark,
+use super::cross_table_lookup::CrossTableLookup;
+use super::table::{OlaTable, TableConstraints, TableStark};
+use crate::builtins::bitwise::bitwise_stark::BitwiseStark;
+use crate::builtins::bitwise::columns::get_bitwise_col_name_map;
+use crate::builtins::cmp::cmp_stark::CmpStark;
+use crate::builtins::cmp::columns::get_cmp_col_name_map;
+use crate::builtins::poseidon::columns::{
+    get_poseidon_chunk_col_name_map, get_poseidon_col_name_map,
 };
-use crate::program::prog_chunk_stark::{self, ProgChunkStark};
-use crate::program::program_stark::{self, ProgramStark};
+use crate::builtins::poseidon::poseidon_chunk_stark::PoseidonChunkStark;
+use crate::builtins::poseidon::poseidon_stark::PoseidonStark;
+use crate::builtins::rangecheck::columns::get_rangecheck_col_name_map;
+use crate::builtins::rangecheck::rangecheck_stark::RangeCheckStark;
+use crate::builtins::sccall::columns::get_sccall_col_name_map;
+use crate::builtins::sccall::sccall_stark::SCCallStark;
+use crate::builtins::storage::columns::get_storage_access_col_name_map;
+use crate::builtins::storage::storage_access_stark::StorageAccessStark;
+use crate::builtins::tape::columns::get_tape_col_name_map;
+use crate::builtins::tape::tape_stark::TapeStark;
+use crate::cpu::columns::get_cpu_col_name_map;
+use crate::cpu::cpu_stark::CpuStark;
+use crate::memory::columns::get_memory_col_name_map;
+use crate::memory::memory_stark::MemoryStark;
+use crate::program::columns::{get_prog_chunk_col_name_map, get_prog_col_name_map};
+use crate::program::prog_chunk_stark::ProgChunkStark;
+use crate::program::program_stark::ProgramStark;
 use plonky2::field::extension::Extendable;
-use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
-use serde::de::{self, MapAccess, Visitor};
-use serde::ser::SerializeStruct;
-use serde::{Deserialize, Deserializer, Serialize};
-
-#[derive(Clone, Debug)]
-pub struct OlaStark<F: RichField + Extendable<D>, const D: usize> {
-    pub cpu_stark: CpuStark<F, D>,
-    pub memory_stark: MemoryStark<F, D>,
-    // builtins
-    pub bitwise_stark: BitwiseStark<F, D>,
-    pub cmp_stark: CmpStark<F, D>,
-    pub rangecheck_stark: RangeCheckStark<F, D>,
-    pub poseidon_stark: PoseidonStark<F, D>,
-    pub poseidon_chunk_stark: PoseidonChunkStark<F, D>,
-    pub storage_access_stark: StorageAccessStark<F, D>,
-    pub tape_stark: TapeStark<F, D>,
-    pub sccall_stark: SCCallStark<F, D>,
-    pub program_stark: ProgramStark<F, D>,
-    pub prog_chunk_stark: ProgChunkStark<F, D>,
-
-    pub cross_table_lookups: Vec<CrossTableLookup<F>>,
-}
-
-impl Serialize for OlaStark<GoldilocksField, 2> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let mut state = serializer.serialize_struct("OlaStark", 13)?;
-        state.serialize_field("cpu_stark", &self.cpu_stark)?;
-        state.serialize_field("memory_stark", &self.memory_stark)?;
-        state.serialize_field("bitwise_stark", &self.bitwise_stark)?;
-        state.serialize_field("cmp_stark", &self.cmp_stark)?;
-        state.serialize_field("rangecheck_stark", &self.rangecheck_stark)?;
-        state.serialize_field("poseidon_stark", &self.poseidon_stark)?;
-        state.serialize_field("poseidon_chunk_stark", &self.poseidon_chunk_stark)?;
-        state.serialize_field("storage_access_stark", &self.storage_access_stark)?;
-        state.serialize_field("tape_stark", &self.tape_stark)?;
-        state.serialize_field("sccall_stark", &self.sccall_stark)?;
-        state.serialize_field("program_stark", &self.program_stark)?;
-        state.serialize_field("prog_chunk_stark", &self.prog_chunk_stark)?;
-        state.serialize_field("cross_table_lookups", &self.cross_table_lookups)?;
-        state.end()
-    }
-}
-
-impl<'de> Deserialize<'de> for OlaStark<GoldilocksField, 2> {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        enum Field {
-            CpuStark,
-            MemoryStark,
-            BitwiseStark,
-            CmpStark,
-            RangeCheckStark,
-            PoseidonStark,
-            PoseidonChunkStark,
-            StorageAccessStark,
-            TapeStark,
-            SccallStark,
-            ProgramStark,
-            ProgChunkStark,
-            CrossTableLookups,
+use plonky2::plonk::config::{GenericConfig, Hasher};
+use serde::{Deserialize, Serialize};
+
+/// Declares the tables of the VM, in proving order: the `Table` variant, the
+/// `OlaStark` field holding the stark and the column names the trace
+/// checkers report. Registering a new table is one line in `ola_tables!`
+/// below.
+macro_rules! ola_tables {
+    ($($variant:ident => $field:ident: $stark:ident, $col_names:path;)+) => {
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        #[serde(bound(
+            serialize = "CrossTableLookup<F>: Serialize",
+            deserialize = "CrossTableLookup<F>: Deserialize<'de>"
+        ))]
+        pub struct OlaStark<F: RichField + Extendable<D>, const D: usize> {
+            $(pub $field: $stark<F, D>,)+
+
+            pub cross_table_lookups: Vec<CrossTableLookup<F>>,
         }
 
-        impl<'de> Deserialize<'de> for Field {
-            fn deserialize<D>(deserializer: D) -> Result<Field, D::Error>
-            where
-                D: Deserializer<'de>,
-            {
-                struct FieldVisitor;
-
-                impl<'de> Visitor<'de> for FieldVisitor {
-                    type Value = Field;
-
-                    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                        formatter.write_str("a valid field identifier")
-                    }
-
-                    fn visit_str<E>(self, value: &str) -> Result<Field, E>
-                    where
-                        E: de::Error,
-                    {
-                        match value {
-                            "cpu_stark" => Ok(Field::CpuStark),
-                            "memory_stark" => Ok(Field::MemoryStark),
-                            "bitwise_stark" => Ok(Field::BitwiseStark),
-                            "cmp_stark" => Ok(Field::CmpStark),
-                            "rangecheck_stark" => Ok(Field::RangeCheckStark),
-                            "poseidon_stark" => Ok(Field::PoseidonStark),
-                            "poseidon_chunk_stark" => Ok(Field::PoseidonChunkStark),
-                            "storage_access_stark" => Ok(Field::StorageAccessStark),
-                            "tape_stark" => Ok(Field::TapeStark),
-                            "sccall_stark" => Ok(Field::SccallStark),
-                            "program_stark" => Ok(Field::ProgramStark),
-                            "prog_chunk_stark" => Ok(Field::ProgChunkStark),
-                            "cross_table_lookups" => Ok(Field::CrossTableLookups),
-                            _ => Err(de::Error::unknown_field(value, FIELDS)),
-                        }
-                    }
-                }
-
-                deserializer.deserialize_identifier(FieldVisitor)
+        impl<F: RichField + Extendable<D>, const D: usize> Default for OlaStark<F, D> {
+            fn default() -> Self {
+                plonky2::field::cfft::ntt::init_gpu();
+
+                let mut ola_stark = Self {
+                    $($field: $stark::default(),)+
+                    cross_table_lookups: vec![],
+                };
+                let cross_table_lookups = ola_stark
+                    .tables_mut()
+                    .iter()
+                    .flat_map(|table| table.cross_table_lookups())
+                    .collect();
+                ola_stark.cross_table_lookups = cross_table_lookups;
+                ola_stark
             }
         }
 
-        struct OlaStarkVisitor {
-            marker: std::marker::PhantomData<GoldilocksField>,
+        impl<F: RichField + Extendable<D>, const D: usize> OlaStark<F, D> {
+            pub(crate) fn tables_mut(&mut self) -> Vec<&mut dyn OlaTable<F, D>> {
+                let tables: Vec<&mut dyn OlaTable<F, D>> = vec![$(&mut self.$field,)+];
+                debug_assert!(tables
+                    .iter()
+                    .enumerate()
+                    .all(|(index, table)| table.table() as usize == index));
+                tables
+            }
         }
 
-        impl<'de> Visitor<'de> for OlaStarkVisitor {
-            type Value = OlaStark<GoldilocksField, 2>;
-
-            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("struct OlaStark")
+        impl TableRegistry<GoldilocksField, 2> for OlaStark<GoldilocksField, 2> {
+            fn tables(&self) -> Vec<&dyn TableConstraints<GoldilocksField, 2>> {
+                vec![$(&self.$field,)+]
             }
+        }
 
-            fn visit_map<V>(self, mut map: V) -> Result<OlaStark<GoldilocksField, 2>, V::Error>
-            where
-                V: MapAccess<'de>,
-            {
-                let mut cpu_stark = None;
-                let mut memory_stark = None;
-                let mut bitwise_stark = None;
-                let mut cmp_stark = None;
-                let mut rangecheck_stark = None;
-                let mut poseidon_stark = None;
-                let mut poseidon_chunk_stark = None;
-                let mut storage_access_stark = None;
-                let mut tape_stark = None;
-                let mut sccall_stark = None;
-                let mut program_stark = None;
-                let mut prog_chunk_stark = None;
-                let mut cross_table_lookups = None;
-                while let Some(key) = map.next_key()? {
-                    match key {
-                        Field::CpuStark => {
-                            if cpu_stark.is_some() {
-                                return Err(de::Error::duplicate_field("cpu_stark"));
-                            }
-                            cpu_stark = Some(map.next_value()?);
-                        }
-                        Field::MemoryStark => {
-                            if memory_stark.is_some() {
-                                return Err(de::Error::duplicate_field("memory_stark"));
-                            }
-                            memory_stark = Some(map.next_value()?);
-                        }
-                        Field::BitwiseStark => {
-                            if bitwise_stark.is_some() {
-                                return Err(de::Error::duplicate_field("bitwise_stark"));
-                            }
-                            bitwise_stark = Some(map.next_value()?);
-                        }
-                        Field::CmpStark => {
-                            if cmp_stark.is_some() {
-                                return Err(de::Error::duplicate_field("cmp_stark"));
-                            }
-                            cmp_stark = Some(map.next_value()?);
-                        }
-                        Field::RangeCheckStark => {
-                            if rangecheck_stark.is_some() {
-                                return Err(de::Error::duplicate_field("rangecheck_stark"));
-                            }
-                            rangecheck_stark = Some(map.next_value()?);
-                        }
-                        Field::PoseidonStark => {
-                            if poseidon_stark.is_some() {
-                                return Err(de::Error::duplicate_field("poseidon_stark"));
-                            }
-                            poseidon_stark = Some(map.next_value()?);
-                        }
-                        Field::PoseidonChunkStark => {
-                            if poseidon_chunk_stark.is_some() {
-                                return Err(de::Error::duplicate_field("poseidon_chunk_stark"));
-                            }
-                            poseidon_chunk_stark = Some(map.next_value()?);
-                        }
-                        Field::StorageAccessStark => {
-                            if storage_access_stark.is_some() {
-                                return Err(de::Error::duplicate_field("storage_access_stark"));
-                            }
-                            storage_access_stark = Some(map.next_value()?);
-                        }
-                        Field::TapeStark => {
-                            if tape_stark.is_some() {
-                                return Err(de::Error::duplicate_field("tape_stark"));
-                            }
-                            tape_stark = Some(map.next_value()?);
-                        }
-                        Field::SccallStark => {
-                            if sccall_stark.is_some() {
-                                return Err(de::Error::duplicate_field("sccall_stark"));
-                            }
-                            sccall_stark = Some(map.next_value()?);
-                        }
-                        Field::ProgramStark => {
-                            if program_stark.is_some() {
-                                return Err(de::Error::duplicate_field("program_stark"));
-                            }
-                            program_stark = Some(map.next_value()?);
-                        }
-                        Field::ProgChunkStark => {
-                            if prog_chunk_stark.is_some() {
-                                return Err(de::Error::duplicate_field("prog_chunk_stark"));
-                            }
-                            prog_chunk_stark = Some(map.next_value()?);
-                        }
-                        Field::CrossTableLookups => {
-                            if cross_table_lookups.is_some() {
-                                return Err(de::Error::duplicate_field("cross_table_lookups"));
-                            }
-                            cross_table_lookups = Some(map.next_value()?);
-                        }
-                    }
-                }
-                // let cpu_stark = cpu_stark.ok_or_else(||
-                // de::Error::missing_field("cpu_stark"))?;
-                let cpu_stark = cpu_stark.ok_or_else(|| de::Error::missing_field("cpu_stark"))?;
-                let memory_stark =
-                    memory_stark.ok_or_else(|| de::Error::missing_field("memory_stark"))?;
-                let bitwise_stark =
-                    bitwise_stark.ok_or_else(|| de::Error::missing_field("bitwise_stark"))?;
-                let cmp_stark = cmp_stark.ok_or_else(|| de::Error::missing_field("cmp_stark"))?;
-                let rangecheck_stark =
-                    rangecheck_stark.ok_or_else(|| de::Error::missing_field("rangecheck_stark"))?;
-                let poseidon_stark =
-                    poseidon_stark.ok_or_else(|| de::Error::missing_field("poseidon_stark"))?;
-                let poseidon_chunk_stark = poseidon_chunk_stark
-                    .ok_or_else(|| de::Error::missing_field("poseidon_chunk_stark"))?;
-                let storage_access_stark = storage_access_stark
-                    .ok_or_else(|| de::Error::missing_field("storage_access_stark"))?;
-                let tape_stark =
-                    tape_stark.ok_or_else(|| de::Error::missing_field("tape_stark"))?;
-                let sccall_stark =
-                    sccall_stark.ok_or_else(|| de::Error::missing_field("sccall_stark"))?;
-                let program_stark =
-                    program_stark.ok_or_else(|| de::Error::missing_field("program_stark"))?;
-                let prog_chunk_stark =
-                    prog_chunk_stark.ok_or_else(|| de::Error::missing_field("prog_chunk_stark"))?;
-                let cross_table_lookups = cross_table_lookups
-                    .ok_or_else(|| de::Error::missing_field("cross_table_lookups"))?;
-                Ok(OlaStark {
-                    cpu_stark,
-                    memory_stark,
-                    bitwise_stark,
-                    cmp_stark,
-                    rangecheck_stark,
-                    poseidon_stark,
-                    poseidon_chunk_stark,
-                    storage_access_stark,
-                    tape_stark,
-                    sccall_stark,
-                    program_stark,
-                    prog_chunk_stark,
-                    cross_table_lookups,
-                })
+        impl<C> StarkRegistry<GoldilocksField, C, 2> for OlaStark<GoldilocksField, 2>
+        where
+            C: GenericConfig<2, F = GoldilocksField>,
+            [(); C::Hasher::HASH_SIZE]:,
+        {
+            fn starks(&self) -> Vec<&dyn TableStark<GoldilocksField, C, 2>> {
+                vec![$(&self.$field,)+]
             }
         }
 
-        const FIELDS: &'static [&'static str] = &[
-            "cpu_stark",
-            "memory_stark",
-            "bitwise_stark",
-            "cmp_stark",
-            "rangecheck_stark",
-            "poseidon_stark",
-            "poseidon_chunk_stark",
-            "storage_access_stark",
-            "tape_stark",
-            "sccall_stark",
-            "program_stark",
-            "prog_chunk_stark",
-            "cross_table_lookups",
-        ];
-        deserializer.deserialize_struct(
-            "OlaStark",
-            FIELDS,
-            OlaStarkVisitor {
-                marker: std::marker::PhantomData,
-            },
-        )
-    }
-}
-
-impl<F: RichField + Extendable<D>, const D: usize> Default for OlaStark<F, D> {
-    fn default() -> Self {
-        plonky2::field::cfft::ntt::init_gpu();
-
-        Self {
-            cpu_stark: CpuStark::default(),
-            memory_stark: MemoryStark::default(),
-            bitwise_stark: BitwiseStark::default(),
-            cmp_stark: CmpStark::default(),
-            rangecheck_stark: RangeCheckStark::default(),
-            poseidon_stark: PoseidonStark::default(),
-            poseidon_chunk_stark: PoseidonChunkStark::default(),
-            storage_access_stark: StorageAccessStark::default(),
-            tape_stark: TapeStark::default(),
-            sccall_stark: SCCallStark::default(),
-            program_stark: ProgramStark::default(),
-            prog_chunk_stark: ProgChunkStark::default(),
-            cross_table_lookups: all_cross_table_lookups(),
+        #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+        pub enum Table {
+            $($variant,)+
         }
-    }
-}
-
-impl<F: RichField + Extendable<D>, const D: usize> OlaStark<F, D> {
-    pub(crate) fn nums_permutation_zs(&self, config: &StarkConfig) -> [usize; NUM_TABLES] {
-        [
-            self.cpu_stark.num_permutation_batches(config),
-            self.memory_stark.num_permutation_batches(config),
-            self.bitwise_stark.num_permutation_batches(config),
-            self.cmp_stark.num_permutation_batches(config),
-            self.rangecheck_stark.num_permutation_batches(config),
-            self.poseidon_stark.num_permutation_batches(config),
-            self.poseidon_chunk_stark.num_permutation_batches(config),
-            self.storage_access_stark.num_permutation_batches(config),
-            self.tape_stark.num_permutation_batches(config),
-            self.sccall_stark.num_permutation_batches(config),
-            self.program_stark.num_permutation_batches(config),
-            self.prog_chunk_stark.num_permutation_batches(config),
-        ]
-    }
 
-    pub(crate) fn permutation_batch_sizes(&self) -> [usize; NUM_TABLES] {
-        [
-            self.cpu_stark.permutation_batch_size(),
-            self.memory_stark.permutation_batch_size(),
-            self.bitwise_stark.permutation_batch_size(),
-            self.cmp_stark.permutation_batch_size(),
-            self.rangecheck_stark.permutation_batch_size(),
-            self.poseidon_stark.permutation_batch_size(),
-            self.poseidon_chunk_stark.permutation_batch_size(),
-            self.storage_access_stark.permutation_batch_size(),
-            self.tape_stark.permutation_batch_size(),
-            self.sccall_stark.permutation_batch_size(),
-            self.program_stark.permutation_batch_size(),
-            self.prog_chunk_stark.permutation_batch_size(),
-        ]
-    }
+        impl Table {
+            /// Column names of the table, keyed by column index.
+            pub fn col_names(self) -> BTreeMap<usize, String> {
+                match self {
+                    $(Table::$variant => $col_names(),)+
+                }
+            }
+        }
+    };
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
-pub enum Table {
-    Cpu = 0,
-    Memory = 1,
+ola_tables! {
+    Cpu => cpu_stark: CpuStark, get_cpu_col_name_map;
+    Memory => memory_stark: MemoryStark, get_memory_col_name_map;
     // builtins
-    Bitwise = 2,
-    Cmp = 3,
-    RangeCheck = 4,
-    Poseidon = 5,
-    PoseidonChunk = 6,
-    StorageAccess = 7,
-    Tape = 8,
-    SCCall = 9,
-    Program = 10,
-    ProgChunk = 11,
+    Bitwise => bitwise_stark: BitwiseStark, get_bitwise_col_name_map;
+    Cmp => cmp_stark: CmpStark, get_cmp_col_name_map;
+    RangeCheck => rangecheck_stark: RangeCheckStark, get_rangecheck_col_name_map;
+    Poseidon => poseidon_stark: PoseidonStark, get_poseidon_col_name_map;
+    PoseidonChunk => poseidon_chunk_stark: PoseidonChunkStark, get_poseidon_chunk_col_name_map;
+    StorageAccess => storage_access_stark: StorageAccessStark, get_storage_access_col_name_map;
+    Tape => tape_stark: TapeStark, get_tape_col_name_map;
+    SCCall => sccall_stark: SCCallStark, get_sccall_col_name_map;
+    Program => program_stark: ProgramStark, get_prog_col_name_map;
+    ProgChunk => prog_chunk_stark: ProgChunkStark, get_prog_chunk_col_name_map;
 }
 
-pub(crate) const NUM_TABLES: usize = 12;
-
-pub(crate) fn all_cross_table_lookups<F: Field>() -> Vec<CrossTableLookup<F>> {
-    vec![
-        ctl_cpu_memory(),
-        ctl_memory_rc_sort(),
-        ctl_memory_rc_region(),
-        ctl_bitwise_cpu(),
-        ctl_cmp_cpu(),
-        ctl_cmp_rangecheck(),
-        ctl_rangecheck_cpu(),
-        ctl_cpu_poseidon_chunk(),
-        ctl_poseidon_chunk_mem(),
-        ctl_chunk_poseidon(),
-        ctl_cpu_poseidon_tree_key(),
-        ctl_cpu_storage_access(),
-        ctl_storage_access_poseidon(),
-        ctl_cpu_tape(),
-        ctl_cpu_sccall(),
-        ctl_cpu_sccall_end(),
-        ctl_cpu_program(),
-        ctl_prog_chunk_prog(),
-        ctl_prog_chunk_storage(),
-    ]
-}
+/// The registered tables along with their constraints. Only implemented for
+/// the field the VM runs over, where the column counts of all tables are
+/// known.
+pub trait TableRegistry<F: RichField + Extendable<D>, const D: usize> {
+    fn tables(&self) -> Vec<&dyn TableConstraints<F, D>>;
 
-fn ctl_cpu_memory<F: Field>() -> CrossTableLookup<F> {
-    let cpu_mem_store_load = TableWithColumns::new(
-        Table::Cpu,
-        cpu_stark::ctl_data_cpu_mem_store_load(),
-        Some(cpu_stark::ctl_filter_cpu_mem_store_load()),
-    );
-    let cpu_mem_call_ret_pc = TableWithColumns::new(
-        Table::Cpu,
-        cpu_stark::ctl_data_cpu_mem_call_ret_pc(),
-        Some(cpu_stark::ctl_filter_cpu_mem_call_ret()),
-    );
-    let cpu_mem_call_ret_fp = TableWithColumns::new(
-        Table::Cpu,
-        cpu_stark::ctl_data_cpu_mem_call_ret_fp(),
-        Some(cpu_stark::ctl_filter_cpu_mem_call_ret()),
-    );
-    let cpu_mem_tload_tstore = TableWithColumns::new(
-        Table::Cpu,
-        cpu_stark::ctl_data_cpu_mem_tload_tstore(),
-        Some(cpu_stark::ctl_filter_cpu_mem_tload_tstore()),
-    );
-    let cpu_sccall_mems = (0..4).map(|i: usize| {
-        TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_cpu_mem_sccall(i),
-            Some(cpu_stark::ctl_filter_cpu_mem_sccall()),
-        )
-    });
-    let cpu_storage_addr = (0..4).map(|i: usize| {
-        TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_cpu_mem_for_storage_addr(i),
-            Some(cpu_stark::ctl_filter_cpu_storage_access()),
-        )
-    });
-    let cpu_storage_value = (0..4).map(|i: usize| {
-        TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_cpu_mem_for_storage_value(i),
-            Some(cpu_stark::ctl_filter_cpu_storage_access()),
-        )
-    });
-
-    let mut all_cpu_lookers = vec![
-        cpu_mem_store_load,
-        cpu_mem_call_ret_pc,
-        cpu_mem_call_ret_fp,
-        cpu_mem_tload_tstore,
-    ];
-    all_cpu_lookers.extend(cpu_sccall_mems);
-    all_cpu_lookers.extend(cpu_storage_addr);
-    all_cpu_lookers.extend(cpu_storage_value);
-    let memory_looked =
-        TableWithColumns::new(Table::Memory, mem_ctl_data(), Some(mem_ctl_filter()));
-    CrossTableLookup::new(all_cpu_lookers, memory_looked)
-}
-
-fn ctl_memory_rc_sort<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::Memory,
-            ctl_data_mem_sort_rc(),
-            Some(ctl_filter_mem_sort_rc()),
-        )],
-        TableWithColumns::new(
-            Table::RangeCheck,
-            rangecheck_stark::ctl_data_memory(),
-            Some(rangecheck_stark::ctl_filter_memory_sort()),
-        ),
-    )
-}
-
-fn ctl_memory_rc_region<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::Memory,
-            ctl_data_mem_rc_diff_cond(),
-            Some(ctl_filter_mem_rc_diff_cond()),
-        )],
-        TableWithColumns::new(
-            Table::RangeCheck,
-            rangecheck_stark::ctl_data_memory(),
-            Some(rangecheck_stark::ctl_filter_memory_region()),
-        ),
-    )
-}
-
-// add bitwise rangecheck instance
-// Cpu table
-// +-----+-----+-----+---------+--------+---------+-----+-----+-----+-----+----
-// | clk | ins | ... | sel_and | sel_or | sel_xor | ... | op0 | op1 | dst | ...
-// +-----+-----+-----+---------+--------+---------+-----+-----+----+----+----
-//
-// Bitwise table
-// +-----+-----+-----+-----+------------+------------+-----------+------------+---
-// | tag | op0 | op1 | res | op0_limb_0 | op0_limb_1 |res_limb_2 | op0_limb_3
-// |...
-// +-----+-----+-----+-----+------------+------------+-----------+------------+---
-//
-// Filter bitwise from CPU Table
-// 1. (sel_add + sel_or + sel_xor) * (op0, op1, dst) = looking_table
-// Filter bitwise from Bitwsie Table
-// 1. (op0, op1, res) = looked_table
-
-// Cross_Lookup_Table(looking_table, looked_table)
-fn ctl_bitwise_cpu<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_with_bitwise(),
-            Some(cpu_stark::ctl_filter_with_bitwise()),
-        )],
-        TableWithColumns::new(
-            Table::Bitwise,
-            bitwise_stark::ctl_data_with_cpu(),
-            Some(bitwise_stark::ctl_filter_with_cpu()),
-        ),
-    )
-}
-
-// add CMP cross lookup instance
-fn ctl_cmp_cpu<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_with_cmp(),
-            Some(cpu_stark::ctl_filter_with_cmp()),
-        )],
-        TableWithColumns::new(
-            Table::Cmp,
-            cmp_stark::ctl_data_with_cpu(),
-            Some(cmp_stark::ctl_filter_with_cpu()),
-        ),
-    )
-}
-
-fn ctl_cmp_rangecheck<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::RangeCheck,
-            rangecheck_stark::ctl_data_with_cmp(),
-            Some(rangecheck_stark::ctl_filter_with_cmp()),
-        )],
-        TableWithColumns::new(
-            Table::Cmp,
-            cmp_stark::ctl_data_with_rangecheck(),
-            Some(cmp_stark::ctl_filter_with_rangecheck()),
-        ),
-    )
-}
-
-// add Rangecheck cross lookup instance
-fn ctl_rangecheck_cpu<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_with_rangecheck(),
-            Some(cpu_stark::ctl_filter_with_rangecheck()),
-        )],
-        TableWithColumns::new(
-            Table::RangeCheck,
-            rangecheck_stark::ctl_data_with_cpu(),
-            Some(rangecheck_stark::ctl_filter_with_cpu()),
-        ),
-    )
-}
-
-fn ctl_cpu_poseidon_chunk<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_with_poseidon_chunk(),
-            Some(cpu_stark::ctl_filter_with_poseidon_chunk()),
-        )],
-        TableWithColumns::new(
-            Table::PoseidonChunk,
-            poseidon_chunk_stark::ctl_data_with_cpu(),
-            Some(poseidon_chunk_stark::ctl_filter_with_cpu()),
-        ),
-    )
-}
-
-fn ctl_poseidon_chunk_mem<F: Field>() -> CrossTableLookup<F> {
-    let looker_src = (0..8).map(|i: usize| {
-        TableWithColumns::new(
-            Table::PoseidonChunk,
-            poseidon_chunk_stark::ctl_data_with_mem_src(i),
-            Some(poseidon_chunk_stark::ctl_filter_with_mem_src(i)),
-        )
-    });
-    let looker_dst = (0..4).map(|i: usize| {
-        TableWithColumns::new(
-            Table::PoseidonChunk,
-            poseidon_chunk_stark::ctl_data_with_mem_dst(i),
-            Some(poseidon_chunk_stark::ctl_filter_with_mem_dst()),
-        )
-    });
-    let all_lookers = looker_src.into_iter().chain(looker_dst).collect();
-    let mem_looked = TableWithColumns::new(
-        Table::Memory,
-        memory_stark::ctl_data_with_poseidon_chunk(),
-        Some(memory_stark::ctl_filter_with_poseidon_chunk()),
-    );
-    CrossTableLookup::new(all_lookers, mem_looked)
-}
-
-fn ctl_chunk_poseidon<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![
-            TableWithColumns::new(
-                Table::PoseidonChunk,
-                poseidon_chunk_stark::ctl_data_with_poseidon(),
-                Some(poseidon_chunk_stark::ctl_filter_with_poseidon()),
-            ),
-            TableWithColumns::new(
-                Table::ProgChunk,
-                prog_chunk_stark::ctl_data_to_poseidon(),
-                Some(prog_chunk_stark::ctl_filter_to_poseidon()),
-            ),
-        ],
-        TableWithColumns::new(
-            Table::Poseidon,
-            poseidon_stark::ctl_data_with_poseidon_chunk(),
-            Some(poseidon_stark::ctl_filter_with_poseidon_chunk()),
-        ),
-    )
-}
-
-fn ctl_cpu_storage_access<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_cpu_storage_access(),
-            Some(cpu_stark::ctl_filter_cpu_storage_access()),
-        )],
-        TableWithColumns::new(
-            Table::StorageAccess,
-            storage_access_stark::ctl_data_with_cpu(),
-            Some(storage_access_stark::ctl_filter_with_cpu_sstore()),
-        ),
-    )
-}
-
-fn ctl_storage_access_poseidon<F: Field>() -> CrossTableLookup<F> {
-    let looker_bit0 = TableWithColumns::new(
-        Table::StorageAccess,
-        storage_access_stark::ctl_data_with_poseidon_bit0(),
-        Some(storage_access_stark::ctl_filter_with_poseidon_bit0()),
-    );
-    let looker_bit0_pre = TableWithColumns::new(
-        Table::StorageAccess,
-        storage_access_stark::ctl_data_with_poseidon_bit0_pre(),
-        Some(storage_access_stark::ctl_filter_with_poseidon_bit0()),
-    );
-    let looker_bit1 = TableWithColumns::new(
-        Table::StorageAccess,
-        storage_access_stark::ctl_data_with_poseidon_bit1(),
-        Some(storage_access_stark::ctl_filter_with_poseidon_bit1()),
-    );
-    let looker_bit1_pre = TableWithColumns::new(
-        Table::StorageAccess,
-        storage_access_stark::ctl_data_with_poseidon_bit1_pre(),
-        Some(storage_access_stark::ctl_filter_with_poseidon_bit1()),
-    );
-    let all_lookers = vec![looker_bit0, looker_bit0_pre, looker_bit1, looker_bit1_pre];
-    let looked = TableWithColumns::new(
-        Table::Poseidon,
-        poseidon_stark::ctl_data_with_storage(),
-        Some(poseidon_stark::ctl_filter_with_storage()),
-    );
-    CrossTableLookup::new(all_lookers, looked)
-}
-
-fn ctl_cpu_poseidon_tree_key<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_poseidon_treekey(),
-            Some(cpu_stark::ctl_filter_poseidon_treekey()),
-        )],
-        TableWithColumns::new(
-            Table::Poseidon,
-            poseidon_stark::ctl_data_cpu_tree_key(),
-            Some(poseidon_stark::ctl_filter_cpu_tree_key()),
-        ),
-    )
-}
-
-fn ctl_cpu_tape<F: Field>() -> CrossTableLookup<F> {
-    let cpu_tape_tload_tstore = TableWithColumns::new(
-        Table::Cpu,
-        cpu_stark::ctl_data_cpu_tape_load_store(),
-        Some(cpu_stark::ctl_filter_cpu_tape_load_store()),
-    );
-    let cpu_tape_sccall_caller = (0..4).map(|i: usize| {
-        TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_cpu_tape_sccall_caller(i),
-            Some(cpu_stark::ctl_filter_cpu_is_sccall_ext()),
-        )
-    });
-    let cpu_tape_sccall_callee_code = (0..4).map(|i: usize| {
-        TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_cpu_tape_sccall_callee_code(i),
-            Some(cpu_stark::ctl_filter_cpu_is_sccall_ext()),
-        )
-    });
-    let cpu_tape_sccall_callee_storage = (0..4).map(|i: usize| {
-        TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_cpu_tape_sccall_callee_storage(i),
-            Some(cpu_stark::ctl_filter_cpu_is_sccall_ext()),
-        )
-    });
-
-    let all_lookers = iter::once(cpu_tape_tload_tstore)
-        .chain(cpu_tape_sccall_caller)
-        .chain(cpu_tape_sccall_callee_code)
-        .chain(cpu_tape_sccall_callee_storage)
-        .collect();
-
-    let tape_looked = TableWithColumns::new(
-        Table::Tape,
-        tape_stark::ctl_data_tape(),
-        Some(tape_stark::ctl_filter_tape()),
-    );
-    CrossTableLookup::new(all_lookers, tape_looked)
-}
-
-fn ctl_cpu_sccall<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_cpu_sccall(),
-            Some(cpu_stark::ctl_filter_cpu_sccall()),
-        )],
-        TableWithColumns::new(
-            Table::SCCall,
-            sccall_stark::ctl_data_sccall(),
-            Some(sccall_stark::ctl_filter_sccall()),
-        ),
-    )
-}
-
-fn ctl_cpu_sccall_end<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_cpu_sccall_end(),
-            Some(cpu_stark::ctl_filter_cpu_sccall_end()),
-        )],
-        TableWithColumns::new(
-            Table::SCCall,
-            sccall_stark::ctl_data_sccall_end(),
-            Some(sccall_stark::ctl_filter_sccall_end()),
-        ),
-    )
-}
+    fn nums_permutation_zs(&self, config: &StarkConfig) -> Vec<usize> {
+        self.tables()
+            .iter()
+            .map(|table| table.num_permutation_batches(config))
+            .collect()
+    }
 
-fn ctl_cpu_program<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![
-            TableWithColumns::new(
-                Table::Cpu,
-                cpu_stark::ctl_data_inst_to_program(),
-                Some(cpu_stark::ctl_filter_with_program_inst()),
-            ),
-            TableWithColumns::new(
-                Table::Cpu,
-                cpu_stark::ctl_data_imm_to_program(),
-                Some(cpu_stark::ctl_filter_with_program_imm()),
-            ),
-        ],
-        TableWithColumns::new(
-            Table::Program,
-            program_stark::ctl_data_by_cpu(),
-            Some(program_stark::ctl_filter_by_cpu()),
-        ),
-    )
-}
+    fn permutation_batch_sizes(&self) -> Vec<usize> {
+        self.tables()
+            .iter()
+            .map(|table| table.permutation_batch_size())
+            .collect()
+    }
 
-fn ctl_prog_chunk_prog<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        (0..8)
-            .map(|i: usize| {
-                TableWithColumns::new(
-                    Table::ProgChunk,
-                    prog_chunk_stark::ctl_data_to_program(i),
-                    Some(prog_chunk_stark::ctl_filter_to_program(i)),
-                )
-            })
-            .collect(),
-        TableWithColumns::new(
-            Table::Program,
-            program_stark::ctl_data_by_program_chunk(),
-            Some(program_stark::ctl_filter_by_program_chunk()),
-        ),
-    )
+    fn nums_lookup_helper_columns(&self, config: &StarkConfig) -> Vec<usize> {
+        self.tables()
+            .iter()
+            .map(|table| table.num_lookup_helper_columns(config))
+            .collect()
+    }
 }
 
-fn ctl_prog_chunk_storage<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::ProgChunk,
-            prog_chunk_stark::ctl_data_to_storage_access(),
-            Some(prog_chunk_stark::ctl_filter_to_storage_access()),
-        )],
-        TableWithColumns::new(
-            Table::StorageAccess,
-            storage_access_stark::ctl_data_for_prog_chunk(),
-            Some(storage_access_stark::ctl_filter_for_prog_chunk()),
-        ),
-    )
+/// The registered tables, proven and verified under the config `C`.
+pub trait StarkRegistry<F, C, const D: usize>: TableRegistry<F, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    fn starks(&self) -> Vec<&dyn TableStark<F, C, D>>;
 }
 
-// Cross_Lookup_Table(looking_table, looked_table)
-/*fn ctl_bitwise_bitwise_fixed_table<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::BitwiseFixed,
-            bitwise_fixed_stark::ctl_data_with_bitwise(),
-            Some(bitwise_fixed_stark::ctl_filter_with_bitwise()),
-        )],
-        TableWithColumns::new(
-            Table::Bitwise,
-            bitwise_stark::ctl_data_with_bitwise_fixed(),
-            Some(bitwise_stark::ctl_filter_with_bitwise_fixed()),
-        ),
-        None,
-    )
-}*/
-
-/*fn ctl_rangecheck_rangecheck_fixed<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::RangecheckFixed,
-            rangecheck_fixed_stark::ctl_data_with_rangecheck(),
-            Some(rangecheck_fixed_stark::ctl_filter_with_rangecheck()),
-        )],
-        TableWithColumns::new(
-            Table::RangeCheck,
-            rangecheck_stark::ctl_data_with_rangecheck_fixed(),
-            Some(rangecheck_stark::ctl_filter_with_rangecheck_fixed()),
-        ),
-        None,
-    )
-}*/
-
-// check the correct program with lookup
-
-// Program table
-// +-----+--------------+-------+----------+
-// | PC  |      INS     |  IMM  | COMPRESS |
-// +-----+--------------+-------+----------+
-// +-----+--------------+-------+----------+
-// |  1  |  0x********  |  U32  |   Field  |
-// +-----+--------------+-------+----------+
-// +-----+--------------+-------+----------+
-// |  2  |  0x********  |  U32  |   Field  |
-// +-----+--------------+-------+----------++
-// +-----+--------------+-------+----------+
-// |  3  |  0x********  |  U32  |   Field  |
-// +-----+--------------+-------+----------+
-
-// CPU table
-// +-----+-----+--------------+-------+----------+
-// | ... | PC  |      INS     |  IMM  | COMPRESS |
-// +-----+-----+--------------+-------+----------+
-// +-----+-----+--------------+-------+----------+
-// | ... |  1  |  0x********  |  U32  |   Field  |
-// +-----+-----+--------------+-------+----------+
-// +-----+-----+--------------+-------+----------+
-// | ... |  2  |  0x********  |  U32  |   Field  |
-// +-----+-----+--------------+-------+----------++
-// +-----+-----+--------------+-------+----------+
-// | ... |  3  |  0x********  |  U32  |   Field  |
-// +-----+-----+--------------+-------+----------+
-
-// Note that COMPRESS will be computed by vector lookup argument protocol
-/*fn ctl_correct_program_cpu<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
-            Table::Cpu,
-            cpu_stark::ctl_data_with_program(),
-            Some(cpu_stark::ctl_filter_with_program()),
-        )],
-        TableWithColumns::new(
-            Table::Program,
-            program_stark::ctl_data_with_cpu(),
-            Some(program_stark::ctl_filter_with_cpu()),
-        ),
-        None,
-    )
-}*/
-
 #[allow(unused_imports)]
 #[cfg(test)]
 mod tests {
+    use crate::cpu::columns::COL_CLK;
     use crate::generation::{generate_traces, GenerationInputs};
+    use crate::memory::columns::COL_MEM_VALUE;
     use crate::stark::config::StarkConfig;
-    use crate::stark::ola_stark::OlaStark;
+    use crate::stark::constraint_check::check_constraints;
+    use crate::stark::ctl_check::check_ctls;
+    use crate::stark::mutation::{run_mutations, MutationConfig, MutationKind};
+    use crate::stark::ola_stark::{OlaStark, Table, TableRegistry};
     use crate::stark::proof::PublicValues;
     use crate::stark::prover::prove_with_traces;
     use crate::stark::serialization::Buffer;
     use crate::stark::stark::Stark;
     use crate::stark::util::trace_rows_to_poly_values;
     use crate::stark::verifier::verify_proof;
+    use crate::stark::verifying_key::{
+        circuit_digest, max_degree_bits, OlaProvingKey, OlaVerifyingKey,
+    };
     use anyhow::Result;
     use assembler::encoder::encode_asm_from_json_file;
     use core::crypto::hash::Hasher;
@@ -937,9 +202,10 @@ mod tests {
     use executor::{BatchCacheManager, Process};
     use itertools::Itertools;
     use log::{debug, LevelFilter};
+    use plonky2::field::polynomial::PolynomialValues;
     use plonky2::plonk::config::{Blake3GoldilocksConfig, GenericConfig, PoseidonGoldilocksConfig};
     use plonky2::util::timing::TimingTree;
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
     use std::fs::File;
     use std::io::{BufRead, BufReader};
     use std::mem;
@@ -959,13 +225,19 @@ mod tests {
 
     #[test]
     fn test_serialize() {
-        let mut ola_stark = OlaStark::default();
+        let mut ola_stark: OlaStark<F, D> = OlaStark::default();
         assert_eq!(ola_stark.bitwise_stark.get_compress_challenge(), None);
         assert_eq!(ola_stark.program_stark.get_compress_challenge(), None);
         let challenge1 = GoldilocksField::rand();
         let challenge2 = GoldilocksField::rand();
-        ola_stark.bitwise_stark.set_compress_challenge(challenge1);
-        ola_stark.program_stark.set_compress_challenge(challenge2);
+        ola_stark
+            .bitwise_stark
+            .set_compress_challenge(challenge1)
+            .unwrap();
+        ola_stark
+            .program_stark
+            .set_compress_challenge(challenge2)
+            .unwrap();
         assert_eq!(
             ola_stark.bitwise_stark.get_compress_challenge(),
             Some(challenge1)
@@ -986,6 +258,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_registry() {
+        let mut ola_stark = OlaStark::<F, D>::default();
+        let tables: Vec<_> = ola_stark
+            .tables()
+            .iter()
+            .map(|table| table.table())
+            .collect();
+        assert_eq!(tables.len(), 12);
+        assert!(tables.windows(2).all(|pair| pair[0] < pair[1]));
+        let tables_mut: Vec<_> = ola_stark
+            .tables_mut()
+            .iter()
+            .map(|table| table.table())
+            .collect();
+        assert_eq!(tables, tables_mut);
+
+        // each table contributes the lookups it is looked up by
+        let ctls = &ola_stark.cross_table_lookups;
+        assert_eq!(ctls.len(), 19);
+        assert!(ctls
+            .windows(2)
+            .all(|pair| pair[0].looked_table.table <= pair[1].looked_table.table));
+    }
+
     #[test]
     fn fibo_loop_test() {
         let calldata = [10u64, 1u64, 2, 4185064725u64]
@@ -1109,6 +406,514 @@ mod tests {
         test_by_asm_json("mem_gep_vector.json".to_string(), None, None);
     }
 
+    #[test]
+    fn test_check_constraints() {
+        let (ola_stark, mut traces, _) = traces_by_asm_json("call.json".to_string(), None, None);
+        let failures = check_constraints(&ola_stark, &traces);
+        assert!(failures.is_empty(), "{}", failures[0]);
+
+        let clk = &mut traces[Table::Cpu as usize][COL_CLK].values[3];
+        *clk += GoldilocksField::ONE;
+        let corrupted = *clk;
+        let failures = check_constraints(&ola_stark, &traces);
+        // the step into and the step out of the corrupted row
+        let rows: Vec<_> = failures.iter().map(|failure| failure.row).collect();
+        assert_eq!(rows, vec![2, 3]);
+        for failure in &failures {
+            assert_eq!(failure.table, Table::Cpu);
+            assert!(failure.location.file().ends_with("cpu_stark.rs"));
+        }
+        let clk = ("clk".to_string(), corrupted);
+        assert!(failures[0].next_values.contains(&clk));
+        assert!(failures[1].local_values.contains(&clk));
+    }
+
+    #[test]
+    fn test_check_ctls() {
+        let (ola_stark, mut traces, _) = traces_by_asm_json("call.json".to_string(), None, None);
+        let ctls = &ola_stark.cross_table_lookups;
+        let mismatches = check_ctls(&traces, ctls);
+        assert!(mismatches.is_empty(), "{}", mismatches[0]);
+
+        // a memory row the cpu looks up
+        let memory = &ctls[0].looked_table;
+        assert_eq!(memory.table, Table::Memory);
+        let filter = memory.filter_column.as_ref().unwrap();
+        let row = (0..traces[Table::Memory as usize][0].len())
+            .find(|&row| {
+                filter
+                    .eval_table(&traces[Table::Memory as usize], row)
+                    .is_one()
+            })
+            .unwrap();
+        traces[Table::Memory as usize][COL_MEM_VALUE].values[row] += GoldilocksField::ONE;
+        let mismatches = check_ctls(&traces, ctls);
+        assert_eq!(mismatches.len(), 2, "{:?}", mismatches);
+        assert!(mismatches.iter().all(|mismatch| mismatch.ctl == 0));
+        // the original tuple is no longer found, the corrupted one is never
+        // looked up
+        let (missing, extra) = (&mismatches[0], &mismatches[1]);
+        assert_eq!((missing.looking.len(), missing.looked.len()), (1, 0));
+        assert_eq!(missing.looking[0].table, Table::Cpu);
+        assert_eq!((extra.looking.len(), extra.looked.len()), (0, 1));
+        assert_eq!(extra.looked[0].row, row);
+        let value = traces[Table::Memory as usize][COL_MEM_VALUE].values[row];
+        assert!(extra.looked[0]
+            .values
+            .contains(&("VALUE".to_string(), value)));
+    }
+
+    #[test]
+    fn test_mutations() {
+        let (ola_stark, traces, _) = traces_by_asm_json("call.json".to_string(), None, None);
+        let report = run_mutations(&ola_stark, &traces, &MutationConfig::default());
+        println!("{}", report);
+        for (table, trace) in ola_stark.tables().iter().zip(&traces) {
+            if !trace[0].values.is_empty() {
+                assert!(
+                    report.tried[&table.table()] > 0,
+                    "{:?} not mutated",
+                    table.table()
+                );
+            }
+        }
+
+        // Columns known to let some mutation of call.json through, each with
+        // the reason it is left open. A column leaving this list has been
+        // constrained, a column joining it has lost a constraint: either way
+        // the list is updated on purpose.
+        let allowed: &[(Table, &str, &[&str])] = &[
+            (
+                Table::Cpu,
+                "context and operand columns aren't constrained on padding rows",
+                &[
+                    "tx_idx",
+                    "addr_storage_0",
+                    "addr_storage_1",
+                    "addr_storage_2",
+                    "addr_storage_3",
+                    "addr_code_0",
+                    "addr_code_1",
+                    "addr_code_2",
+                    "addr_code_3",
+                    "imm_val",
+                    "op0",
+                    "op1",
+                ],
+            ),
+            (
+                Table::Cpu,
+                "dst, aux0 and aux1 are free on rows whose opcode doesn't use them",
+                &["dst", "aux0", "aux1"],
+            ),
+            (
+                Table::Cpu,
+                "is_entry_sc and the next line flags aren't derived from env_idx, inst and tx_idx",
+                &[
+                    "is_entry_sc",
+                    "is_next_line_diff_inst",
+                    "is_next_line_same_tx",
+                ],
+            ),
+            (
+                Table::Memory,
+                "the value of a prophet write nobody reads, padding included, is never looked up",
+                &["VALUE"],
+            ),
+            (
+                Table::Bitwise,
+                "preprocessed, checked against the verifying key",
+                &["FIX_RANGE_CHECK_U8"],
+            ),
+            (
+                Table::Cmp,
+                "the inverse is only a witness when gte is zero",
+                &["CMP_ABS_DIFF_INV"],
+            ),
+            (
+                Table::RangeCheck,
+                "preprocessed, checked against the verifying key",
+                &["fix_range_check_u16"],
+            ),
+            (
+                Table::PoseidonChunk,
+                "columns aren't constrained on padding rows",
+                &[
+                    "TX_IDX", "ENV_IDX", "CLK", "OPCODE", "OP0", "DST", "VALUE_0", "VALUE_1",
+                    "VALUE_2", "VALUE_3", "VALUE_4", "VALUE_5", "VALUE_6", "VALUE_7", "CAP_0",
+                    "CAP_1", "CAP_2", "CAP_3",
+                ],
+            ),
+            (
+                Table::StorageAccess,
+                "columns aren't constrained on padding rows",
+                &[
+                    "IS_WRITE",
+                    "ADDR_LIMB_0",
+                    "ADDR_LIMB_1",
+                    "ADDR_LIMB_2",
+                    "ADDR_LIMB_3",
+                ],
+            ),
+            (
+                Table::Tape,
+                "columns aren't constrained on padding rows",
+                &["is_init_seg"],
+            ),
+            (
+                Table::Program,
+                "only read through the permutation with the program chunks",
+                &["COMP_PROG_PERM"],
+            ),
+            (
+                Table::ProgChunk,
+                "columns aren't constrained on padding rows",
+                &[
+                    "ADDR_0",
+                    "ADDR_1",
+                    "ADDR_2",
+                    "ADDR_3",
+                    "START_PC",
+                    "INST_0",
+                    "INST_1",
+                    "INST_2",
+                    "INST_3",
+                    "INST_4",
+                    "INST_5",
+                    "INST_6",
+                    "INST_7",
+                    "CAP_0",
+                    "CAP_1",
+                    "CAP_2",
+                    "CAP_3",
+                    "HASH_0",
+                    "HASH_1",
+                    "HASH_2",
+                    "HASH_3",
+                    "HASH_4",
+                    "HASH_5",
+                    "HASH_6",
+                    "HASH_7",
+                    "HASH_8",
+                    "HASH_9",
+                    "HASH_10",
+                    "HASH_11",
+                    "IS_FIRST_LINE",
+                ],
+            ),
+        ];
+        let allowed: BTreeMap<(Table, String), &str> = allowed
+            .iter()
+            .flat_map(|(table, reason, names)| {
+                names
+                    .iter()
+                    .map(move |name| ((*table, name.to_string()), *reason))
+            })
+            .collect();
+        let under_constrained: BTreeSet<(Table, String)> = report
+            .under_constrained_columns()
+            .into_iter()
+            .map(|(table, name, _)| (table, name))
+            .collect();
+        assert_eq!(
+            under_constrained
+                .iter()
+                .filter(|column| !allowed.contains_key(column))
+                .collect_vec(),
+            Vec::<&(Table, String)>::new(),
+            "newly under-constrained columns"
+        );
+        assert_eq!(
+            allowed
+                .iter()
+                .filter(|(column, _)| !under_constrained.contains(column))
+                .collect_vec(),
+            Vec::<(&(Table, String), &&str)>::new(),
+            "columns no longer under-constrained"
+        );
+
+        // Tables whose rows can be swapped unnoticed: each row stands alone
+        // and the table is only read through lookups, which ignore the order.
+        let swapped: BTreeSet<Table> = report
+            .survivors
+            .iter()
+            .filter(|mutation| mutation.kind == MutationKind::SwapRows)
+            .map(|mutation| mutation.table)
+            .collect();
+        assert_eq!(
+            swapped,
+            BTreeSet::from([
+                Table::Bitwise,
+                Table::Cmp,
+                Table::RangeCheck,
+                Table::Poseidon,
+                Table::Program,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_batch_fri() {
+        let calldata = [10u64, 1u64, 2, 4185064725u64]
+            .iter()
+            .map(|v| GoldilocksField::from_canonical_u64(*v))
+            .collect_vec();
+        let (ola_stark, traces, public_values) =
+            traces_by_asm_json("fib_asm.json".to_string(), Some(calldata), None);
+        let config = StarkConfig::standard_fast_config();
+        let batch_config = StarkConfig {
+            batch_fri: true,
+            ..StarkConfig::standard_fast_config()
+        };
+
+        let proving_key = OlaProvingKey::<F, C, D>::new(
+            &config,
+            max_degree_bits(&traces),
+            &mut TimingTree::default(),
+        );
+        let verifying_key = &proving_key.verifying_key;
+
+        let mut proof_sizes = vec![];
+        for (prove_config, other_config) in [(&config, &batch_config), (&batch_config, &config)] {
+            let proof = prove_with_traces::<F, C, D>(
+                &ola_stark,
+                &proving_key,
+                prove_config,
+                traces.clone(),
+                public_values.clone(),
+                &mut TimingTree::default(),
+            )
+            .unwrap();
+            let mut buffer = Buffer::new(Vec::new());
+            buffer.write_all_proof(&proof).unwrap();
+            proof_sizes.push(buffer.len());
+            let proof = Buffer::new(buffer.bytes())
+                .read_all_proof::<F, C, D>()
+                .unwrap();
+            let mut reencoded = Buffer::new(Vec::new());
+            reencoded.write_all_proof(&proof).unwrap();
+            assert_eq!(reencoded.bytes(), buffer.bytes());
+            assert_eq!(
+                proof.batch_opening_proofs.is_empty(),
+                !prove_config.batch_fri
+            );
+            verify_proof(
+                OlaStark::default(),
+                verifying_key,
+                proof.clone(),
+                prove_config,
+            )
+            .unwrap();
+            assert!(verify_proof(OlaStark::default(), verifying_key, proof, other_config).is_err());
+        }
+        println!(
+            "proof size: {} bytes, with batched FRI: {} bytes",
+            proof_sizes[0], proof_sizes[1]
+        );
+        assert!(proof_sizes[1] < proof_sizes[0]);
+    }
+
+    #[test]
+    fn test_zero_knowledge() {
+        let (ola_stark, traces, public_values) =
+            traces_by_asm_json("call.json".to_string(), None, None);
+        let config = StarkConfig::standard_fast_config();
+        for batch_fri in [false, true] {
+            let zk_config = StarkConfig {
+                batch_fri,
+                zero_knowledge: true,
+                ..StarkConfig::standard_fast_config()
+            };
+            let proving_key = OlaProvingKey::<F, C, D>::new(
+                &zk_config,
+                max_degree_bits(&traces),
+                &mut TimingTree::default(),
+            );
+            let verifying_key = &proving_key.verifying_key;
+
+            let proofs = (0..2)
+                .map(|_| {
+                    prove_with_traces::<F, C, D>(
+                        &ola_stark,
+                        &proving_key,
+                        &zk_config,
+                        traces.clone(),
+                        public_values.clone(),
+                        &mut TimingTree::default(),
+                    )
+                    .unwrap()
+                })
+                .collect::<Vec<_>>();
+            // The same traces give different openings.
+            let cpu = Table::Cpu as usize;
+            assert_ne!(
+                proofs[0].stark_proofs[cpu].openings.local_values,
+                proofs[1].stark_proofs[cpu].openings.local_values
+            );
+            assert_ne!(
+                proofs[0].stark_proofs[cpu].openings.quotient_polys,
+                proofs[1].stark_proofs[cpu].openings.quotient_polys
+            );
+
+            for proof in proofs {
+                let mut buffer = Buffer::new(Vec::new());
+                buffer.write_all_proof(&proof).unwrap();
+                let proof = Buffer::new(buffer.bytes())
+                    .read_all_proof::<F, C, D>()
+                    .unwrap();
+                verify_proof(
+                    OlaStark::default(),
+                    verifying_key,
+                    proof.clone(),
+                    &zk_config,
+                )
+                .unwrap();
+                assert!(verify_proof(OlaStark::default(), verifying_key, proof, &config).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_low_memory() {
+        let (ola_stark, traces, public_values) =
+            traces_by_asm_json("call.json".to_string(), None, None);
+        let config = StarkConfig::standard_fast_config();
+        let proving_key = OlaProvingKey::<F, C, D>::new(
+            &config,
+            max_degree_bits(&traces),
+            &mut TimingTree::default(),
+        );
+        let verifying_key = &proving_key.verifying_key;
+
+        let proofs = [false, true]
+            .into_iter()
+            .map(|low_memory| {
+                let prove_config = StarkConfig {
+                    low_memory,
+                    ..StarkConfig::standard_fast_config()
+                };
+                prove_with_traces::<F, C, D>(
+                    &ola_stark,
+                    &proving_key,
+                    &prove_config,
+                    traces.clone(),
+                    public_values.clone(),
+                    &mut TimingTree::default(),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        // The traces committed again have the same caps, and the proofs the
+        // same openings.
+        for (proof, low_memory_proof) in proofs[0].stark_proofs.iter().zip(&proofs[1].stark_proofs)
+        {
+            assert_eq!(proof.trace_cap, low_memory_proof.trace_cap);
+            assert_eq!(
+                proof.quotient_polys_cap,
+                low_memory_proof.quotient_polys_cap
+            );
+            assert_eq!(
+                proof.openings.local_values,
+                low_memory_proof.openings.local_values
+            );
+        }
+        for proof in proofs {
+            verify_proof(OlaStark::default(), verifying_key, proof, &config).unwrap();
+        }
+
+        // Batched openings need the commitments of all tables at once.
+        let batch_low_memory_config = StarkConfig {
+            batch_fri: true,
+            low_memory: true,
+            ..StarkConfig::standard_fast_config()
+        };
+        assert!(prove_with_traces::<F, C, D>(
+            &ola_stark,
+            &proving_key,
+            &batch_low_memory_config,
+            traces,
+            public_values,
+            &mut TimingTree::default(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_verifying_key() {
+        let (ola_stark, traces, public_values) =
+            traces_by_asm_json("call.json".to_string(), None, None);
+        let config = StarkConfig::standard_fast_config();
+        let degree_bits = max_degree_bits(&traces);
+        let key_dir = tempfile::tempdir().unwrap();
+        let key_path = key_dir.path().join("proving_key");
+        OlaProvingKey::<F, C, D>::new(&config, degree_bits, &mut TimingTree::default())
+            .save(&key_path)
+            .unwrap();
+        let proving_key = OlaProvingKey::<F, C, D>::load(&key_path, &config).unwrap();
+        let other_config = StarkConfig {
+            zero_knowledge: true,
+            ..StarkConfig::standard_fast_config()
+        };
+        assert!(OlaProvingKey::<F, C, D>::load(&key_path, &other_config).is_err());
+
+        // The verifier derives the key of the prover on its own.
+        let verifying_key =
+            &OlaVerifyingKey::<F, C, D>::new(&config, degree_bits, &mut TimingTree::default());
+        assert_eq!(verifying_key.digest(), proving_key.verifying_key.digest());
+        assert_eq!(
+            verifying_key.circuit_digest,
+            circuit_digest::<F, C, D>(&config)
+        );
+
+        let proof = prove_with_traces::<F, C, D>(
+            &ola_stark,
+            &proving_key,
+            &config,
+            traces.clone(),
+            public_values.clone(),
+            &mut TimingTree::default(),
+        )
+        .unwrap();
+        verify_proof(OlaStark::default(), verifying_key, proof.clone(), &config).unwrap();
+
+        let small_key =
+            OlaProvingKey::<F, C, D>::new(&config, degree_bits - 1, &mut TimingTree::default());
+        assert!(prove_with_traces::<F, C, D>(
+            &ola_stark,
+            &small_key,
+            &config,
+            traces,
+            public_values,
+            &mut TimingTree::default(),
+        )
+        .is_err());
+
+        let mut other_version = verifying_key.clone();
+        other_version.version += 1;
+        assert!(verify_proof(OlaStark::default(), &other_version, proof.clone(), &config).is_err());
+
+        let mut other_circuit = verifying_key.clone();
+        other_circuit.circuit_digest =
+            <<C as GenericConfig<D>>::Hasher as plonky2::plonk::config::Hasher<F>>::hash_no_pad(&[
+                F::ONE,
+            ]);
+        assert!(verify_proof(OlaStark::default(), &other_circuit, proof.clone(), &config).is_err());
+
+        // The rangecheck fixed column committed as the u8 range of the
+        // bitwise table.
+        let mut other_caps = verifying_key.clone();
+        let rangecheck_caps = other_caps.preprocessed_caps[Table::RangeCheck as usize].clone();
+        for (degree_bits, cap) in other_caps.preprocessed_caps[Table::Bitwise as usize]
+            .as_mut()
+            .unwrap()
+        {
+            if let Some(other) = rangecheck_caps.as_ref().unwrap().get(degree_bits) {
+                *cap = other.clone();
+            }
+        }
+        assert!(verify_proof(OlaStark::default(), &other_caps, proof, &config).is_err());
+    }
+
     // #[test]
     // fn test_ola_string_assert() {
     //     test_by_asm_json("string_assert.json".to_string(), None);
@@ -1120,6 +925,37 @@ mod tests {
         call_data: Option<Vec<GoldilocksField>>,
         db_name: Option<String>,
     ) {
+        let (ola_stark, traces, public_values) = traces_by_asm_json(file_name, call_data, db_name);
+
+        let config = StarkConfig::standard_fast_config();
+        let proving_key = OlaProvingKey::<F, C, D>::new(
+            &config,
+            max_degree_bits(&traces),
+            &mut TimingTree::default(),
+        );
+        let proof = prove_with_traces::<F, C, D>(
+            &ola_stark,
+            &proving_key,
+            &config,
+            traces,
+            public_values,
+            &mut TimingTree::default(),
+        );
+
+        if let Ok(proof) = proof {
+            let ola_stark = OlaStark::default();
+            let verify_res = verify_proof(ola_stark, &proving_key.verifying_key, proof, &config);
+            println!("verify result:{:?}", verify_res);
+        } else {
+            println!("proof err:{:?}", proof);
+        }
+    }
+
+    fn traces_by_asm_json(
+        file_name: String,
+        call_data: Option<Vec<GoldilocksField>>,
+        db_name: Option<String>,
+    ) -> (OlaStark<F, D>, Vec<Vec<PolynomialValues<F>>>, PublicValues) {
         let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         path.push("../assembler/test_data/asm/");
         path.push(file_name);
@@ -1234,22 +1070,6 @@ mod tests {
 
         let mut ola_stark = OlaStark::default();
         let (traces, public_values) = generate_traces(program, &mut ola_stark, inputs);
-
-        let config = StarkConfig::standard_fast_config();
-        let proof = prove_with_traces::<F, C, D>(
-            &ola_stark,
-            &config,
-            traces,
-            public_values,
-            &mut TimingTree::default(),
-        );
-
-        if let Ok(proof) = proof {
-            let ola_stark = OlaStark::default();
-            let verify_res = verify_proof(ola_stark, proof, &config);
-            println!("verify result:{:?}", verify_res);
-        } else {
-            println!("proof err:{:?}", proof);
-        }
+        (ola_stark, traces, public_values)
     }
 }
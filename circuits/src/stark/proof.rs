@@ -16,26 +16,43 @@ use plonky2::plonk::config::GenericConfig;
 use serde::{Deserialize, Serialize};
 
 use super::config::StarkConfig;
-use super::ola_stark::NUM_TABLES;
 use super::permutation::GrandProductChallengeSet;
+use super::util::merge_columns;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct AllProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
-    pub stark_proofs: [StarkProof<F, C, D>; NUM_TABLES],
-    pub compress_challenges: [F; NUM_TABLES],
+    pub stark_proofs: Vec<StarkProof<F, C, D>>,
+    /// FRI proofs for the openings of tables of the same degree, when the
+    /// config batches FRI.
+    pub batch_opening_proofs: Vec<BatchOpeningProof<F, C, D>>,
+    pub compress_challenges: Vec<F>,
+    /// The log2 of the length of the trace of each table. The opening proofs
+    /// only give the degree of the committed polynomials, which in
+    /// zero-knowledge mode is the same for all tables of fewer rows than
+    /// `StarkConfig::num_blinding_values`.
+    pub degree_bits: Vec<usize>,
     pub public_values: PublicValues,
 }
 
-impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> AllProof<F, C, D> {
-    pub fn degree_bits(&self, config: &StarkConfig) -> [usize; NUM_TABLES] {
-        std::array::from_fn(|i| self.stark_proofs[i].recover_degree_bits(config))
-    }
+/// A FRI proof for the openings of several tables of the same degree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BatchOpeningProof<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    /// The tables whose openings are proven, in the order of their oracles.
+    pub tables: Vec<usize>,
+    pub opening_proof: FriProof<F, C::Hasher, D>,
 }
 
 pub(crate) struct AllProofChallenges<F: RichField + Extendable<D>, const D: usize> {
-    pub stark_challenges: [StarkProofChallenges<F, D>; NUM_TABLES],
+    pub stark_challenges: Vec<StarkProofChallenges<F, D>>,
     pub ctl_challenges: GrandProductChallengeSet<F>,
+    /// FRI challenges of each of the `batch_opening_proofs`.
+    pub batch_fri_challenges: Vec<FriChallenges<F, D>>,
 }
 
 #[allow(unused)] // TODO: should be used soon
@@ -43,12 +60,12 @@ pub(crate) struct AllChallengerState<F: RichField + Extendable<D>, const D: usiz
     /// Sponge state of the challenger before starting each proof,
     /// along with the final state after all proofs are done. This final state
     /// isn't strictly needed.
-    pub states: [[F; SPONGE_WIDTH]; NUM_TABLES + 1],
+    pub states: Vec<[F; SPONGE_WIDTH]>,
     pub ctl_challenges: GrandProductChallengeSet<F>,
 }
 
 pub struct AllProofTarget<const D: usize> {
-    pub stark_proofs: [StarkProofTarget<D>; NUM_TABLES],
+    pub stark_proofs: Vec<StarkProofTarget<D>>,
     pub public_values: PublicValuesTarget,
 }
 
@@ -114,21 +131,12 @@ pub struct StarkProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>,
     pub quotient_polys_cap: MerkleCap<F, C::Hasher>,
     /// Purported values of each polynomial at the challenge point.
     pub openings: StarkOpeningSet<F, D>,
-    /// A batch FRI argument for all openings.
-    pub opening_proof: FriProof<F, C::Hasher, D>,
+    /// A batch FRI argument for all openings, or `None` when they are proven
+    /// in one of the `AllProof::batch_opening_proofs`.
+    pub opening_proof: Option<FriProof<F, C::Hasher, D>>,
 }
 
 impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> StarkProof<F, C, D> {
-    /// Recover the length of the trace from a STARK proof and a STARK config.
-    pub fn recover_degree_bits(&self, config: &StarkConfig) -> usize {
-        let initial_merkle_proof = &self.opening_proof.query_round_proofs[0]
-            .initial_trees_proof
-            .evals_proofs[0]
-            .1;
-        let lde_bits = config.fri_config.cap_height + initial_merkle_proof.siblings.len();
-        lde_bits - config.fri_config.rate_bits
-    }
-
     pub fn num_ctl_zs(&self) -> usize {
         self.openings.ctl_zs_last.len()
     }
@@ -154,9 +162,12 @@ impl<const D: usize> StarkProofTarget<D> {
     }
 }
 
-pub(crate) struct StarkProofChallenges<F: RichField + Extendable<D>, const D: usize> {
+pub struct StarkProofChallenges<F: RichField + Extendable<D>, const D: usize> {
     /// Randomness used in any permutation arguments.
-    pub permutation_challenge_sets: Option<Vec<GrandProductChallengeSet<F>>>,
+    pub(crate) permutation_challenge_sets: Option<Vec<GrandProductChallengeSet<F>>>,
+
+    /// Randomness used in any lookup arguments.
+    pub lookup_challenges: Option<Vec<F>>,
 
     /// Random values used to combine STARK constraints.
     pub stark_alphas: Vec<F>,
@@ -164,12 +175,14 @@ pub(crate) struct StarkProofChallenges<F: RichField + Extendable<D>, const D: us
     /// Point at which the STARK polynomials are opened.
     pub stark_zeta: F::Extension,
 
-    pub fri_challenges: FriChallenges<F, D>,
+    /// `None` when the openings are proven in a batch.
+    pub fri_challenges: Option<FriChallenges<F, D>>,
 }
 
 #[allow(unused)]
 pub(crate) struct StarkProofChallengesTarget<const D: usize> {
     pub permutation_challenge_sets: Option<Vec<GrandProductChallengeSet<Target>>>,
+    pub lookup_challenges: Option<Vec<Target>>,
     pub stark_alphas: Vec<Target>,
     pub stark_zeta: ExtensionTarget<D>,
     pub fri_challenges: FriChallengesTarget<D>,
@@ -196,14 +209,19 @@ pub struct StarkOpeningSet<F: RichField + Extendable<D>, const D: usize> {
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> StarkOpeningSet<F, D> {
+    /// Opens the commitments at `zeta`. The values of the trace and of the
+    /// `preprocessed_columns` are opened together, in column order.
+    #[allow(clippy::too_many_arguments)]
     pub fn new<C: GenericConfig<D, F = F>>(
         zeta: F::Extension,
         g: F,
         trace_commitment: &PolynomialBatch<F, C, D>,
+        preprocessed_commitment: Option<&PolynomialBatch<F, C, D>>,
+        preprocessed_columns: &[usize],
         permutation_ctl_zs_commitment: &PolynomialBatch<F, C, D>,
         quotient_commitment: &PolynomialBatch<F, C, D>,
         degree_bits: usize,
-        num_permutation_zs: usize,
+        num_aux_columns: usize,
     ) -> Self {
         let eval_commitment = |z: F::Extension, c: &PolynomialBatch<F, C, D>| {
             c.polynomials
@@ -217,16 +235,25 @@ impl<F: RichField + Extendable<D>, const D: usize> StarkOpeningSet<F, D> {
                 .map(|p| p.eval(z))
                 .collect::<Vec<_>>()
         };
+        let eval_trace = |z: F::Extension| {
+            let main = eval_commitment(z, trace_commitment);
+            match preprocessed_commitment {
+                Some(commitment) => {
+                    merge_columns(preprocessed_columns, main, eval_commitment(z, commitment))
+                }
+                None => main,
+            }
+        };
         let zeta_next = zeta.scalar_mul(g);
         Self {
-            local_values: eval_commitment(zeta, trace_commitment),
-            next_values: eval_commitment(zeta_next, trace_commitment),
+            local_values: eval_trace(zeta),
+            next_values: eval_trace(zeta_next),
             permutation_ctl_zs: eval_commitment(zeta, permutation_ctl_zs_commitment),
             permutation_ctl_zs_next: eval_commitment(zeta_next, permutation_ctl_zs_commitment),
             ctl_zs_last: eval_commitment_base(
                 F::primitive_root_of_unity(degree_bits).inverse(),
                 permutation_ctl_zs_commitment,
-            )[num_permutation_zs..]
+            )[num_aux_columns..]
                 .to_vec(),
             quotient_polys: eval_commitment(zeta, quotient_commitment),
         }
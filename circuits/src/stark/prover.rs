@@ -1,55 +1,57 @@
 use core::program::Program;
 use std::any::type_name;
 use std::collections::BTreeMap;
+use std::time::Duration;
 
 use anyhow::{ensure, Result};
-use log::{error, info};
+use log::{debug, error, info, log_enabled, Level};
 use maybe_rayon::*;
+use plonky2::field::cfft::get_twiddles;
 use plonky2::field::extension::Extendable;
 use plonky2::field::packable::Packable;
 use plonky2::field::packed::PackedField;
 use plonky2::field::polynomial::{PolynomialCoeffs, PolynomialValues};
 use plonky2::field::types::Field;
 use plonky2::field::zero_poly_coset::ZeroPolyOnCoset;
-use plonky2::fri::oracle::PolynomialBatch;
+use plonky2::fri::oracle::{PolynomialBatch, SALT_SIZE};
 use plonky2::hash::hash_types::RichField;
+use plonky2::hash::merkle_tree::MerkleTree;
 use plonky2::iop::challenger::Challenger;
 use plonky2::plonk::config::{GenericConfig, Hasher};
 use plonky2::timed;
 use plonky2::util::timing::TimingTree;
 use plonky2::util::transpose;
-use plonky2_util::{log2_ceil, log2_strict};
-
-use super::ola_stark::{OlaStark, Table, NUM_TABLES};
-use crate::builtins::bitwise::bitwise_stark::BitwiseStark;
-use crate::builtins::cmp::cmp_stark::CmpStark;
-use crate::builtins::poseidon::poseidon_chunk_stark::PoseidonChunkStark;
-use crate::builtins::poseidon::poseidon_stark::PoseidonStark;
-use crate::builtins::sccall::sccall_stark::SCCallStark;
-use crate::builtins::storage::storage_access_stark::StorageAccessStark;
-use crate::program::prog_chunk_stark::ProgChunkStark;
-use crate::program::program_stark::ProgramStark;
-// use crate::builtins::tape::tape_stark::TapeStark;
+use plonky2_util::{ceil_div_usize, log2_ceil, log2_strict, reverse_index_bits_in_place};
+
+use super::batch_fri::{prove_batch_openings, PendingOpenings, TableProof};
+use super::ola_stark::{OlaStark, StarkRegistry};
 //use crate::columns::NUM_CPU_COLS;
 use super::config::StarkConfig;
+#[cfg(feature = "check_constraints")]
+use super::constraint_check;
 use super::constraint_consumer::ConstraintConsumer;
 use super::cross_table_lookup::{cross_table_lookup_data, CtlCheckVars, CtlData};
+#[cfg(feature = "check_constraints")]
+use super::ctl_check::check_ctls;
+use super::get_challenges::observe_degree_bits;
+use super::lookup::{lookup_helper_columns, LookupCheckVars};
 use super::permutation::PermutationCheckVars;
 use super::permutation::{
     compute_permutation_z_polys, get_n_grand_product_challenge_sets, GrandProductChallengeSet,
 };
 use super::proof::{AllProof, PublicValues, StarkOpeningSet, StarkProof};
 use super::stark::Stark;
+use super::util::{main_columns, merge_columns, MemorySampler};
 use super::vanishing_poly::eval_vanishing_poly;
 use super::vars::StarkEvaluationVars;
-use crate::cpu::cpu_stark::CpuStark;
+use super::verifying_key::{max_degree_bits, OlaProvingKey};
 use crate::generation::{generate_traces, GenerationInputs};
-use crate::memory::memory_stark::MemoryStark;
 
 /// Generate traces, then create all STARK proofs.
 pub fn prove<F, C, const D: usize>(
     program: Program,
     ola_stark: &mut OlaStark<F, D>,
+    proving_key: &OlaProvingKey<F, C, D>,
     inputs: GenerationInputs,
     config: &StarkConfig,
     timing: &mut TimingTree,
@@ -58,28 +60,26 @@ where
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     [(); C::Hasher::HASH_SIZE]:,
-    [(); CpuStark::<F, D>::COLUMNS]:,
-    [(); MemoryStark::<F, D>::COLUMNS]:,
-    [(); BitwiseStark::<F, D>::COLUMNS]:,
-    [(); CmpStark::<F, D>::COLUMNS]:,
-    // [(); RangeCheckStark::<F, D>::COLUMNS]:,
-    [(); PoseidonStark::<F, D>::COLUMNS]:,
-    [(); PoseidonChunkStark::<F, D>::COLUMNS]:,
-    [(); StorageAccessStark::<F, D>::COLUMNS]:,
-    // [(); TapeStark::<F, D>::COLUMNS]:,
-    [(); SCCallStark::<F, D>::COLUMNS]:,
-    [(); ProgramStark::<F, D>::COLUMNS]:,
-    [(); ProgChunkStark::<F, D>::COLUMNS]:,
+    OlaStark<F, D>: StarkRegistry<F, C, D>,
 {
     let (traces, public_values) = generate_traces(program, ola_stark, inputs);
-    prove_with_traces(ola_stark, config, traces, public_values, timing)
+    prove_with_traces(
+        ola_stark,
+        proving_key,
+        config,
+        traces,
+        public_values,
+        timing,
+    )
 }
 
-/// Compute all STARK proofs.
+/// Compute all STARK proofs. The preprocessed columns of the traces are
+/// opened from the commitments of `proving_key`.
 pub fn prove_with_traces<F, C, const D: usize>(
     ola_stark: &OlaStark<F, D>,
+    proving_key: &OlaProvingKey<F, C, D>,
     config: &StarkConfig,
-    trace_poly_values: [Vec<PolynomialValues<F>>; NUM_TABLES],
+    trace_poly_values: Vec<Vec<PolynomialValues<F>>>,
     public_values: PublicValues,
     timing: &mut TimingTree,
 ) -> Result<AllProof<F, C, D>>
@@ -87,56 +87,93 @@ where
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     [(); C::Hasher::HASH_SIZE]:,
-    [(); CpuStark::<F, D>::COLUMNS]:,
-    [(); MemoryStark::<F, D>::COLUMNS]:,
-    [(); BitwiseStark::<F, D>::COLUMNS]:,
-    [(); CmpStark::<F, D>::COLUMNS]:,
-    // [(); RangeCheckStark::<F, D>::COLUMNS]:,
-    [(); PoseidonStark::<F, D>::COLUMNS]:,
-    [(); PoseidonChunkStark::<F, D>::COLUMNS]:,
-    [(); StorageAccessStark::<F, D>::COLUMNS]:,
-    // [(); TapeStark::<F, D>::COLUMNS]:,
-    [(); SCCallStark::<F, D>::COLUMNS]:,
-    [(); ProgramStark::<F, D>::COLUMNS]:,
-    [(); ProgChunkStark::<F, D>::COLUMNS]:,
+    OlaStark<F, D>: StarkRegistry<F, C, D>,
 {
-    let rate_bits = config.fri_config.rate_bits;
-    let cap_height = config.fri_config.cap_height;
+    let degree_bits = max_degree_bits(&trace_poly_values);
+    config.validate::<F, D>(degree_bits)?;
+    ensure!(
+        degree_bits <= proving_key.max_degree_bits(),
+        "The traces have up to 2^{} rows, the proving key covers at most 2^{}.",
+        degree_bits,
+        proving_key.max_degree_bits()
+    );
+
+    #[cfg(feature = "check_constraints")]
+    {
+        let failures = constraint_check::check_constraints(ola_stark, &trace_poly_values);
+        for failure in &failures {
+            error!("{}", failure);
+        }
+        ensure!(
+            failures.is_empty(),
+            "{} constraints do not vanish on the trace",
+            failures.len()
+        );
+        let mismatches = check_ctls(&trace_poly_values, &ola_stark.cross_table_lookups);
+        for mismatch in &mismatches {
+            error!("{}", mismatch);
+        }
+        ensure!(
+            mismatches.is_empty(),
+            "{} cross-table lookup tuples are unmatched",
+            mismatches.len()
+        );
+    }
 
     let mut twiddle_map = BTreeMap::new();
+    let memory_sampler = log_enabled!(Level::Debug)
+        .then(|| MemorySampler::start(Duration::from_millis(10)))
+        .flatten();
 
     #[cfg(feature = "benchmark")]
     let start = Instant::now();
 
-    let trace_commitments = timed!(
+    let starks = ola_stark.starks();
+    let preprocessed_columns = starks
+        .iter()
+        .map(|stark| stark.preprocessed_columns())
+        .collect::<Vec<_>>();
+    let table_degree_bits = trace_poly_values
+        .iter()
+        .map(|trace| log2_strict(trace[0].len()))
+        .collect::<Vec<_>>();
+    let preprocessed_commitments = table_degree_bits
+        .iter()
+        .enumerate()
+        .map(|(i, &bits)| proving_key.preprocessed_commitment(i, bits))
+        .collect::<Result<Vec<_>>>()?;
+
+    let (trace_caps, mut trace_commitments): (Vec<_>, Vec<_>) = timed!(
         timing,
         "compute trace commitments",
         trace_poly_values
             .iter()
-            .map(|trace| {
-                PolynomialBatch::<F, C, D>::from_values(
-                    // TODO: Cloning this isn't great; consider having `from_values` accept a
-                    // reference, or having `compute_permutation_z_polys` read
-                    // trace values from the `PolynomialBatch`.
-                    trace.clone(),
-                    rate_bits,
-                    false,
-                    cap_height,
+            .zip(&preprocessed_columns)
+            .map(|(trace, preprocessed_columns)| {
+                // The traces are still needed for the cross-table lookups,
+                // only the committed columns are copied, to interpolate them.
+                let commitment = commit_values::<F, C, D>(
+                    main_columns(preprocessed_columns, trace)
+                        .into_iter()
+                        .cloned()
+                        .collect(),
+                    config,
                     timing,
                     &mut twiddle_map,
-                )
+                );
+                let cap = commitment.merkle_tree.cap.clone();
+                (cap, (!config.low_memory).then_some(commitment))
             })
-            .collect::<Vec<_>>()
+            .unzip()
     );
+    report_peak_memory("trace commitments", memory_sampler.as_ref());
 
     #[cfg(feature = "benchmark")]
     info!("trace_commitments total time: {:?}", start.elapsed());
 
-    let trace_caps = trace_commitments
-        .iter()
-        .map(|c| c.merkle_tree.cap.clone())
-        .collect::<Vec<_>>();
     let mut challenger = Challenger::<F, C::Hasher>::new();
+    challenger.observe_hash::<C::Hasher>(proving_key.verifying_key.digest());
+    observe_degree_bits(&mut challenger, &table_degree_bits);
     for cap in &trace_caps {
         challenger.observe_cap(cap);
     }
@@ -150,6 +187,7 @@ where
         &ola_stark.cross_table_lookups,
         &mut challenger,
     );
+    report_peak_memory("cross-table lookups", memory_sampler.as_ref());
 
     #[cfg(feature = "benchmark")]
     info!("cross_table_lookup_data total time: {:?}", start.elapsed());
@@ -157,186 +195,74 @@ where
     #[cfg(feature = "benchmark")]
     let start = Instant::now();
 
-    let cpu_proof = prove_single_table(
-        &ola_stark.cpu_stark,
-        config,
-        &trace_poly_values[Table::Cpu as usize],
-        &trace_commitments[Table::Cpu as usize],
-        &ctl_data_per_table[Table::Cpu as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-
-    #[cfg(feature = "benchmark")]
-    info!("prove_cpu_table total time: {:?}", start.elapsed());
-
-    #[cfg(feature = "benchmark")]
-    let start = Instant::now();
-
-    let memory_proof = prove_single_table(
-        &ola_stark.memory_stark,
-        config,
-        &trace_poly_values[Table::Memory as usize],
-        &trace_commitments[Table::Memory as usize],
-        &ctl_data_per_table[Table::Memory as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
+    let mut stark_proofs = Vec::with_capacity(starks.len());
+    let mut pending_openings = Vec::with_capacity(starks.len());
+    // Each table's trace is dropped, or consumed by its commitment in low
+    // memory mode, once the table's permutation and lookup columns are
+    // computed, and its commitments once its openings are proven.
+    for (i, (trace, ctl_data)) in trace_poly_values
+        .into_iter()
+        .zip(ctl_data_per_table)
+        .enumerate()
+    {
+        let (stark_proof, pending) = starks[i].prove(
+            config,
+            trace,
+            trace_commitments[i].take(),
+            preprocessed_commitments[i],
+            &ctl_data,
+            &mut challenger,
+            timing,
+            &mut twiddle_map,
+        )?;
+        stark_proofs.push(stark_proof);
+        pending_openings.push(pending);
+        if config.low_memory {
+            twiddle_map.clear();
+        }
+        report_peak_memory(&format!("table {}", i), memory_sampler.as_ref());
+    }
 
-    let bitwise_proof = prove_single_table(
-        &ola_stark.bitwise_stark,
-        config,
-        &trace_poly_values[Table::Bitwise as usize],
-        &trace_commitments[Table::Bitwise as usize],
-        &ctl_data_per_table[Table::Bitwise as usize],
+    let batch_opening_proofs = prove_batch_openings(
+        pending_openings,
+        &preprocessed_commitments,
         &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let cmp_proof = prove_single_table(
-        &ola_stark.cmp_stark,
         config,
-        &trace_poly_values[Table::Cmp as usize],
-        &trace_commitments[Table::Cmp as usize],
-        &ctl_data_per_table[Table::Cmp as usize],
-        &mut challenger,
         timing,
         &mut twiddle_map,
-    )?;
-    let rangecheck_proof = prove_single_table(
-        &ola_stark.rangecheck_stark,
-        config,
-        &trace_poly_values[Table::RangeCheck as usize],
-        &trace_commitments[Table::RangeCheck as usize],
-        &ctl_data_per_table[Table::RangeCheck as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let poseidon_proof = prove_single_table(
-        &ola_stark.poseidon_stark,
-        config,
-        &trace_poly_values[Table::Poseidon as usize],
-        &trace_commitments[Table::Poseidon as usize],
-        &ctl_data_per_table[Table::Poseidon as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let poseidon_chunk_proof = prove_single_table(
-        &ola_stark.poseidon_chunk_stark,
-        config,
-        &trace_poly_values[Table::PoseidonChunk as usize],
-        &trace_commitments[Table::PoseidonChunk as usize],
-        &ctl_data_per_table[Table::PoseidonChunk as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let storage_access_proof = prove_single_table(
-        &ola_stark.storage_access_stark,
-        config,
-        &trace_poly_values[Table::StorageAccess as usize],
-        &trace_commitments[Table::StorageAccess as usize],
-        &ctl_data_per_table[Table::StorageAccess as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let tape_proof = prove_single_table(
-        &ola_stark.tape_stark,
-        config,
-        &trace_poly_values[Table::Tape as usize],
-        &trace_commitments[Table::Tape as usize],
-        &ctl_data_per_table[Table::Tape as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let sccall_proof = prove_single_table(
-        &ola_stark.sccall_stark,
-        config,
-        &trace_poly_values[Table::SCCall as usize],
-        &trace_commitments[Table::SCCall as usize],
-        &ctl_data_per_table[Table::SCCall as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let program_proof = prove_single_table(
-        &ola_stark.program_stark,
-        config,
-        &trace_poly_values[Table::Program as usize],
-        &trace_commitments[Table::Program as usize],
-        &ctl_data_per_table[Table::Program as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let prog_chunk_proof = prove_single_table(
-        &ola_stark.prog_chunk_stark,
-        config,
-        &trace_poly_values[Table::ProgChunk as usize],
-        &trace_commitments[Table::ProgChunk as usize],
-        &ctl_data_per_table[Table::ProgChunk as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
+    );
+    report_peak_memory("batch openings", memory_sampler.as_ref());
 
     #[cfg(feature = "benchmark")]
-    info!("prove_other_table total time: {:?}", start.elapsed());
-
-    let stark_proofs = [
-        cpu_proof,
-        memory_proof,
-        bitwise_proof,
-        cmp_proof,
-        rangecheck_proof,
-        poseidon_proof,
-        poseidon_chunk_proof,
-        storage_access_proof,
-        tape_proof,
-        sccall_proof,
-        program_proof,
-        prog_chunk_proof,
-    ];
+    info!("prove_tables total time: {:?}", start.elapsed());
 
-    let compress_challenges = [
-        F::ZERO,
-        F::ZERO,
-        ola_stark.bitwise_stark.get_compress_challenge().unwrap(),
-        F::ZERO,
-        F::ZERO,
-        F::ZERO,
-        F::ZERO,
-        F::ZERO,
-        F::ZERO,
-        F::ZERO,
-        ola_stark.program_stark.get_compress_challenge().unwrap(),
-        F::ZERO,
-    ];
+    let compress_challenges = starks
+        .iter()
+        .map(|stark| stark.compress_challenge().unwrap_or(F::ZERO))
+        .collect();
 
     Ok(AllProof {
         stark_proofs,
+        batch_opening_proofs,
         compress_challenges,
+        degree_bits: table_degree_bits,
         public_values,
     })
 }
 
 /// Compute proof for a single STARK table.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn prove_single_table<F, C, S, const D: usize>(
     stark: &S,
     config: &StarkConfig,
-    trace_poly_values: &[PolynomialValues<F>],
-    trace_commitment: &PolynomialBatch<F, C, D>,
+    trace_poly_values: Vec<PolynomialValues<F>>,
+    trace_commitment: Option<PolynomialBatch<F, C, D>>,
+    preprocessed_commitment: Option<&PolynomialBatch<F, C, D>>,
     ctl_data: &CtlData<F>,
     challenger: &mut Challenger<F, C::Hasher>,
     timing: &mut TimingTree,
     twiddle_map: &mut BTreeMap<usize, Vec<F>>,
-) -> Result<StarkProof<F, C, D>>
+) -> Result<TableProof<F, C, D>>
 where
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -350,7 +276,7 @@ where
     let rate_bits = config.fri_config.rate_bits;
     let cap_height = config.fri_config.cap_height;
     assert!(
-        fri_params.total_arities() <= degree_bits + rate_bits - cap_height,
+        fri_params.total_arities() <= fri_params.degree_bits + rate_bits - cap_height,
         "FRI total reduction arity is too large.",
     );
 
@@ -368,11 +294,12 @@ where
     #[cfg(feature = "benchmark")]
     let start = Instant::now();
 
+    let trace_values = &trace_poly_values[..];
     let permutation_zs = permutation_challenges.as_ref().map(|challenges| {
         timed!(
             timing,
             "compute permutation Z(x) polys",
-            compute_permutation_z_polys::<F, C, S, D>(stark, config, trace_poly_values, challenges)
+            compute_permutation_z_polys::<F, C, S, D>(stark, config, trace_values, challenges)
         )
     });
 
@@ -386,14 +313,50 @@ where
 
     let num_permutation_zs = permutation_zs.as_ref().map(|v| v.len()).unwrap_or(0);
 
-    let z_polys = match permutation_zs {
-        None => ctl_data.z_polys(),
-        Some(mut permutation_zs) => {
-            permutation_zs.extend(ctl_data.z_polys());
-            permutation_zs
+    // Lookup arguments.
+    let lookup_challenges = stark
+        .uses_lookups()
+        .then(|| challenger.get_n_challenges(config.num_challenges));
+
+    let lookup_columns = lookup_challenges.as_ref().map(|challenges| {
+        timed!(timing, "compute lookup helper columns", {
+            let batch_size = stark.lookup_batch_size();
+            stark
+                .lookups()
+                .iter()
+                .flat_map(|lookup| {
+                    challenges.iter().flat_map(move |&challenge| {
+                        lookup_helper_columns(lookup, trace_values, challenge, batch_size)
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+    });
+
+    let num_lookup_columns = lookup_columns.as_ref().map(|v| v.len()).unwrap_or(0);
+
+    let mut z_polys = permutation_zs.unwrap_or_default();
+    z_polys.extend(lookup_columns.unwrap_or_default());
+    z_polys.extend(ctl_data.z_polys());
+    assert!(!z_polys.is_empty(), "No CTL?");
+
+    // The trace isn't needed past its permutation and lookup columns.
+    let trace_commitment = match trace_commitment {
+        Some(commitment) => {
+            drop(trace_poly_values);
+            commitment
         }
+        None => timed!(
+            timing,
+            "recompute trace commitment",
+            commit_values(
+                main_columns(&stark.preprocessed_columns(), trace_poly_values),
+                config,
+                timing,
+                twiddle_map,
+            )
+        ),
     };
-    assert!(!z_polys.is_empty(), "No CTL?");
 
     #[cfg(feature = "benchmark")]
     let start = Instant::now();
@@ -401,14 +364,7 @@ where
     let permutation_ctl_zs_commitment = timed!(
         timing,
         "compute Zs commitment",
-        PolynomialBatch::from_values(
-            z_polys,
-            rate_bits,
-            false,
-            config.fri_config.cap_height,
-            timing,
-            twiddle_map,
-        )
+        commit_values(z_polys, config, timing, twiddle_map)
     );
 
     #[cfg(feature = "benchmark")]
@@ -426,13 +382,16 @@ where
     if cfg!(test) {
         check_constraints(
             stark,
-            trace_commitment,
+            &trace_commitment,
+            preprocessed_commitment,
             &permutation_ctl_zs_commitment,
             permutation_challenges.as_ref(),
+            lookup_challenges.as_ref(),
             ctl_data,
             alphas.clone(),
             degree_bits,
             num_permutation_zs,
+            num_lookup_columns,
             config,
         );
     }
@@ -441,13 +400,16 @@ where
         "compute quotient polys",
         compute_quotient_polys::<F, <F as Packable>::Packing, C, S, D>(
             stark,
-            trace_commitment,
+            &trace_commitment,
+            preprocessed_commitment,
             &permutation_ctl_zs_commitment,
             permutation_challenges.as_ref(),
+            lookup_challenges.as_ref(),
             ctl_data,
             alphas,
             degree_bits,
             num_permutation_zs,
+            num_lookup_columns,
             config,
         )
     );
@@ -460,29 +422,43 @@ where
     #[cfg(feature = "benchmark")]
     let start = Instant::now();
 
-    let all_quotient_chunks = timed!(
+    let quotient_len = stark.quotient_len(config, degree_bits);
+    let chunk_len = config.quotient_chunk_len(degree_bits);
+    let committed_degree = degree << config.blinding_bits(degree_bits);
+    let mut all_quotient_chunks: Vec<_> = timed!(
         timing,
         "split quotient polys",
         quotient_polys
             .into_par_iter()
             .flat_map(|mut quotient_poly| {
-                quotient_poly
-                    .trim_to_len(degree * stark.quotient_degree_factor())
-                    .expect(
-                        "Quotient has failed, the vanishing polynomial is not divisible by Z_H",
-                    );
-                // Split quotient into degree-n chunks.
-                quotient_poly.chunks(degree)
+                quotient_poly.trim_to_len(quotient_len).expect(
+                    "Quotient has failed, the vanishing polynomial is not divisible by Z_H",
+                );
+                let chunks = quotient_poly.chunks(chunk_len);
+                if config.zero_knowledge {
+                    mask_quotient_chunks(
+                        chunks,
+                        chunk_len,
+                        config.num_blinding_values(),
+                        committed_degree,
+                    )
+                } else {
+                    chunks
+                }
             })
             .collect()
     );
+    if config.zero_knowledge {
+        // Masks the FRI batch, which is opened at `zeta` like the quotient.
+        all_quotient_chunks.push(PolynomialCoeffs::new(F::rand_vec(committed_degree)));
+    }
     let quotient_commitment = timed!(
         timing,
         "compute quotient commitment",
-        PolynomialBatch::from_coeffs(
+        commit_coeffs(
             all_quotient_chunks,
             rate_bits,
-            false,
+            config.zero_knowledge,
             config.fri_config.cap_height,
             timing,
             twiddle_map,
@@ -516,11 +492,13 @@ where
     let openings = StarkOpeningSet::new(
         zeta,
         g,
-        trace_commitment,
+        &trace_commitment,
+        preprocessed_commitment,
+        &stark.preprocessed_columns(),
         &permutation_ctl_zs_commitment,
         &quotient_commitment,
         degree_bits,
-        stark.num_permutation_batches(config),
+        num_permutation_zs + num_lookup_columns,
     );
 
     #[cfg(feature = "benchmark")]
@@ -530,11 +508,33 @@ where
 
     challenger.observe_openings(&openings.to_fri_openings());
 
-    let initial_merkle_trees = vec![
-        trace_commitment,
+    let fri_instance = stark.fri_instance(zeta, g, degree_bits, ctl_data.len(), config);
+    let trace_cap = trace_commitment.merkle_tree.cap.clone();
+
+    if config.batch_fri {
+        let stark_proof = StarkProof {
+            trace_cap,
+            permutation_ctl_zs_cap,
+            quotient_polys_cap,
+            openings,
+            opening_proof: None,
+        };
+        let pending = PendingOpenings {
+            degree_bits,
+            instance: fri_instance,
+            trace_commitment,
+            permutation_ctl_zs_commitment,
+            quotient_commitment,
+        };
+        return Ok((stark_proof, Some(pending)));
+    }
+
+    let mut initial_merkle_trees = vec![
+        &trace_commitment,
         &permutation_ctl_zs_commitment,
         &quotient_commitment,
     ];
+    initial_merkle_trees.extend(preprocessed_commitment);
 
     #[cfg(feature = "benchmark")]
     let start = Instant::now();
@@ -543,7 +543,7 @@ where
         timing,
         "compute openings proof",
         PolynomialBatch::prove_openings(
-            &stark.fri_instance(zeta, g, degree_bits, ctl_data.len(), config),
+            &fri_instance,
             &initial_merkle_trees,
             challenger,
             &fri_params,
@@ -557,26 +557,205 @@ where
         info!("opening_proof total time: {:?}", start.elapsed());
     }
 
-    Ok(StarkProof {
-        trace_cap: trace_commitment.merkle_tree.cap.clone(),
+    let stark_proof = StarkProof {
+        trace_cap,
         permutation_ctl_zs_cap,
         quotient_polys_cap,
         openings,
-        opening_proof,
-    })
+        opening_proof: Some(opening_proof),
+    };
+    Ok((stark_proof, None))
+}
+
+/// Logs the peak resident memory of the process during a proving phase,
+/// and what is left at its end.
+fn report_peak_memory(phase: &str, memory_sampler: Option<&MemorySampler>) {
+    if let Some(memory_sampler) = memory_sampler {
+        let (peak, current) = memory_sampler.take_peak();
+        debug!(
+            "{}: peak memory {} MiB, {} MiB at the end",
+            phase,
+            peak >> 20,
+            current >> 20
+        );
+    }
+}
+
+/// Commits to polynomials given by their values on the trace domain `H`. In
+/// zero-knowledge mode they are randomized off `H`, and the Merkle leaves
+/// salted.
+fn commit_values<F, C, const D: usize>(
+    values: Vec<PolynomialValues<F>>,
+    config: &StarkConfig,
+    timing: &mut TimingTree,
+    twiddle_map: &mut BTreeMap<usize, Vec<F>>,
+) -> PolynomialBatch<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let rate_bits = config.fri_config.rate_bits;
+    let cap_height = config.fri_config.cap_height;
+    if config.zero_knowledge {
+        let degree_bits = log2_strict(values[0].len());
+        let num_blinding_values = config.num_blinding_values();
+        let committed_degree = values[0].len() << config.blinding_bits(degree_bits);
+        commit_coeffs(
+            values
+                .into_par_iter()
+                .map(|values| randomize_off_subgroup(values, num_blinding_values, committed_degree))
+                .collect(),
+            rate_bits,
+            true,
+            cap_height,
+            timing,
+            twiddle_map,
+        )
+    } else {
+        let coeffs = timed!(
+            timing,
+            "IFFT",
+            values.into_par_iter().map(|v| v.ifft()).collect()
+        );
+        commit_coeffs(coeffs, rate_bits, false, cap_height, timing, twiddle_map)
+    }
+}
+
+/// Commits to `polynomials` as `PolynomialBatch::from_coeffs` does, but with
+/// the LDE values reduced to their canonical representation before they are
+/// hashed. Proofs only carry canonical encodings, so the Merkle leaves they
+/// open must hash the same once read back.
+pub(crate) fn commit_coeffs<F, C, const D: usize>(
+    polynomials: Vec<PolynomialCoeffs<F>>,
+    rate_bits: usize,
+    blinding: bool,
+    cap_height: usize,
+    timing: &mut TimingTree,
+    twiddle_map: &mut BTreeMap<usize, Vec<F>>,
+) -> PolynomialBatch<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let degree = polynomials[0].len();
+    let twiddles = twiddle_map
+        .entry(degree)
+        .or_insert_with(|| get_twiddles(degree));
+    let salt_size = if blinding { SALT_SIZE } else { 0 };
+    let lde_values: Vec<Vec<F>> = timed!(
+        timing,
+        "FFT + blinding",
+        polynomials
+            .par_iter()
+            .map(|p| {
+                assert_eq!(p.len(), degree, "Polynomial degrees inconsistent");
+                let mut values = p
+                    .coset_fft_with_options(F::coset_shift(), twiddles, 1 << rate_bits)
+                    .values;
+                for x in values.iter_mut() {
+                    *x = F::from_canonical_u64(x.to_canonical_u64());
+                }
+                values
+            })
+            .chain(
+                (0..salt_size)
+                    .into_par_iter()
+                    .map(|_| F::rand_vec(degree << rate_bits)),
+            )
+            .collect()
+    );
+
+    let mut leaves = timed!(timing, "transpose LDEs", transpose(&lde_values));
+    reverse_index_bits_in_place(&mut leaves);
+    let merkle_tree = timed!(
+        timing,
+        "build Merkle tree",
+        MerkleTree::new_v2(leaves, cap_height)
+    );
+
+    PolynomialBatch {
+        polynomials,
+        merkle_tree,
+        degree_log: log2_strict(degree),
+        rate_bits,
+        blinding,
+    }
+}
+
+/// Interpolates `values` over `H` and adds `Z_H(x) r(x)` for a random `r`
+/// with `num_blinding_values` coefficients, then pads the coefficients to
+/// `committed_degree`. The values on `H` are unchanged, while the at most
+/// `num_blinding_values` evaluations a proof reveals off `H` are uniformly
+/// random.
+fn randomize_off_subgroup<F: Field>(
+    values: PolynomialValues<F>,
+    num_blinding_values: usize,
+    committed_degree: usize,
+) -> PolynomialCoeffs<F> {
+    let mut coeffs = values.ifft().coeffs;
+    let n = coeffs.len();
+    let r = F::rand_vec(num_blinding_values);
+    coeffs.resize(committed_degree, F::ZERO);
+    // Z_H(x) r(x) = x^n r(x) - r(x).
+    for (i, &r) in r.iter().enumerate() {
+        coeffs[i] -= r;
+        coeffs[n + i] += r;
+    }
+    PolynomialCoeffs::new(coeffs)
+}
+
+/// Masks the chunks `t_i` of `chunk_len` coefficients of a quotient
+/// `t(x) = sum_i x^(i c) t_i(x)` as `t'_i = t_i - b_(i-1) + x^c b_i`, for
+/// random `b_i` with `num_blinding_values` coefficients and
+/// `b_(-1) = b_(k-1) = 0`, padded to `committed_degree`. The sum telescopes,
+/// so `t` is unchanged, but the evaluations a proof reveals of each `t'_i`
+/// are random.
+fn mask_quotient_chunks<F: Field>(
+    chunks: Vec<PolynomialCoeffs<F>>,
+    chunk_len: usize,
+    num_blinding_values: usize,
+    committed_degree: usize,
+) -> Vec<PolynomialCoeffs<F>> {
+    let num_chunks = chunks.len();
+    let mut carry = vec![F::ZERO; num_blinding_values];
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mask = if i + 1 < num_chunks {
+                F::rand_vec(num_blinding_values)
+            } else {
+                vec![F::ZERO; num_blinding_values]
+            };
+            let mut coeffs = chunk.coeffs;
+            coeffs.resize(committed_degree, F::ZERO);
+            for (j, (&b, &m)) in carry.iter().zip(&mask).enumerate() {
+                coeffs[j] -= b;
+                coeffs[chunk_len + j] += m;
+            }
+            carry = mask;
+            PolynomialCoeffs::new(coeffs)
+        })
+        .collect()
 }
 
 /// Computes the quotient polynomials `(sum alpha^i C_i(x)) / Z_H(x)` for
 /// `alpha` in `alphas`, where the `C_i`s are the Stark constraints.
+#[allow(clippy::too_many_arguments)]
 fn compute_quotient_polys<'a, F, P, C, S, const D: usize>(
     stark: &S,
     trace_commitment: &'a PolynomialBatch<F, C, D>,
+    preprocessed_commitment: Option<&'a PolynomialBatch<F, C, D>>,
     permutation_ctl_zs_commitment: &'a PolynomialBatch<F, C, D>,
     permutation_challenges: Option<&'a Vec<GrandProductChallengeSet<F>>>,
+    lookup_challenges: Option<&'a Vec<F>>,
     ctl_data: &CtlData<F>,
     alphas: Vec<F>,
     degree_bits: usize,
     num_permutation_zs: usize,
+    num_lookup_columns: usize,
     config: &StarkConfig,
 ) -> Vec<PolynomialCoeffs<F>>
 where
@@ -587,14 +766,18 @@ where
     [(); S::COLUMNS]:,
 {
     let degree = 1 << degree_bits;
-    let rate_bits = config.fri_config.rate_bits;
+    // The LDEs of the commitments have `2^lde_bits` times the size of `H`.
+    let lde_bits = config.fri_config.rate_bits + config.blinding_bits(degree_bits);
 
-    let quotient_degree_bits = log2_ceil(stark.quotient_degree_factor());
+    let quotient_degree_bits = log2_ceil(ceil_div_usize(
+        stark.quotient_len(config, degree_bits),
+        degree,
+    ));
     assert!(
-        quotient_degree_bits <= rate_bits,
+        quotient_degree_bits <= lde_bits,
         "Having constraints of degree higher than the rate is not supported yet."
     );
-    let step = 1 << (rate_bits - quotient_degree_bits);
+    let step = 1 << (lde_bits - quotient_degree_bits);
     // When opening the `Z`s polys at the "next" point, need to look at the point
     // `next_step` steps away.
     let next_step = 1 << quotient_degree_bits;
@@ -607,12 +790,20 @@ where
 
     let z_h_on_coset = ZeroPolyOnCoset::<F>::new(degree_bits, quotient_degree_bits);
 
+    let preprocessed_columns = stark.preprocessed_columns();
     // Retrieve the LDE values at index `i`.
     let get_trace_values_packed = |i_start| -> [P; S::COLUMNS] {
-        trace_commitment
-            .get_lde_values_packed(i_start, step)
-            .try_into()
-            .unwrap()
+        let values = trace_commitment.get_lde_values_packed(i_start, step);
+        match preprocessed_commitment {
+            Some(commitment) => merge_columns(
+                &preprocessed_columns,
+                values,
+                commitment.get_lde_values_packed(i_start, step),
+            ),
+            None => values,
+        }
+        .try_into()
+        .unwrap()
     };
 
     // Last element of the subgroup.
@@ -658,18 +849,30 @@ where
                         .to_vec(),
                     permutation_challenge_sets: permutation_challenge_sets.to_vec(),
                 });
+            let lookup_columns = num_permutation_zs..num_permutation_zs + num_lookup_columns;
+            let lookup_check_vars = lookup_challenges.map(|challenges| LookupCheckVars {
+                local_values: permutation_ctl_zs_commitment.get_lde_values_packed(i_start, step)
+                    [lookup_columns.clone()]
+                .to_vec(),
+                next_values: permutation_ctl_zs_commitment
+                    .get_lde_values_packed(i_next_start, step)[lookup_columns]
+                    .to_vec(),
+                challenges: challenges.to_vec(),
+            });
+            let num_aux_columns = num_permutation_zs + num_lookup_columns;
             let ctl_vars = ctl_data
                 .zs_columns
                 .iter()
                 .enumerate()
                 .map(|(i, zs_columns)| CtlCheckVars::<F, F, P, 1> {
                     local_z: permutation_ctl_zs_commitment.get_lde_values_packed(i_start, step)
-                        [num_permutation_zs + i],
+                        [num_aux_columns + i],
                     next_z: permutation_ctl_zs_commitment.get_lde_values_packed(i_next_start, step)
-                        [num_permutation_zs + i],
+                        [num_aux_columns + i],
                     challenges: zs_columns.challenge,
                     columns: &zs_columns.columns,
                     filter_column: &zs_columns.filter_column,
+                    argument: zs_columns.argument,
                 })
                 .collect::<Vec<_>>();
             eval_vanishing_poly::<F, F, P, C, S, D, 1>(
@@ -677,6 +880,7 @@ where
                 config,
                 vars,
                 permutation_check_vars,
+                lookup_check_vars,
                 &ctl_vars,
                 &mut consumer,
             );
@@ -708,15 +912,19 @@ where
 /// Can also be used to check the degree of the constraints by evaluating on a
 /// larger subgroup.
 #[allow(unused)]
+#[allow(clippy::too_many_arguments)]
 fn check_constraints<'a, F, C, S, const D: usize>(
     stark: &S,
     trace_commitment: &'a PolynomialBatch<F, C, D>,
+    preprocessed_commitment: Option<&'a PolynomialBatch<F, C, D>>,
     permutation_ctl_zs_commitment: &'a PolynomialBatch<F, C, D>,
     permutation_challenges: Option<&'a Vec<GrandProductChallengeSet<F>>>,
+    lookup_challenges: Option<&'a Vec<F>>,
     ctl_data: &CtlData<F>,
     alphas: Vec<F>,
     degree_bits: usize,
     num_permutation_zs: usize,
+    num_lookup_columns: usize,
     config: &StarkConfig,
 ) where
     F: RichField + Extendable<D>,
@@ -737,17 +945,35 @@ fn check_constraints<'a, F, C, S, const D: usize>(
 
     let subgroup = F::two_adic_subgroup(degree_bits + rate_bits);
 
-    // Get the evaluations of a batch of polynomials over our subgroup.
+    // Get the evaluations of a batch of polynomials over our subgroup. They
+    // are reduced modulo `x^size - 1` first, which leaves them unchanged on
+    // the subgroup, as randomized polynomials have a higher degree.
     let get_subgroup_evals = |comm: &PolynomialBatch<F, C, D>| -> Vec<Vec<F>> {
         let values = comm
             .polynomials
             .par_iter()
-            .map(|coeffs| coeffs.clone().fft().values)
+            .map(|coeffs| {
+                let mut reduced = vec![F::ZERO; size];
+                for (i, &coeff) in coeffs.coeffs.iter().enumerate() {
+                    reduced[i % size] += coeff;
+                }
+                PolynomialCoeffs::new(reduced).fft().values
+            })
             .collect::<Vec<_>>();
         transpose(&values)
     };
 
-    let trace_subgroup_evals = get_subgroup_evals(trace_commitment);
+    let trace_subgroup_evals = match preprocessed_commitment {
+        Some(commitment) => {
+            let preprocessed_columns = stark.preprocessed_columns();
+            get_subgroup_evals(trace_commitment)
+                .into_iter()
+                .zip(get_subgroup_evals(commitment))
+                .map(|(main, fixed)| merge_columns(&preprocessed_columns, main, fixed))
+                .collect()
+        }
+        None => get_subgroup_evals(trace_commitment),
+    };
     let permutation_ctl_zs_subgroup_evals = get_subgroup_evals(permutation_ctl_zs_commitment);
 
     // Last element of the subgroup.
@@ -780,17 +1006,25 @@ fn check_constraints<'a, F, C, S, const D: usize>(
                         .to_vec(),
                     permutation_challenge_sets: permutation_challenge_sets.to_vec(),
                 });
-
+            let lookup_columns = num_permutation_zs..num_permutation_zs + num_lookup_columns;
+            let lookup_check_vars = lookup_challenges.map(|challenges| LookupCheckVars {
+                local_values: permutation_ctl_zs_subgroup_evals[i][lookup_columns.clone()].to_vec(),
+                next_values: permutation_ctl_zs_subgroup_evals[i_next][lookup_columns].to_vec(),
+                challenges: challenges.to_vec(),
+            });
+
+            let num_aux_columns = num_permutation_zs + num_lookup_columns;
             let ctl_vars = ctl_data
                 .zs_columns
                 .iter()
                 .enumerate()
                 .map(|(iii, zs_columns)| CtlCheckVars::<F, F, F, 1> {
-                    local_z: permutation_ctl_zs_subgroup_evals[i][num_permutation_zs + iii],
-                    next_z: permutation_ctl_zs_subgroup_evals[i_next][num_permutation_zs + iii],
+                    local_z: permutation_ctl_zs_subgroup_evals[i][num_aux_columns + iii],
+                    next_z: permutation_ctl_zs_subgroup_evals[i_next][num_aux_columns + iii],
                     challenges: zs_columns.challenge,
                     columns: &zs_columns.columns,
                     filter_column: &zs_columns.filter_column,
+                    argument: zs_columns.argument,
                 })
                 .collect::<Vec<_>>();
             eval_vanishing_poly::<F, F, F, C, S, D, 1>(
@@ -798,6 +1032,7 @@ fn check_constraints<'a, F, C, S, const D: usize>(
                 config,
                 vars,
                 permutation_check_vars,
+                lookup_check_vars,
                 &ctl_vars,
                 &mut consumer,
             );
@@ -817,3 +1052,104 @@ fn check_constraints<'a, F, C, S, const D: usize>(
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::interpolation::interpolant;
+    use plonky2::field::polynomial::{PolynomialCoeffs, PolynomialValues};
+    use plonky2::field::types::Field;
+
+    use super::{mask_quotient_chunks, randomize_off_subgroup};
+    use crate::stark::config::StarkConfig;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_tiny_table_openings_hide_trace() {
+        let config = StarkConfig {
+            zero_knowledge: true,
+            ..StarkConfig::standard_fast_config()
+        };
+        let degree_bits = 2;
+        let n = 1 << degree_bits;
+        let num_blinding_values = config.num_blinding_values();
+        let committed_degree = n << config.blinding_bits(degree_bits);
+        assert!(num_blinding_values > n);
+        assert!(committed_degree >= n + num_blinding_values);
+
+        let trace = PolynomialValues::new(F::rand_vec(n));
+        let committed =
+            randomize_off_subgroup(trace.clone(), num_blinding_values, committed_degree);
+        assert_eq!(committed.len(), committed_degree);
+        for (x, &value) in F::two_adic_subgroup(degree_bits).iter().zip(&trace.values) {
+            assert_eq!(committed.eval(*x), value);
+        }
+
+        // Any other trace, blinded by a suitable `r`, has the same
+        // evaluations at as many points off `H` as a proof reveals.
+        let other_trace = PolynomialValues::new(F::rand_vec(n));
+        let other = other_trace.clone().ifft();
+        let points = (0..num_blinding_values)
+            .map(|i| F::from_canonical_usize(i + 2))
+            .collect::<Vec<_>>();
+        let r = interpolant(
+            &points
+                .iter()
+                .map(|&x| {
+                    let z_h = x.exp_u64(n as u64) - F::ONE;
+                    (x, (committed.eval(x) - other.eval(x)) / z_h)
+                })
+                .collect::<Vec<_>>(),
+        );
+        assert!(r.len() <= num_blinding_values);
+        let mut z_h_coeffs = vec![F::ZERO; n + 1];
+        z_h_coeffs[0] = F::NEG_ONE;
+        z_h_coeffs[n] = F::ONE;
+        let other_committed = &other + &(&PolynomialCoeffs::new(z_h_coeffs) * &r);
+        assert!(other_committed.degree_plus_one() <= committed_degree);
+        for &x in &points {
+            assert_eq!(other_committed.eval(x), committed.eval(x));
+        }
+        for (x, &value) in F::two_adic_subgroup(degree_bits)
+            .iter()
+            .zip(&other_trace.values)
+        {
+            assert_eq!(other_committed.eval(*x), value);
+        }
+        assert_ne!(trace, other_trace);
+    }
+
+    #[test]
+    fn test_masked_quotient_chunks_recombine() {
+        let config = StarkConfig {
+            zero_knowledge: true,
+            ..StarkConfig::standard_fast_config()
+        };
+        let degree_bits = 2;
+        let num_blinding_values = config.num_blinding_values();
+        let committed_degree = (1 << degree_bits) << config.blinding_bits(degree_bits);
+        let chunk_len = config.quotient_chunk_len(degree_bits);
+        let quotient = PolynomialCoeffs::new(F::rand_vec(3 * chunk_len + 1));
+
+        let chunks = mask_quotient_chunks(
+            quotient.chunks(chunk_len),
+            chunk_len,
+            num_blinding_values,
+            committed_degree,
+        );
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks.iter().all(|chunk| chunk.len() == committed_degree));
+        assert_ne!(
+            chunks[1].coeffs[..chunk_len],
+            quotient.coeffs[chunk_len..2 * chunk_len]
+        );
+        let x = F::rand();
+        let x_pow_chunk_len = x.exp_u64(chunk_len as u64);
+        let recombined = chunks
+            .iter()
+            .rev()
+            .fold(F::ZERO, |acc, chunk| acc * x_pow_chunk_len + chunk.eval(x));
+        assert_eq!(recombined, quotient.eval(x));
+    }
+}
@@ -1,5 +1,5 @@
 use std::io::Cursor;
-use std::io::{Read, Result, Write};
+use std::io::{Error, ErrorKind, Read, Result, Write};
 
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::polynomial::PolynomialCoeffs;
@@ -11,7 +11,7 @@ use plonky2::hash::merkle_proofs::MerkleProof;
 use plonky2::hash::merkle_tree::MerkleCap;
 use plonky2::plonk::config::{GenericConfig, GenericHashOut, Hasher};
 
-use super::proof::{AllProof, PublicValues, StarkOpeningSet, StarkProof};
+use super::proof::{AllProof, BatchOpeningProof, PublicValues, StarkOpeningSet, StarkProof};
 
 #[derive(Debug)]
 pub struct Buffer(Cursor<Vec<u8>>);
@@ -50,12 +50,18 @@ impl Buffer {
     fn write_field<F: PrimeField64>(&mut self, x: F) -> Result<()> {
         self.0.write_all(&x.to_canonical_u64().to_le_bytes())
     }
+    // Only canonical encodings are accepted, so a proof has one encoding.
     fn read_field<F: Field64>(&mut self) -> Result<F> {
         let mut buf = [0; std::mem::size_of::<u64>()];
         self.0.read_exact(&mut buf)?;
-        Ok(F::from_canonical_u64(u64::from_le_bytes(
-            buf.try_into().unwrap(),
-        )))
+        let x = u64::from_le_bytes(buf);
+        if x >= F::ORDER {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("non-canonical field element {}", x),
+            ));
+        }
+        Ok(F::from_canonical_u64(x))
     }
 
     fn write_field_ext<F: RichField + Extendable<D>, const D: usize>(
@@ -119,7 +125,14 @@ impl Buffer {
     fn read_hash<F: RichField, H: Hasher<F>>(&mut self) -> Result<H::Hash> {
         let mut buf = vec![0; H::HASH_SIZE];
         self.0.read_exact(&mut buf)?;
-        Ok(H::Hash::from_bytes(&buf))
+        let h = H::Hash::from_bytes(&buf);
+        if h.to_bytes() != buf {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "non-canonical hash encoding",
+            ));
+        }
+        Ok(h)
     }
 
     pub fn write_merkle_cap<F: RichField, H: Hasher<F>>(
@@ -354,7 +367,13 @@ impl Buffer {
         self.write_merkle_cap(&proof.permutation_ctl_zs_cap)?;
         self.write_merkle_cap(&proof.quotient_polys_cap)?;
         self.write_opening_set(&proof.openings)?;
-        self.write_fri_proof::<F, C, D>(&proof.opening_proof)
+        match &proof.opening_proof {
+            Some(opening_proof) => {
+                self.write_u8(1)?;
+                self.write_fri_proof::<F, C, D>(opening_proof)
+            }
+            None => self.write_u8(0),
+        }
     }
     pub fn read_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
         &mut self,
@@ -363,7 +382,10 @@ impl Buffer {
         let permutation_ctl_zs_cap = self.read_merkle_cap()?;
         let quotient_polys_cap = self.read_merkle_cap()?;
         let openings = self.read_opening_set()?;
-        let opening_proof = self.read_fri_proof::<F, C, D>()?;
+        let opening_proof = match self.read_u8()? {
+            0 => None,
+            _ => Some(self.read_fri_proof::<F, C, D>()?),
+        };
 
         Ok(StarkProof {
             trace_cap,
@@ -374,6 +396,38 @@ impl Buffer {
         })
     }
 
+    pub fn write_batch_opening_proof<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+        batch: &BatchOpeningProof<F, C, D>,
+    ) -> Result<()> {
+        self.write_u32(batch.tables.len() as u32)?;
+        for &table in &batch.tables {
+            self.write_u32(table as u32)?;
+        }
+        self.write_fri_proof::<F, C, D>(&batch.opening_proof)
+    }
+    pub fn read_batch_opening_proof<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        &mut self,
+    ) -> Result<BatchOpeningProof<F, C, D>> {
+        let len = self.read_u32()? as usize;
+        let tables = (0..len)
+            .map(|_| Ok(self.read_u32()? as usize))
+            .collect::<Result<Vec<_>>>()?;
+        let opening_proof = self.read_fri_proof::<F, C, D>()?;
+        Ok(BatchOpeningProof {
+            tables,
+            opening_proof,
+        })
+    }
+
     pub fn write_all_proof<
         F: RichField + Extendable<D>,
         C: GenericConfig<D, F = F>,
@@ -386,8 +440,16 @@ impl Buffer {
         for p in &proof.stark_proofs {
             self.write_proof(p)?;
         }
+        self.write_u32(proof.batch_opening_proofs.len() as u32)?;
+        for batch in &proof.batch_opening_proofs {
+            self.write_batch_opening_proof(batch)?;
+        }
 
         self.write_field_vec(&proof.compress_challenges)?;
+        self.write_u32(proof.degree_bits.len() as u32)?;
+        for &bits in &proof.degree_bits {
+            self.write_u8(bits as u8)?;
+        }
         // PublicValues
         Ok(())
     }
@@ -403,11 +465,81 @@ impl Buffer {
         for _ in 0..len {
             stark_proofs.push(self.read_proof()?);
         }
+        let mut batch_opening_proofs = vec![];
+        let len = self.read_u32()? as usize;
+        for _ in 0..len {
+            batch_opening_proofs.push(self.read_batch_opening_proof()?);
+        }
         let compress_challenges = self.read_field_vec()?;
+        let len = self.read_u32()? as usize;
+        let degree_bits = (0..len)
+            .map(|_| Ok(self.read_u8()? as usize))
+            .collect::<Result<Vec<_>>>()?;
         Ok(AllProof {
-            stark_proofs: stark_proofs.try_into().unwrap(),
-            compress_challenges: compress_challenges.try_into().unwrap(),
+            stark_proofs,
+            batch_opening_proofs,
+            compress_challenges,
+            degree_bits,
             public_values: PublicValues::default(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::polynomial::{PolynomialCoeffs, PolynomialValues};
+    use plonky2::field::types::{Field, Field64};
+    use plonky2::hash::merkle_proofs::verify_merkle_proof_to_cap;
+    use plonky2::plonk::config::Blake3GoldilocksConfig;
+    use plonky2::util::timing::TimingTree;
+
+    use super::Buffer;
+    use crate::stark::prover::commit_coeffs;
+
+    #[test]
+    fn test_reject_non_canonical_field() {
+        let mut bytes = 1u32.to_le_bytes().to_vec();
+        bytes.extend(GoldilocksField::ORDER.to_le_bytes());
+        assert!(Buffer::new(bytes)
+            .read_field_vec::<GoldilocksField>()
+            .is_err());
+
+        let mut bytes = 1u32.to_le_bytes().to_vec();
+        bytes.extend((GoldilocksField::ORDER - 1).to_le_bytes());
+        assert_eq!(
+            Buffer::new(bytes)
+                .read_field_vec::<GoldilocksField>()
+                .unwrap(),
+            vec![GoldilocksField::NEG_ONE]
+        );
+    }
+
+    #[test]
+    fn test_committed_leaves_round_trip() {
+        // Leaves are read back canonically, and must hash as the prover's did.
+        // The interpolant of a constant column has non-canonical coefficients,
+        // and so non-canonical LDE values unless they are reduced.
+        let polynomials = vec![
+            PolynomialValues::new(vec![GoldilocksField::ONE; 16]).ifft(),
+            PolynomialCoeffs::new(GoldilocksField::rand_vec(16)),
+        ];
+        let commitment = commit_coeffs::<GoldilocksField, Blake3GoldilocksConfig, 2>(
+            polynomials,
+            3,
+            false,
+            2,
+            &mut TimingTree::default(),
+            &mut BTreeMap::new(),
+        );
+        let tree = &commitment.merkle_tree;
+        for (index, leaf) in tree.leaves.iter().enumerate() {
+            let mut buffer = Buffer::new(Vec::new());
+            buffer.write_field_vec(leaf).unwrap();
+            let leaf = Buffer::new(buffer.bytes()).read_field_vec().unwrap();
+            verify_merkle_proof_to_cap(leaf, index, &tree.cap, &tree.prove(index)).unwrap();
+        }
+    }
+}
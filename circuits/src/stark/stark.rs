@@ -1,5 +1,6 @@
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
 use plonky2::field::types::Field;
 use plonky2::fri::structure::{
     FriBatchInfo, FriBatchInfoTarget, FriInstanceInfo, FriInstanceInfoTarget, FriOracleInfo,
@@ -12,6 +13,7 @@ use plonky2_util::ceil_div_usize;
 
 use super::config::StarkConfig;
 use super::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use super::lookup::Lookup;
 use super::permutation::PermutationPair;
 use super::vars::StarkEvaluationTargets;
 use super::vars::StarkEvaluationVars;
@@ -19,6 +21,7 @@ use super::vars::StarkEvaluationVars;
 const TRACE_ORACLE_INDEX: usize = 0;
 const PERMUTATION_CTL_ORACLE_INDEX: usize = 1;
 const QUOTIENT_ORACLE_INDEX: usize = 2;
+const PREPROCESSED_ORACLE_INDEX: usize = 3;
 
 /// Represents a STARK system.
 pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
@@ -79,8 +82,37 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         1.max(self.constraint_degree() - 1)
     }
 
-    fn num_quotient_polys(&self, config: &StarkConfig) -> usize {
-        self.quotient_degree_factor() * config.num_challenges
+    /// The number of coefficients of the quotient of each challenge, at
+    /// most. With `config.zero_knowledge` the committed polynomials have
+    /// `config.num_blinding_values()` more coefficients, which raise the
+    /// degree of the quotient.
+    fn quotient_len(&self, config: &StarkConfig, degree_bits: usize) -> usize {
+        let n = 1 << degree_bits;
+        if config.zero_knowledge {
+            // The constraints, including the degree 3 cross-table lookup
+            // checks, have degree at most `d (n + h - 1)`, one more with the
+            // transition selector, the quotient `n` less.
+            let degree = self.constraint_degree().max(3);
+            degree * (n + config.num_blinding_values() - 1) - n + 2
+        } else {
+            self.quotient_degree_factor() * n
+        }
+    }
+
+    /// The number of chunks of `config.quotient_chunk_len(degree_bits)`
+    /// coefficients the quotient of each challenge is split into.
+    fn num_quotient_chunks(&self, config: &StarkConfig, degree_bits: usize) -> usize {
+        ceil_div_usize(
+            self.quotient_len(config, degree_bits),
+            config.quotient_chunk_len(degree_bits),
+        )
+    }
+
+    /// The quotient chunks of every challenge, followed in zero-knowledge mode
+    /// by the polynomial masking the FRI batch.
+    fn num_quotient_polys(&self, config: &StarkConfig, degree_bits: usize) -> usize {
+        self.num_quotient_chunks(config, degree_bits) * config.num_challenges
+            + config.zero_knowledge as usize
     }
 
     /// Computes the FRI instance used to prove this Stark.
@@ -92,17 +124,39 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         num_ctl_zs: usize,
         config: &StarkConfig,
     ) -> FriInstanceInfo<F, D> {
+        // Preprocessed columns are committed apart from the trace, but opened
+        // in column order along with it.
+        let preprocessed_columns = self.preprocessed_columns();
+        let num_preprocessed = preprocessed_columns.len();
         let trace_oracle = FriOracleInfo {
-            num_polys: Self::COLUMNS,
-            blinding: false,
+            num_polys: Self::COLUMNS - num_preprocessed,
+            blinding: config.zero_knowledge,
         };
-        let trace_info = FriPolynomialInfo::from_range(TRACE_ORACLE_INDEX, 0..Self::COLUMNS);
+        let (mut num_main, mut num_fixed) = (0, 0);
+        let trace_info = (0..Self::COLUMNS)
+            .map(|col| {
+                if preprocessed_columns.contains(&col) {
+                    num_fixed += 1;
+                    FriPolynomialInfo {
+                        oracle_index: PREPROCESSED_ORACLE_INDEX,
+                        polynomial_index: num_fixed - 1,
+                    }
+                } else {
+                    num_main += 1;
+                    FriPolynomialInfo {
+                        oracle_index: TRACE_ORACLE_INDEX,
+                        polynomial_index: num_main - 1,
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
 
-        let num_permutation_batches = self.num_permutation_batches(config);
-        let num_perutation_ctl_polys = num_permutation_batches + num_ctl_zs;
+        let num_aux_columns =
+            self.num_permutation_batches(config) + self.num_lookup_helper_columns(config);
+        let num_perutation_ctl_polys = num_aux_columns + num_ctl_zs;
         let permutation_ctl_oracle = FriOracleInfo {
             num_polys: num_perutation_ctl_polys,
-            blinding: false,
+            blinding: config.zero_knowledge,
         };
         let permutation_ctl_zs_info = FriPolynomialInfo::from_range(
             PERMUTATION_CTL_ORACLE_INDEX,
@@ -111,13 +165,13 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
 
         let ctl_zs_info = FriPolynomialInfo::from_range(
             PERMUTATION_CTL_ORACLE_INDEX,
-            num_permutation_batches..num_permutation_batches + num_ctl_zs,
+            num_aux_columns..num_aux_columns + num_ctl_zs,
         );
 
-        let num_quotient_polys = self.num_quotient_polys(config);
+        let num_quotient_polys = self.num_quotient_polys(config, degree_bits);
         let quotient_oracle = FriOracleInfo {
             num_polys: num_quotient_polys,
-            blinding: false,
+            blinding: config.zero_knowledge,
         };
         let quotient_info =
             FriPolynomialInfo::from_range(QUOTIENT_ORACLE_INDEX, 0..num_quotient_polys);
@@ -139,8 +193,15 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
             point: F::Extension::primitive_root_of_unity(degree_bits).inverse(),
             polynomials: ctl_zs_info,
         };
+        let mut oracles = vec![trace_oracle, permutation_ctl_oracle, quotient_oracle];
+        if num_preprocessed > 0 {
+            oracles.push(FriOracleInfo {
+                num_polys: num_preprocessed,
+                blinding: false,
+            });
+        }
         FriInstanceInfo {
-            oracles: vec![trace_oracle, permutation_ctl_oracle, quotient_oracle],
+            oracles,
             batches: vec![zeta_batch, zeta_next_batch, ctl_last_batch],
         }
     }
@@ -161,8 +222,9 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         };
         let trace_info = FriPolynomialInfo::from_range(TRACE_ORACLE_INDEX, 0..Self::COLUMNS);
 
-        let num_permutation_batches = self.num_permutation_batches(inner_config);
-        let num_perutation_ctl_polys = num_permutation_batches + num_ctl_zs;
+        let num_aux_columns = self.num_permutation_batches(inner_config)
+            + self.num_lookup_helper_columns(inner_config);
+        let num_perutation_ctl_polys = num_aux_columns + num_ctl_zs;
         let permutation_ctl_oracle = FriOracleInfo {
             num_polys: num_perutation_ctl_polys,
             blinding: false,
@@ -174,10 +236,10 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
 
         let ctl_zs_info = FriPolynomialInfo::from_range(
             PERMUTATION_CTL_ORACLE_INDEX,
-            num_permutation_batches..num_permutation_batches + num_ctl_zs,
+            num_aux_columns..num_aux_columns + num_ctl_zs,
         );
 
-        let num_quotient_polys = self.num_quotient_polys(inner_config);
+        let num_quotient_polys = self.num_quotient_polys(inner_config, degree_bits);
         let quotient_oracle = FriOracleInfo {
             num_polys: num_quotient_polys,
             blinding: false,
@@ -242,4 +304,46 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
             self.permutation_batch_size(),
         )
     }
+
+    /// Lookups of columns into a table column of the trace, proven with a
+    /// logarithmic derivative argument. Empty by default.
+    fn lookups(&self) -> Vec<Lookup> {
+        vec![]
+    }
+
+    fn uses_lookups(&self) -> bool {
+        !self.lookups().is_empty()
+    }
+
+    /// The number of looking columns whose inverses one helper column sums.
+    fn lookup_batch_size(&self) -> usize {
+        // A helper column is constrained by
+        //     h \prod(alpha + c_k) = \sum_k \prod_{l != k}(alpha + c_l)
+        // which has degree one more than the batch size.
+        self.quotient_degree_factor()
+    }
+
+    /// The number of lookup helper columns, placed after the permutation Zs.
+    fn num_lookup_helper_columns(&self, config: &StarkConfig) -> usize {
+        let batch_size = self.lookup_batch_size();
+        self.lookups()
+            .iter()
+            .map(|lookup| lookup.num_helper_columns(batch_size))
+            .sum::<usize>()
+            * config.num_challenges
+    }
+
+    /// Columns whose values only depend on the height of the trace, in
+    /// increasing order. They are committed once, in the verifying key,
+    /// instead of in every proof. Empty by default.
+    fn preprocessed_columns(&self) -> Vec<usize> {
+        vec![]
+    }
+
+    /// The values of the `preprocessed_columns` for a trace of
+    /// `2^degree_bits` rows, or `None` if the table has no preprocessed
+    /// columns or cannot have that many rows.
+    fn preprocessed_values(&self, _degree_bits: usize) -> Option<Vec<PolynomialValues<F>>> {
+        None
+    }
 }
@@ -0,0 +1,252 @@
+//! The interface every table of the VM implements, so that trace generation,
+//! proving, verifying and the trace checkers can walk the tables of an
+//! `OlaStark` instead of naming each of them.
+
+use core::trace::trace::Trace;
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::fri::oracle::PolynomialBatch;
+use plonky2::fri::structure::FriInstanceInfo;
+use plonky2::hash::hash_types::RichField;
+use plonky2::hash::merkle_tree::MerkleCap;
+use plonky2::iop::challenger::Challenger;
+use plonky2::plonk::config::{GenericConfig, Hasher};
+use plonky2::util::timing::TimingTree;
+
+use super::batch_fri::TableProof;
+use super::config::StarkConfig;
+use super::constraint_check::{check_table, ConstraintFailure};
+use super::constraint_consumer::ConstraintConsumer;
+use super::cross_table_lookup::{CrossTableLookup, CtlCheckVars, CtlData};
+use super::lookup::Lookup;
+use super::ola_stark::Table;
+use super::proof::{StarkProof, StarkProofChallenges};
+use super::prover::prove_single_table;
+use super::stark::Stark;
+use super::vars::StarkEvaluationVars;
+use super::verifier::verify_stark_proof_with_challenges;
+
+/// Generates the trace of a table, along with the compress challenge the
+/// table draws from it, if any.
+pub type TraceJob<F> = Box<dyn FnOnce() -> (Vec<PolynomialValues<F>>, Option<F>) + Send>;
+
+/// A table of the VM, as registered in `OlaStark`.
+pub trait OlaTable<F: RichField + Extendable<D>, const D: usize>: Sync {
+    fn table(&self) -> Table;
+
+    /// Takes what the table needs from the execution trace. Jobs are taken
+    /// in `Table` order, rows another table still needs are cloned.
+    fn trace_job(&self, trace: &mut Trace) -> TraceJob<F>;
+
+    /// The cross-table lookups this table is the looked table of.
+    fn cross_table_lookups(&self) -> Vec<CrossTableLookup<F>> {
+        vec![]
+    }
+
+    fn compress_challenge(&self) -> Option<F> {
+        None
+    }
+
+    fn set_compress_challenge(&mut self, _challenge: F) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// What the trace checkers and the challenge derivation need from a table,
+/// for any config.
+pub trait TableConstraints<F: RichField + Extendable<D>, const D: usize>: OlaTable<F, D> {
+    fn num_columns(&self) -> usize;
+
+    fn num_permutation_batches(&self, config: &StarkConfig) -> usize;
+
+    fn permutation_batch_size(&self) -> usize;
+
+    fn num_lookup_helper_columns(&self, config: &StarkConfig) -> usize;
+
+    fn lookups(&self) -> Vec<Lookup>;
+
+    fn fri_instance(
+        &self,
+        zeta: F::Extension,
+        g: F,
+        degree_bits: usize,
+        num_ctl_zs: usize,
+        config: &StarkConfig,
+    ) -> FriInstanceInfo<F, D>;
+
+    fn preprocessed_columns(&self) -> Vec<usize>;
+
+    fn preprocessed_values(&self, degree_bits: usize) -> Option<Vec<PolynomialValues<F>>>;
+
+    /// Checks the constraints on the given rows of the table's trace, each
+    /// along with the row that follows it.
+    fn check_rows(
+        &self,
+        trace: &[PolynomialValues<F>],
+        rows: &mut dyn Iterator<Item = usize>,
+    ) -> Vec<ConstraintFailure<F>>;
+
+    /// Combines the constraints on the given rows with each of `alphas`,
+    /// first-row, last-row and transition ones alike.
+    fn eval_constraints(&self, local_values: &[F], next_values: &[F], alphas: Vec<F>) -> Vec<F>;
+}
+
+/// Proving and verifying a table under the config `C`.
+pub trait TableStark<F, C, const D: usize>: TableConstraints<F, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    /// Proves the table, whose preprocessed columns, if any, are committed
+    /// in `preprocessed_commitment` rather than `trace_commitment`. With
+    /// `config.batch_fri`, the openings are left unproven and returned along
+    /// with the commitments they need, otherwise the commitments are dropped
+    /// once the openings are proven. Without a `trace_commitment`, the
+    /// trace is committed again, consuming it, once the columns of its
+    /// permutation and lookup arguments are computed.
+    #[allow(clippy::too_many_arguments)]
+    fn prove(
+        &self,
+        config: &StarkConfig,
+        trace_poly_values: Vec<PolynomialValues<F>>,
+        trace_commitment: Option<PolynomialBatch<F, C, D>>,
+        preprocessed_commitment: Option<&PolynomialBatch<F, C, D>>,
+        ctl_data: &CtlData<F>,
+        challenger: &mut Challenger<F, C::Hasher>,
+        timing: &mut TimingTree,
+        twiddle_map: &mut BTreeMap<usize, Vec<F>>,
+    ) -> Result<TableProof<F, C, D>>;
+
+    #[allow(clippy::too_many_arguments)]
+    fn verify(
+        &self,
+        proof: &StarkProof<F, C, D>,
+        challenges: &StarkProofChallenges<F, D>,
+        ctl_vars: &[CtlCheckVars<F, F::Extension, F::Extension, D>],
+        preprocessed_cap: Option<&MerkleCap<F, C::Hasher>>,
+        degree_bits: usize,
+        config: &StarkConfig,
+    ) -> Result<()>;
+}
+
+impl<F, S, const D: usize> TableConstraints<F, D> for S
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D> + OlaTable<F, D>,
+    [(); S::COLUMNS]:,
+{
+    fn num_columns(&self) -> usize {
+        S::COLUMNS
+    }
+
+    fn num_permutation_batches(&self, config: &StarkConfig) -> usize {
+        Stark::num_permutation_batches(self, config)
+    }
+
+    fn permutation_batch_size(&self) -> usize {
+        Stark::permutation_batch_size(self)
+    }
+
+    fn num_lookup_helper_columns(&self, config: &StarkConfig) -> usize {
+        Stark::num_lookup_helper_columns(self, config)
+    }
+
+    fn lookups(&self) -> Vec<Lookup> {
+        Stark::lookups(self)
+    }
+
+    fn fri_instance(
+        &self,
+        zeta: F::Extension,
+        g: F,
+        degree_bits: usize,
+        num_ctl_zs: usize,
+        config: &StarkConfig,
+    ) -> FriInstanceInfo<F, D> {
+        Stark::fri_instance(self, zeta, g, degree_bits, num_ctl_zs, config)
+    }
+
+    fn preprocessed_columns(&self) -> Vec<usize> {
+        Stark::preprocessed_columns(self)
+    }
+
+    fn preprocessed_values(&self, degree_bits: usize) -> Option<Vec<PolynomialValues<F>>> {
+        Stark::preprocessed_values(self, degree_bits)
+    }
+
+    fn check_rows(
+        &self,
+        trace: &[PolynomialValues<F>],
+        rows: &mut dyn Iterator<Item = usize>,
+    ) -> Vec<ConstraintFailure<F>> {
+        check_table(self, OlaTable::table(self), trace, rows)
+    }
+
+    fn eval_constraints(&self, local_values: &[F], next_values: &[F], alphas: Vec<F>) -> Vec<F> {
+        let mut consumer = ConstraintConsumer::new(alphas, F::ONE, F::ONE, F::ONE);
+        self.eval_packed_base(
+            StarkEvaluationVars {
+                local_values: local_values.try_into().unwrap(),
+                next_values: next_values.try_into().unwrap(),
+            },
+            &mut consumer,
+        );
+        consumer.accumulators()
+    }
+}
+
+impl<F, C, S, const D: usize> TableStark<F, C, D> for S
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D> + OlaTable<F, D>,
+    [(); S::COLUMNS]:,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    fn prove(
+        &self,
+        config: &StarkConfig,
+        trace_poly_values: Vec<PolynomialValues<F>>,
+        trace_commitment: Option<PolynomialBatch<F, C, D>>,
+        preprocessed_commitment: Option<&PolynomialBatch<F, C, D>>,
+        ctl_data: &CtlData<F>,
+        challenger: &mut Challenger<F, C::Hasher>,
+        timing: &mut TimingTree,
+        twiddle_map: &mut BTreeMap<usize, Vec<F>>,
+    ) -> Result<TableProof<F, C, D>> {
+        prove_single_table(
+            self,
+            config,
+            trace_poly_values,
+            trace_commitment,
+            preprocessed_commitment,
+            ctl_data,
+            challenger,
+            timing,
+            twiddle_map,
+        )
+    }
+
+    fn verify(
+        &self,
+        proof: &StarkProof<F, C, D>,
+        challenges: &StarkProofChallenges<F, D>,
+        ctl_vars: &[CtlCheckVars<F, F::Extension, F::Extension, D>],
+        preprocessed_cap: Option<&MerkleCap<F, C::Hasher>>,
+        degree_bits: usize,
+        config: &StarkConfig,
+    ) -> Result<()> {
+        verify_stark_proof_with_challenges(
+            self,
+            proof,
+            challenges,
+            ctl_vars,
+            preprocessed_cap,
+            degree_bits,
+            config,
+        )
+    }
+}
@@ -9,6 +9,10 @@ use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::util::transpose;
 use std::convert::TryInto;
 use std::mem::{size_of, transmute_copy, ManuallyDrop};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 /// Construct an integer from its constituent bits (in little-endian order)
 pub fn limb_from_bits_le<P: PackedField>(iter: impl IntoIterator<Item = P>) -> P {
@@ -57,6 +61,107 @@ pub fn trace_to_poly_values<F: Field, const COLUMNS: usize>(
         .collect()
 }
 
+/// The columns of a trace committed in proofs, leaving out the
+/// `preprocessed` ones.
+pub(crate) fn main_columns<T>(
+    preprocessed: &[usize],
+    columns: impl IntoIterator<Item = T>,
+) -> Vec<T> {
+    columns
+        .into_iter()
+        .enumerate()
+        .filter(|(col, _)| !preprocessed.contains(col))
+        .map(|(_, column)| column)
+        .collect()
+}
+
+/// The resident memory of the process, in bytes. `None` where `/proc` does
+/// not report it.
+pub(crate) fn resident_memory() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let resident_kb = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<usize>()
+        .ok()?;
+    Some(resident_kb << 10)
+}
+
+/// Samples the resident memory of the process on a background thread, to
+/// measure the peak of each phase of a proof. The high-water mark `/proc`
+/// keeps can only be reset by writing `/proc/self/clear_refs`, which also
+/// clears the page reference bits of the whole process, so the peak is
+/// tracked here instead. Phases shorter than the sampling interval may
+/// report a peak below their real one.
+pub(crate) struct MemorySampler {
+    peak: Arc<AtomicUsize>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MemorySampler {
+    /// Starts sampling every `interval`. `None` where `/proc` does not report
+    /// the resident memory.
+    pub(crate) fn start(interval: Duration) -> Option<Self> {
+        let peak = Arc::new(AtomicUsize::new(resident_memory()?));
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let peak = peak.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    if let Some(bytes) = resident_memory() {
+                        peak.fetch_max(bytes, Ordering::Relaxed);
+                    }
+                    thread::sleep(interval);
+                }
+            })
+        };
+        Some(Self {
+            peak,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// The peak resident memory since the last call, and the current one,
+    /// which the peak of the next phase starts from.
+    pub(crate) fn take_peak(&self) -> (usize, usize) {
+        let current = resident_memory().unwrap_or(0);
+        let peak = self.peak.swap(current, Ordering::Relaxed);
+        (peak.max(current), current)
+    }
+}
+
+impl Drop for MemorySampler {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Puts values of the main and `preprocessed` columns back in column order.
+pub(crate) fn merge_columns<T>(preprocessed: &[usize], main: Vec<T>, fixed: Vec<T>) -> Vec<T> {
+    let num_columns = main.len() + fixed.len();
+    let mut main = main.into_iter();
+    let mut fixed = fixed.into_iter();
+    (0..num_columns)
+        .map(|col| {
+            if preprocessed.contains(&col) {
+                fixed.next()
+            } else {
+                main.next()
+            }
+            .expect("The number of preprocessed values does not match the columns.")
+        })
+        .collect()
+}
+
 /// Returns the 32-bit little-endian limbs of a `U256`.
 #[allow(unused)]
 pub(crate) fn u256_limbs<F: Field>(u256: U256) -> [F; 8] {
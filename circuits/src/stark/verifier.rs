@@ -5,13 +5,16 @@ use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::types::Field;
 use plonky2::fri::verifier::verify_fri_proof;
 use plonky2::hash::hash_types::RichField;
+use plonky2::hash::merkle_tree::MerkleCap;
 use plonky2::plonk::config::{GenericConfig, Hasher};
 use plonky2::plonk::plonk_common::reduce_with_powers;
 
+use super::batch_fri::verify_batch_opening_proof;
 use super::config::StarkConfig;
 use super::constraint_consumer::ConstraintConsumer;
 use super::cross_table_lookup::{verify_cross_table_lookups, CtlCheckVars};
-use super::ola_stark::{OlaStark, Table, NUM_TABLES};
+use super::lookup::LookupCheckVars;
+use super::ola_stark::{OlaStark, StarkRegistry, TableRegistry};
 use super::permutation::{GrandProductChallenge, PermutationCheckVars};
 use super::proof::{
     AllProof, AllProofChallenges, PublicValues, StarkOpeningSet, StarkProof, StarkProofChallenges,
@@ -19,177 +22,133 @@ use super::proof::{
 use super::stark::Stark;
 use super::vanishing_poly::eval_vanishing_poly;
 use super::vars::StarkEvaluationVars;
-use crate::builtins::bitwise::bitwise_stark::BitwiseStark;
-use crate::builtins::cmp::cmp_stark::CmpStark;
-use crate::builtins::poseidon::poseidon_chunk_stark::PoseidonChunkStark;
-use crate::builtins::poseidon::poseidon_stark::PoseidonStark;
-use crate::builtins::rangecheck::rangecheck_stark::RangeCheckStark;
-use crate::builtins::sccall::sccall_stark::SCCallStark;
-use crate::builtins::storage::storage_access_stark::StorageAccessStark;
-// use crate::builtins::tape::tape_stark::TapeStark;
-use crate::cpu::cpu_stark::CpuStark;
-use crate::memory::memory_stark::MemoryStark;
-use crate::program::prog_chunk_stark::ProgChunkStark;
-use crate::program::program_stark::ProgramStark;
+use super::verifying_key::{circuit_digest, OlaVerifyingKey, VERIFYING_KEY_VERSION};
 
+/// Verifies `all_proof` against `verifying_key`. The key must be trusted,
+/// derived by the verifier with `OlaVerifyingKey::new` rather than sent by
+/// the prover: the openings of the preprocessed columns are only checked
+/// against its caps.
 pub fn verify_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
-    ola_stark: OlaStark<F, D>,
+    mut ola_stark: OlaStark<F, D>,
+    verifying_key: &OlaVerifyingKey<F, C, D>,
     all_proof: AllProof<F, C, D>,
     config: &StarkConfig,
 ) -> Result<()>
 where
     [(); C::Hasher::HASH_SIZE]:,
-    [(); CpuStark::<F, D>::COLUMNS]:,
-    [(); MemoryStark::<F, D>::COLUMNS]:,
-    [(); BitwiseStark::<F, D>::COLUMNS]:,
-    [(); CmpStark::<F, D>::COLUMNS]:,
-    [(); RangeCheckStark::<F, D>::COLUMNS]:,
-    [(); PoseidonStark::<F, D>::COLUMNS]:,
-    [(); PoseidonChunkStark::<F, D>::COLUMNS]:,
-    [(); StorageAccessStark::<F, D>::COLUMNS]:,
-    // [(); TapeStark::<F, D>::COLUMNS]:,
-    [(); SCCallStark::<F, D>::COLUMNS]:,
-    [(); ProgramStark::<F, D>::COLUMNS]:,
-    [(); ProgChunkStark::<F, D>::COLUMNS]:,
+    OlaStark<F, D>: StarkRegistry<F, C, D>,
 {
+    ensure!(
+        verifying_key.version == VERIFYING_KEY_VERSION,
+        "Unsupported verifying key version {}, expected {}.",
+        verifying_key.version,
+        VERIFYING_KEY_VERSION
+    );
+    ensure!(
+        verifying_key.circuit_digest == circuit_digest::<F, C, D>(config),
+        "The verifying key is not for this circuit."
+    );
+
+    let num_tables = ola_stark.tables().len();
+    ensure!(
+        all_proof.stark_proofs.len() == num_tables
+            && all_proof.compress_challenges.len() == num_tables
+            && all_proof.degree_bits.len() == num_tables
+            && verifying_key.preprocessed_caps.len() == num_tables,
+        "The proof does not have one STARK proof per table."
+    );
+
+    let mut batched = vec![false; num_tables];
+    for batch in &all_proof.batch_opening_proofs {
+        ensure!(!batch.tables.is_empty(), "Empty batch opening proof.");
+        for &table in &batch.tables {
+            ensure!(
+                table < num_tables && !batched[table],
+                "Table {} is not in exactly one batch opening proof.",
+                table
+            );
+            batched[table] = true;
+        }
+        ensure!(
+            batch.tables.iter().all(
+                |&table| all_proof.degree_bits[table] == all_proof.degree_bits[batch.tables[0]]
+            ),
+            "The tables of a batch opening proof differ in degree."
+        );
+    }
+    for (proof, batched) in all_proof.stark_proofs.iter().zip(batched) {
+        ensure!(
+            batched == config.batch_fri && proof.opening_proof.is_none() == batched,
+            "The openings are not proven as the config requires."
+        );
+    }
+    let degree_bits = &all_proof.degree_bits;
+    config.validate::<F, D>(degree_bits.iter().copied().max().unwrap_or(0))?;
+
     let AllProofChallenges {
         stark_challenges,
         ctl_challenges,
-    } = all_proof.get_challenges(&ola_stark, config);
+        batch_fri_challenges,
+    } = all_proof.get_challenges(&ola_stark, verifying_key, config);
 
     let nums_permutation_zs = ola_stark.nums_permutation_zs(config);
-
-    let OlaStark {
-        cpu_stark,
-        memory_stark,
-        mut bitwise_stark,
-        cmp_stark,
-        rangecheck_stark,
-        poseidon_stark,
-        poseidon_chunk_stark,
-        storage_access_stark,
-        tape_stark,
-        sccall_stark,
-        mut program_stark,
-        prog_chunk_stark,
-        cross_table_lookups,
-    } = ola_stark;
-
-    if bitwise_stark.get_compress_challenge().is_none() {
-        bitwise_stark
-            .set_compress_challenge(all_proof.compress_challenges[Table::Bitwise as usize])
-            .unwrap();
-    }
-    if program_stark.get_compress_challenge().is_none() {
-        program_stark
-            .set_compress_challenge(all_proof.compress_challenges[Table::Program as usize])
-            .unwrap();
+    let nums_lookup_columns = ola_stark.nums_lookup_helper_columns(config);
+    let nums_aux_columns: Vec<usize> = nums_permutation_zs
+        .iter()
+        .zip(&nums_lookup_columns)
+        .map(|(permutation_zs, lookup_columns)| permutation_zs + lookup_columns)
+        .collect();
+
+    for (table, &challenge) in ola_stark
+        .tables_mut()
+        .into_iter()
+        .zip(&all_proof.compress_challenges)
+    {
+        if table.compress_challenge().is_none() {
+            table.set_compress_challenge(challenge)?;
+        }
     }
 
     let ctl_vars_per_table = CtlCheckVars::from_proofs(
         &all_proof.stark_proofs,
-        &cross_table_lookups,
+        &ola_stark.cross_table_lookups,
         &ctl_challenges,
-        &nums_permutation_zs,
+        &nums_aux_columns,
     );
 
-    verify_stark_proof_with_challenges(
-        cpu_stark,
-        &all_proof.stark_proofs[Table::Cpu as usize],
-        &stark_challenges[Table::Cpu as usize],
-        &ctl_vars_per_table[Table::Cpu as usize],
-        config,
-    )?;
-    verify_stark_proof_with_challenges(
-        memory_stark,
-        &all_proof.stark_proofs[Table::Memory as usize],
-        &stark_challenges[Table::Memory as usize],
-        &ctl_vars_per_table[Table::Memory as usize],
-        config,
-    )?;
-    verify_stark_proof_with_challenges(
-        bitwise_stark,
-        &all_proof.stark_proofs[Table::Bitwise as usize],
-        &stark_challenges[Table::Bitwise as usize],
-        &ctl_vars_per_table[Table::Bitwise as usize],
-        config,
-    )?;
-
-    verify_stark_proof_with_challenges(
-        cmp_stark,
-        &all_proof.stark_proofs[Table::Cmp as usize],
-        &stark_challenges[Table::Cmp as usize],
-        &ctl_vars_per_table[Table::Cmp as usize],
-        config,
-    )?;
-
-    verify_stark_proof_with_challenges(
-        rangecheck_stark,
-        &all_proof.stark_proofs[Table::RangeCheck as usize],
-        &stark_challenges[Table::RangeCheck as usize],
-        &ctl_vars_per_table[Table::RangeCheck as usize],
-        config,
-    )?;
-
-    verify_stark_proof_with_challenges(
-        poseidon_stark,
-        &all_proof.stark_proofs[Table::Poseidon as usize],
-        &stark_challenges[Table::Poseidon as usize],
-        &ctl_vars_per_table[Table::Poseidon as usize],
-        config,
-    )?;
-
-    verify_stark_proof_with_challenges(
-        poseidon_chunk_stark,
-        &all_proof.stark_proofs[Table::PoseidonChunk as usize],
-        &stark_challenges[Table::PoseidonChunk as usize],
-        &ctl_vars_per_table[Table::PoseidonChunk as usize],
-        config,
-    )?;
-
-    verify_stark_proof_with_challenges(
-        storage_access_stark,
-        &all_proof.stark_proofs[Table::StorageAccess as usize],
-        &stark_challenges[Table::StorageAccess as usize],
-        &ctl_vars_per_table[Table::StorageAccess as usize],
-        config,
-    )?;
-
-    verify_stark_proof_with_challenges(
-        tape_stark,
-        &all_proof.stark_proofs[Table::Tape as usize],
-        &stark_challenges[Table::Tape as usize],
-        &ctl_vars_per_table[Table::Tape as usize],
-        config,
-    )?;
-
-    verify_stark_proof_with_challenges(
-        sccall_stark,
-        &all_proof.stark_proofs[Table::SCCall as usize],
-        &stark_challenges[Table::SCCall as usize],
-        &ctl_vars_per_table[Table::SCCall as usize],
-        config,
-    )?;
-
-    verify_stark_proof_with_challenges(
-        program_stark,
-        &all_proof.stark_proofs[Table::Program as usize],
-        &stark_challenges[Table::Program as usize],
-        &ctl_vars_per_table[Table::Program as usize],
-        config,
-    )?;
+    for (i, stark) in ola_stark.starks().into_iter().enumerate() {
+        stark.verify(
+            &all_proof.stark_proofs[i],
+            &stark_challenges[i],
+            &ctl_vars_per_table[i],
+            verifying_key.preprocessed_cap(i, degree_bits[i])?,
+            degree_bits[i],
+            config,
+        )?;
+    }
 
-    verify_stark_proof_with_challenges(
-        prog_chunk_stark,
-        &all_proof.stark_proofs[Table::ProgChunk as usize],
-        &stark_challenges[Table::ProgChunk as usize],
-        &ctl_vars_per_table[Table::ProgChunk as usize],
-        config,
-    )?;
+    let tables = ola_stark.tables();
+    for (batch, fri_challenges) in all_proof
+        .batch_opening_proofs
+        .iter()
+        .zip(&batch_fri_challenges)
+    {
+        verify_batch_opening_proof(
+            &tables,
+            &all_proof.stark_proofs,
+            &stark_challenges,
+            batch,
+            fri_challenges,
+            all_proof.degree_bits[batch.tables[0]],
+            verifying_key,
+            config,
+        )?;
+    }
 
     // TODO:
     // let public_values = all_proof.public_values;
-    let extra_looking_products = vec![vec![F::ONE; config.num_challenges]; NUM_TABLES];
+    let extra_looking_products =
+        vec![vec![F::ONE; config.num_challenges]; all_proof.stark_proofs.len()];
     // extra_looking_products.push(Vec::new());
     // for c in 0..config.num_challenges {
     //     extra_looking_products[Table::StorageAccess as usize].push(
@@ -198,8 +157,12 @@ where
     // }
 
     verify_cross_table_lookups::<F, C, D>(
-        cross_table_lookups,
-        all_proof.stark_proofs.map(|p| p.openings.ctl_zs_last),
+        ola_stark.cross_table_lookups,
+        all_proof
+            .stark_proofs
+            .into_iter()
+            .map(|p| p.openings.ctl_zs_last)
+            .collect(),
         extra_looking_products,
         config,
     )
@@ -223,17 +186,19 @@ pub(crate) fn verify_stark_proof_with_challenges<
     S: Stark<F, D>,
     const D: usize,
 >(
-    stark: S,
+    stark: &S,
     proof: &StarkProof<F, C, D>,
     challenges: &StarkProofChallenges<F, D>,
     ctl_vars: &[CtlCheckVars<F, F::Extension, F::Extension, D>],
+    preprocessed_cap: Option<&MerkleCap<F, C::Hasher>>,
+    degree_bits: usize,
     config: &StarkConfig,
 ) -> Result<()>
 where
     [(); S::COLUMNS]:,
     [(); C::Hasher::HASH_SIZE]:,
 {
-    validate_proof_shape(&stark, proof, config, ctl_vars.len())?;
+    validate_proof_shape(stark, proof, config, degree_bits, ctl_vars.len())?;
     let StarkOpeningSet {
         local_values,
         next_values,
@@ -247,7 +212,6 @@ where
         next_values: &next_values.to_vec().try_into().unwrap(),
     };
 
-    let degree_bits = proof.recover_degree_bits(config);
     let (l_0, l_last) = eval_l_0_and_l_last(degree_bits, challenges.stark_zeta);
     let last = F::primitive_root_of_unity(degree_bits).inverse();
     let z_last = challenges.stark_zeta - last.into();
@@ -267,11 +231,19 @@ where
         next_zs: permutation_ctl_zs_next[..num_permutation_zs].to_vec(),
         permutation_challenge_sets: challenges.permutation_challenge_sets.clone().unwrap(),
     });
+    let lookup_columns =
+        num_permutation_zs..num_permutation_zs + stark.num_lookup_helper_columns(config);
+    let lookup_data = stark.uses_lookups().then(|| LookupCheckVars {
+        local_values: permutation_ctl_zs[lookup_columns.clone()].to_vec(),
+        next_values: permutation_ctl_zs_next[lookup_columns].to_vec(),
+        challenges: challenges.lookup_challenges.clone().unwrap(),
+    });
     eval_vanishing_poly::<F, F::Extension, F::Extension, C, S, D, D>(
-        &stark,
+        stark,
         config,
         vars,
         permutation_data,
+        lookup_data,
         ctl_vars,
         &mut consumer,
     );
@@ -281,44 +253,56 @@ where
     // quotient(x)`, at zeta.
     let zeta_pow_deg = challenges.stark_zeta.exp_power_of_2(degree_bits);
     let z_h_zeta = zeta_pow_deg - F::Extension::ONE;
-    // `quotient_polys_zeta` holds `num_challenges * quotient_degree_factor`
-    // evaluations. Each chunk of `quotient_degree_factor` holds the evaluations
-    // of `t_0(zeta),...,t_{quotient_degree_factor-1}(zeta)` where the "real"
-    // quotient polynomial is `t(X) = t_0(X) + t_1(X)*X^n + t_2(X)*X^{2n} + ...`.
+    // `quotient_polys_zeta` holds `num_challenges * num_quotient_chunks`
+    // evaluations. Each chunk of `num_quotient_chunks` holds the evaluations
+    // of `t_0(zeta),...,t_{num_quotient_chunks-1}(zeta)` where the "real"
+    // quotient polynomial is `t(X) = t_0(X) + t_1(X)*X^c + t_2(X)*X^{2c} + ...`,
+    // with `c` the chunk length, `n` unless in zero-knowledge mode.
     // So to reconstruct `t(zeta)` we can compute `reduce_with_powers(chunk,
-    // zeta^n)` for each `quotient_degree_factor`-sized chunk of the original
-    // evaluations.
-    for (i, chunk) in quotient_polys
-        .chunks(stark.quotient_degree_factor())
+    // zeta^c)` for each `num_quotient_chunks`-sized chunk of the original
+    // evaluations. In zero-knowledge mode, the evaluation of the FRI masking
+    // polynomial follows.
+    let num_quotient_chunks = stark.num_quotient_chunks(config, degree_bits);
+    let zeta_pow_chunk_len = challenges
+        .stark_zeta
+        .exp_u64(config.quotient_chunk_len(degree_bits) as u64);
+    for (i, chunk) in quotient_polys[..num_quotient_chunks * config.num_challenges]
+        .chunks(num_quotient_chunks)
         .enumerate()
     {
         ensure!(
-            vanishing_polys_zeta[i] == z_h_zeta * reduce_with_powers(chunk, zeta_pow_deg),
+            vanishing_polys_zeta[i] == z_h_zeta * reduce_with_powers(chunk, zeta_pow_chunk_len),
             "Mismatch between evaluation and opening of quotient polynomial in {}",
             type_name::<S>()
         );
     }
 
-    let merkle_caps = vec![
-        proof.trace_cap.clone(),
-        proof.permutation_ctl_zs_cap.clone(),
-        proof.quotient_polys_cap.clone(),
-    ];
-
-    verify_fri_proof::<F, C, D>(
-        &stark.fri_instance(
-            challenges.stark_zeta,
-            F::primitive_root_of_unity(degree_bits),
-            degree_bits,
-            ctl_zs_last.len(),
-            config,
-        ),
-        &proof.openings.to_fri_openings(),
-        &challenges.fri_challenges,
-        &merkle_caps,
-        &proof.opening_proof,
-        &config.fri_params(degree_bits),
-    )?;
+    // Openings proven in a batch are checked once all tables are verified.
+    if let (Some(opening_proof), Some(fri_challenges)) =
+        (&proof.opening_proof, &challenges.fri_challenges)
+    {
+        let mut merkle_caps = vec![
+            proof.trace_cap.clone(),
+            proof.permutation_ctl_zs_cap.clone(),
+            proof.quotient_polys_cap.clone(),
+        ];
+        merkle_caps.extend(preprocessed_cap.cloned());
+
+        verify_fri_proof::<F, C, D>(
+            &stark.fri_instance(
+                challenges.stark_zeta,
+                F::primitive_root_of_unity(degree_bits),
+                degree_bits,
+                ctl_zs_last.len(),
+                config,
+            ),
+            &proof.openings.to_fri_openings(),
+            fri_challenges,
+            &merkle_caps,
+            opening_proof,
+            &config.fri_params(degree_bits),
+        )?;
+    }
 
     Ok(())
 }
@@ -327,6 +311,7 @@ fn validate_proof_shape<F, C, S, const D: usize>(
     stark: &S,
     proof: &StarkProof<F, C, D>,
     config: &StarkConfig,
+    degree_bits: usize,
     num_ctl_zs: usize,
 ) -> anyhow::Result<()>
 where
@@ -355,10 +340,11 @@ where
         quotient_polys,
     } = openings;
 
-    let degree_bits = proof.recover_degree_bits(config);
     let fri_params = config.fri_params(degree_bits);
     let cap_height = fri_params.config.cap_height;
-    let num_zs = num_ctl_zs + stark.num_permutation_batches(config);
+    let num_zs = num_ctl_zs
+        + stark.num_permutation_batches(config)
+        + stark.num_lookup_helper_columns(config);
 
     ensure!(trace_cap.height() == cap_height);
     ensure!(permutation_ctl_zs_cap.height() == cap_height);
@@ -369,7 +355,7 @@ where
     ensure!(permutation_ctl_zs.len() == num_zs);
     ensure!(permutation_ctl_zs_next.len() == num_zs);
     ensure!(ctl_zs_last.len() == num_ctl_zs);
-    ensure!(quotient_polys.len() == stark.num_quotient_polys(config));
+    ensure!(quotient_polys.len() == stark.num_quotient_polys(config, degree_bits));
 
     Ok(())
 }
@@ -0,0 +1,316 @@
+//! Commitments to the preprocessed columns of the tables. Their values only
+//! depend on the height of a table, so they are committed once per height
+//! into the keys, and the verifier checks their openings against the
+//! verifying key rather than against caps the proof carries.
+//!
+//! Nothing in a proof ties it to honest preprocessed columns but the
+//! verifying key, so the verifier must derive its key itself, with
+//! `OlaVerifyingKey::new`, instead of accepting one from the prover.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use anyhow::{anyhow, ensure, Result};
+use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::fri::oracle::PolynomialBatch;
+use plonky2::hash::hash_types::RichField;
+use plonky2::hash::merkle_tree::MerkleCap;
+use plonky2::iop::challenger::Challenger;
+use plonky2::plonk::config::{GenericConfig, GenericHashOut, Hasher};
+use plonky2::timed;
+use plonky2::util::timing::TimingTree;
+use plonky2_util::log2_strict;
+use serde::{Deserialize, Serialize};
+
+use super::config::StarkConfig;
+use super::ola_stark::{OlaStark, TableRegistry};
+use super::prover::commit_coeffs;
+
+/// Version of the verifying key, bumped whenever what its digest covers
+/// changes.
+pub const VERIFYING_KEY_VERSION: u32 = 1;
+
+/// Caps of the preprocessed columns of a table, by degree bits.
+pub type PreprocessedCaps<F, H> = BTreeMap<usize, MerkleCap<F, H>>;
+
+/// Identifies the circuit a proof is for and holds the caps of the
+/// preprocessed columns of its tables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct OlaVerifyingKey<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+{
+    pub version: u32,
+    /// Digest of the constraints of the tables and of the cross-table
+    /// lookups, see `circuit_digest`.
+    pub circuit_digest: <C::Hasher as Hasher<F>>::Hash,
+    /// Caps of the preprocessed columns of each table. `None` for tables
+    /// without preprocessed columns.
+    pub preprocessed_caps: Vec<Option<PreprocessedCaps<F, C::Hasher>>>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    OlaVerifyingKey<F, C, D>
+{
+    /// Recomputes the key of `config` for tables of at most
+    /// `2^max_degree_bits` rows, the key a verifier can trust.
+    pub fn new(config: &StarkConfig, max_degree_bits: usize, timing: &mut TimingTree) -> Self
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+        OlaStark<F, D>: TableRegistry<F, D>,
+    {
+        OlaProvingKey::new(config, max_degree_bits, timing).verifying_key
+    }
+
+    /// The circuit identifier: a digest of the version, the circuit digest
+    /// and the preprocessed caps. Proofs observe it before any challenge.
+    pub fn digest(&self) -> <C::Hasher as Hasher<F>>::Hash {
+        let mut elements = vec![F::from_canonical_u32(self.version)];
+        elements.extend(self.circuit_digest.to_vec());
+        for (table, caps) in self.preprocessed_caps.iter().enumerate() {
+            for (&degree_bits, cap) in caps.iter().flatten() {
+                elements.push(F::from_canonical_usize(table));
+                elements.push(F::from_canonical_usize(degree_bits));
+                elements.extend(cap.flatten());
+            }
+        }
+        C::Hasher::hash_no_pad(&elements)
+    }
+
+    /// The cap of the preprocessed columns of `table` at `2^degree_bits`
+    /// rows, `None` if the table has no preprocessed columns.
+    pub(crate) fn preprocessed_cap(
+        &self,
+        table: usize,
+        degree_bits: usize,
+    ) -> Result<Option<&MerkleCap<F, C::Hasher>>> {
+        match &self.preprocessed_caps[table] {
+            None => Ok(None),
+            Some(caps) => caps.get(&degree_bits).map(Some).ok_or_else(|| {
+                anyhow!(
+                    "The key has no preprocessed columns for table {} with 2^{} rows.",
+                    table,
+                    degree_bits
+                )
+            }),
+        }
+    }
+}
+
+/// The commitments to the preprocessed columns the prover opens, along
+/// with the verifying key made of their caps. Built once per config and
+/// maximal height, then saved and loaded for each proof.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct OlaProvingKey<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    pub verifying_key: OlaVerifyingKey<F, C, D>,
+    max_degree_bits: usize,
+    preprocessed_commitments: Vec<Option<BTreeMap<usize, PolynomialBatch<F, C, D>>>>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    OlaProvingKey<F, C, D>
+{
+    /// Commits the preprocessed columns of every table, for each height of
+    /// at most `2^max_degree_bits` rows the table can have.
+    pub fn new(config: &StarkConfig, max_degree_bits: usize, timing: &mut TimingTree) -> Self
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+        OlaStark<F, D>: TableRegistry<F, D>,
+    {
+        let ola_stark = OlaStark::<F, D>::default();
+        let mut twiddle_map = BTreeMap::new();
+
+        let mut preprocessed_commitments = vec![];
+        for table in ola_stark.tables() {
+            if table.preprocessed_columns().is_empty() {
+                preprocessed_commitments.push(None);
+                continue;
+            }
+            let mut commitments = BTreeMap::new();
+            for degree_bits in 0..=max_degree_bits {
+                let values = match table.preprocessed_values(degree_bits) {
+                    Some(values) => values,
+                    None => continue,
+                };
+                let commitment = timed!(
+                    timing,
+                    &format!(
+                        "commit preprocessed columns of {:?} with 2^{} rows",
+                        table.table(),
+                        degree_bits
+                    ),
+                    // Opened along with polynomials of `2^blinding_bits` times
+                    // their degree, so committed over as large a domain.
+                    commit_coeffs::<F, C, D>(
+                        values.into_iter().map(|v| v.ifft()).collect(),
+                        config.fri_config.rate_bits + config.blinding_bits(degree_bits),
+                        false,
+                        config.fri_config.cap_height,
+                        timing,
+                        &mut twiddle_map,
+                    )
+                );
+                commitments.insert(degree_bits, commitment);
+            }
+            preprocessed_commitments.push(Some(commitments));
+        }
+
+        let preprocessed_caps = preprocessed_commitments
+            .iter()
+            .map(|commitments| {
+                commitments.as_ref().map(|commitments| {
+                    commitments
+                        .iter()
+                        .map(|(&degree_bits, commitment)| {
+                            (degree_bits, commitment.merkle_tree.cap.clone())
+                        })
+                        .collect()
+                })
+            })
+            .collect();
+
+        Self {
+            verifying_key: OlaVerifyingKey {
+                version: VERIFYING_KEY_VERSION,
+                circuit_digest: circuit_digest::<F, C, D>(config),
+                preprocessed_caps,
+            },
+            max_degree_bits,
+            preprocessed_commitments,
+        }
+    }
+
+    /// Writes the key to `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = File::create(path)?;
+        bincode::serialize_into(BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    /// Reads a key written by `save`, checking it was built for `config`.
+    pub fn load<P: AsRef<Path>>(path: P, config: &StarkConfig) -> Result<Self>
+    where
+        OlaStark<F, D>: TableRegistry<F, D>,
+    {
+        let file = File::open(path)?;
+        let key: Self = bincode::deserialize_from(BufReader::new(file))?;
+        ensure!(
+            key.verifying_key.version == VERIFYING_KEY_VERSION,
+            "Unsupported proving key version {}, expected {}.",
+            key.verifying_key.version,
+            VERIFYING_KEY_VERSION
+        );
+        ensure!(
+            key.verifying_key.circuit_digest == circuit_digest::<F, C, D>(config),
+            "The proving key is not for this circuit."
+        );
+        let cap_len = 1 << config.fri_config.cap_height;
+        ensure!(
+            key.preprocessed_commitments.iter().flatten().flatten().all(
+                |(&degree_bits, commitment)| {
+                    commitment.rate_bits
+                        == config.fri_config.rate_bits + config.blinding_bits(degree_bits)
+                        && commitment.merkle_tree.cap.len() == cap_len
+                }
+            ),
+            "The proving key is not committed with the FRI parameters of the config."
+        );
+        Ok(key)
+    }
+
+    /// Tables of at most `2^max_degree_bits` rows can be proven with the key.
+    pub fn max_degree_bits(&self) -> usize {
+        self.max_degree_bits
+    }
+
+    /// The commitment to the preprocessed columns of `table` at
+    /// `2^degree_bits` rows, `None` if the table has no preprocessed columns.
+    pub(crate) fn preprocessed_commitment(
+        &self,
+        table: usize,
+        degree_bits: usize,
+    ) -> Result<Option<&PolynomialBatch<F, C, D>>> {
+        match &self.preprocessed_commitments[table] {
+            None => Ok(None),
+            Some(commitments) => commitments.get(&degree_bits).map(Some).ok_or_else(|| {
+                anyhow!(
+                    "The key has no preprocessed columns for table {} with 2^{} rows.",
+                    table,
+                    degree_bits
+                )
+            }),
+        }
+    }
+}
+
+/// The degree bits of the tallest of `traces`, the height a proving key
+/// must cover to prove them.
+pub fn max_degree_bits<F: Field>(traces: &[Vec<PolynomialValues<F>>]) -> usize {
+    traces
+        .iter()
+        .map(|trace| log2_strict(trace[0].len()))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Digest of the constraints and cross-table lookups of `OlaStark`. The
+/// constraints of each table are fingerprinted by their evaluation at a
+/// pseudo-random pair of rows, where distinct constraint polynomials agree
+/// with negligible probability.
+pub fn circuit_digest<F, C, const D: usize>(config: &StarkConfig) -> <C::Hasher as Hasher<F>>::Hash
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    OlaStark<F, D>: TableRegistry<F, D>,
+{
+    let mut ola_stark = OlaStark::<F, D>::default();
+    // Compress challenges are drawn from the traces, any fixed value will do
+    // for the fingerprint.
+    for table in ola_stark.tables_mut() {
+        table
+            .set_compress_challenge(F::TWO)
+            .expect("A fresh table has no compress challenge.");
+    }
+
+    let mut challenger = Challenger::<F, C::Hasher>::new();
+    let mut elements = vec![];
+    for (index, table) in ola_stark.tables().into_iter().enumerate() {
+        let num_columns = table.num_columns();
+        challenger.observe_element(F::from_canonical_usize(index));
+        let local_values = challenger.get_n_challenges(num_columns);
+        let next_values = challenger.get_n_challenges(num_columns);
+        let alphas = challenger.get_n_challenges(config.num_challenges);
+
+        elements.push(F::from_canonical_usize(num_columns));
+        elements.push(F::from_canonical_usize(
+            table.num_permutation_batches(config),
+        ));
+        elements.push(F::from_canonical_usize(
+            table.num_lookup_helper_columns(config),
+        ));
+        for lookup in table.lookups() {
+            elements.extend(
+                lookup
+                    .columns
+                    .iter()
+                    .chain([&lookup.table_column, &lookup.frequencies_column])
+                    .map(|&col| F::from_canonical_usize(col)),
+            );
+        }
+        elements.extend(
+            table
+                .preprocessed_columns()
+                .into_iter()
+                .map(F::from_canonical_usize),
+        );
+        elements.extend(table.eval_constraints(&local_values, &next_values, alphas));
+    }
+    for ctl in &ola_stark.cross_table_lookups {
+        elements.extend(ctl.flatten());
+    }
+    C::Hasher::hash_no_pad(&elements)
+}
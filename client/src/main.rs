@@ -1,13 +1,14 @@
 extern crate clap;
 
 use assembler::encoder::encode_asm_from_json_file;
-use circuits::generation::GenerationInputs;
-use circuits::stark::config::StarkConfig;
+use circuits::generation::{generate_traces, GenerationInputs};
+use circuits::stark::config::SecurityPreset;
 use circuits::stark::ola_stark::OlaStark;
-use circuits::stark::prover::prove;
+use circuits::stark::prover::prove_with_traces;
 use circuits::stark::serialization::Buffer;
 use circuits::stark::verifier::verify_proof;
-use clap::{arg, Command};
+use circuits::stark::verifying_key::{OlaProvingKey, OlaVerifyingKey};
+use clap::{arg, ArgAction, Command};
 use core::program::binary_program::BinaryProgram;
 use core::program::Program;
 use core::state::state_storage::StateStorage;
@@ -57,19 +58,43 @@ fn main() {
                 ])
                 .arg_required_else_help(true),
         )
+        .subcommand(
+            Command::new("setup")
+                .about("generate the proving key of a security preset")
+                .args(&[
+                    arg!(-o --output <ProvingKey> "Must set a file for save the proving key"),
+                    arg!(-d --"degree-bits" <DegreeBits> "Must set the log2 of the most rows a table of the traces to prove can have")
+                        .value_parser(clap::value_parser!(usize)),
+                    arg!(-p --preset [Preset] "Security preset, the 128-bit presets need a larger extension field than the prover supports")
+                        .value_parser(SecurityPreset::PROVABLE.map(SecurityPreset::name))
+                        .default_value("100-bit"),
+                ])
+                .arg_required_else_help(true),
+        )
         .subcommand(
             Command::new("prove")
                 .about("generate proof from executed program")
                 .args(&[
                     arg!(-i --input <Trace> "Must set a trace file generated by OlaVM executor"),
                     arg!(-o --output <Proof> "Must set a file for save proofs"),
+                    arg!(-k --pk <ProvingKey> "Must set a proving key file generated by OlaVM setup"),
+                    arg!(-p --preset [Preset] "Security preset the proving key was generated with")
+                        .value_parser(SecurityPreset::PROVABLE.map(SecurityPreset::name))
+                        .default_value("100-bit"),
+                    arg!(--"low-memory" "Keep one table's commitments in memory at a time, at the cost of proving time")
+                        .action(ArgAction::SetTrue),
                 ])
                 .arg_required_else_help(true),
         )
         .subcommand(
             Command::new("verify")
                 .about("verifiy generated proof")
-                .args(&[arg!(-i --input <Trace> "Must set a proof file generated by OlaVM prover")])
+                .args(&[
+                    arg!(-i --input <Trace> "Must set a proof file generated by OlaVM prover"),
+                    arg!(-p --preset [Preset] "Security preset the proof was generated with")
+                        .value_parser(SecurityPreset::PROVABLE.map(SecurityPreset::name))
+                        .default_value("100-bit"),
+                ])
                 .arg_required_else_help(true),
         )
         .get_matches();
@@ -171,6 +196,33 @@ fn main() {
 
             println!("Run done!");
         }
+        Some(("setup", sub_matches)) => {
+            let preset = sub_matches.get_one::<String>("preset").expect("default");
+            let config = match preset.parse::<SecurityPreset>() {
+                Ok(preset) => preset.config(),
+                Err(error) => {
+                    println!("{error}");
+                    return;
+                }
+            };
+            let degree_bits = *sub_matches
+                .get_one::<usize>("degree-bits")
+                .expect("required");
+            if let Err(error) = config.validate::<F, D>(degree_bits) {
+                println!("{error}");
+                return;
+            }
+            let proving_key =
+                OlaProvingKey::<F, C, D>::new(&config, degree_bits, &mut TimingTree::default());
+
+            let path = sub_matches.get_one::<String>("output").expect("required");
+            println!("Output proving key file path: {}", path);
+            if let Err(error) = proving_key.save(path) {
+                println!("Save proving key failed due to: {error}");
+                return;
+            }
+            println!("Setup done!");
+        }
         Some(("prove", sub_matches)) => {
             let path = sub_matches.get_one::<String>("input").expect("required");
             println!("Input trace file path: {}", path);
@@ -191,15 +243,40 @@ fn main() {
             let inputs = GenerationInputs::default();
 
             let mut ola_stark = OlaStark::<F, D>::default();
-            let config = StarkConfig::standard_fast_config();
-            let proof = prove::<F, C, D>(
-                program,
-                &mut ola_stark,
-                inputs,
+            let preset = sub_matches.get_one::<String>("preset").expect("default");
+            let mut config = match preset.parse::<SecurityPreset>() {
+                Ok(preset) => preset.config(),
+                Err(error) => {
+                    println!("{error}");
+                    return;
+                }
+            };
+            config.low_memory = sub_matches.get_flag("low-memory");
+            let path = sub_matches.get_one::<String>("pk").expect("required");
+            println!("Proving key file path: {}", path);
+            let proving_key = match OlaProvingKey::<F, C, D>::load(path, &config) {
+                Ok(proving_key) => proving_key,
+                Err(error) => {
+                    println!("Load proving key failed due to: {error}");
+                    return;
+                }
+            };
+            let (traces, public_values) = generate_traces(program, &mut ola_stark, inputs);
+            let proof = prove_with_traces::<F, C, D>(
+                &ola_stark,
+                &proving_key,
                 &config,
+                traces,
+                public_values,
                 &mut TimingTree::default(),
-            )
-            .unwrap();
+            );
+            let proof = match proof {
+                Ok(proof) => proof,
+                Err(error) => {
+                    println!("Prove failed due to: {error}");
+                    return;
+                }
+            };
 
             let path = sub_matches.get_one::<String>("output").expect("required");
             println!("Output proof file path: {}", path);
@@ -231,8 +308,24 @@ fn main() {
             let de_proof = de_proof.unwrap();
 
             let ola_stark = OlaStark::<F, D>::default();
-            let config = StarkConfig::standard_fast_config();
-            match verify_proof(ola_stark, de_proof, &config) {
+            let preset = sub_matches.get_one::<String>("preset").expect("default");
+            let config = match preset.parse::<SecurityPreset>() {
+                Ok(preset) => preset.config(),
+                Err(error) => {
+                    println!("{error}");
+                    return;
+                }
+            };
+            // The key is derived here rather than read from the prover, who
+            // could otherwise pick the preprocessed columns.
+            let degree_bits = de_proof.degree_bits.iter().copied().max().unwrap_or(0);
+            if let Err(error) = config.validate::<F, D>(degree_bits) {
+                println!("Verify failed due to: {error}");
+                return;
+            }
+            let verifying_key =
+                OlaVerifyingKey::<F, C, D>::new(&config, degree_bits, &mut TimingTree::default());
+            match verify_proof(ola_stark, &verifying_key, de_proof, &config) {
                 Err(error) => println!("Verify failed due to: {error}"),
                 _ => println!("Verify succeed!"),
             }
@@ -0,0 +1,342 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::Write;
+
+use super::binary_program::BinaryInstruction;
+use super::compact::CompactProgram;
+use super::decoder::{
+    decode_compact_program_to_instructions, disassemble_instruction, immediate_target,
+};
+use super::FIELD_ORDER;
+use crate::vm::hardware::OlaRegister;
+use crate::vm::opcodes::OlaOpcode;
+use crate::vm::operands::{ImmediateValue, OlaOperand};
+
+/// Straight-line run of instructions, entered only at `start` and left only
+/// after its last instruction. `call` does not end a block because control
+/// comes back to the next instruction.
+#[derive(Debug, Clone)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub end: usize,
+    pub instructions: Vec<(usize, BinaryInstruction)>,
+    pub successors: Vec<usize>,
+    pub calls: Vec<usize>,
+    pub has_indirect_jump: bool,
+}
+
+impl BasicBlock {
+    pub fn terminator(&self) -> Option<&(usize, BinaryInstruction)> {
+        self.instructions.last()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameIssue {
+    /// `ret` or `end` reached with r9 moved by `delta` since function entry.
+    UnbalancedExit { pc: usize, delta: i64 },
+    /// A block is entered with different r9 offsets along different paths.
+    InconsistentEntry {
+        block: usize,
+        first: i64,
+        second: i64,
+    },
+    /// r9 written by something other than `add r9 r9 <imm>`.
+    UnknownUpdate { pc: usize },
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub entry: usize,
+    pub name: String,
+    pub blocks: BTreeSet<usize>,
+    pub frame_size: Option<u64>,
+    pub frame_issues: Vec<FrameIssue>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProgramAnalysis {
+    pub blocks: BTreeMap<usize, BasicBlock>,
+    pub functions: BTreeMap<usize, Function>,
+    pub unreachable: BTreeSet<usize>,
+}
+
+impl ProgramAnalysis {
+    pub fn from_compact_program(program: &CompactProgram) -> Result<Self, String> {
+        let instructions = decode_compact_program_to_instructions(program)?;
+        Ok(Self::from_instructions(&instructions))
+    }
+
+    pub fn from_instructions(instructions: &[BinaryInstruction]) -> Self {
+        let mut pcs: Vec<usize> = vec![];
+        let mut pc: usize = 0;
+        for instruction in instructions {
+            pcs.push(pc);
+            pc += instruction.binary_length() as usize;
+        }
+        let boundaries: BTreeSet<usize> = pcs.iter().cloned().collect();
+
+        let mut leaders: BTreeSet<usize> = BTreeSet::new();
+        let mut entries: BTreeSet<usize> = BTreeSet::new();
+        if !pcs.is_empty() {
+            leaders.insert(0);
+            entries.insert(0);
+        }
+        for (pc, instruction) in pcs.iter().zip(instructions.iter()) {
+            let next = pc + instruction.binary_length() as usize;
+            let target = immediate_target(instruction).filter(|t| boundaries.contains(t));
+            match instruction.opcode {
+                OlaOpcode::CALL => {
+                    if let Some(target) = target {
+                        leaders.insert(target);
+                        entries.insert(target);
+                    }
+                }
+                OlaOpcode::JMP | OlaOpcode::CJMP => {
+                    leaders.extend(target);
+                    leaders.insert(next);
+                }
+                OlaOpcode::RET | OlaOpcode::END => {
+                    leaders.insert(next);
+                }
+                _ => {}
+            }
+        }
+        leaders.retain(|leader| boundaries.contains(leader));
+
+        let mut blocks: BTreeMap<usize, BasicBlock> = BTreeMap::new();
+        let mut current: Option<BasicBlock> = None;
+        for (pc, instruction) in pcs.iter().zip(instructions.iter()) {
+            if leaders.contains(pc) {
+                if let Some(block) = current.take() {
+                    blocks.insert(block.start, block);
+                }
+                current = Some(BasicBlock {
+                    start: *pc,
+                    end: *pc,
+                    instructions: vec![],
+                    successors: vec![],
+                    calls: vec![],
+                    has_indirect_jump: false,
+                });
+            }
+            if let Some(block) = current.as_mut() {
+                block.end = pc + instruction.binary_length() as usize;
+                block.instructions.push((*pc, instruction.clone()));
+            }
+        }
+        if let Some(block) = current.take() {
+            blocks.insert(block.start, block);
+        }
+
+        for block in blocks.values_mut() {
+            for (_, instruction) in &block.instructions {
+                if instruction.opcode == OlaOpcode::CALL {
+                    block
+                        .calls
+                        .extend(immediate_target(instruction).filter(|t| boundaries.contains(t)));
+                }
+            }
+            let (_, last) = block.instructions.last().unwrap();
+            let target = immediate_target(last).filter(|t| boundaries.contains(t));
+            let fallthrough = Some(block.end).filter(|next| boundaries.contains(next));
+            match last.opcode {
+                OlaOpcode::RET | OlaOpcode::END => {}
+                OlaOpcode::JMP => match target {
+                    Some(target) => block.successors.push(target),
+                    None => block.has_indirect_jump = true,
+                },
+                OlaOpcode::CJMP => {
+                    match target {
+                        Some(target) => block.successors.push(target),
+                        None => block.has_indirect_jump = true,
+                    }
+                    block.successors.extend(fallthrough);
+                }
+                _ => block.successors.extend(fallthrough),
+            }
+            block.successors.dedup();
+        }
+
+        let mut functions: BTreeMap<usize, Function> = BTreeMap::new();
+        let mut reached: BTreeSet<usize> = BTreeSet::new();
+        for entry in entries {
+            let function_blocks = reachable_blocks(&blocks, entry);
+            reached.extend(function_blocks.iter().cloned());
+            let name = if entry == 0 {
+                "main".to_string()
+            } else {
+                format!("func_{}", entry)
+            };
+            let (frame_size, frame_issues) = analyze_frame(&blocks, entry);
+            functions.insert(
+                entry,
+                Function {
+                    entry,
+                    name,
+                    blocks: function_blocks,
+                    frame_size,
+                    frame_issues,
+                },
+            );
+        }
+        let unreachable = blocks
+            .keys()
+            .filter(|start| !reached.contains(start))
+            .cloned()
+            .collect();
+
+        Self {
+            blocks,
+            functions,
+            unreachable,
+        }
+    }
+
+    /// Graphviz DOT of one function's CFG, `None` if `entry` is not a
+    /// function entry.
+    pub fn function_to_dot(&self, entry: usize) -> Option<String> {
+        let function = self.functions.get(&entry)?;
+        let mut dot = String::new();
+        writeln!(dot, "digraph \"{}\" {{", function.name).unwrap();
+        writeln!(dot, "  node [shape=box, fontname=\"monospace\"];").unwrap();
+        for start in &function.blocks {
+            let block = &self.blocks[start];
+            let mut label = String::new();
+            for (pc, instruction) in &block.instructions {
+                let asm = disassemble_instruction(instruction, None)
+                    .unwrap_or_else(|_| instruction.opcode.token());
+                write!(label, "{}: {}\\l", pc, dot_escape(&asm)).unwrap();
+            }
+            for callee in &block.calls {
+                let name = self
+                    .functions
+                    .get(callee)
+                    .map_or(format!("func_{}", callee), |f| f.name.clone());
+                write!(label, "; calls {}\\l", name).unwrap();
+            }
+            writeln!(dot, "  bb{} [label=\"{}\"];", start, label).unwrap();
+        }
+        for start in &function.blocks {
+            for successor in &self.blocks[start].successors {
+                writeln!(dot, "  bb{} -> bb{};", start, successor).unwrap();
+            }
+        }
+        writeln!(dot, "}}").unwrap();
+        Some(dot)
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn reachable_blocks(blocks: &BTreeMap<usize, BasicBlock>, entry: usize) -> BTreeSet<usize> {
+    let mut visited: BTreeSet<usize> = BTreeSet::new();
+    let mut queue: VecDeque<usize> = VecDeque::from([entry]);
+    while let Some(start) = queue.pop_front() {
+        if !visited.insert(start) {
+            continue;
+        }
+        if let Some(block) = blocks.get(&start) {
+            queue.extend(block.successors.iter().cloned());
+        }
+    }
+    visited.retain(|start| blocks.contains_key(start));
+    visited
+}
+
+// Tracks how far r9 has moved since function entry along every path. A
+// function frame is opened by `add r9 r9 N` and closed by `add r9 r9 -N`.
+fn analyze_frame(
+    blocks: &BTreeMap<usize, BasicBlock>,
+    entry: usize,
+) -> (Option<u64>, Vec<FrameIssue>) {
+    let frame_size = blocks
+        .get(&entry)
+        .and_then(|block| block.instructions.first())
+        .and_then(|(_, instruction)| frame_adjustment(instruction))
+        .and_then(|delta| delta.ok())
+        .filter(|delta| *delta > 0)
+        .map(|delta| delta as u64);
+
+    let mut issues: Vec<FrameIssue> = vec![];
+    let mut entry_deltas: BTreeMap<usize, i64> = BTreeMap::new();
+    let mut queue: VecDeque<usize> = VecDeque::from([entry]);
+    entry_deltas.insert(entry, 0);
+    let mut visited: BTreeSet<usize> = BTreeSet::new();
+    while let Some(start) = queue.pop_front() {
+        if !visited.insert(start) {
+            continue;
+        }
+        let block = match blocks.get(&start) {
+            Some(block) => block,
+            None => continue,
+        };
+        let mut delta = Some(entry_deltas[&start]);
+        for (pc, instruction) in &block.instructions {
+            match frame_adjustment(instruction) {
+                Some(Ok(adjust)) => delta = delta.map(|d| d + adjust),
+                Some(Err(())) => {
+                    issues.push(FrameIssue::UnknownUpdate { pc: *pc });
+                    delta = None;
+                }
+                None => {}
+            }
+            if let (Some(d), OlaOpcode::RET | OlaOpcode::END) = (delta, instruction.opcode) {
+                if d != 0 {
+                    issues.push(FrameIssue::UnbalancedExit { pc: *pc, delta: d });
+                }
+            }
+        }
+        let delta = match delta {
+            Some(delta) => delta,
+            None => continue,
+        };
+        for successor in &block.successors {
+            match entry_deltas.get(successor) {
+                Some(first) if *first != delta => {
+                    issues.push(FrameIssue::InconsistentEntry {
+                        block: *successor,
+                        first: *first,
+                        second: delta,
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    entry_deltas.insert(*successor, delta);
+                    queue.push_back(*successor);
+                }
+            }
+        }
+    }
+    (frame_size, issues)
+}
+
+// `Some(Ok(n))` for `add r9 r9 n`, `Some(Err(()))` for any other write to
+// r9, `None` when r9 is untouched.
+fn frame_adjustment(instruction: &BinaryInstruction) -> Option<Result<i64, ()>> {
+    let r9 = OlaOperand::RegisterOperand {
+        register: OlaRegister::R9,
+    };
+    // mstore carries the stored register in dst
+    if instruction.opcode == OlaOpcode::MSTORE || instruction.dst.as_ref() != Some(&r9) {
+        return None;
+    }
+    if instruction.opcode != OlaOpcode::ADD || instruction.op0.as_ref() != Some(&r9) {
+        return Some(Err(()));
+    }
+    match &instruction.op1 {
+        Some(OlaOperand::ImmediateOperand { value }) => Some(signed_immediate(value).ok_or(())),
+        _ => Some(Err(())),
+    }
+}
+
+// Immediates above half the field order are negative offsets, e.g. the `-4`
+// of `add r9 r9 -4`.
+pub(crate) fn signed_immediate(value: &ImmediateValue) -> Option<i64> {
+    match value.to_u64() {
+        Ok(v) if v > FIELD_ORDER / 2 => Some(-((FIELD_ORDER - v) as i64)),
+        Ok(v) => Some(v as i64),
+        Err(_) => None,
+    }
+}
@@ -236,7 +236,36 @@ impl BinaryInstruction {
                     instruction_binary
                 )
             })?;
+        let imm_u64 = match binary_code.get(1) {
+            Some(imm_line) => Some(
+                ImmediateValue::from_str(imm_line.as_str())?
+                    .to_u64()
+                    .map_err(|_| {
+                        format!(
+                            "decode binary instruction error, invalid immediate: {}",
+                            imm_line
+                        )
+                    })?,
+            ),
+            None => None,
+        };
+        if binary_code.len() > 2 {
+            return Err(format!(
+                "decode binary instruction error, input code length is {}: {}",
+                binary_code.len(),
+                instruction_binary
+            ));
+        }
+        Self::decode_words(instruction_u64, imm_u64, prophet)
+    }
 
+    /// Decodes an instruction word and its optional immediate word.
+    pub fn decode_words(
+        instruction_u64: u64,
+        imm_u64: Option<u64>,
+        prophet: Option<OlaProphet>,
+    ) -> Result<Self, String> {
+        let instruction_binary = format!("0x{:0>16x}", instruction_u64);
         let opcode = all::<OlaOpcode>()
             .collect::<Vec<_>>()
             .iter()
@@ -259,18 +288,13 @@ impl BinaryInstruction {
             } else {
                 1
             };
-        if binary_code.len() != instruction_length {
-            return Err(format!("decode binary instruction error, length should be {}, but input code length is {}: {}", instruction_length, binary_code.len(), instruction_binary));
+        let code_length = if imm_u64.is_some() { 2 } else { 1 };
+        if code_length != instruction_length {
+            return Err(format!("decode binary instruction error, length should be {}, but input code length is {}: {}", instruction_length, code_length, instruction_binary));
         }
-        let immediate_value = if instruction_length == 2 {
-            let imm_line = binary_code
-                .get(1)
-                .ok_or("Binary code has only 1 string")?
-                .clone();
-            let imm = ImmediateValue::from_str(imm_line.as_str())?;
-            Some(imm)
-        } else {
-            None
+        let immediate_value = match imm_u64 {
+            Some(imm) => Some(ImmediateValue::from_u64(imm)?),
+            None => None,
         };
 
         if opcode == OlaOpcode::MSTORE || opcode == OlaOpcode::MLOAD {
@@ -512,7 +536,7 @@ impl Display for BinaryInstruction {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OlaProphet {
     pub host: usize,
     pub code: String,
@@ -522,7 +546,17 @@ pub struct OlaProphet {
     pub outputs: Vec<OlaProphetOutput>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Prophet code of the form `native:<id>` runs the Rust prophet registered
+/// under `<id>` instead of interpreting a `%{ ... %}` body.
+pub const NATIVE_PROPHET_PREFIX: &str = "native:";
+
+impl OlaProphet {
+    pub fn native_id(&self) -> Option<&str> {
+        self.code.strip_prefix(NATIVE_PROPHET_PREFIX)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OlaProphetInput {
     pub name: String,
     pub length: usize,
@@ -530,7 +564,7 @@ pub struct OlaProphetInput {
     pub is_input_output: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OlaProphetOutput {
     pub name: String,
     pub length: usize,
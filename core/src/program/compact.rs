@@ -0,0 +1,311 @@
+use anyhow::{anyhow, bail};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+
+use super::binary_program::{BinaryProgram, OlaProphet, OlaProphetInput, OlaProphetOutput};
+use super::decoder::instruction_length_from_u64;
+use crate::crypto::poseidon_trace::calculate_arbitrary_poseidon_u64s;
+use crate::util::converts::u64s_to_bytes;
+
+pub const COMPACT_PROGRAM_MAGIC: [u8; 4] = *b"OLAP";
+pub const COMPACT_PROGRAM_VERSION: u16 = 1;
+
+const FLAG_DEBUG_SECTION: u16 = 1;
+const HASH_LEN: usize = 32;
+
+/// Binary container of a program, the storage and execution form of
+/// [`BinaryProgram`].
+///
+/// Layout, all integers little-endian:
+/// `magic | version: u16 | flags: u16 | word count: u32 | words: u64* |
+/// prophet table | debug section (if flagged) | program hash: [u8; 32]`.
+///
+/// The program hash is the poseidon hash of the container without its debug
+/// section, so stripping debug info never changes the identity of a program.
+/// Bytes are packed 7 per field element so every chunk is canonical.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactProgram {
+    pub words: Vec<u64>,
+    pub prophets: Vec<OlaProphet>,
+    pub debug_info: Option<BTreeMap<usize, String>>,
+}
+
+impl CompactProgram {
+    pub fn from_binary_program(program: &BinaryProgram) -> anyhow::Result<Self> {
+        let mut prophets = program.prophets.clone();
+        prophets.sort_by_key(|prophet| prophet.host);
+        Ok(Self {
+            words: program.bytecode_u64s()?,
+            prophets,
+            debug_info: program.debug_info.clone(),
+        })
+    }
+
+    /// Converts back into the JSON form, formatting instruction words as
+    /// zero padded hex and immediates as plain hex, like the assembler does.
+    /// Fails if the last instruction is missing its immediate word.
+    pub fn to_binary_program(&self) -> anyhow::Result<BinaryProgram> {
+        let mut lines: Vec<String> = Vec::with_capacity(self.words.len());
+        let mut iter = self.words.iter().enumerate();
+        while let Some((pc, word)) = iter.next() {
+            lines.push(format!("0x{:0>16x}", word));
+            if instruction_length_from_u64(*word) == 2 {
+                match iter.next() {
+                    Some((_, imm)) => lines.push(format!("{:#x}", imm)),
+                    None => bail!("pc {}: immediate word missing at end of program", pc),
+                }
+            }
+        }
+        Ok(BinaryProgram {
+            bytecode: lines.join("\n"),
+            prophets: self.prophets.clone(),
+            debug_info: self.debug_info.clone(),
+        })
+    }
+
+    pub fn is_compact(bytes: &[u8]) -> bool {
+        bytes.starts_with(&COMPACT_PROGRAM_MAGIC)
+    }
+
+    pub fn hash(&self) -> [u8; HASH_LEN] {
+        let body = self.encode_body(false);
+        let mut inputs: Vec<u64> = Vec::with_capacity(body.len() / 7 + 2);
+        inputs.push(body.len() as u64);
+        for chunk in body.chunks(7) {
+            let mut word = [0u8; 8];
+            word[1..1 + chunk.len()].copy_from_slice(chunk);
+            inputs.push(u64::from_be_bytes(word));
+        }
+        let hash = calculate_arbitrary_poseidon_u64s(&inputs);
+        let mut bytes = [0u8; HASH_LEN];
+        bytes.copy_from_slice(&u64s_to_bytes(&hash));
+        bytes
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = self.encode_body(true);
+        bytes.extend_from_slice(&self.hash());
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        if !Self::is_compact(bytes) {
+            bail!("compact program decode error, magic mismatch")
+        }
+        if bytes.len() < COMPACT_PROGRAM_MAGIC.len() + HASH_LEN {
+            bail!("compact program decode error, container too short")
+        }
+        let (body, hash) = bytes.split_at(bytes.len() - HASH_LEN);
+        let mut reader = Cursor::new(body);
+        reader.set_position(COMPACT_PROGRAM_MAGIC.len() as u64);
+
+        let version = reader.read_u16::<LittleEndian>()?;
+        if version != COMPACT_PROGRAM_VERSION {
+            bail!(
+                "compact program decode error, unsupported version {}",
+                version
+            )
+        }
+        let flags = reader.read_u16::<LittleEndian>()?;
+        if flags & !FLAG_DEBUG_SECTION != 0 {
+            bail!("compact program decode error, unknown flags {:#x}", flags)
+        }
+
+        let word_count = reader.read_u32::<LittleEndian>()? as usize;
+        let mut words = Vec::with_capacity(word_count.min(body.len() / 8));
+        for _ in 0..word_count {
+            words.push(reader.read_u64::<LittleEndian>()?);
+        }
+
+        let prophet_count = reader.read_u32::<LittleEndian>()?;
+        let mut prophets = Vec::new();
+        for _ in 0..prophet_count {
+            prophets.push(read_prophet(&mut reader)?);
+        }
+
+        let debug_info = if flags & FLAG_DEBUG_SECTION != 0 {
+            let entry_count = reader.read_u32::<LittleEndian>()?;
+            let mut debug_info = BTreeMap::new();
+            for _ in 0..entry_count {
+                let pc = reader.read_u64::<LittleEndian>()? as usize;
+                debug_info.insert(pc, read_string(&mut reader)?);
+            }
+            Some(debug_info)
+        } else {
+            None
+        };
+        if reader.position() != body.len() as u64 {
+            bail!("compact program decode error, trailing bytes before program hash")
+        }
+
+        let program = Self {
+            words,
+            prophets,
+            debug_info,
+        };
+        if program.hash() != hash {
+            bail!("compact program decode error, program hash mismatch")
+        }
+        Ok(program)
+    }
+
+    fn encode_body(&self, with_debug: bool) -> Vec<u8> {
+        let debug_info = if with_debug {
+            self.debug_info.as_ref()
+        } else {
+            None
+        };
+        let flags = if debug_info.is_some() {
+            FLAG_DEBUG_SECTION
+        } else {
+            0
+        };
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(16 + self.words.len() * 8);
+        bytes.extend_from_slice(&COMPACT_PROGRAM_MAGIC);
+        bytes
+            .write_u16::<LittleEndian>(COMPACT_PROGRAM_VERSION)
+            .unwrap();
+        bytes.write_u16::<LittleEndian>(flags).unwrap();
+        bytes
+            .write_u32::<LittleEndian>(self.words.len() as u32)
+            .unwrap();
+        for word in &self.words {
+            bytes.write_u64::<LittleEndian>(*word).unwrap();
+        }
+
+        bytes
+            .write_u32::<LittleEndian>(self.prophets.len() as u32)
+            .unwrap();
+        for prophet in &self.prophets {
+            write_prophet(&mut bytes, prophet);
+        }
+
+        if let Some(debug_info) = debug_info {
+            bytes
+                .write_u32::<LittleEndian>(debug_info.len() as u32)
+                .unwrap();
+            for (pc, asm) in debug_info {
+                bytes.write_u64::<LittleEndian>(*pc as u64).unwrap();
+                write_string(&mut bytes, asm);
+            }
+        }
+        bytes
+    }
+}
+
+impl TryFrom<&BinaryProgram> for CompactProgram {
+    type Error = anyhow::Error;
+
+    fn try_from(program: &BinaryProgram) -> Result<Self, Self::Error> {
+        Self::from_binary_program(program)
+    }
+}
+
+impl TryFrom<&CompactProgram> for BinaryProgram {
+    type Error = anyhow::Error;
+
+    fn try_from(program: &CompactProgram) -> Result<Self, Self::Error> {
+        program.to_binary_program()
+    }
+}
+
+fn write_string(bytes: &mut Vec<u8>, s: &str) {
+    bytes.write_u32::<LittleEndian>(s.len() as u32).unwrap();
+    bytes.extend_from_slice(s.as_bytes());
+}
+
+fn read_string(reader: &mut Cursor<&[u8]>) -> anyhow::Result<String> {
+    let len = reader.read_u32::<LittleEndian>()? as usize;
+    let remaining = reader.get_ref().len() - reader.position() as usize;
+    if len > remaining {
+        bail!("compact program decode error, string length out of range")
+    }
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|e| anyhow!("compact program decode error, {}", e))
+}
+
+fn write_bool(bytes: &mut Vec<u8>, value: bool) {
+    bytes.write_u8(value as u8).unwrap();
+}
+
+fn read_bool(reader: &mut Cursor<&[u8]>) -> anyhow::Result<bool> {
+    match reader.read_u8()? {
+        0 => Ok(false),
+        1 => Ok(true),
+        v => bail!("compact program decode error, invalid bool {}", v),
+    }
+}
+
+fn write_prophet(bytes: &mut Vec<u8>, prophet: &OlaProphet) {
+    bytes
+        .write_u64::<LittleEndian>(prophet.host as u64)
+        .unwrap();
+    write_string(bytes, &prophet.code);
+    bytes
+        .write_u32::<LittleEndian>(prophet.ctx.len() as u32)
+        .unwrap();
+    for (name, value) in &prophet.ctx {
+        write_string(bytes, name);
+        bytes.write_u64::<LittleEndian>(*value).unwrap();
+    }
+    bytes
+        .write_u32::<LittleEndian>(prophet.inputs.len() as u32)
+        .unwrap();
+    for input in &prophet.inputs {
+        write_string(bytes, &input.name);
+        bytes
+            .write_u64::<LittleEndian>(input.length as u64)
+            .unwrap();
+        write_bool(bytes, input.is_ref);
+        write_bool(bytes, input.is_input_output);
+    }
+    bytes
+        .write_u32::<LittleEndian>(prophet.outputs.len() as u32)
+        .unwrap();
+    for output in &prophet.outputs {
+        write_string(bytes, &output.name);
+        bytes
+            .write_u64::<LittleEndian>(output.length as u64)
+            .unwrap();
+        write_bool(bytes, output.is_ref);
+        write_bool(bytes, output.is_input_output);
+    }
+}
+
+fn read_prophet(reader: &mut Cursor<&[u8]>) -> anyhow::Result<OlaProphet> {
+    let host = reader.read_u64::<LittleEndian>()? as usize;
+    let code = read_string(reader)?;
+    let mut ctx = Vec::new();
+    for _ in 0..reader.read_u32::<LittleEndian>()? {
+        let name = read_string(reader)?;
+        ctx.push((name, reader.read_u64::<LittleEndian>()?));
+    }
+    let mut inputs = Vec::new();
+    for _ in 0..reader.read_u32::<LittleEndian>()? {
+        inputs.push(OlaProphetInput {
+            name: read_string(reader)?,
+            length: reader.read_u64::<LittleEndian>()? as usize,
+            is_ref: read_bool(reader)?,
+            is_input_output: read_bool(reader)?,
+        });
+    }
+    let mut outputs = Vec::new();
+    for _ in 0..reader.read_u32::<LittleEndian>()? {
+        outputs.push(OlaProphetOutput {
+            name: read_string(reader)?,
+            length: reader.read_u64::<LittleEndian>()? as usize,
+            is_ref: read_bool(reader)?,
+            is_input_output: read_bool(reader)?,
+        });
+    }
+    Ok(OlaProphet {
+        host,
+        code,
+        ctx,
+        inputs,
+        outputs,
+    })
+}
@@ -1,6 +1,14 @@
-use super::binary_program::{BinaryInstruction, BinaryProgram, OlaProphet};
+use super::binary_program::{
+    BinaryInstruction, BinaryProgram, OlaProphet, OlaProphetInput, OlaProphetOutput,
+};
+use super::compact::CompactProgram;
+use super::FIELD_ORDER;
+use crate::vm::hardware::OlaSpecialRegister;
 use crate::vm::opcodes::OlaOpcode;
-use std::collections::HashMap;
+use crate::vm::operands::{ImmediateValue, OlaOperand};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::str::FromStr;
 
 pub fn decode_binary_program_from_file(path: String) -> Result<Vec<BinaryInstruction>, String> {
     let program_json = std::fs::read_to_string(path)
@@ -53,16 +61,388 @@ pub fn decode_binary_program_to_instructions(
     Ok(instructions)
 }
 
+pub fn decode_compact_program_to_instructions(
+    program: &CompactProgram,
+) -> Result<Vec<BinaryInstruction>, String> {
+    let mut prophets: HashMap<usize, &OlaProphet> = HashMap::new();
+    for prophet in &program.prophets {
+        prophets.insert(prophet.host, prophet);
+    }
+
+    let mut instructions: Vec<BinaryInstruction> = vec![];
+    let mut host: usize = 0;
+    while host < program.words.len() {
+        let instruction_u64 = program.words[host];
+        let imm_u64 = if instruction_length_from_u64(instruction_u64) == 2 {
+            Some(*program.words.get(host + 1).ok_or(format!(
+                "decode compact program error, immediate missing at {}",
+                host
+            ))?)
+        } else {
+            None
+        };
+        let prophet = prophets.get(&host).map(|prophet| (*prophet).clone());
+        let instruction = BinaryInstruction::decode_words(instruction_u64, imm_u64, prophet)?;
+        host += instruction.binary_length() as usize;
+        instructions.push(instruction);
+    }
+    Ok(instructions)
+}
+
 fn get_instruction_length(instruction: String) -> Result<u8, String> {
     let instruction_without_prefix = instruction.trim_start_matches("0x");
     let instruction_u64 = u64::from_str_radix(instruction_without_prefix, 16)
         .map_err(|err| format!("Convert str to u64 failed {}", err))?;
+    Ok(instruction_length_from_u64(instruction_u64))
+}
+
+pub(crate) fn instruction_length_from_u64(instruction_u64: u64) -> u8 {
     let is_op1_imm = instruction_u64 & (1 << BinaryInstruction::BIT_SHIFT_OP1_IMM) != 0;
     let is_mstore = instruction_u64 & OlaOpcode::MSTORE.binary_bit_mask() != 0;
     let is_mload = instruction_u64 & OlaOpcode::MLOAD.binary_bit_mask() != 0;
     if is_op1_imm || is_mstore || is_mload {
-        Ok(2)
+        2
+    } else {
+        1
+    }
+}
+
+/// Assembler-compatible form of a decoded program. Serializes to the same JSON
+/// layout the assembler consumes (`program` + `prophets`), so feeding it back
+/// to the assembler regenerates the original bytecode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisassembledProgram {
+    pub program: String,
+    pub prophets: Vec<DisassembledProphet>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisassembledProphet {
+    pub label: String,
+    pub code: String,
+    pub inputs: Vec<OlaProphetInput>,
+    pub outputs: Vec<OlaProphetOutput>,
+}
+
+/// Decodes `program.bytecode` back into assembly. Immediate jump and call
+/// targets landing on instruction boundaries are replaced by labels, prophets
+/// are re-attached after their host instruction, and when `debug_info` is
+/// present the label names used in the original source are recovered.
+pub fn disassemble_binary_program(program: BinaryProgram) -> Result<DisassembledProgram, String> {
+    let debug_info = program.debug_info.clone();
+    let mut program_prophets = program.prophets.clone();
+    program_prophets.sort_by_key(|prophet| prophet.host);
+    let instructions = decode_binary_program_to_instructions(program)?;
+
+    let mut pcs: Vec<usize> = vec![];
+    let mut pc: usize = 0;
+    for instruction in &instructions {
+        pcs.push(pc);
+        pc += instruction.binary_length() as usize;
+    }
+    let boundaries: BTreeSet<usize> = pcs.iter().cloned().collect();
+
+    let mut call_targets: BTreeSet<usize> = BTreeSet::new();
+    let mut jmp_targets: BTreeSet<usize> = BTreeSet::new();
+    for instruction in &instructions {
+        if let Some(target) = immediate_target(instruction) {
+            if !boundaries.contains(&target) {
+                continue;
+            }
+            if instruction.opcode == OlaOpcode::CALL {
+                call_targets.insert(target);
+            } else {
+                jmp_targets.insert(target);
+            }
+        }
+    }
+    call_targets.insert(0);
+
+    // label names written in the original source, keyed by target pc
+    let mut source_names: HashMap<usize, String> = HashMap::new();
+    if let Some(debug) = &debug_info {
+        for (instruction, pc) in instructions.iter().zip(pcs.iter()) {
+            let target = match immediate_target(instruction) {
+                Some(target) if boundaries.contains(&target) => target,
+                _ => continue,
+            };
+            if let Some(name) = debug.get(pc).and_then(|asm| source_label_operand(asm)) {
+                let is_call_name = !name.starts_with(".LBL");
+                if is_call_name == (instruction.opcode == OlaOpcode::CALL) {
+                    source_names.entry(target).or_insert(name);
+                }
+            }
+        }
+    }
+
+    let mut used_names: HashSet<String> = HashSet::new();
+    let mut call_labels: BTreeMap<usize, String> = BTreeMap::new();
+    call_labels.insert(0, "main".to_string());
+    used_names.insert("main".to_string());
+    for target in call_targets.iter().filter(|target| **target != 0) {
+        let name = match source_names.get(target) {
+            Some(name) if !used_names.contains(name) => name.clone(),
+            _ => {
+                let mut name = format!("func_{}", target);
+                while used_names.contains(&name) {
+                    name = format!("_{}", name);
+                }
+                name
+            }
+        };
+        used_names.insert(name.clone());
+        call_labels.insert(*target, name);
+    }
+
+    let func_starts: Vec<usize> = call_labels.keys().cloned().collect();
+    let func_index = |pc: usize| func_starts.iter().filter(|start| **start <= pc).count() - 1;
+    let mut jmp_labels: BTreeMap<usize, String> = BTreeMap::new();
+    for target in &jmp_targets {
+        let name = match source_names.get(target) {
+            Some(name) if !used_names.contains(name) => name.clone(),
+            _ => {
+                let mut scope = func_index(*target);
+                let mut name = format!(".LBL{}_{}", scope, target);
+                while used_names.contains(&name) {
+                    scope += func_starts.len();
+                    name = format!(".LBL{}_{}", scope, target);
+                }
+                name
+            }
+        };
+        used_names.insert(name.clone());
+        jmp_labels.insert(*target, name);
+    }
+
+    let mut prophet_labels: BTreeMap<usize, String> = BTreeMap::new();
+    let mut prophets: Vec<DisassembledProphet> = vec![];
+    for prophet in program_prophets {
+        let index = pcs
+            .iter()
+            .position(|pc| *pc == prophet.host)
+            .ok_or(format!(
+                "disassemble error, prophet host {} is not an instruction boundary",
+                prophet.host
+            ))?;
+        let next_pc = pcs.get(index + 1).ok_or(format!(
+            "disassemble error, prophet host {} is the last instruction",
+            prophet.host
+        ))?;
+        let label = format!(".PROPHET{}_{}", func_index(prophet.host), prophet.host);
+        if prophet_labels.insert(*next_pc, label.clone()).is_some() {
+            return Err(format!(
+                "disassemble error, more than one prophet hosted at {}",
+                prophet.host
+            ));
+        }
+        prophets.push(DisassembledProphet {
+            label,
+            code: prophet.code,
+            inputs: prophet.inputs,
+            outputs: prophet.outputs,
+        });
+    }
+
+    let mut lines: Vec<String> = vec![];
+    for (instruction, pc) in instructions.iter().zip(pcs.iter()) {
+        if let Some(label) = prophet_labels.get(pc) {
+            lines.push(format!("{}:", label));
+        }
+        if let Some(label) = call_labels.get(pc) {
+            lines.push(format!("{}:", label));
+        }
+        if let Some(label) = jmp_labels.get(pc) {
+            lines.push(format!("{}:", label));
+        }
+        let target_label = immediate_target(instruction).and_then(|target| {
+            if instruction.opcode == OlaOpcode::CALL {
+                call_labels.get(&target)
+            } else {
+                jmp_labels.get(&target)
+            }
+        });
+        let asm = disassemble_instruction(instruction, target_label)?;
+        lines.push(format!("  {} ; {}", asm, pc));
+    }
+
+    Ok(DisassembledProgram {
+        program: lines.join("\n"),
+        prophets,
+    })
+}
+
+pub(crate) fn immediate_target(instruction: &BinaryInstruction) -> Option<usize> {
+    match instruction.opcode {
+        OlaOpcode::JMP | OlaOpcode::CJMP | OlaOpcode::CALL => match &instruction.op1 {
+            Some(OlaOperand::ImmediateOperand { value }) => {
+                value.to_u64().ok().map(|target| target as usize)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// last operand of a source line when it is a label or identifier, e.g. `call
+// foo`
+fn source_label_operand(asm: &str) -> Option<String> {
+    let without_comment = &asm[0..asm.find(';').unwrap_or(asm.len())];
+    let operand = without_comment.split_whitespace().last()?;
+    let is_jmp_label = operand.starts_with(".LBL")
+        && operand[4..]
+            .split_once('_')
+            .map(|(a, b)| is_digits(a) && is_digits(b))
+            .unwrap_or(false);
+    let is_identifier = operand
+        .trim_start_matches('_')
+        .chars()
+        .next()
+        .map(|c| c.is_ascii_alphabetic())
+        .unwrap_or(false)
+        && operand
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && OlaSpecialRegister::from_str(operand).is_err();
+    if is_jmp_label || is_identifier {
+        Some(operand.to_string())
+    } else {
+        None
+    }
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+pub(crate) fn disassemble_instruction(
+    instruction: &BinaryInstruction,
+    target_label: Option<&String>,
+) -> Result<String, String> {
+    let operand = |op: &Option<OlaOperand>, name: &str| -> Result<String, String> {
+        match op {
+            Some(op) => asm_operand_token(op),
+            None => Err(format!(
+                "disassemble error, {} missing: {}",
+                name, instruction
+            )),
+        }
+    };
+    let token = instruction.opcode.token();
+    let asm = match instruction.opcode {
+        OlaOpcode::ADD
+        | OlaOpcode::MUL
+        | OlaOpcode::AND
+        | OlaOpcode::OR
+        | OlaOpcode::XOR
+        | OlaOpcode::EQ
+        | OlaOpcode::NEQ
+        | OlaOpcode::GTE
+        | OlaOpcode::TLOAD
+        | OlaOpcode::POSEIDON => format!(
+            "{} {} {} {}",
+            token,
+            operand(&instruction.dst, "dst")?,
+            operand(&instruction.op0, "op0")?,
+            operand(&instruction.op1, "op1")?
+        ),
+        OlaOpcode::MOV | OlaOpcode::NOT | OlaOpcode::SIGCHECK => format!(
+            "{} {} {}",
+            token,
+            operand(&instruction.dst, "dst")?,
+            operand(&instruction.op1, "op1")?
+        ),
+        OlaOpcode::MLOAD => format!(
+            "{} {} {}",
+            token,
+            operand(&instruction.dst, "dst")?,
+            asm_mem_operand(instruction)?
+        ),
+        OlaOpcode::MSTORE => format!(
+            "{} {} {}",
+            token,
+            asm_mem_operand(instruction)?,
+            operand(&instruction.dst, "dst")?
+        ),
+        OlaOpcode::CJMP => format!(
+            "{} {} {}",
+            token,
+            operand(&instruction.op0, "op0")?,
+            match target_label {
+                Some(label) => label.clone(),
+                None => operand(&instruction.op1, "op1")?,
+            }
+        ),
+        OlaOpcode::SCCALL
+        | OlaOpcode::SLOAD
+        | OlaOpcode::SSTORE
+        | OlaOpcode::TSTORE
+        | OlaOpcode::EVENT => format!(
+            "{} {} {}",
+            token,
+            operand(&instruction.op0, "op0")?,
+            operand(&instruction.op1, "op1")?
+        ),
+        OlaOpcode::JMP | OlaOpcode::CALL | OlaOpcode::RC | OlaOpcode::ASSERT => format!(
+            "{} {}",
+            token,
+            match target_label {
+                Some(label) => label.clone(),
+                None => operand(&instruction.op1, "op1")?,
+            }
+        ),
+        OlaOpcode::RET | OlaOpcode::END => token,
+    };
+    Ok(asm)
+}
+
+fn asm_mem_operand(instruction: &BinaryInstruction) -> Result<String, String> {
+    let anchor = match &instruction.op0 {
+        Some(OlaOperand::RegisterOperand { register }) => register,
+        _ => {
+            return Err(format!(
+                "disassemble error, invalid memory anchor: {}",
+                instruction
+            ))
+        }
+    };
+    match &instruction.op1 {
+        Some(OlaOperand::ImmediateOperand { value }) => {
+            Ok(format!("[{},{}]", anchor, asm_immediate_token(value)?))
+        }
+        Some(OlaOperand::RegisterWithFactor { register, factor }) => Ok(format!(
+            "[{},{},{}]",
+            anchor,
+            register,
+            asm_immediate_token(factor)?
+        )),
+        _ => Err(format!(
+            "disassemble error, invalid memory offset: {}",
+            instruction
+        )),
+    }
+}
+
+fn asm_operand_token(op: &OlaOperand) -> Result<String, String> {
+    match op {
+        OlaOperand::ImmediateOperand { value } => asm_immediate_token(value),
+        OlaOperand::RegisterOperand { register } => Ok(format!("{}", register)),
+        OlaOperand::SpecialReg { special_reg } => Ok(format!("{}", special_reg)),
+        OlaOperand::RegisterWithOffset { .. } | OlaOperand::RegisterWithFactor { .. } => {
+            Err(format!("disassemble error, unexpected operand: {}", op))
+        }
+    }
+}
+
+// Decimal form accepted by the assembler; values just below the field order
+// are printed as small negatives, which is how compilers write stack offsets.
+fn asm_immediate_token(value: &ImmediateValue) -> Result<String, String> {
+    let value = value
+        .to_u64()
+        .map_err(|err| format!("disassemble error, invalid immediate: {}", err))?;
+    let distance_to_order = FIELD_ORDER - value;
+    if distance_to_order <= u32::MAX as u64 {
+        Ok(format!("-{}", distance_to_order))
     } else {
-        Ok(1)
+        Ok(format!("{}", value))
     }
 }
@@ -5,9 +5,13 @@ use plonky2::field::types::Field64;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 
+pub mod analysis;
 pub mod binary_program;
+pub mod compact;
 pub mod decoder;
 pub mod instruction;
+pub mod prophet_check;
+pub mod verifier;
 
 /// fixme: use 10 registers
 pub const REGISTER_NUM: usize = 10;
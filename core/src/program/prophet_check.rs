@@ -0,0 +1,427 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::{Display, Formatter};
+
+use super::analysis::signed_immediate;
+use super::binary_program::{BinaryInstruction, BinaryProgram, OlaProphet};
+use super::compact::CompactProgram;
+use super::decoder::{
+    decode_compact_program_to_instructions, disassemble_instruction, immediate_target,
+};
+use super::REGISTER_NUM;
+use crate::vm::hardware::{OlaRegister, OlaSpecialRegister};
+use crate::vm::opcodes::OlaOpcode;
+use crate::vm::operands::OlaOperand;
+
+/// A prophet output word used by an instruction before the program has
+/// checked it. Prophet outputs are chosen by the prover, so such a use is
+/// only sound if the value is constrained afterwards in some other way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProphetOutputWarning {
+    pub host: usize,
+    pub output: String,
+    /// Word of the output, for outputs longer than one word.
+    pub offset: usize,
+    pub pc: usize,
+    pub instruction: String,
+}
+
+impl Display for ProphetOutputWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "prophet at {}: output {}[{}] used unchecked by `{}` at pc {}",
+            self.host, self.output, self.offset, self.instruction, self.pc
+        )
+    }
+}
+
+pub fn check_binary_prophet_outputs(
+    program: &BinaryProgram,
+) -> Result<Vec<ProphetOutputWarning>, String> {
+    let compact = CompactProgram::from_binary_program(program).map_err(|err| err.to_string())?;
+    check_prophet_outputs(&compact)
+}
+
+/// Follows every prophet output from its host instruction and reports the
+/// ones used before they are checked.
+///
+/// An output is checked once a value derived from it is range checked,
+/// asserted, or compared with `eq`/`neq`/`gte` and the result asserted or
+/// branched on. Arithmetic and moves, including spills to r9-relative stack
+/// slots, only carry it along. Any other use counts: storing it elsewhere,
+/// using it as an address or jump target, branching on it directly, passing
+/// it in a register to `call` or a host call, and returning it in r0.
+///
+/// Calls are not followed; every register is assumed clobbered after them.
+pub fn check_prophet_outputs(
+    program: &CompactProgram,
+) -> Result<Vec<ProphetOutputWarning>, String> {
+    let instructions = decode_compact_program_to_instructions(program)?;
+    let mut code: BTreeMap<usize, BinaryInstruction> = BTreeMap::new();
+    let mut pc: usize = 0;
+    for instruction in instructions {
+        let length = instruction.binary_length() as usize;
+        code.insert(pc, instruction);
+        pc += length;
+    }
+    let hosts: BTreeSet<usize> = program.prophets.iter().map(|p| p.host).collect();
+
+    let mut warnings: Vec<ProphetOutputWarning> = vec![];
+    for prophet in &program.prophets {
+        warnings.extend(check_prophet(&code, &hosts, prophet));
+    }
+    Ok(warnings)
+}
+
+fn check_prophet(
+    code: &BTreeMap<usize, BinaryInstruction>,
+    hosts: &BTreeSet<usize>,
+    prophet: &OlaProphet,
+) -> Vec<ProphetOutputWarning> {
+    let words: Vec<(&str, usize)> = prophet
+        .outputs
+        .iter()
+        .flat_map(|output| (0..output.length).map(move |i| (output.name.as_str(), i)))
+        .collect();
+
+    let mut states: BTreeMap<usize, TaintState> = BTreeMap::new();
+    states.insert(prophet.host, TaintState::new());
+    let mut queue: VecDeque<usize> = VecDeque::from([prophet.host]);
+    let mut uses: BTreeSet<(usize, usize)> = BTreeSet::new();
+    while let Some(pc) = queue.pop_front() {
+        let instruction = match code.get(&pc) {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+        let mut state = states[&pc].clone();
+        let mut used: BTreeSet<usize> = BTreeSet::new();
+        state.step(instruction, words.len(), &mut used);
+        uses.extend(used.into_iter().map(|word| (pc, word)));
+        // a prophet runs right after its host instruction
+        if pc == prophet.host {
+            state.validated.clear();
+            state.stale = false;
+        } else if hosts.contains(&pc) {
+            state.stale = true;
+        }
+
+        for successor in successors(pc, instruction) {
+            match states.get_mut(&successor) {
+                Some(existing) => {
+                    if existing.join(&state) {
+                        queue.push_back(successor);
+                    }
+                }
+                None => {
+                    states.insert(successor, state.clone());
+                    queue.push_back(successor);
+                }
+            }
+        }
+    }
+
+    uses.into_iter()
+        .map(|(pc, word)| {
+            let instruction = &code[&pc];
+            ProphetOutputWarning {
+                host: prophet.host,
+                output: words[word].0.to_string(),
+                offset: words[word].1,
+                pc,
+                instruction: disassemble_instruction(instruction, None)
+                    .unwrap_or_else(|_| instruction.opcode.token()),
+            }
+        })
+        .collect()
+}
+
+// Calls continue at the next instruction, the callee is not entered.
+fn successors(pc: usize, instruction: &BinaryInstruction) -> Vec<usize> {
+    let next = pc + instruction.binary_length() as usize;
+    let target = immediate_target(instruction);
+    match instruction.opcode {
+        OlaOpcode::RET | OlaOpcode::END => vec![],
+        OlaOpcode::JMP => target.into_iter().collect(),
+        OlaOpcode::CJMP => target.into_iter().chain(Some(next)).collect(),
+        _ => vec![next],
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Clean,
+    /// The address `psp + offset`.
+    Prophet(i64),
+    /// Derived from the listed unchecked output words, `check` when it is
+    /// the result of a comparison.
+    Output {
+        words: BTreeSet<usize>,
+        check: bool,
+    },
+}
+
+impl Value {
+    fn words(&self) -> BTreeSet<usize> {
+        match self {
+            Value::Output { words, .. } => words.clone(),
+            _ => BTreeSet::new(),
+        }
+    }
+
+    fn derive(values: &[Value], check: bool) -> Value {
+        let words: BTreeSet<usize> = values.iter().flat_map(|value| value.words()).collect();
+        if words.is_empty() {
+            Value::Clean
+        } else {
+            Value::Output { words, check }
+        }
+    }
+
+    // Ordered Clean < Prophet < Output, so states only grow while joining.
+    fn join(&self, other: &Value) -> Value {
+        match (self, other) {
+            (a, b) if a == b => a.clone(),
+            (Value::Clean, other) | (other, Value::Clean) => other.clone(),
+            (
+                Value::Output {
+                    words: a,
+                    check: check_a,
+                },
+                Value::Output {
+                    words: b,
+                    check: check_b,
+                },
+            ) => Value::Output {
+                words: a.union(b).cloned().collect(),
+                check: *check_a && *check_b,
+            },
+            (output @ Value::Output { .. }, Value::Prophet(_))
+            | (Value::Prophet(_), output @ Value::Output { .. }) => output.clone(),
+            (Value::Prophet(_), Value::Prophet(_)) => Value::Output {
+                words: BTreeSet::new(),
+                check: false,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TaintState {
+    registers: Vec<Value>,
+    /// Values spilled to `[r9,offset]`.
+    slots: BTreeMap<i64, Value>,
+    validated: BTreeSet<usize>,
+    /// Another prophet ran since, psp no longer points at our outputs.
+    stale: bool,
+}
+
+impl TaintState {
+    fn new() -> Self {
+        Self {
+            registers: vec![Value::Clean; REGISTER_NUM],
+            slots: BTreeMap::new(),
+            validated: BTreeSet::new(),
+            stale: false,
+        }
+    }
+
+    fn join(&mut self, other: &TaintState) -> bool {
+        let before = self.clone();
+        for (register, value) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *register = register.join(value);
+        }
+        for (offset, value) in &other.slots {
+            let joined = self.slots.get(offset).unwrap_or(&Value::Clean).join(value);
+            self.slots.insert(*offset, joined);
+        }
+        self.validated = self
+            .validated
+            .intersection(&other.validated)
+            .cloned()
+            .collect();
+        self.stale |= other.stale;
+        *self != before
+    }
+
+    fn read(&self, operand: &Option<OlaOperand>) -> Value {
+        match operand {
+            Some(OlaOperand::RegisterOperand { register }) => {
+                self.registers[register.index() as usize].clone()
+            }
+            Some(OlaOperand::SpecialReg {
+                special_reg: OlaSpecialRegister::PSP,
+            }) if !self.stale => Value::Prophet(0),
+            _ => Value::Clean,
+        }
+    }
+
+    fn write(&mut self, operand: &Option<OlaOperand>, value: Value) {
+        if let Some(OlaOperand::RegisterOperand { register }) = operand {
+            if *register != OlaRegister::R9 {
+                self.registers[register.index() as usize] = value;
+            }
+        }
+    }
+
+    fn validate(&mut self, words: &BTreeSet<usize>) {
+        let strip = |value: &mut Value| {
+            if let Value::Output { words: tainted, .. } = value {
+                tainted.retain(|word| !words.contains(word));
+                if tainted.is_empty() {
+                    *value = Value::Clean;
+                }
+            }
+        };
+        self.registers.iter_mut().for_each(strip);
+        self.slots.values_mut().for_each(strip);
+        self.slots.retain(|_, value| *value != Value::Clean);
+        self.validated.extend(words.iter().cloned());
+    }
+
+    // Tracks r9 so that spilled values are found again after the frame moves.
+    fn write_r9(&mut self, instruction: &BinaryInstruction) {
+        let r9 = Some(OlaOperand::RegisterOperand {
+            register: OlaRegister::R9,
+        });
+        if instruction.opcode == OlaOpcode::MSTORE || instruction.dst != r9 {
+            return;
+        }
+        let delta = match (&instruction.opcode, &instruction.op1) {
+            (OlaOpcode::ADD, Some(OlaOperand::ImmediateOperand { value }))
+                if instruction.op0 == r9 =>
+            {
+                signed_immediate(value)
+            }
+            _ => None,
+        };
+        self.slots = match delta {
+            Some(delta) => std::mem::take(&mut self.slots)
+                .into_iter()
+                .map(|(offset, value)| (offset - delta, value))
+                .collect(),
+            None => BTreeMap::new(),
+        };
+    }
+
+    // Address of a memory operand: the anchor value, the immediate offset if
+    // there is one, and the words used to compute it.
+    fn address(&self, instruction: &BinaryInstruction) -> (Value, Option<i64>, BTreeSet<usize>) {
+        let anchor = self.read(&instruction.op0);
+        let mut used = anchor.words();
+        let offset = match &instruction.op1 {
+            Some(OlaOperand::ImmediateOperand { value }) => signed_immediate(value),
+            Some(OlaOperand::RegisterWithFactor { register, .. }) => {
+                used.extend(self.registers[register.index() as usize].words());
+                None
+            }
+            _ => None,
+        };
+        (anchor, offset, used)
+    }
+
+    fn is_stack(&self, instruction: &BinaryInstruction) -> bool {
+        instruction.op0
+            == Some(OlaOperand::RegisterOperand {
+                register: OlaRegister::R9,
+            })
+    }
+
+    fn step(
+        &mut self,
+        instruction: &BinaryInstruction,
+        word_count: usize,
+        used: &mut BTreeSet<usize>,
+    ) {
+        let op0 = self.read(&instruction.op0);
+        let op1 = self.read(&instruction.op1);
+        match instruction.opcode {
+            OlaOpcode::MOV => self.write(&instruction.dst, op1),
+            OlaOpcode::ADD => {
+                let value = match (&op0, &instruction.op1) {
+                    (Value::Prophet(base), Some(OlaOperand::ImmediateOperand { value })) => {
+                        signed_immediate(value)
+                            .map_or(Value::Clean, |imm| Value::Prophet(base + imm))
+                    }
+                    _ => Value::derive(&[op0, op1], false),
+                };
+                self.write(&instruction.dst, value);
+            }
+            OlaOpcode::MUL | OlaOpcode::AND | OlaOpcode::OR | OlaOpcode::XOR => {
+                self.write(&instruction.dst, Value::derive(&[op0, op1], false))
+            }
+            OlaOpcode::NOT => self.write(&instruction.dst, Value::derive(&[op1], false)),
+            OlaOpcode::EQ | OlaOpcode::NEQ | OlaOpcode::GTE => {
+                self.write(&instruction.dst, Value::derive(&[op0, op1], true))
+            }
+            OlaOpcode::ASSERT | OlaOpcode::RC => self.validate(&op1.words()),
+            OlaOpcode::CJMP => match op0 {
+                Value::Output { words, check: true } => self.validate(&words),
+                value => used.extend(value.words()),
+            },
+            OlaOpcode::JMP => used.extend(op1.words()),
+            OlaOpcode::CALL => {
+                used.extend(op1.words());
+                for value in &self.registers {
+                    used.extend(value.words());
+                }
+                self.registers = vec![Value::Clean; REGISTER_NUM];
+            }
+            OlaOpcode::RET => used.extend(self.registers[0].words()),
+            OlaOpcode::END => {}
+            OlaOpcode::MLOAD => {
+                let (anchor, offset, address_words) = self.address(instruction);
+                used.extend(address_words);
+                let value = match (anchor, offset) {
+                    (_, Some(offset)) if self.is_stack(instruction) => {
+                        self.slots.get(&offset).cloned().unwrap_or(Value::Clean)
+                    }
+                    (Value::Prophet(base), Some(offset)) => {
+                        let word = base + offset;
+                        if word >= 0
+                            && (word as usize) < word_count
+                            && !self.validated.contains(&(word as usize))
+                        {
+                            Value::Output {
+                                words: BTreeSet::from([word as usize]),
+                                check: false,
+                            }
+                        } else {
+                            Value::Clean
+                        }
+                    }
+                    _ => Value::Clean,
+                };
+                self.write(&instruction.dst, value);
+            }
+            OlaOpcode::MSTORE => {
+                let (_, offset, address_words) = self.address(instruction);
+                used.extend(address_words);
+                // mstore carries the stored register in dst
+                let stored = self.read(&instruction.dst);
+                match offset {
+                    Some(offset) if self.is_stack(instruction) => {
+                        if stored == Value::Clean {
+                            self.slots.remove(&offset);
+                        } else {
+                            self.slots.insert(offset, stored);
+                        }
+                    }
+                    _ => used.extend(stored.words()),
+                }
+            }
+            OlaOpcode::POSEIDON
+            | OlaOpcode::SLOAD
+            | OlaOpcode::SSTORE
+            | OlaOpcode::TLOAD
+            | OlaOpcode::TSTORE
+            | OlaOpcode::SCCALL
+            | OlaOpcode::SIGCHECK
+            | OlaOpcode::EVENT => {
+                used.extend(op0.words());
+                used.extend(op1.words());
+                self.write(&instruction.dst, Value::Clean);
+            }
+        }
+        self.write_r9(instruction);
+    }
+}
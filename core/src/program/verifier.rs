@@ -0,0 +1,264 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+
+use super::binary_program::{BinaryInstruction, BinaryProgram, OlaProphet};
+use super::compact::CompactProgram;
+use super::decoder::{immediate_target, instruction_length_from_u64};
+use crate::vm::opcodes::OlaOpcode;
+
+/// A single reason a program is rejected at deploy time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgramViolation {
+    Malformed(String),
+    DecodeError {
+        pc: usize,
+        err: String,
+    },
+    TruncatedInstruction {
+        pc: usize,
+    },
+    TargetOutOfRange {
+        pc: usize,
+        opcode: String,
+        target: usize,
+    },
+    TargetInsideImmediate {
+        pc: usize,
+        opcode: String,
+        target: usize,
+    },
+    ProphetHostOutOfRange {
+        host: usize,
+    },
+    ProphetHostInsideImmediate {
+        host: usize,
+    },
+    DuplicateProphetHost {
+        host: usize,
+    },
+    ProphetShapeError {
+        host: usize,
+        err: String,
+    },
+    NoReachableEnd,
+}
+
+impl Display for ProgramViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgramViolation::Malformed(err) => write!(f, "malformed program: {}", err),
+            ProgramViolation::DecodeError { pc, err } => {
+                write!(f, "pc {}: decode failed: {}", pc, err)
+            }
+            ProgramViolation::TruncatedInstruction { pc } => {
+                write!(f, "pc {}: immediate word missing at end of program", pc)
+            }
+            ProgramViolation::TargetOutOfRange { pc, opcode, target } => write!(
+                f,
+                "pc {}: {} target {} is out of program range",
+                pc, opcode, target
+            ),
+            ProgramViolation::TargetInsideImmediate { pc, opcode, target } => write!(
+                f,
+                "pc {}: {} target {} is not an instruction boundary",
+                pc, opcode, target
+            ),
+            ProgramViolation::ProphetHostOutOfRange { host } => {
+                write!(f, "prophet host {} is out of program range", host)
+            }
+            ProgramViolation::ProphetHostInsideImmediate { host } => {
+                write!(f, "prophet host {} is not an instruction boundary", host)
+            }
+            ProgramViolation::DuplicateProphetHost { host } => {
+                write!(f, "more than one prophet hosted at {}", host)
+            }
+            ProgramViolation::ProphetShapeError { host, err } => {
+                write!(f, "prophet at {}: {}", host, err)
+            }
+            ProgramViolation::NoReachableEnd => {
+                write!(f, "no end instruction is reachable from pc 0")
+            }
+        }
+    }
+}
+
+pub fn verify_binary_program(program: &BinaryProgram) -> Result<(), Vec<ProgramViolation>> {
+    let compact = CompactProgram::from_binary_program(program)
+        .map_err(|err| vec![ProgramViolation::Malformed(err.to_string())])?;
+    verify_program(&compact)
+}
+
+/// Checks a program statically before it is stored, collecting every
+/// violation instead of stopping at the first one.
+pub fn verify_program(program: &CompactProgram) -> Result<(), Vec<ProgramViolation>> {
+    let mut violations: Vec<ProgramViolation> = vec![];
+    let words = &program.words;
+
+    let mut boundaries: BTreeSet<usize> = BTreeSet::new();
+    let mut pc: usize = 0;
+    while pc < words.len() {
+        boundaries.insert(pc);
+        let length = instruction_length_from_u64(words[pc]) as usize;
+        if pc + length > words.len() {
+            violations.push(ProgramViolation::TruncatedInstruction { pc });
+        }
+        pc += length;
+    }
+
+    let mut instructions: HashMap<usize, BinaryInstruction> = HashMap::new();
+    for pc in boundaries.iter().cloned() {
+        let imm = if instruction_length_from_u64(words[pc]) == 2 {
+            match words.get(pc + 1) {
+                Some(imm) => Some(*imm),
+                None => continue,
+            }
+        } else {
+            None
+        };
+        match BinaryInstruction::decode_words(words[pc], imm, None) {
+            Ok(instruction) => {
+                instructions.insert(pc, instruction);
+            }
+            Err(err) => violations.push(ProgramViolation::DecodeError { pc, err }),
+        }
+    }
+
+    for pc in boundaries.iter() {
+        let instruction = match instructions.get(pc) {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+        if let Some(target) = immediate_target(instruction) {
+            let opcode = instruction.opcode.token();
+            if target >= words.len() {
+                violations.push(ProgramViolation::TargetOutOfRange {
+                    pc: *pc,
+                    opcode,
+                    target,
+                });
+            } else if !boundaries.contains(&target) {
+                violations.push(ProgramViolation::TargetInsideImmediate {
+                    pc: *pc,
+                    opcode,
+                    target,
+                });
+            }
+        }
+    }
+
+    let mut hosts: HashSet<usize> = HashSet::new();
+    for prophet in &program.prophets {
+        let host = prophet.host;
+        if !hosts.insert(host) {
+            violations.push(ProgramViolation::DuplicateProphetHost { host });
+        }
+        if host >= words.len() {
+            violations.push(ProgramViolation::ProphetHostOutOfRange { host });
+        } else if !boundaries.contains(&host) {
+            violations.push(ProgramViolation::ProphetHostInsideImmediate { host });
+        }
+        for err in prophet_shape_errors(prophet) {
+            violations.push(ProgramViolation::ProphetShapeError { host, err });
+        }
+    }
+
+    if !has_reachable_end(&boundaries, &instructions, words.len()) {
+        violations.push(ProgramViolation::NoReachableEnd);
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+fn prophet_shape_errors(prophet: &OlaProphet) -> Vec<String> {
+    let mut errors: Vec<String> = vec![];
+    match prophet.native_id() {
+        Some(id) => {
+            if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                errors.push(format!("invalid native prophet id {:?}", id));
+            }
+        }
+        None => {
+            if !prophet.code.starts_with("%{") || !prophet.code.ends_with("%}") {
+                errors.push("code is not wrapped in %{ %}".to_string());
+            }
+        }
+    }
+
+    let mut input_names: HashSet<&str> = HashSet::new();
+    for input in &prophet.inputs {
+        if input.name.is_empty() {
+            errors.push("input with empty name".to_string());
+        } else if !input_names.insert(input.name.as_str()) {
+            errors.push(format!("input {} declared twice", input.name));
+        }
+        if input.length == 0 {
+            errors.push(format!("input {} has zero length", input.name));
+        }
+    }
+
+    let mut output_names: HashSet<&str> = HashSet::new();
+    for output in &prophet.outputs {
+        if output.name.is_empty() {
+            errors.push("output with empty name".to_string());
+        } else if !output_names.insert(output.name.as_str()) {
+            errors.push(format!("output {} declared twice", output.name));
+        }
+        if output.length == 0 {
+            errors.push(format!("output {} has zero length", output.name));
+        }
+        if output.is_input_output {
+            let paired = prophet.inputs.iter().any(|input| {
+                input.is_input_output && input.name == output.name && input.length == output.length
+            });
+            if !paired {
+                errors.push(format!(
+                    "in-out output {} has no matching in-out input",
+                    output.name
+                ));
+            }
+        }
+    }
+    errors
+}
+
+// Walks the control flow from pc 0. Register jumps cannot be followed
+// statically, calls are assumed to return to the next instruction.
+fn has_reachable_end(
+    boundaries: &BTreeSet<usize>,
+    instructions: &HashMap<usize, BinaryInstruction>,
+    len: usize,
+) -> bool {
+    let mut visited: HashSet<usize> = HashSet::new();
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    if boundaries.contains(&0) {
+        queue.push_back(0);
+    }
+    while let Some(pc) = queue.pop_front() {
+        if !visited.insert(pc) {
+            continue;
+        }
+        let instruction = match instructions.get(&pc) {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+        let next = pc + instruction.binary_length() as usize;
+        let target = immediate_target(instruction).filter(|t| boundaries.contains(t));
+        let successors: Vec<usize> = match instruction.opcode {
+            OlaOpcode::END => return true,
+            OlaOpcode::RET => vec![],
+            OlaOpcode::JMP => target.into_iter().collect(),
+            OlaOpcode::CJMP | OlaOpcode::CALL => target.into_iter().chain(Some(next)).collect(),
+            _ => vec![next],
+        };
+        for successor in successors {
+            if successor < len && !visited.contains(&successor) {
+                queue.push_back(successor);
+            }
+        }
+    }
+    false
+}
@@ -1,6 +1,9 @@
 use crate::{
     trace::exe_trace::TxExeTrace,
-    vm::{hardware::StorageAccessLog, types::Event},
+    vm::{
+        hardware::StorageAccessLog,
+        types::{Event, ProphetLog},
+    },
 };
 
 #[derive(Debug, Clone)]
@@ -8,6 +11,9 @@ pub struct TxResult {
     pub trace: TxExeTrace,
     pub storage_access_logs: Vec<StorageAccessLog>,
     pub events: Vec<Event>,
+    /// Empty for `ExecuteMode::Invoke`, which does not run prophet
+    /// diagnostics.
+    pub prophet_logs: Vec<ProphetLog>,
 }
 
 pub struct BatchResult {
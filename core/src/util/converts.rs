@@ -31,6 +31,16 @@ pub fn u32s_be_to_u256(u32s: [u64; 8]) -> anyhow::Result<U256> {
     Ok(U256::from_big_endian(&u64s_to_bytes(&limbs)))
 }
 
+pub fn u256_to_u32s_be(value: U256) -> [u64; 8] {
+    let mut u32s = [0u64; 8];
+    for i in 0..4 {
+        let limb = value.0[3 - i];
+        u32s[i * 2] = limb >> 32;
+        u32s[i * 2 + 1] = limb & u32::MAX as u64;
+    }
+    u32s
+}
+
 pub fn from_hex_be(value: &str) -> anyhow::Result<[u8; 32]> {
     let value = value.trim_start_matches("0x");
 
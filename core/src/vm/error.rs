@@ -16,6 +16,15 @@ pub enum ProcessorError {
     #[error("Interpreter run error: {0}")]
     InterpreterRunError(String),
 
+    #[error("Native prophet error: {0}")]
+    NativeProphetError(String),
+
+    #[error("Prophet compile error: {0}")]
+    ProphetCompileError(String),
+
+    #[error("Prophet limit exceeded: {0}")]
+    ProphetLimitExceeded(String),
+
     #[error("U32 range check fail, value out range")]
     U32RangeCheckFail,
 
@@ -97,6 +106,9 @@ pub enum ProcessorError {
     #[error("ProgLoadError: {0}")]
     ProgLoadError(String),
 
+    #[error("Program verification failed: {0}")]
+    ProgramVerifyError(String),
+
     #[error("Too many cpu lifecycle: {0}")]
     CpuLifeCycleOverflow(u64),
 
@@ -142,6 +142,16 @@ pub struct ImmediateValue {
 
 impl ImmediateValue {
     const ORDER: u64 = 0xFFFFFFFF00000001;
+
+    pub fn from_u64(value: u64) -> Result<Self, String> {
+        if value >= ImmediateValue::ORDER {
+            return Err(format!("Immediate overflow: {:#x}", value));
+        }
+        Ok(ImmediateValue {
+            hex: format!("{:#x}", value),
+        })
+    }
+
     pub fn to_u64(&self) -> Result<u64, ParseIntError> {
         let without_prefix = self.hex.trim_start_matches("0x");
         return u64::from_str_radix(without_prefix, 16);
@@ -13,6 +13,26 @@ pub struct Event {
     pub data: Vec<u64>,
 }
 
+/// A line printed by a prophet with `printf`, or a failed prophet
+/// `assert`, at the instruction the prophet is attached to.
+#[derive(Debug, Clone)]
+pub struct ProphetLog {
+    pub address: ContractAddress,
+    pub pc: u64,
+    pub clk: u64,
+    pub message: String,
+}
+
+impl Display for ProphetLog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Prophet {{ address: {:?}, pc: {}, clk: {} }}: {}",
+            self.address, self.pc, self.clk, self.message
+        )
+    }
+}
+
 impl Display for Event {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
@@ -11,7 +11,7 @@ use std::mem;
 use anyhow::Ok;
 
 use crate::{
-    config::{ExecuteMode, ADDR_U64_ENTRYPOINT},
+    config::{ExecuteMode, ProphetLimits, ADDR_U64_ENTRYPOINT},
     ola_storage::OlaCachedStorage,
     tx_exe_manager::{OlaTapeInitInfo, TxExeManager},
 };
@@ -30,6 +30,7 @@ pub struct BlockExeManager {
     tx_traces: Vec<TxExeTrace>,
     storage_access_logs: Vec<StorageAccessLog>,
     events: Vec<Event>,
+    prophet_limits: ProphetLimits,
 }
 
 impl BlockExeManager {
@@ -53,9 +54,14 @@ impl BlockExeManager {
             tx_traces: vec![],
             storage_access_logs: vec![],
             events: vec![],
+            prophet_limits: ProphetLimits::default(),
         })
     }
 
+    pub fn set_prophet_limits(&mut self, limits: ProphetLimits) {
+        self.prophet_limits = limits;
+    }
+
     pub fn invoke(&mut self, tx: OlaTapeInitInfo) -> anyhow::Result<TxResult> {
         self.storage.clear_tx_cache();
         let mut tx_exe_manager: TxExeManager = TxExeManager::new(
@@ -66,6 +72,7 @@ impl BlockExeManager {
             ADDR_U64_ENTRYPOINT,
             self.events.len(),
         );
+        tx_exe_manager.set_prophet_limits(self.prophet_limits);
         let result = tx_exe_manager.invoke()?;
         self.storage.on_tx_success();
         self.on_tx_success(result.clone());
@@ -98,6 +105,7 @@ impl BlockExeManager {
             ADDR_U64_ENTRYPOINT,
             self.events.len(),
         );
+        tx_exe_manager.set_prophet_limits(self.prophet_limits);
         let result = tx_exe_manager.invoke()?;
         let block_tip_queries = self.storage.get_tx_storage_access_logs();
         self.storage.on_tx_success();
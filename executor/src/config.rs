@@ -1,5 +1,7 @@
 use core::vm::hardware::ContractAddress;
 
+pub use interpreter::interpreter::limits::ProphetLimits;
+
 pub const ADDR_U64_ENTRYPOINT: ContractAddress = [0, 0, 0, 32769];
 pub const ADDR_U64_CODE_STORAGE: ContractAddress = [0, 0, 0, 32770];
 pub const ADDR_U64_NONCE_HOLDER: ContractAddress = [0, 0, 0, 32771];
@@ -1,8 +1,9 @@
 use core::{
     crypto::poseidon_trace::calculate_arbitrary_poseidon_u64s,
     program::{
-        binary_program::{BinaryInstruction, BinaryProgram, OlaProphet},
-        decoder::decode_binary_program_to_instructions,
+        binary_program::{BinaryInstruction, OlaProphet},
+        compact::CompactProgram,
+        decoder::decode_compact_program_to_instructions,
     },
     trace::exe_trace::{
         CpuExePiece, CpuPieceAuxSCCall, ExeTraceStepDiff, MemExePiece, PoseidonPiece, RcExePiece,
@@ -23,15 +24,18 @@ use core::{
         },
     },
 };
-use std::{collections::HashMap, vec};
+use std::{collections::HashMap, sync::Arc, vec};
 
 use anyhow::Ok;
-use interpreter::{interpreter::Interpreter, utils::number::NumberRet};
-use regex::Regex;
+use interpreter::{error::ErrorStage, native::NATIVE_PROPHETS, utils::number::NumberRet};
 
 use crate::{
-    config::ExecuteMode, ecdsa::msg_ecdsa_verify, exe_trace::tx::TxTraceManager,
-    ola_storage::OlaCachedStorage, tx_exe_manager::TxEventManager,
+    config::{ExecuteMode, ProphetLimits},
+    ecdsa::msg_ecdsa_verify,
+    exe_trace::tx::TxTraceManager,
+    ola_storage::OlaCachedStorage,
+    prophet_cache::{compiled_prophets, with_heap_ptr, CompiledProphets},
+    tx_exe_manager::TxEventManager,
 };
 
 const MAX_CLK: u64 = 1000_000_000_000;
@@ -53,6 +57,8 @@ pub(crate) struct OlaContractExecutor {
     registers: [u64; NUM_GENERAL_PURPOSE_REGISTER],
     memory: OlaMemory,
     instructions: HashMap<u64, BinaryInstruction>,
+    prophets: Arc<CompiledProphets>,
+    prophet_limits: ProphetLimits,
     output: Vec<u64>,
     state: OlaContractExecutorState,
 }
@@ -61,9 +67,11 @@ impl OlaContractExecutor {
     pub fn new(
         mode: ExecuteMode,
         context: ExeContext,
-        program: BinaryProgram,
+        program: CompactProgram,
+        prophet_limits: ProphetLimits,
     ) -> anyhow::Result<Self> {
-        let instructions = decode_binary_program_to_instructions(program);
+        let prophets = compiled_prophets(&program)?;
+        let instructions = decode_compact_program_to_instructions(&program);
         match instructions {
             Result::Ok(instruction_vec) => {
                 if instruction_vec.is_empty() {
@@ -89,6 +97,8 @@ impl OlaContractExecutor {
                     registers: [0; NUM_GENERAL_PURPOSE_REGISTER],
                     memory,
                     instructions,
+                    prophets,
+                    prophet_limits,
                     output: vec![],
                     state: OlaContractExecutorState::Running,
                 })
@@ -300,6 +310,7 @@ impl OlaContractExecutor {
         storage: &mut OlaCachedStorage,
     ) -> anyhow::Result<Option<ExeTraceStepDiff>> {
         let tp = tape.tp();
+        let (pc, clk) = (self.pc, self.clk);
         let prophet_attached = instruction.prophet.clone();
         let (state_diff, mut trace_diff) = match instruction.opcode {
             OlaOpcode::ADD
@@ -333,7 +344,8 @@ impl OlaContractExecutor {
         self.apply_state_diff(tape, storage, state_diff)?;
 
         if let Some(prophet) = prophet_attached {
-            let (exe_mem_diffs, trace_mem_diffs) = self.process_prophet(prophet)?;
+            let (exe_mem_diffs, trace_mem_diffs) =
+                self.process_prophet(prophet, tx_event_manager, pc, clk)?;
             let state_diff = vec![OlaStateDiff::Memory(exe_mem_diffs)];
             self.apply_state_diff(tape, storage, state_diff)?;
             if trace_diff.is_some() {
@@ -1635,24 +1647,59 @@ impl OlaContractExecutor {
     fn process_prophet(
         &mut self,
         prophet: OlaProphet,
+        tx_event_manager: &mut TxEventManager,
+        pc: u64,
+        clk: u64,
     ) -> anyhow::Result<(Vec<MemoryDiff>, Vec<MemExePiece>)> {
-        let re = Regex::new(r"^%\{([\s\S]*)%}$").map_err(|err| {
-            ProcessorError::RegexNewError(format!("Regex::new failed with err: {}", err))
-        })?;
-        let code = re
-            .captures(&prophet.code)
-            .ok_or(ProcessorError::RegexCaptureError(String::from(
-                "Regex capture failed in prophet code",
-            )))?
-            .get(1)
-            .ok_or(ProcessorError::ArrayIndexError(String::from(
-                "Empty data at index 1 in prophet.code",
-            )))?
-            .as_str();
-        let mut interpreter = Interpreter::new(code);
+        let flatten_inputs = self.load_prophet_inputs(&prophet)?;
+        let mut logs = vec![];
+        let values: Vec<u64> = match prophet.native_id() {
+            Some(id) => {
+                let values = NATIVE_PROPHETS
+                    .run(id, &flatten_inputs, &self.memory)
+                    .map_err(ProcessorError::NativeProphetError)?;
+                let expected: usize = prophet.outputs.iter().map(|output| output.length).sum();
+                if values.len() != expected {
+                    return Err(ProcessorError::NativeProphetError(format!(
+                        "{} returned {} values, {} outputs declared",
+                        id,
+                        values.len(),
+                        expected
+                    ))
+                    .into());
+                }
+                values
+            }
+            None => self.run_interpreted_prophet(&prophet, flatten_inputs, &mut logs)?,
+        };
+        for message in logs {
+            tx_event_manager.on_prophet_log(self.context.code_addr, pc, clk, message);
+        }
+
+        self.psp_start = self.memory.psp();
+        let mut exe_diffs: Vec<MemoryDiff> = vec![];
+        let mut trace_diffs: Vec<MemExePiece> = vec![];
+        for value in values {
+            let exe_diff = MemoryDiff {
+                addr: self.memory.psp(),
+                value,
+            };
+            let trace_diff = MemExePiece {
+                clk: 0,
+                addr: self.memory.psp(),
+                value,
+                is_write: true,
+                opcode: None,
+            };
+            exe_diffs.push(exe_diff);
+            trace_diffs.push(trace_diff);
+        }
+        Ok((exe_diffs, trace_diffs))
+    }
 
+    fn load_prophet_inputs(&self, prophet: &OlaProphet) -> anyhow::Result<Vec<u64>> {
         let mut flatten_inputs: Vec<u64> = vec![];
-        for input in prophet.clone().inputs {
+        for input in prophet.inputs.iter() {
             // reg 1,2,3 then memory mode, -3, -4, -5...(count from -3)
             let enqueued_len = flatten_inputs.len();
             let origin_values: Vec<u64> = if enqueued_len < 3 && enqueued_len + input.length < 3 {
@@ -1694,36 +1741,56 @@ impl OlaContractExecutor {
                 flatten_inputs.extend(origin_values);
             };
         }
-        let mut cloned = prophet.clone();
-        cloned.ctx.push(("_heap_ptr".to_string(), self.memory.hp()));
+        Ok(flatten_inputs)
+    }
+
+    fn run_interpreted_prophet(
+        &self,
+        prophet: &OlaProphet,
+        flatten_inputs: Vec<u64>,
+        logs: &mut Vec<String>,
+    ) -> anyhow::Result<Vec<u64>> {
+        let interpreter =
+            self.prophets
+                .get(prophet.host)
+                .ok_or(ProcessorError::ProphetCompileError(format!(
+                    "prophet at {} is not compiled",
+                    prophet.host
+                )))?;
+        // production invokes skip `printf` and still evaluate `assert`, but
+        // capture no logs
+        let logs = match self.mode {
+            ExecuteMode::Invoke => None,
+            _ => Some(logs),
+        };
         let out = interpreter
-            .run(&cloned, flatten_inputs, &self.memory)
-            .map_err(|err| ProcessorError::InterpreterRunError(err))?;
-        let mut exe_diffs: Vec<MemoryDiff> = vec![];
-        let mut trace_diffs: Vec<MemExePiece> = vec![];
+            .execute_with_logs(
+                &with_heap_ptr(prophet, self.memory.hp()),
+                flatten_inputs,
+                &self.memory,
+                &self.prophet_limits,
+                logs,
+            )
+            .map_err(|err| match err.stage {
+                ErrorStage::Limit => ProcessorError::ProphetLimitExceeded(format!(
+                    "prophet at {}: {}",
+                    prophet.host, err
+                )),
+                _ => ProcessorError::InterpreterRunError(err.to_string()),
+            })?;
         match out {
-            NumberRet::Single(_) => return Err(ProcessorError::ParseIntError.into()),
+            NumberRet::Single(_) => Err(ProcessorError::ParseIntError.into()),
             NumberRet::Multiple(mut values) => {
-                self.psp_start = self.memory.psp();
+                // the heap pointer comes back last
                 let _ = values.pop();
-                for value in values {
-                    let exe_diff = MemoryDiff {
-                        addr: self.memory.psp(),
-                        value: value.get_number() as u64,
-                    };
-                    let trace_diff = MemExePiece {
-                        clk: 0,
-                        addr: self.memory.psp(),
-                        value: value.get_number() as u64,
-                        is_write: true,
-                        opcode: None,
-                    };
-                    exe_diffs.push(exe_diff);
-                    trace_diffs.push(trace_diff);
-                }
+                let values = values
+                    .iter()
+                    .map(|value| value.get_number().map(|value| value as u64))
+                    .collect::<Result<Vec<u64>, String>>()
+                    .map_err(ProcessorError::InterpreterRunError)?;
+                Ok(values)
             }
         }
-        Ok((exe_diffs, trace_diffs))
     }
 
     fn is_trace_needed(&self) -> bool {
@@ -0,0 +1,750 @@
+//! Runs one program through the legacy [`Process`] and through
+//! [`TxExeManager`] with the same block and transaction, and reports where
+//! the two disagree before the legacy path is retired.
+//!
+//! Traces are compared per table after reducing both sides to the columns
+//! they share. Cross-contract calls are only followed by the contract
+//! executor, so programs using `sccall` are reported as a legacy error.
+
+use crate::batch_exe_manager::BlockExeInfo;
+use crate::config::ExecuteMode;
+use crate::load_tx::init_tape;
+use crate::ola_storage::{DiskStorageWriter, OlaCachedStorage};
+use crate::tx_exe_manager::{OlaTapeInitInfo, TxExeManager};
+use crate::{BatchCacheManager, Process};
+use core::merkle_tree::log::StorageLogKind;
+use core::program::binary_program::BinaryProgram;
+use core::program::Program;
+use core::state::state_storage::StateStorage;
+use core::types::merkle_tree::encode_addr;
+use core::vm::error::ProcessorError;
+use core::vm::hardware::{ContractAddress, StorageAccessKind};
+use core::vm::memory::PSP_START_ADDR;
+use core::vm::opcodes::OlaOpcode;
+use core::vm::transaction::TxCtxInfo;
+use core::vm::vm_state::VMState;
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::{Field, PrimeField64};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::mem::discriminant;
+
+// When nothing was returned the legacy `end` takes the last tape word, the
+// callee address' last limb, as the return data length.
+const CONTRACT_ADDRESS: ContractAddress = [0, 0, 4096, 0];
+const ORIGIN_ADDRESS: ContractAddress = [2001, 2002, 2003, 2004];
+const BLOCK: BlockExeInfo = BlockExeInfo {
+    block_number: 3,
+    block_timestamp: 1692846754,
+    sequencer_address: [1001, 1002, 1003, 1004],
+    chain_id: 1027,
+};
+const TX_VERSION: u64 = 0;
+
+type Row = Vec<u64>;
+
+/// What one executor observed, every table already reduced to shared
+/// columns.
+#[derive(Debug, Default)]
+pub(crate) struct Observed {
+    tables: BTreeMap<&'static str, Vec<Row>>,
+}
+
+impl Observed {
+    fn table(&mut self, name: &'static str, rows: Vec<Row>) {
+        self.tables.insert(name, rows);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Divergence {
+    pub table: &'static str,
+    /// First differing row, `None` when only the row counts differ.
+    pub row: Option<usize>,
+    pub legacy: String,
+    pub contract: String,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.row {
+            Some(row) => write!(
+                f,
+                "{}[{}]: legacy {}, contract executor {}",
+                self.table, row, self.legacy, self.contract
+            ),
+            None => write!(
+                f,
+                "{}: legacy {}, contract executor {}",
+                self.table, self.legacy, self.contract
+            ),
+        }
+    }
+}
+
+/// Why an executor stopped before `end`.
+#[derive(Debug)]
+pub(crate) enum Failure {
+    /// Compared by variant, the two executors word their messages
+    /// differently.
+    Vm(ProcessorError),
+    Panic,
+    /// Failures of the harness itself, never an agreement.
+    Other(String),
+}
+
+impl Failure {
+    fn same_kind(&self, other: &Failure) -> bool {
+        match (self, other) {
+            (Failure::Vm(left), Failure::Vm(right)) => discriminant(left) == discriminant(right),
+            (Failure::Panic, Failure::Panic) => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<ProcessorError> for Failure {
+    fn from(err: ProcessorError) -> Self {
+        Failure::Vm(err)
+    }
+}
+
+impl From<anyhow::Error> for Failure {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<ProcessorError>() {
+            Ok(err) => Failure::Vm(err),
+            Err(err) => Failure::Other(err.to_string()),
+        }
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::Vm(err) => write!(f, "{:?}", err),
+            Failure::Panic => write!(f, "panicked"),
+            Failure::Other(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Runs `program` with `calldata` through both executors. Both failing
+/// counts as agreement only when they fail with the same kind of error.
+pub(crate) fn diff_program(program: &BinaryProgram, calldata: &[u64]) -> Vec<Divergence> {
+    let legacy = catch_panic(|| run_legacy(program, calldata));
+    let contract = catch_panic(|| run_contract_executor(program, calldata).map_err(Failure::from));
+    match (legacy, contract) {
+        (Ok(legacy), Ok(contract)) => diff_observed(&legacy, &contract),
+        (Err(legacy), Err(contract)) if legacy.same_kind(&contract) => vec![],
+        (legacy, contract) => vec![Divergence {
+            table: "result",
+            row: None,
+            legacy: describe_result(&legacy),
+            contract: describe_result(&contract),
+        }],
+    }
+}
+
+// Both executors still index the tape and memory with unchecked arithmetic.
+fn catch_panic(run: impl FnOnce() -> Result<Observed, Failure>) -> Result<Observed, Failure> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(run)).unwrap_or(Err(Failure::Panic))
+}
+
+fn describe_result(result: &Result<Observed, Failure>) -> String {
+    match result {
+        Ok(_) => "ok".to_string(),
+        Err(err) => format!("error: {}", err),
+    }
+}
+
+fn diff_observed(legacy: &Observed, contract: &Observed) -> Vec<Divergence> {
+    let empty: Vec<Row> = vec![];
+    let mut divergences: Vec<Divergence> = vec![];
+    for (table, legacy_rows) in &legacy.tables {
+        let contract_rows = contract.tables.get(table).unwrap_or(&empty);
+        let first = legacy_rows
+            .iter()
+            .zip(contract_rows.iter())
+            .position(|(legacy_row, contract_row)| legacy_row != contract_row);
+        if let Some(row) = first {
+            divergences.push(Divergence {
+                table,
+                row: Some(row),
+                legacy: format!("{:?}", legacy_rows[row]),
+                contract: format!("{:?}", contract_rows[row]),
+            });
+        } else if legacy_rows.len() != contract_rows.len() {
+            divergences.push(Divergence {
+                table,
+                row: None,
+                legacy: format!("{} rows", legacy_rows.len()),
+                contract: format!("{} rows", contract_rows.len()),
+            });
+        }
+    }
+    divergences
+}
+
+fn tx_info(calldata: &[u64]) -> OlaTapeInitInfo {
+    OlaTapeInitInfo {
+        version: TX_VERSION,
+        origin_address: ORIGIN_ADDRESS,
+        calldata: calldata.to_vec(),
+        nonce: None,
+        signature_r: None,
+        signature_s: None,
+        tx_hash: None,
+    }
+}
+
+// Same tape contents `TxExeManager::init_tape` writes.
+fn legacy_ctx_info() -> TxCtxInfo {
+    TxCtxInfo {
+        block_number: fe(BLOCK.block_number),
+        block_timestamp: fe(BLOCK.block_timestamp),
+        sequencer_address: fe4(BLOCK.sequencer_address),
+        version: fe(TX_VERSION),
+        chain_id: fe(BLOCK.chain_id),
+        caller_address: fe4(ORIGIN_ADDRESS),
+        nonce: GoldilocksField::ZERO,
+        signature_r: [GoldilocksField::ZERO; 4],
+        signature_s: [GoldilocksField::ZERO; 4],
+        tx_hash: [GoldilocksField::ZERO; 4],
+    }
+}
+
+fn run_legacy(program: &BinaryProgram, calldata: &[u64]) -> Result<Observed, Failure> {
+    let mut legacy_program = Program {
+        instructions: program.bytecode.split('\n').map(String::from).collect(),
+        trace: Default::default(),
+        debug_info: None,
+        print_flag: false,
+        prophets: program
+            .prophets
+            .iter()
+            .map(|prophet| (prophet.host as u64, prophet.clone()))
+            .collect(),
+        pre_exe_flag: false,
+    };
+    let code: Vec<GoldilocksField> = program
+        .bytecode_u64s()
+        .map_err(|err| Failure::Other(err.to_string()))?
+        .into_iter()
+        .map(fe)
+        .collect();
+    let address = fe4(CONTRACT_ADDRESS);
+    legacy_program
+        .trace
+        .addr_program_hash
+        .insert(encode_addr(&address), code);
+
+    let mut process = Process::new();
+    process.block_timestamp = BLOCK.block_timestamp;
+    init_tape(
+        &mut process,
+        calldata.iter().cloned().map(fe).collect(),
+        fe4(ORIGIN_ADDRESS),
+        address,
+        address,
+        &legacy_ctx_info(),
+    );
+    process.addr_code = address;
+    process.addr_storage = address;
+
+    let state = process.execute(
+        &mut legacy_program,
+        &StateStorage::new_test(),
+        &mut BatchCacheManager::default(),
+    )?;
+    if let VMState::SCCall(_) = state {
+        return Err(Failure::Other(
+            "sccall is not followed by the legacy harness".to_string(),
+        ));
+    }
+    let trace = &legacy_program.trace;
+
+    let mut observed = Observed::default();
+    observed.table(
+        "return_data",
+        vec![process.return_data.iter().map(|v| v.0).collect()],
+    );
+    observed.table(
+        "storage",
+        process
+            .storage_queries
+            .iter()
+            .map(|query| {
+                let mut row = vec![(query.kind != StorageLogKind::Read) as u64];
+                row.extend(query.contract_addr.iter().map(|v| v.0));
+                row.extend(query.storage_key.iter().map(|v| v.0));
+                row.extend(query.value.iter().map(|v| v.0));
+                row
+            })
+            .collect(),
+    );
+    // the legacy executor has no `event` opcode
+    observed.table("events", vec![]);
+    observed.table(
+        "cpu",
+        trace
+            .exec
+            .iter()
+            .map(|step| {
+                let mut row = vec![step.clk as u64, step.pc, step.opcode.0, step.is_ext_line.0];
+                row.extend(step.regs.iter().map(|v| v.to_canonical_u64()));
+                row
+            })
+            .collect(),
+    );
+    observed.table(
+        "memory",
+        sorted(
+            trace
+                .memory
+                .iter()
+                .map(|cell| {
+                    vec![
+                        cell.addr.0,
+                        cell.clk.0,
+                        cell.is_write.0,
+                        cell.value.to_canonical_u64(),
+                    ]
+                })
+                .collect(),
+        ),
+    );
+    observed.table(
+        "rc",
+        trace
+            .builtin_rangecheck
+            .iter()
+            .filter(|row| row.filter_looked_for_cpu.is_one())
+            .map(|row| vec![row.val.0])
+            .collect(),
+    );
+    observed.table(
+        "bitwise",
+        trace
+            .builtin_bitwise_combined
+            .iter()
+            .map(|row| vec![row.opcode, row.op0.0, row.op1.0, row.res.0])
+            .collect(),
+    );
+    observed.table(
+        "cmp",
+        trace
+            .builtin_cmp
+            .iter()
+            .map(|row| vec![row.op0.0, row.op1.0, row.gte.0])
+            .collect(),
+    );
+    observed.table(
+        "poseidon",
+        trace
+            .builtin_poseidon_chunk
+            .iter()
+            .filter(|row| row.acc_cnt.is_zero())
+            .map(|row| vec![row.clk as u64, row.op0.0, row.op1.0, row.dst.0])
+            .collect(),
+    );
+    observed.table(
+        "tape",
+        sorted(
+            trace
+                .tape
+                .iter()
+                .map(|row| vec![row.addr.0, row.opcode.0, row.value.0])
+                .collect(),
+        ),
+    );
+    Ok(observed)
+}
+
+fn run_contract_executor(program: &BinaryProgram, calldata: &[u64]) -> anyhow::Result<Observed> {
+    let db_dir = tempfile::tempdir()?;
+    let db_path = db_dir.path().display().to_string();
+    DiskStorageWriter::new(db_path.clone())?.save_program(program.clone(), CONTRACT_ADDRESS)?;
+    let mut storage = OlaCachedStorage::new(db_path, Some(BLOCK.block_timestamp))?;
+    let result = TxExeManager::new(
+        ExecuteMode::Invoke,
+        BLOCK,
+        tx_info(calldata),
+        &mut storage,
+        CONTRACT_ADDRESS,
+        0,
+    )
+    .invoke()?;
+    let trace = &result.trace;
+    let end_mask = OlaOpcode::END.binary_bit_mask();
+
+    let mut observed = Observed::default();
+    observed.table(
+        "return_data",
+        vec![trace
+            .tape
+            .iter()
+            .filter(|piece| piece.opcode == Some(OlaOpcode::TSTORE))
+            .map(|piece| piece.value)
+            .collect()],
+    );
+    observed.table(
+        "storage",
+        result
+            .storage_access_logs
+            .iter()
+            .map(|log| {
+                let mut row = vec![(log.kind != StorageAccessKind::Read) as u64];
+                row.extend(log.contract_addr);
+                row.extend(log.storage_key);
+                row.extend(log.value.or(log.pre_value).unwrap_or_default());
+                row
+            })
+            .collect(),
+    );
+    observed.table(
+        "events",
+        result
+            .events
+            .iter()
+            .map(|event| {
+                let mut row: Row = event.topics.iter().flatten().cloned().collect();
+                row.extend(event.data.iter());
+                row
+            })
+            .collect(),
+    );
+    observed.table(
+        "cpu",
+        trace
+            .cpu
+            .iter()
+            .flat_map(|(_, _, _, pieces)| pieces.iter())
+            // the legacy trace has no ext line for the entry contract's `end`
+            .filter(|piece| !(piece.is_ext_line && piece.opcode == end_mask))
+            .map(|piece| {
+                let mut row = vec![piece.clk, piece.pc, piece.opcode, piece.is_ext_line as u64];
+                row.extend(piece.registers);
+                row
+            })
+            .collect(),
+    );
+    observed.table(
+        "memory",
+        sorted(
+            trace
+                .env_mem
+                .values()
+                .flatten()
+                // the legacy memory table drops the heap pointer's initial
+                // write, prophet outputs are the only other writes without an
+                // opcode
+                .filter(|piece| piece.opcode.is_some() || piece.addr >= PSP_START_ADDR)
+                .map(|piece| vec![piece.addr, piece.clk, piece.is_write as u64, piece.value])
+                .collect(),
+        ),
+    );
+    observed.table(
+        "rc",
+        trace
+            .rc
+            .iter()
+            .map(|piece| vec![piece.value as u64])
+            .collect(),
+    );
+    observed.table(
+        "bitwise",
+        trace
+            .bitwise
+            .iter()
+            .map(|piece| {
+                vec![
+                    piece.opcode.binary_bit_mask(),
+                    piece.op0 as u64,
+                    piece.op1 as u64,
+                    piece.res as u64,
+                ]
+            })
+            .collect(),
+    );
+    observed.table(
+        "cmp",
+        trace
+            .cmp
+            .iter()
+            .map(|piece| vec![piece.op0 as u64, piece.op1 as u64, piece.is_gte as u64])
+            .collect(),
+    );
+    observed.table(
+        "poseidon",
+        trace
+            .poseidon
+            .iter()
+            .map(|piece| vec![piece.clk, piece.src_addr, piece.len, piece.dst_addr])
+            .collect(),
+    );
+    observed.table(
+        "tape",
+        sorted(
+            trace
+                .tape
+                .iter()
+                .map(|piece| {
+                    vec![
+                        piece.addr,
+                        piece.opcode.map_or(0, |opcode| opcode.binary_bit_mask()),
+                        piece.value,
+                    ]
+                })
+                .collect(),
+        ),
+    );
+    Ok(observed)
+}
+
+fn sorted(mut rows: Vec<Row>) -> Vec<Row> {
+    rows.sort();
+    rows
+}
+
+fn fe(value: u64) -> GoldilocksField {
+    GoldilocksField::from_canonical_u64(value)
+}
+
+fn fe4(values: [u64; 4]) -> [GoldilocksField; 4] {
+    values.map(fe)
+}
+
+mod tests {
+    use super::diff_program;
+    use assembler::encoder::{encode_asm_from_json_file, encode_asm_from_json_string};
+    use assembler::test_data_generator::random_asm_program;
+    use core::program::binary_program::BinaryProgram;
+    use ola_lang_abi::{Abi, Value};
+    use std::collections::BTreeSet;
+    use std::fs::File;
+
+    /// Tables the contract executor leaves empty, its instructions never
+    /// emit bitwise or comparison builtin rows.
+    const CONTRACT_EXECUTOR_GAPS: &[&str] = &["bitwise", "cmp"];
+
+    /// Programs under `assembler/test_data` with their calldata and the
+    /// tables besides `CONTRACT_EXECUTOR_GAPS` the two executors currently
+    /// disagree on, "result" when only one of them ran to `end` or both
+    /// failed with different errors. The legacy tape table also records
+    /// `tload` reads. Contracts with an ABI under `executor/test` are in
+    /// `ABI_TEST_DATA`. The system contracts, `sccall_caller` and `vote` are
+    /// left out, they only reach `end` when called by another contract or
+    /// with calldata from an ABI the repo does not have, as are the asm files
+    /// the assembler rejects.
+    const TEST_DATA: &[(&str, &[u64], &[&str])] = &[
+        ("asm/bitwise.json", &[], &[]),
+        ("asm/call.json", &[], &[]),
+        ("asm/comparison.json", &[], &[]),
+        ("asm/context_fetch.json", &[0, 3458276513], &[]),
+        ("asm/fib_asm.json", &[], &[]),
+        ("asm/fibo_loop.json", &[10, 1, 2, 1015130275], &[]),
+        ("asm/fibo_recursive.json", &[], &[]),
+        ("asm/global.json", &[0, 4171824493], &["tape"]),
+        ("asm/malloc.json", &[], &[]),
+        ("asm/mem_gep.json", &[], &[]),
+        ("asm/mem_gep_vector.json", &[], &[]),
+        ("asm/memory.json", &[], &[]),
+        ("asm/poseidon.json", &[], &[]),
+        ("asm/poseidon_hash.json", &[0, 1239976900], &[]),
+        (
+            "asm/printf.json",
+            &[5, 111, 108, 97, 118, 109, 11, 12, 8, 3238128773],
+            &[],
+        ),
+        ("asm/ptr_call.json", &[0, 2657046596], &[]),
+        ("asm/range_check.json", &[], &[]),
+        ("asm/sc_input.json", &[10, 20, 2, 253268590], &[]),
+        (
+            "asm/sccall/sccall_callee.json",
+            &[5, 11, 2, 2062500454],
+            &[],
+        ),
+        ("asm/sqrt_prophet_asm.json", &[], &[]),
+        ("asm/storage.json", &[], &[]),
+        ("asm/storage_multi_keys.json", &[], &[]),
+        ("asm/storage_u32.json", &[0, 2364819430], &[]),
+        ("asm/tape.json", &[], &["tape"]),
+        ("bin/storage_u32_bin.json", &[0, 2364819430], &[]),
+        ("bin/u256_basic_bin.json", &[0, 2590488802], &[]),
+    ];
+
+    /// Program, ABI file, function index, arguments and expected
+    /// divergences of a contract called through its ABI.
+    type AbiCall = (
+        &'static str,
+        &'static str,
+        usize,
+        fn() -> Vec<Value>,
+        &'static [&'static str],
+    );
+
+    fn create_book() -> Vec<Value> {
+        vec![Value::U32(12), Value::String("hello".to_string())]
+    }
+
+    fn erc20_constructor() -> Vec<Value> {
+        vec![
+            Value::String("OlaToken".to_string()),
+            Value::String("OLA".to_string()),
+            Value::U32(2),
+            Value::U32(1000000000),
+        ]
+    }
+
+    const ABI_TEST_DATA: &[AbiCall] = &[
+        ("bin/books_bin.json", "books_abi.json", 0, create_book, &[]),
+        (
+            "bin/erc20_bin.json",
+            "erc20_abi.json",
+            0,
+            erc20_constructor,
+            &[],
+        ),
+        (
+            "bin/storage_u256_bin.json",
+            "storage_u256_abi.json",
+            0,
+            Vec::new,
+            &[],
+        ),
+    ];
+
+    /// Same as `ABI_TEST_DATA` for the asm sources, run with `--ignored`.
+    const SLOW_ABI_TEST_DATA: &[AbiCall] = &[
+        ("asm/books.json", "books_abi.json", 0, create_book, &[]),
+        ("asm/books_asm.json", "books_abi.json", 0, create_book, &[]),
+        (
+            "asm/erc20_asm.json",
+            "erc20_abi.json",
+            0,
+            erc20_constructor,
+            &[],
+        ),
+        (
+            "asm/storage_u256_asm.json",
+            "storage_u256_abi.json",
+            0,
+            Vec::new,
+            &[],
+        ),
+    ];
+
+    /// Same as `TEST_DATA` for programs that are slow to assemble in a debug
+    /// build, run with `--ignored`.
+    const SLOW_TEST_DATA: &[(&str, &[u64], &[&str])] = &[
+        // the executors disagree on the `sigcheck` opcode bit
+        (
+            "asm/ecdsa_asm.json",
+            &[0, 370402988],
+            &["cpu", "memory", "tape"],
+        ),
+        ("asm/hash_asm.json", &[0, 2051797338], &[]),
+        ("asm/u256_basic_asm.json", &[0, 2590488802], &[]),
+        (
+            "asm/vote_simple_asm.json",
+            &[7, 1, 2, 3, 4, 5, 6, 7, 8, 3826510503],
+            &[],
+        ),
+    ];
+
+    fn load(path: &str) -> BinaryProgram {
+        let path = format!(
+            "{}/../assembler/test_data/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            path
+        );
+        if path.contains("/bin/") {
+            let json = std::fs::read_to_string(&path).unwrap();
+            serde_json::from_str(&json).unwrap()
+        } else {
+            encode_asm_from_json_file(path).unwrap()
+        }
+    }
+
+    fn abi_calldata(abi: &str, function: usize, args: &[Value]) -> Vec<u64> {
+        let path = format!("{}/test/contracts-abi/{}", env!("CARGO_MANIFEST_DIR"), abi);
+        let abi: Abi = serde_json::from_reader(File::open(path).unwrap()).unwrap();
+        let signature = abi.functions[function].signature();
+        abi.encode_input_with_signature(signature.as_str(), args)
+            .unwrap()
+    }
+
+    fn check_divergences(
+        path: &str,
+        calldata: &[u64],
+        expected: &[&str],
+        unexpected: &mut Vec<String>,
+    ) {
+        let divergences = diff_program(&load(path), calldata);
+        let tables: BTreeSet<&str> = divergences
+            .iter()
+            .map(|d| d.table)
+            .filter(|table| !CONTRACT_EXECUTOR_GAPS.contains(table))
+            .collect();
+        if tables != expected.iter().cloned().collect() {
+            unexpected.extend(divergences.iter().map(|d| format!("{}: {}", path, d)));
+            unexpected.push(format!("{}: expected divergences in {:?}", path, expected));
+        }
+    }
+
+    fn check_test_data(programs: &[(&str, &[u64], &[&str])]) {
+        let mut unexpected = vec![];
+        for (path, calldata, expected) in programs {
+            check_divergences(path, calldata, expected, &mut unexpected);
+        }
+        assert!(unexpected.is_empty(), "{}", unexpected.join("\n"));
+    }
+
+    fn check_abi_test_data(programs: &[AbiCall]) {
+        let mut unexpected = vec![];
+        for (path, abi, function, args, expected) in programs {
+            let calldata = abi_calldata(abi, *function, &args());
+            check_divergences(path, &calldata, expected, &mut unexpected);
+        }
+        assert!(unexpected.is_empty(), "{}", unexpected.join("\n"));
+    }
+
+    #[test]
+    fn test_diff_test_data() {
+        check_test_data(TEST_DATA);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_diff_slow_test_data() {
+        check_test_data(SLOW_TEST_DATA);
+    }
+
+    #[test]
+    fn test_diff_abi_test_data() {
+        check_abi_test_data(ABI_TEST_DATA);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_diff_slow_abi_test_data() {
+        check_abi_test_data(SLOW_ABI_TEST_DATA);
+    }
+
+    #[test]
+    fn test_diff_random_programs() {
+        for seed in 0..8 {
+            let json = random_asm_program(seed, 40);
+            let program = encode_asm_from_json_string(json.clone()).unwrap();
+            for divergence in diff_program(&program, &[]) {
+                assert!(
+                    CONTRACT_EXECUTOR_GAPS.contains(&divergence.table),
+                    "seed {}: {}\n{}",
+                    seed,
+                    divergence,
+                    json
+                );
+            }
+        }
+    }
+}
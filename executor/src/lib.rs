@@ -4,6 +4,7 @@ use crate::decode::{decode_raw_instruction, REG_NOT_USED};
 use crate::storage::StorageTree;
 use core::state::state_storage::StateStorage;
 use core::vm::error::ProcessorError;
+use core::vm::hardware::OlaMemory;
 use core::vm::memory::{MemoryTree, HP_START_ADDR, PSP_START_ADDR};
 
 use core::merkle_tree::log::{StorageLog, StorageQuery};
@@ -22,25 +23,26 @@ use core::crypto::poseidon_trace::{
     POSEIDON_OUTPUT_VALUE_LEN,
 };
 use core::program::binary_program::OlaProphet;
-use core::program::binary_program::OlaProphetInput;
 use core::types::account::Address;
 
 use core::types::merkle_tree::TREE_VALUE_LEN;
 use core::types::merkle_tree::{tree_key_default, TreeKey, TreeValue};
 use core::types::storage::StorageKey;
 use core::util::poseidon_utils::POSEIDON_INPUT_NUM;
-use core::vm::heap::HEAP_PTR;
+use interpreter::error::ErrorStage;
+use interpreter::interpreter::limits::ProphetLimits;
 use interpreter::interpreter::Interpreter;
+use interpreter::native::NATIVE_PROPHETS;
 use interpreter::utils::number::NumberRet::{Multiple, Single};
 use log::debug;
 use plonky2::field::goldilocks_field::GoldilocksField;
 use plonky2::field::types::Field64;
 use plonky2::field::types::{Field, PrimeField64};
-use regex::Regex;
 use std::collections::{BTreeMap, HashMap};
 
 use crate::ecdsa::ecdsa_verify;
 use crate::load_tx::append_caller_callee_addr;
+use crate::prophet_cache::with_heap_ptr;
 use crate::tape::TapeTree;
 use crate::trace::{gen_memory_table, gen_tape_table};
 use core::memory_zone_process;
@@ -55,10 +57,13 @@ mod decode;
 pub mod batch_exe_manager;
 pub mod config;
 pub(crate) mod contract_executor;
+#[cfg(test)]
+mod differential;
 mod ecdsa;
 mod exe_trace;
 pub mod load_tx;
 pub mod ola_storage;
+mod prophet_cache;
 pub mod storage;
 mod tape;
 #[cfg(test)]
@@ -193,9 +198,7 @@ macro_rules! tape_copy {
 const FP_REG_INDEX: usize = 9;
 const PROPHET_INPUT_REG_LEN: usize = 3;
 const PROPHET_INPUT_REG_START_INDEX: usize = 1;
-const PROPHET_INPUT_REG_END_INDEX: usize = PROPHET_INPUT_REG_START_INDEX + PROPHET_INPUT_REG_LEN;
 // start from fp-3
-const PROPHET_INPUT_FP_START_OFFSET: u64 = 3;
 const TP_START_ADDR: GoldilocksField = GoldilocksField::ZERO;
 
 #[derive(Default, Debug)]
@@ -336,131 +339,108 @@ impl Process {
         }
     }
 
-    pub fn read_prophet_input(
-        &mut self,
-        input: &OlaProphetInput,
-        reg_cnt: usize,
-        reg_index: &mut usize,
-        fp: &mut u64,
-    ) -> Result<u64, ProcessorError> {
-        let mut value;
-        if *reg_index < reg_cnt {
-            value = self.registers[*reg_index].0;
-            *reg_index += 1;
-        } else {
-            value = self
-                .memory
-                .read(
-                    self.registers[FP_REG_INDEX].0 - *fp,
-                    self.clk,
-                    GoldilocksField::from_canonical_u64(0 as u64),
-                    GoldilocksField::from_canonical_u64(MemoryType::ReadWrite as u64),
-                    GoldilocksField::from_canonical_u64(MemoryOperation::Read as u64),
-                    GoldilocksField::from_canonical_u64(FilterLockForMain::False as u64),
-                    GoldilocksField::ZERO,
-                    GoldilocksField::ZERO,
-                    self.env_idx,
-                )?
-                .to_canonical_u64();
-            *fp += 1;
-        }
-        if input.is_ref {
-            value = self
-                .memory
-                .read(
-                    value,
-                    self.clk,
-                    GoldilocksField::from_canonical_u64(0 as u64),
-                    GoldilocksField::from_canonical_u64(MemoryType::ReadWrite as u64),
-                    GoldilocksField::from_canonical_u64(MemoryOperation::Read as u64),
-                    GoldilocksField::from_canonical_u64(FilterLockForMain::False as u64),
-                    GoldilocksField::ZERO,
-                    GoldilocksField::ZERO,
-                    self.env_idx,
-                )?
-                .to_canonical_u64();
+    /// Runs `prophet` and writes its outputs from `psp`, loading inputs and
+    /// calling the interpreter the same way the contract executor does.
+    /// Inputs are read without adding memory trace rows.
+    pub fn prophet(&mut self, prophet: &mut OlaProphet) -> Result<(), ProcessorError> {
+        let inputs = self.load_prophet_inputs(prophet)?;
+        let memory = self.prophet_memory()?;
+        let values: Vec<u64> = match prophet.native_id() {
+            Some(id) => NATIVE_PROPHETS
+                .run(id, &inputs, &memory)
+                .map_err(ProcessorError::NativeProphetError)?,
+            None => {
+                let prophet = with_heap_ptr(prophet, HP_START_ADDR);
+                let interpreter = Interpreter::compile(&prophet).map_err(|err| {
+                    ProcessorError::ProphetCompileError(format!(
+                        "prophet at {}: {}",
+                        prophet.host, err
+                    ))
+                })?;
+                let out = interpreter
+                    .execute_with_limits(&prophet, inputs, &memory, &ProphetLimits::default())
+                    .map_err(|err| match err.stage {
+                        ErrorStage::Limit => ProcessorError::ProphetLimitExceeded(format!(
+                            "prophet at {}: {}",
+                            prophet.host, err
+                        )),
+                        _ => ProcessorError::InterpreterRunError(err.to_string()),
+                    })?;
+                match out {
+                    Single(_) => return Err(ProcessorError::ParseIntError),
+                    Multiple(mut values) => {
+                        // the heap pointer comes back last
+                        let _ = values.pop();
+                        values
+                            .iter()
+                            .map(|value| value.get_number().map(|value| value as u64))
+                            .collect::<Result<Vec<u64>, String>>()
+                            .map_err(ProcessorError::InterpreterRunError)?
+                    }
+                }
+            }
+        };
+
+        self.psp_start = self.psp;
+        for value in values {
+            self.memory.write(
+                self.psp.0,
+                0,
+                GoldilocksField::ZERO,
+                GoldilocksField::from_canonical_u64(MemoryType::WriteOnce as u64),
+                GoldilocksField::from_canonical_u64(MemoryOperation::Write as u64),
+                GoldilocksField::from_canonical_u64(FilterLockForMain::False as u64),
+                GoldilocksField::ONE,
+                GoldilocksField::ZERO,
+                GoldilocksField::from_canonical_u64(value),
+                self.env_idx,
+            );
+            self.psp += GoldilocksField::ONE;
         }
-        Ok(value)
+        Ok(())
     }
 
-    pub fn prophet(&mut self, prophet: &mut OlaProphet) -> Result<(), ProcessorError> {
-        println!("refactoring, prophet not work now.");
-        // debug!("prophet code:{}", prophet.code);
-
-        // let re = Regex::new(r"^%\{([\s\S]*)%}$").map_err(|err| {
-        //     ProcessorError::RegexNewError(format!("Regex::new failed with err: {}",
-        // err)) })?;
-        // let code = re
-        //     .captures(&prophet.code)
-        //     .ok_or(ProcessorError::RegexCaptureError(String::from(
-        //         "Regex capture failed in prophet code",
-        //     )))?
-        //     .get(1)
-        //     .ok_or(ProcessorError::ArrayIndexError(String::from(
-        //         "Empty data at index 1 in prophet.code",
-        //     )))?
-        //     .as_str();
-        // debug!("code:{}", code);
-        // let mut interpreter = Interpreter::new(code);
-
-        // let mut values = Vec::new();
-
-        // let reg_cnt = PROPHET_INPUT_REG_END_INDEX;
-        // let mut reg_index = PROPHET_INPUT_REG_START_INDEX;
-        // let mut fp = PROPHET_INPUT_FP_START_OFFSET;
-        // for input in prophet.inputs.iter() {
-        //     if input.length == 1 {
-        //         let value = self.read_prophet_input(&input, reg_cnt, &mut reg_index,
-        // &mut fp)?;         values.push(value);
-        //     } else {
-        //         let mut index = 0;
-        //         while index < input.length {
-        //             let value =
-        //                 self.read_prophet_input(&input, reg_cnt, &mut reg_index, &mut
-        // fp)?;             values.push(value);
-        //             index += 1;
-        //         }
-        //     }
-        // }
-
-        // prophet.ctx.push((HEAP_PTR.to_string(), self.hp.0));
-        // let out = interpreter
-        //     .run(prophet, values, &self.memory)
-        //     .map_err(|err| ProcessorError::InterpreterRunError(err))?;
-        // // todo: need process error!
-        // debug!("interpreter:{:?}", out);
-        // match out {
-        //     Single(_) => return Err(ProcessorError::ParseIntError),
-        //     Multiple(mut values) => {
-        //         self.psp_start = self.psp;
-        //         self.hp = GoldilocksField(
-        //             values
-        //                 .pop()
-        //                 .ok_or(ProcessorError::ArrayIndexError(String::from(
-        //                     "Multiple value empty",
-        //                 )))?
-        //                 .get_number() as u64,
-        //         );
-        //         debug!("prophet addr:{}", self.psp.0);
-        //         for value in values {
-        //             self.memory.write(
-        //                 self.psp.0,
-        //                 0, //write， clk is 0
-        //                 GoldilocksField::from_canonical_u64(0 as u64),
-        //                 GoldilocksField::from_canonical_u64(MemoryType::WriteOnce as
-        // u64),
-        // GoldilocksField::from_canonical_u64(MemoryOperation::Write as u64),
-        //                 GoldilocksField::from_canonical_u64(FilterLockForMain::False
-        // as u64),                 GoldilocksField::from_canonical_u64(1_u64),
-        //                 GoldilocksField::from_canonical_u64(0_u64),
-        //                 GoldilocksField(value.get_number() as u64),
-        //                 self.env_idx,
-        //             );
-        //             self.psp += GoldilocksField::ONE;
-        //         }
-        //     }
-        // }
-        Ok(())
+    // Registers 1 to 3, then the stack below `fp - 2`.
+    fn load_prophet_inputs(&self, prophet: &OlaProphet) -> Result<Vec<u64>, ProcessorError> {
+        let fp = self.registers[FP_REG_INDEX].0;
+        let mut inputs = vec![];
+        for input in prophet.inputs.iter() {
+            for _ in 0..input.length {
+                let index = inputs.len();
+                let mut value = if index < PROPHET_INPUT_REG_LEN {
+                    self.registers[PROPHET_INPUT_REG_START_INDEX + index].0
+                } else {
+                    self.peek_memory(fp - index as u64)?
+                };
+                if input.is_ref {
+                    value = self.peek_memory(value)?;
+                }
+                inputs.push(value);
+            }
+        }
+        Ok(inputs)
+    }
+
+    fn peek_memory(&self, addr: u64) -> Result<u64, ProcessorError> {
+        self.memory
+            .trace
+            .get(&addr)
+            .and_then(|cells| cells.last())
+            .map(|cell| cell.value.0)
+            .ok_or(ProcessorError::MemVistInv(addr))
+    }
+
+    // The interpreter reads memory through the contract executor's layout.
+    fn prophet_memory(&self) -> Result<OlaMemory, ProcessorError> {
+        let mut memory = OlaMemory::default();
+        for (addr, cells) in self.memory.trace.iter() {
+            if let Some(cell) = cells.last() {
+                memory
+                    .write(*addr, cell.value.0)
+                    .map_err(|err| ProcessorError::MemoryAccessError(err.to_string()))?;
+            }
+        }
+        Ok(memory)
     }
 
     fn print_vm_state(&mut self, instruction: &str) {
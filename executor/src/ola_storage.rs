@@ -1,6 +1,6 @@
 use core::{
     crypto::poseidon_trace::calculate_arbitrary_poseidon_u64s,
-    program::binary_program::BinaryProgram,
+    program::{binary_program::BinaryProgram, compact::CompactProgram, verifier::verify_program},
     util::converts::{bytes_to_u64s, u64s_to_bytes},
     vm::{
         error::ProcessorError,
@@ -14,9 +14,10 @@ use std::{collections::HashMap, num::NonZeroUsize, path::PathBuf};
 
 use anyhow::{bail, Ok};
 use lru::LruCache;
-use plonky2::hash::utils::poseidon_hash_bytes;
 use rocksdb::{BlockBasedOptions, ColumnFamilyDescriptor, Options, WriteBatch, DB};
 
+use crate::prophet_cache::compiled_prophets;
+
 #[derive(Debug, Clone, Copy)]
 pub enum SequencerColumnFamily {
     State,
@@ -75,9 +76,15 @@ impl DiskStorageWriter {
         contract_addr: ContractAddress,
     ) -> anyhow::Result<()> {
         let prog_hash_treekey = Self::get_program_treekey(contract_addr);
-        let program_bytes = bincode::serialize(&program)?;
+        let compact = CompactProgram::from_binary_program(&program)?;
+        if let Err(violations) = verify_program(&compact) {
+            let report: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+            bail!(ProcessorError::ProgramVerifyError(report.join("\n")))
+        }
+        compiled_prophets(&compact)?;
+        let program_bytes = compact.encode();
 
-        let program_hash = poseidon_hash_bytes(program_bytes.as_ref()).to_vec();
+        let program_hash = compact.hash().to_vec();
         let hash_u64s = bytes_to_u64s(program_hash.clone());
         self.save(
             prog_hash_treekey,
@@ -196,7 +203,7 @@ impl DiskStorageReader {
         }
     }
 
-    pub fn load_program(&self, contract_addr: ContractAddress) -> anyhow::Result<BinaryProgram> {
+    pub fn load_program(&self, contract_addr: ContractAddress) -> anyhow::Result<CompactProgram> {
         let prog_hash_treekey = Self::get_program_treekey(contract_addr);
         let prog_hash = self.load(prog_hash_treekey)?;
         if let Some(hash) = prog_hash {
@@ -209,8 +216,13 @@ impl DiskStorageReader {
                     ProcessorError::ProgLoadError(format!("load program bytes failed: {}", e))
                 })?;
                 if let Some(bytes) = loaded {
-                    let program: BinaryProgram = bincode::deserialize(&bytes)?;
-                    Ok(program)
+                    if CompactProgram::is_compact(&bytes) {
+                        CompactProgram::decode(&bytes)
+                    } else {
+                        // programs saved before the compact container was introduced
+                        let program: BinaryProgram = bincode::deserialize(&bytes)?;
+                        CompactProgram::from_binary_program(&program)
+                    }
                 } else {
                     Err(
                         ProcessorError::ProgLoadError("program bytes not found.".to_string())
@@ -249,7 +261,7 @@ pub struct OlaCachedStorage {
     storage_logs: Vec<StorageAccessLog>,
     tx_storage_logs: Vec<StorageAccessLog>,
     disk_storage_reader: DiskStorageReader,
-    prog_cache: LruCache<ContractAddress, BinaryProgram>,
+    prog_cache: LruCache<ContractAddress, CompactProgram>,
 }
 
 impl OlaCachedStorage {
@@ -321,7 +333,10 @@ impl OlaCachedStorage {
         Ok(())
     }
 
-    pub fn get_program(&mut self, contract_addr: ContractAddress) -> anyhow::Result<BinaryProgram> {
+    pub fn get_program(
+        &mut self,
+        contract_addr: ContractAddress,
+    ) -> anyhow::Result<CompactProgram> {
         let cached = self.prog_cache.get(&contract_addr);
         if let Some(program) = cached {
             return Ok(program.clone());
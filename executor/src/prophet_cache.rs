@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use core::program::binary_program::OlaProphet;
+use core::program::compact::CompactProgram;
+use core::vm::error::ProcessorError;
+use interpreter::interpreter::Interpreter;
+use lru::LruCache;
+use once_cell::sync::Lazy;
+
+pub(crate) const HEAP_PTR_CTX: &str = "_heap_ptr";
+
+const PROPHET_CACHE_SIZE: usize = 50;
+
+static PROPHET_CACHE: Lazy<Mutex<LruCache<[u8; 32], Arc<CompiledProphets>>>> = Lazy::new(|| {
+    Mutex::new(LruCache::new(
+        NonZeroUsize::new(PROPHET_CACHE_SIZE).unwrap(),
+    ))
+});
+
+/// Interpreted prophets of one program, parsed and checked once and shared
+/// by every executor running that program. Native prophets are not kept
+/// here, they are looked up in the native registry when they run.
+pub(crate) struct CompiledProphets {
+    interpreters: HashMap<usize, Interpreter>,
+}
+
+impl CompiledProphets {
+    pub(crate) fn compile(program: &CompactProgram) -> anyhow::Result<Self> {
+        let mut interpreters = HashMap::new();
+        for prophet in program.prophets.iter() {
+            if prophet.native_id().is_some() {
+                continue;
+            }
+            let interpreter = Interpreter::compile(&with_heap_ptr(prophet, 0)).map_err(|err| {
+                ProcessorError::ProphetCompileError(format!("prophet at {}: {}", prophet.host, err))
+            })?;
+            interpreters.insert(prophet.host, interpreter);
+        }
+        Ok(Self { interpreters })
+    }
+
+    pub(crate) fn get(&self, host: usize) -> Option<&Interpreter> {
+        self.interpreters.get(&host)
+    }
+}
+
+/// Compiled prophets of `program`, compiling them on the first load of a
+/// program hash.
+pub(crate) fn compiled_prophets(program: &CompactProgram) -> anyhow::Result<Arc<CompiledProphets>> {
+    if program.prophets.is_empty() {
+        return Ok(Arc::new(CompiledProphets {
+            interpreters: HashMap::new(),
+        }));
+    }
+    let hash = program.hash();
+    if let Some(compiled) = PROPHET_CACHE.lock().unwrap().get(&hash) {
+        return Ok(compiled.clone());
+    }
+    let compiled = Arc::new(CompiledProphets::compile(program)?);
+    PROPHET_CACHE.lock().unwrap().put(hash, compiled.clone());
+    Ok(compiled)
+}
+
+/// Interpreted prophets see the heap pointer as an extra context value.
+pub(crate) fn with_heap_ptr(prophet: &OlaProphet, hp: u64) -> OlaProphet {
+    let mut prophet = prophet.clone();
+    prophet.ctx.push((HEAP_PTR_CTX.to_string(), hp));
+    prophet
+}
@@ -4,12 +4,14 @@ mod tests {
         batch_exe_manager::BlockExeInfo,
         config::*,
         ola_storage::{DiskStorageWriter, OlaCachedStorage},
+        prophet_cache::compiled_prophets,
         tx_exe_manager::{OlaTapeInitInfo, TxExeManager},
     };
     use anyhow::Ok;
     use core::{
         program::{
             binary_program::{BinaryInstruction, BinaryProgram},
+            compact::CompactProgram,
             decoder::decode_binary_program_to_instructions,
         },
         vm::{
@@ -25,6 +27,7 @@ mod tests {
         fs::File,
         io::BufReader,
         path::PathBuf,
+        sync::Arc,
         time::{SystemTime, UNIX_EPOCH},
     };
 
@@ -61,6 +64,143 @@ mod tests {
         println!("events: {:?}", events)
     }
 
+    #[test]
+    fn test_prophet_limits() {
+        let mut writer = get_writer().unwrap();
+        let address = [0, 0, 0, 43985];
+        deploy(&mut writer, "contracts/u256_basic_bin.json", address).unwrap();
+        let mut storage = get_storage().unwrap();
+        let block_info = BlockExeInfo {
+            block_number: 0,
+            block_timestamp: 0,
+            sequencer_address: [1001, 1002, 1003, 1004],
+            chain_id: 1027,
+        };
+        let tx = OlaTapeInitInfo {
+            version: 0,
+            origin_address: [2001, 2002, 2003, 2004],
+            calldata: vec![0u64, 2590488802],
+            nonce: Some(0),
+            signature_r: None,
+            signature_s: None,
+            tx_hash: None,
+        };
+        let mut tx_exe_manager: TxExeManager =
+            TxExeManager::new(ExecuteMode::Debug, block_info, tx, &mut storage, address, 0);
+        tx_exe_manager.set_prophet_limits(ProphetLimits {
+            max_steps: 3,
+            ..ProphetLimits::default()
+        });
+        let err = tx_exe_manager.invoke().unwrap_err().to_string();
+        assert!(err.contains("step limit of 3 exceeded"), "{}", err);
+    }
+
+    #[test]
+    fn test_prophet_logs() {
+        let mut writer = get_writer().unwrap();
+        let address = [0, 0, 0, 43986];
+        deploy(&mut writer, "contracts/u256_basic_bin.json", address).unwrap();
+        let run = |mode: ExecuteMode| {
+            let mut storage = get_storage().unwrap();
+            let block_info = BlockExeInfo {
+                block_number: 0,
+                block_timestamp: 0,
+                sequencer_address: [1001, 1002, 1003, 1004],
+                chain_id: 1027,
+            };
+            let tx = OlaTapeInitInfo {
+                version: 0,
+                origin_address: [2001, 2002, 2003, 2004],
+                calldata: vec![0u64, 2590488802],
+                nonce: Some(0),
+                signature_r: None,
+                signature_s: None,
+                tx_hash: None,
+            };
+            TxExeManager::new(mode, block_info, tx, &mut storage, address, 0)
+                .invoke()
+                .unwrap()
+                .prophet_logs
+        };
+
+        let logs = run(ExecuteMode::Debug);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].address, address);
+        assert!(logs[0].clk > 0);
+        assert_eq!(logs[0].message, "U256: 0xf, limbs:=[0,0,0,0,0,0,0,15]");
+        assert!(run(ExecuteMode::Invoke).is_empty());
+    }
+
+    #[test]
+    fn test_u256_native_prophets() {
+        let mut writer = get_writer().unwrap();
+        let interpreted_address = [0, 0, 0, 43982];
+        deploy(
+            &mut writer,
+            "contracts/u256_basic_bin.json",
+            interpreted_address,
+        )
+        .unwrap();
+
+        let mut path = get_test_dir();
+        path.push("contracts/u256_basic_bin.json");
+        let reader = BufReader::new(File::open(path).unwrap());
+        let mut program: BinaryProgram = serde_json::from_reader(reader).unwrap();
+        for prophet in program.prophets.iter_mut() {
+            let native = [
+                ("function split_hi", "native:split_hi"),
+                ("function split_lo", "native:split_lo"),
+                ("function mod", "native:u64_mod"),
+                ("function div", "native:u64_div"),
+            ]
+            .iter()
+            .find(|(function, _)| prophet.code.contains(function));
+            if let Some((_, id)) = native {
+                prophet.code = id.to_string();
+            }
+        }
+        assert_eq!(
+            program
+                .prophets
+                .iter()
+                .filter(|prophet| prophet.native_id().is_some())
+                .count(),
+            4
+        );
+        let native_address = [0, 0, 0, 43983];
+        writer.save_program(program, native_address).unwrap();
+
+        let calldata = vec![0u64, 2590488802];
+        let interpreted = call(interpreted_address, calldata.clone(), None).unwrap();
+        let native = call(native_address, calldata, None).unwrap();
+        assert_eq!(interpreted, native);
+    }
+
+    #[test]
+    fn test_prophets_compiled_once() {
+        let mut path = get_test_dir();
+        path.push("contracts/u256_basic_bin.json");
+        let reader = BufReader::new(File::open(path).unwrap());
+        let mut program: BinaryProgram = serde_json::from_reader(reader).unwrap();
+        let compact = CompactProgram::from_binary_program(&program).unwrap();
+        let compiled = compiled_prophets(&compact).unwrap();
+        assert!(Arc::ptr_eq(
+            &compiled,
+            &compiled_prophets(&compact).unwrap()
+        ));
+        for prophet in compact.prophets.iter() {
+            assert!(compiled.get(prophet.host).is_some());
+        }
+
+        program.prophets[0].code = program.prophets[0].code.replace("cid.in", "cid.missing");
+        let writer = get_writer().unwrap();
+        let err = writer
+            .save_program(program, [0, 0, 0, 43984])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("cid.missing"), "{}", err);
+    }
+
     #[test]
     fn test_simple_vote() {
         let mut writer = get_writer().unwrap();
@@ -3,7 +3,7 @@ use core::{
     tx::TxResult,
     vm::{
         hardware::{ContractAddress, ExeContext, OlaTape},
-        types::{Event, Hash},
+        types::{Event, Hash, ProphetLog},
     },
 };
 use std::collections::HashMap;
@@ -12,7 +12,7 @@ use anyhow::Ok;
 
 use crate::{
     batch_exe_manager::BlockExeInfo,
-    config::{ExecuteMode, ADDR_U64_ENTRYPOINT, FUNCTION_SELECTOR_SYSTEM_ENTRANCE},
+    config::{ExecuteMode, ProphetLimits, ADDR_U64_ENTRYPOINT, FUNCTION_SELECTOR_SYSTEM_ENTRANCE},
     contract_executor::{OlaContractExecutor, OlaContractExecutorState},
     exe_trace::tx::TxTraceManager,
     ola_storage::OlaCachedStorage,
@@ -41,6 +41,7 @@ pub(crate) struct TxEventManager {
     prev_events_cnt_in_batch: usize,
     biz_contract_address: ContractAddress,
     events: Vec<Event>,
+    prophet_logs: Vec<ProphetLog>,
 }
 
 impl TxEventManager {
@@ -54,6 +55,7 @@ impl TxEventManager {
             prev_events_cnt_in_batch,
             biz_contract_address,
             events: Vec::new(),
+            prophet_logs: Vec::new(),
         }
     }
 
@@ -71,6 +73,15 @@ impl TxEventManager {
             data,
         });
     }
+
+    pub fn on_prophet_log(&mut self, address: ContractAddress, pc: u64, clk: u64, message: String) {
+        self.prophet_logs.push(ProphetLog {
+            address,
+            pc,
+            clk,
+            message,
+        });
+    }
 }
 
 pub struct TxExeManager<'batch> {
@@ -83,6 +94,7 @@ pub struct TxExeManager<'batch> {
     trace_manager: TxTraceManager,
     entry_contract: ContractAddress,
     accessed_bytecodes: HashMap<ContractAddress, Vec<u64>>,
+    prophet_limits: ProphetLimits,
 }
 
 impl<'batch> TxExeManager<'batch> {
@@ -121,12 +133,19 @@ impl<'batch> TxExeManager<'batch> {
             trace_manager: TxTraceManager::default(),
             entry_contract,
             accessed_bytecodes: HashMap::new(),
+            prophet_limits: ProphetLimits::default(),
         };
         let init_values = manager.init_tape(block_info, tx, entry_contract);
         manager.trace_manager.init_tape(init_values);
         manager
     }
 
+    /// Bounds for the interpreted prophets of every contract the transaction
+    /// runs.
+    pub fn set_prophet_limits(&mut self, limits: ProphetLimits) {
+        self.prophet_limits = limits;
+    }
+
     fn init_tape(
         &mut self,
         block_info: BlockExeInfo,
@@ -155,7 +174,7 @@ impl<'batch> TxExeManager<'batch> {
     pub fn invoke(&mut self) -> anyhow::Result<TxResult> {
         let program = self.storage.get_program(self.entry_contract)?;
         self.accessed_bytecodes
-            .insert(self.entry_contract, program.bytecode_u64s()?);
+            .insert(self.entry_contract, program.words.clone());
         let entry_env = OlaContractExecutor::new(
             self.mode,
             ExeContext {
@@ -163,6 +182,7 @@ impl<'batch> TxExeManager<'batch> {
                 code_addr: self.entry_contract,
             },
             program,
+            self.prophet_limits,
         )?;
         self.enqueue_new_env(entry_env);
 
@@ -200,7 +220,7 @@ impl<'batch> TxExeManager<'batch> {
 
                         if !self.accessed_bytecodes.contains_key(&callee_addr) {
                             self.accessed_bytecodes
-                                .insert(callee_addr, callee_program.bytecode_u64s()?);
+                                .insert(callee_addr, callee_program.words.clone());
                         }
                         let callee = OlaContractExecutor::new(
                             self.mode,
@@ -209,6 +229,7 @@ impl<'batch> TxExeManager<'batch> {
                                 code_addr: callee_addr,
                             },
                             callee_program,
+                            self.prophet_limits,
                         )?;
                         self.enqueue_new_env(callee);
                     }
@@ -232,7 +253,7 @@ impl<'batch> TxExeManager<'batch> {
 
                         if !self.accessed_bytecodes.contains_key(&callee_addr) {
                             self.accessed_bytecodes
-                                .insert(callee_addr, callee_program.bytecode_u64s()?);
+                                .insert(callee_addr, callee_program.words.clone());
                         }
                         let callee = OlaContractExecutor::new(
                             self.mode,
@@ -241,6 +262,7 @@ impl<'batch> TxExeManager<'batch> {
                                 code_addr: callee_addr,
                             },
                             callee_program,
+                            self.prophet_limits,
                         )?;
                         self.enqueue_new_env(callee);
                     }
@@ -261,6 +283,7 @@ impl<'batch> TxExeManager<'batch> {
             trace: self.get_tx_trace(),
             storage_access_logs: self.storage.get_tx_storage_access_logs(),
             events: self.tx_event_manager.events.clone(),
+            prophet_logs: self.tx_event_manager.prophet_logs.clone(),
         };
         Ok(result)
     }
@@ -274,6 +297,7 @@ impl<'batch> TxExeManager<'batch> {
                 code_addr: self.entry_contract,
             },
             program,
+            self.prophet_limits,
         )?;
         self.enqueue_new_env(entry_env);
         let mut output: Vec<u64> = vec![];
@@ -301,6 +325,7 @@ impl<'batch> TxExeManager<'batch> {
                                 code_addr: callee_addr,
                             },
                             callee_program,
+                            self.prophet_limits,
                         )?;
                         self.enqueue_new_env(callee);
                     }
@@ -314,6 +339,7 @@ impl<'batch> TxExeManager<'batch> {
                                 code_addr: callee_addr,
                             },
                             callee_program,
+                            self.prophet_limits,
                         )?;
                         self.enqueue_new_env(callee);
                     }
@@ -331,6 +357,12 @@ impl<'batch> TxExeManager<'batch> {
         Ok(output)
     }
 
+    /// Prophet `printf` and `assert` output of the transaction so far,
+    /// for callers of [`TxExeManager::call`].
+    pub fn prophet_logs(&self) -> &[ProphetLog] {
+        &self.tx_event_manager.prophet_logs
+    }
+
     fn pop_env(&mut self) -> Option<(usize, OlaContractExecutor)> {
         if let Some((env_idx, env)) = self.env_stack.pop() {
             if self.mode == ExecuteMode::Debug {
@@ -2,7 +2,7 @@ use core::vm::hardware::{ContractAddress, OlaStorage};
 
 use crate::{
     batch_exe_manager::BlockExeInfo,
-    config::{ExecuteMode, ADDR_U64_ENTRYPOINT},
+    config::{ExecuteMode, ProphetLimits, ADDR_U64_ENTRYPOINT},
     ola_storage::OlaCachedStorage,
     tx_exe_manager::{OlaTapeInitInfo, TxExeManager},
 };
@@ -10,6 +10,7 @@ use crate::{
 pub struct TxPreExecutor {
     block_info: BlockExeInfo,
     storage: OlaCachedStorage,
+    prophet_limits: ProphetLimits,
 }
 
 impl TxPreExecutor {
@@ -30,9 +31,14 @@ impl TxPreExecutor {
         Ok(Self {
             block_info,
             storage,
+            prophet_limits: ProphetLimits::default(),
         })
     }
 
+    pub fn set_prophet_limits(&mut self, limits: ProphetLimits) {
+        self.prophet_limits = limits;
+    }
+
     pub fn invoke(&mut self, tx: OlaTapeInitInfo) -> anyhow::Result<()> {
         self.storage.clear_tx_cache();
         let mut tx_exe_manager: TxExeManager = TxExeManager::new(
@@ -43,6 +49,7 @@ impl TxPreExecutor {
             ADDR_U64_ENTRYPOINT,
             0,
         );
+        tx_exe_manager.set_prophet_limits(self.prophet_limits);
         let _ = tx_exe_manager.invoke()?;
         self.storage.on_tx_success();
         Ok(())
@@ -0,0 +1,68 @@
+#![no_main]
+
+use core::program::binary_program::{OlaProphet, OlaProphetInput, OlaProphetOutput};
+use core::vm::hardware::OlaMemory;
+use interpreter::interpreter::limits::ProphetLimits;
+use interpreter::interpreter::Interpreter;
+use libfuzzer_sys::fuzz_target;
+
+// The first 72 bytes are the values of `cid.x` and `cid.a`, the rest is the
+// prophet body. Bodies that compile must then run to an output or an error,
+// never panic, within small limits so every input finishes quickly.
+const INPUT_LEN: usize = 9;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < INPUT_LEN * 8 {
+        return;
+    }
+    let (values, body) = data.split_at(INPUT_LEN * 8);
+    let body = match std::str::from_utf8(body) {
+        Ok(body) => body,
+        Err(_) => return,
+    };
+    let prophet = OlaProphet {
+        host: 0,
+        code: format!("%{{{}%}}", body),
+        ctx: vec![],
+        inputs: [("cid.x", 1), ("cid.a", 8)]
+            .iter()
+            .map(|(name, length)| OlaProphetInput {
+                name: name.to_string(),
+                length: *length,
+                is_ref: false,
+                is_input_output: false,
+            })
+            .collect(),
+        outputs: [("cid.y", 1), ("cid.b", 8)]
+            .iter()
+            .map(|(name, length)| OlaProphetOutput {
+                name: name.to_string(),
+                length: *length,
+                is_ref: false,
+                is_input_output: false,
+            })
+            .collect(),
+    };
+    let interpreter = match Interpreter::compile(&prophet) {
+        Ok(interpreter) => interpreter,
+        Err(_) => return,
+    };
+    let values = values
+        .chunks(8)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    let limits = ProphetLimits {
+        max_steps: 1 << 12,
+        max_stack_depth: 16,
+        max_alloc_words: 1 << 10,
+        max_output_len: 1 << 10,
+    };
+    let mut logs = vec![];
+    let _ = interpreter.execute_with_logs(
+        &prophet,
+        values,
+        &OlaMemory::default(),
+        &limits,
+        Some(&mut logs),
+    );
+});
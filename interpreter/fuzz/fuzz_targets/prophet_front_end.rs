@@ -0,0 +1,38 @@
+#![no_main]
+
+use core::program::binary_program::{OlaProphet, OlaProphetInput, OlaProphetOutput};
+use interpreter::interpreter::Interpreter;
+use libfuzzer_sys::fuzz_target;
+
+// Lexing, parsing and semantic analysis of arbitrary prophet bodies must
+// fail with an error, never panic or hang.
+fuzz_target!(|data: &[u8]| {
+    let body = match std::str::from_utf8(data) {
+        Ok(body) => body,
+        Err(_) => return,
+    };
+    let prophet = OlaProphet {
+        host: 0,
+        code: format!("%{{{}%}}", body),
+        ctx: vec![],
+        inputs: [("cid.x", 1), ("cid.a", 8)]
+            .iter()
+            .map(|(name, length)| OlaProphetInput {
+                name: name.to_string(),
+                length: *length,
+                is_ref: false,
+                is_input_output: false,
+            })
+            .collect(),
+        outputs: [("cid.y", 1), ("cid.b", 8)]
+            .iter()
+            .map(|(name, length)| OlaProphetOutput {
+                name: name.to_string(),
+                length: *length,
+                is_ref: false,
+                is_input_output: false,
+            })
+            .collect(),
+    };
+    let _ = Interpreter::compile(&prophet);
+});
@@ -0,0 +1,126 @@
+use std::fmt;
+
+/// Line and column, both starting at 1, of a token in the prophet body
+/// between `%{` and `%}`. Line 0 marks a position that is not known.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourcePos {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SourcePos {
+    pub fn new(line: usize, column: usize) -> Self {
+        SourcePos { line, column }
+    }
+
+    pub fn is_known(&self) -> bool {
+        self.line != 0
+    }
+}
+
+impl fmt::Display for SourcePos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorStage {
+    Lex,
+    Parse,
+    Sema,
+    Runtime,
+    /// A [`ProphetLimits`] bound was exceeded at run time.
+    ///
+    /// [`ProphetLimits`]: crate::interpreter::limits::ProphetLimits
+    Limit,
+}
+
+impl fmt::Display for ErrorStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let stage = match self {
+            ErrorStage::Lex => "lex",
+            ErrorStage::Parse => "parse",
+            ErrorStage::Sema => "sema",
+            ErrorStage::Runtime => "runtime",
+            ErrorStage::Limit => "limit",
+        };
+        write!(f, "{}", stage)
+    }
+}
+
+/// Error of any stage of the prophet front end or executor. Traversals raise
+/// plain messages, the position of the innermost node is attached on the
+/// way up and the stage is set by the [`Interpreter`] entry point.
+///
+/// [`Interpreter`]: crate::interpreter::Interpreter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterpreterError {
+    pub stage: ErrorStage,
+    pub pos: Option<SourcePos>,
+    pub msg: String,
+}
+
+impl InterpreterError {
+    pub fn new(stage: ErrorStage, pos: Option<SourcePos>, msg: impl Into<String>) -> Self {
+        InterpreterError {
+            stage,
+            pos: pos.filter(|pos| pos.is_known()),
+            msg: msg.into(),
+        }
+    }
+
+    pub fn lex(pos: SourcePos, msg: impl Into<String>) -> Self {
+        Self::new(ErrorStage::Lex, Some(pos), msg)
+    }
+
+    pub fn parse(pos: SourcePos, msg: impl Into<String>) -> Self {
+        Self::new(ErrorStage::Parse, Some(pos), msg)
+    }
+
+    pub fn limit(msg: impl Into<String>) -> Self {
+        Self::new(ErrorStage::Limit, None, msg)
+    }
+
+    /// Attaches `pos` unless a more precise position is already known.
+    pub fn at(mut self, pos: SourcePos) -> Self {
+        if self.pos.is_none() && pos.is_known() {
+            self.pos = Some(pos);
+        }
+        self
+    }
+
+    pub fn in_stage(mut self, stage: ErrorStage) -> Self {
+        self.stage = stage;
+        self
+    }
+}
+
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.pos {
+            Some(pos) => write!(f, "{} error at {}: {}", self.stage, pos, self.msg),
+            None => write!(f, "{} error: {}", self.stage, self.msg),
+        }
+    }
+}
+
+impl std::error::Error for InterpreterError {}
+
+impl From<String> for InterpreterError {
+    fn from(msg: String) -> Self {
+        Self::new(ErrorStage::Runtime, None, msg)
+    }
+}
+
+impl From<&str> for InterpreterError {
+    fn from(msg: &str) -> Self {
+        Self::new(ErrorStage::Runtime, None, msg)
+    }
+}
+
+impl From<InterpreterError> for String {
+    fn from(err: InterpreterError) -> Self {
+        err.to_string()
+    }
+}
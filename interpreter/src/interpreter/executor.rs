@@ -2,44 +2,48 @@ use core::program::binary_program::OlaProphet;
 use core::util::converts::u32s_be_to_u256;
 use core::vm::hardware::OlaMemory;
 use std::collections::HashMap;
-use std::ops::Deref;
 
+use crate::error::InterpreterError;
+use crate::interpreter::limits::{ProphetLimits, ProphetUsage};
 use crate::lexer::token::Token;
 use crate::lexer::token::Token::{Array, ArrayId, Cid, Id, IndexId};
 use crate::parser::node::{
-    ArrayIdentNode, ArrayNumNode, AssignNode, BinOpNode, BlockNode, CallNode, CompoundNode,
-    CondStatNode, ContextIdentNode, EntryBlockNode, EntryNode, FeltNumNode, FunctionNode,
-    IdentDeclarationNode, IdentIndexNode, IdentNode, IntegerNumNode, LoopStatNode, MallocNode,
-    MultiAssignNode, PrintfNode, ReturnNode, SqrtNode, TypeNode, UnaryOpNode,
+    ArrayIdentNode, ArrayNumNode, AssertNode, AssignNode, BinOpNode, BlockNode, CallNode, CastNode,
+    CompoundNode, CondStatNode, ContextIdentNode, EntryBlockNode, EntryNode, FeltNumNode,
+    ForStatNode, FunctionNode, IdentDeclarationNode, IdentIndexNode, IdentNode, IntegerNumNode,
+    LoopCtlNode, LoopStatNode, MallocNode, MultiAssignNode, PrintfNode, ReturnNode, SqrtNode,
+    TypeNode, UnaryOpNode,
 };
-use crate::parser::traversal::{is_node_type, safe_downcast_ref, Traversal};
+use crate::parser::traversal::{downcast_map, is_node_type, Traversal};
 use crate::sema::symbol::Symbol::FuncSymbol;
+use crate::utils::builtins::{call_builtin, isqrt};
 use crate::utils::number::Number::{Bool, Nil};
 use crate::utils::number::NumberRet::{Multiple, Single};
 use crate::utils::number::{Number, NumberResult, NumberRet};
 use log::debug;
+use plonky2::field::types::PrimeField64;
 
 #[macro_export]
 macro_rules! ident_lookup {
     ($func:tt, $idents: tt, $ret: ty, $single : tt) => {
         pub fn $func(&mut self, name: &str) -> $ret {
             if let Some(value) = self.call_stack.records[self.stack_depth].$idents.get(name) {
-                ident_lookup_ret!(value, $single)
+                ident_lookup_ret!(name, value, $single)
             } else if let Some(value) = self.call_stack.records[GLOBAL_LEVEL].$idents.get(name) {
-                ident_lookup_ret!(value, $single)
+                ident_lookup_ret!(name, value, $single)
             } else {
-                panic!("ident :{} not exist", name)
+                Err(format!("ident :{} not exist", name).into())
             }
         }
     };
     ($func:tt, $idents: tt,  $ret: ty, $index: ident, $single : tt) => {
         pub fn $func(&mut self, name: &str, $index: usize) -> NumberResult {
             if let Some(value) = self.call_stack.records[self.stack_depth].$idents.get(name) {
-                ident_lookup_ret!(value, $index, $single)
+                ident_lookup_ret!(name, value, $index, $single)
             } else if let Some(value) = self.call_stack.records[GLOBAL_LEVEL].$idents.get(name) {
-                ident_lookup_ret!(value, $index, $single)
+                ident_lookup_ret!(name, value, $index, $single)
             } else {
-                panic!("index ident :{} not exist", name)
+                Err(format!("index ident :{} not exist", name).into())
             }
         }
     };
@@ -47,13 +51,18 @@ macro_rules! ident_lookup {
 
 #[macro_export]
 macro_rules! ident_lookup_ret {
-    ($value: tt, $index: tt, $single : tt) => {
-        return Ok($single(
-            $value.clone().unwrap().get($index).unwrap().clone(),
-        ))
+    ($name: tt, $value: tt, $index: tt, $single : tt) => {
+        match $value.as_ref().map(|values| values.get($index)) {
+            Some(Some(value)) => Ok($single(value.clone())),
+            Some(None) => Err(format!("index {} of {} out of range", $index, $name).into()),
+            None => Err(format!("ident :{} used before assignment", $name).into()),
+        }
     };
-    ($value: tt, $single : tt) => {
-        return Ok($single($value.clone().unwrap()))
+    ($name: tt, $value: tt, $single : tt) => {
+        match $value {
+            Some(value) => Ok($single(value.clone())),
+            None => Err(format!("ident :{} used before assignment", $name).into()),
+        }
     };
 }
 
@@ -72,6 +81,8 @@ pub struct RuntimeRecord {
     pub record_level: usize,
     pub idents: HashMap<String, Option<Number>>,
     pub array_idents: HashMap<String, Option<Vec<Number>>>,
+    /// `u32` and `u64` variables, values assigned to them are cast.
+    pub unsigned: HashMap<String, Token>,
 }
 
 impl RuntimeRecord {
@@ -82,6 +93,7 @@ impl RuntimeRecord {
             record_level,
             idents: HashMap::new(),
             array_idents: HashMap::new(),
+            unsigned: HashMap::new(),
         }
     }
 }
@@ -104,16 +116,35 @@ pub struct Executor<'a> {
     // pub vm_mem: &'a MemoryTree,
     mem: &'a OlaMemory,
     stack_depth: usize,
+    travel_depth: usize,
+    limits: ProphetLimits,
+    usage: ProphetUsage,
+    // set by `break` or `continue` until the enclosing loop handles it
+    loop_ctl: Option<Token>,
+    // `printf` is skipped and failed `assert`s go unreported without a log
+    // to write to
+    logs: Option<&'a mut Vec<String>>,
 }
 
 impl<'a> Executor<'a> {
-    pub fn new(prophet: &OlaProphet, values: Vec<u64>, mem: &'a OlaMemory) -> Self {
+    pub fn new(
+        prophet: &OlaProphet,
+        values: Vec<u64>,
+        mem: &'a OlaMemory,
+        limits: &ProphetLimits,
+        logs: Option<&'a mut Vec<String>>,
+    ) -> Result<Self, InterpreterError> {
         let mut executor = Executor {
             call_stack: CallStack::new(),
             context: Vec::new(),
             outputs: Vec::new(),
             mem,
             stack_depth: GLOBAL_LEVEL,
+            travel_depth: 0,
+            limits: *limits,
+            usage: ProphetUsage::default(),
+            loop_ctl: None,
+            logs,
         };
         executor.call_stack.records.push(RuntimeRecord::new(
             "global".to_string(),
@@ -121,20 +152,23 @@ impl<'a> Executor<'a> {
             GLOBAL_LEVEL,
         ));
 
-        let mut index = 0;
+        let mut index: usize = 0;
         for input in prophet.inputs.iter() {
+            let input_values = index
+                .checked_add(input.length)
+                .and_then(|end| values.get(index..end))
+                .ok_or(format!(
+                    "input {} needs {} values, {} given",
+                    input.name,
+                    input.length,
+                    values.len().saturating_sub(index)
+                ))?;
             if input.length == 1 {
                 executor.call_stack.records[executor.stack_depth]
                     .idents
-                    .insert(
-                        input.name.to_string(),
-                        Some(Number::from(*values.get(index).unwrap())),
-                    );
+                    .insert(input.name.to_string(), Some(Number::from(input_values[0])));
             } else {
-                let values: Vec<_> = values[index..index + input.length]
-                    .iter()
-                    .map(|e| Number::from(*e))
-                    .collect();
+                let values: Vec<_> = input_values.iter().map(|e| Number::from(*e)).collect();
                 executor.call_stack.records[executor.stack_depth]
                     .array_idents
                     .insert(input.name.to_string(), Some(values));
@@ -160,119 +194,91 @@ impl<'a> Executor<'a> {
             executor.outputs.push(output.name.clone());
         }
 
-        executor
+        Ok(executor)
+    }
+
+    // Variables are looked up in the current record first, then in the
+    // global one.
+    fn record_level(&self, name: &str, array: bool) -> Result<usize, InterpreterError> {
+        [self.stack_depth, GLOBAL_LEVEL]
+            .into_iter()
+            .find(|level| {
+                self.call_stack.records.get(*level).map_or(false, |record| {
+                    if array {
+                        record.array_idents.contains_key(name)
+                    } else {
+                        record.idents.contains_key(name)
+                    }
+                })
+            })
+            .ok_or(format!("assign ident :{} not exist", name).into())
+    }
+
+    fn declared_value(
+        &self,
+        level: usize,
+        name: &str,
+        value: Number,
+    ) -> Result<Number, InterpreterError> {
+        match self.call_stack.records[level].unsigned.get(name) {
+            Some(token) => Ok(value.cast(token)?),
+            None => Ok(value),
+        }
     }
 
     pub fn assign_value(&mut self, id: &Token, value: NumberRet) -> NumberResult {
         match id {
             Id(name) | Cid(name) => {
                 debug!("assign ident  name:{}, value:{:?}", name, value);
-                let value = value.get_single();
-
-                if self.call_stack.records[self.stack_depth]
-                    .idents
-                    .get(&name.to_string())
-                    .is_some()
-                {
-                    self.call_stack.records[self.stack_depth]
-                        .idents
-                        .insert(name.to_string(), Some(value));
-                } else if self.call_stack.records[GLOBAL_LEVEL]
+                let value = value.get_single()?;
+                let level = self.record_level(name, false)?;
+                let value = self.declared_value(level, name, value)?;
+                self.call_stack.records[level]
                     .idents
-                    .get(&name.to_string())
-                    .is_some()
-                {
-                    self.call_stack.records[GLOBAL_LEVEL]
-                        .idents
-                        .insert(name.to_string(), Some(value));
-                } else {
-                    panic!("assign ident :{} not exist", name);
-                }
+                    .insert(name.to_string(), Some(value));
             }
             ArrayId(name) => {
-                let value = value.get_multiple();
+                let value = value.get_multiple()?;
                 debug!("assign array ident  name:{}, value:{:?}", name, value);
-                if self.call_stack.records[self.stack_depth]
-                    .array_idents
-                    .get(&name.to_string())
-                    .is_some()
-                {
-                    assert_eq!(
-                        self.call_stack.records[self.stack_depth].array_idents[&name.to_string()]
-                            .as_ref()
-                            .unwrap()
-                            .len(),
-                        value.len()
-                    );
-                    self.call_stack.records[self.stack_depth]
-                        .array_idents
-                        .insert(name.to_string(), Some(value));
-                } else if self.call_stack.records[GLOBAL_LEVEL]
-                    .array_idents
-                    .get(&name.to_string())
-                    .is_some()
-                {
-                    self.call_stack.records[GLOBAL_LEVEL]
-                        .array_idents
-                        .insert(name.to_string(), Some(value));
-                } else {
-                    panic!("assign ident :{} not exist", name);
+                let level = self.record_level(name, true)?;
+                let value = value
+                    .into_iter()
+                    .map(|value| self.declared_value(level, name, value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if let Some(Some(values)) = self.call_stack.records[level].array_idents.get(name) {
+                    if values.len() != value.len() {
+                        return Err(format!(
+                            "assign {} values to array {} of length {}",
+                            value.len(),
+                            name,
+                            values.len()
+                        )
+                        .into());
+                    }
                 }
+                self.call_stack.records[level]
+                    .array_idents
+                    .insert(name.to_string(), Some(value));
             }
             IndexId(name, index_node) => {
-                if self.call_stack.records[self.stack_depth]
-                    .array_idents
-                    .get(&name.to_string())
-                    .is_some()
-                {
-                    let index = self.travel(index_node)?.get_single().get_number();
-                    debug!(
-                        "assign index ident  name:{} index:{} , value:{:?}",
-                        name, index, value
-                    );
-                    assert!(
-                        self.call_stack.records[self.stack_depth].array_idents[&name.to_string()]
-                            .as_ref()
-                            .unwrap()
-                            .len()
-                            > index
-                    );
-                    let mut values = self.call_stack.records[self.stack_depth].array_idents
-                        [&name.to_string()]
-                        .clone()
-                        .unwrap();
-                    values[index] = value.get_single();
-
-                    self.call_stack.records[self.stack_depth]
-                        .array_idents
-                        .insert(name.to_string(), Some(values));
-                } else if self.call_stack.records[GLOBAL_LEVEL]
+                let level = self.record_level(name, true)?;
+                let index = self.travel(index_node)?.get_single()?.get_number()?;
+                debug!(
+                    "assign index ident  name:{} index:{} , value:{:?}",
+                    name, index, value
+                );
+                let value = self.declared_value(level, name, value.get_single()?)?;
+                let values = self.call_stack.records[level]
                     .array_idents
-                    .get(&name.to_string())
-                    .is_some()
-                {
-                    let index = self.travel(index_node)?.get_single().get_number();
-                    assert!(
-                        self.call_stack.records[GLOBAL_LEVEL].array_idents[&name.to_string()]
-                            .as_ref()
-                            .unwrap()
-                            .len()
-                            > index
-                    );
-                    let mut values = self.call_stack.records[GLOBAL_LEVEL].array_idents
-                        [&name.to_string()]
-                        .clone()
-                        .unwrap();
-                    values[index] = value.get_single();
-
-                    self.call_stack.records[GLOBAL_LEVEL]
-                        .array_idents
-                        .insert(name.to_string(), Some(values));
-                } else {
-                    panic!("assign ident :{} not exist", name);
-                }
+                    .get_mut(name)
+                    .and_then(|values| values.as_mut())
+                    .ok_or(format!("ident :{} used before assignment", name))?;
+                let item = values
+                    .get_mut(index)
+                    .ok_or(format!("index {} of {} out of range", index, name))?;
+                *item = value;
             }
-            _ => panic!("not support assign id type"),
+            _ => return Err(format!("not support assign id type {}", id).into()),
         }
         Ok(Single(Nil))
     }
@@ -281,6 +287,40 @@ impl<'a> Executor<'a> {
     ident_lookup!(array_lookup, array_idents, NumberResult, Multiple);
     ident_lookup!(index_lookup, array_idents, NumberResult, index, Single);
 
+    fn binop(
+        &self,
+        lhs: Number,
+        operator: &Token,
+        rhs: Number,
+    ) -> Result<Number, InterpreterError> {
+        lhs.check_binop(operator, &rhs)?;
+        let unsigned = lhs.is_unsigned() || rhs.is_unsigned();
+
+        let ret = match operator {
+            Token::Plus | Token::Minus | Token::Multiply | Token::IntegerDivision | Token::Mod
+                if unsigned =>
+            {
+                lhs.unsigned_binop(operator, &rhs)?
+            }
+            _ if operator.is_bitwise() => lhs.unsigned_binop(operator, &rhs)?,
+            Token::Plus => lhs + rhs,
+            Token::Multiply => lhs * rhs,
+            Token::Minus => lhs - rhs,
+            Token::IntegerDivision => lhs / rhs,
+            Token::Mod => lhs % rhs,
+            Token::Equal => Bool(lhs == rhs),
+            Token::NotEqual => Bool(lhs != rhs),
+            Token::LessThan => Bool(lhs < rhs),
+            Token::GreaterThan => Bool(lhs > rhs),
+            Token::LessEqual => Bool(lhs <= rhs),
+            Token::GreaterEqual => Bool(lhs >= rhs),
+            Token::And => Bool(lhs.get_number()? != 0 && rhs.get_number()? != 0),
+            Token::Or => Bool(lhs.get_number()? != 0 || rhs.get_number()? != 0),
+            _ => return Err(format!("Unknown operator found: {}", operator).into()),
+        };
+        Ok(ret)
+    }
+
     pub fn is_return(&mut self, ret: &NumberRet) -> bool {
         if let Multiple(_) = ret {
             return true;
@@ -291,6 +331,16 @@ impl<'a> Executor<'a> {
 }
 
 impl<'a> Traversal for Executor<'a> {
+    fn travel_depth(&mut self) -> &mut usize {
+        &mut self.travel_depth
+    }
+
+    fn on_travel(&mut self) -> Result<(), InterpreterError> {
+        self.usage
+            .step(&self.limits)
+            .map_err(InterpreterError::limit)
+    }
+
     fn travel_entry(&mut self, node: &mut EntryNode) -> NumberResult {
         for declaration in node.global_declarations.iter() {
             self.travel(declaration)?;
@@ -312,6 +362,9 @@ impl<'a> Traversal for Executor<'a> {
                 }
             }
         }
+        self.usage
+            .output(out_values.len() as u64, &self.limits)
+            .map_err(InterpreterError::limit)?;
 
         for ctx in &self.context {
             if let Some(value) = self.call_stack.records[GLOBAL_LEVEL].idents.get(ctx) {
@@ -329,6 +382,20 @@ impl<'a> Traversal for Executor<'a> {
     }
 
     fn travel_call(&mut self, node: &mut CallNode) -> NumberResult {
+        if node.func_symbol.is_none() {
+            let mut args = Vec::new();
+            for param in node.actual_params.iter() {
+                args.push(self.travel(param)?);
+            }
+            return call_builtin(&node.func_name.to_string(), args);
+        }
+        // records below the call are the global one and the entry one
+        if self.call_stack.records.len().saturating_sub(2) >= self.limits.max_stack_depth {
+            return Err(InterpreterError::limit(format!(
+                "stack depth limit of {} exceeded",
+                self.limits.max_stack_depth
+            )));
+        }
         let record_level = self.call_stack.records.len();
         let mut ctx = RuntimeRecord::new(
             node.func_name.to_string(),
@@ -336,21 +403,42 @@ impl<'a> Traversal for Executor<'a> {
             record_level + 1,
         );
 
-        let mut ret = Ok(Single(Nil));
-        if let FuncSymbol(_func_name, ref params, block) =
-            node.func_symbol.clone().unwrap().read().unwrap().deref()
-        {
-            for (param, input) in params.iter().zip(node.actual_params.iter()) {
-                if let Single(number) = self.travel(input).unwrap() {
-                    ctx.idents.insert(param.0.to_string(), Some(number));
-                } else if let Multiple(numbers) = self.travel(input).unwrap() {
+        let func_symbol = node
+            .func_symbol
+            .as_ref()
+            .ok_or(format!("function {} not resolved", node.func_name))?
+            .read()
+            .map_err(|_| InterpreterError::from("symbol lock is poisoned"))?
+            .clone();
+        let (params, block) = match func_symbol {
+            FuncSymbol(_func_name, params, block) => (params, block),
+            _ => return Err(format!("{} is not a function", node.func_name).into()),
+        };
+        for (param, input) in params.iter().zip(node.actual_params.iter()) {
+            let param_type = match &param.1 .0 {
+                Array(element_type, _) => element_type.as_ref(),
+                token => token,
+            };
+            let cast = |number: Number| match param_type {
+                Token::U32 | Token::U64 => number.cast(param_type),
+                _ => Ok(number),
+            };
+            match self.travel(input)? {
+                Single(number) => {
+                    ctx.idents.insert(param.0.to_string(), Some(cast(number)?));
+                }
+                Multiple(numbers) => {
+                    let numbers = numbers.into_iter().map(cast).collect::<Result<_, _>>()?;
                     ctx.array_idents.insert(param.0.to_string(), Some(numbers));
                 }
             }
-            self.call_stack.records.push(ctx);
-            self.stack_depth += 1;
-            ret = self.travel(&block);
+            if matches!(param_type, Token::U32 | Token::U64) {
+                ctx.unsigned.insert(param.0.to_string(), param_type.clone());
+            }
         }
+        self.call_stack.records.push(ctx);
+        self.stack_depth += 1;
+        let ret = self.travel(&block);
         self.call_stack.records.pop();
         self.stack_depth -= 1;
         ret
@@ -378,43 +466,46 @@ impl<'a> Traversal for Executor<'a> {
         }
         self.travel(&node.compound_statement)?;
         self.call_stack.records.pop();
+        self.stack_depth -= 1;
         Ok(Single(Nil))
     }
 
     fn travel_declaration(&mut self, node: &mut IdentDeclarationNode) -> NumberResult {
         let IdentDeclarationNode {
-            ident_node: IdentNode { identifier },
-            type_node: TypeNode { token },
+            ident_node: IdentNode { identifier, .. },
+            type_node: TypeNode { token, .. },
+            ..
         } = node;
 
-        if let Array(_element_type, len) = token {
+        if let Array(element_type, len) = token {
             if let Id(name) = identifier {
                 if self.call_stack.records[self.stack_depth]
                     .array_idents
                     .get(name)
                     != None
                 {
-                    return Err(format!(
-                        "Found duplicate variable declaration for '{}'!",
-                        name
-                    ));
+                    return Err(
+                        format!("Found duplicate variable declaration for '{}'!", name).into(),
+                    );
                 }
+                self.usage
+                    .alloc(*len as u64, &self.limits)
+                    .map_err(InterpreterError::limit)?;
                 self.call_stack.records[self.stack_depth]
                     .array_idents
                     .insert(name.to_string(), Some(vec![Nil; *len]));
+                self.declare_unsigned(name, element_type);
             } else {
-                panic!("cannot get id name");
+                return Err(format!("cannot get id name from {}", identifier).into());
             }
         } else if let Id(name) = identifier {
             if self.call_stack.records[self.stack_depth].idents.get(name) != None {
-                return Err(format!(
-                    "Found duplicate variable declaration for '{}'!",
-                    name
-                ));
+                return Err(format!("Found duplicate variable declaration for '{}'!", name).into());
             }
             self.call_stack.records[self.stack_depth]
                 .idents
                 .insert(name.to_string(), None);
+            self.declare_unsigned(name, token);
         }
         Ok(Single(Nil))
     }
@@ -449,13 +540,14 @@ impl<'a> Traversal for Executor<'a> {
         if let IdentIndexNode {
             identifier: Id(name),
             index,
+            ..
         } = node
         {
             let value = self.travel(index)?;
             debug!("ident:{},{:?}", name, value);
-            self.index_lookup(name, value.get_single().get_number())
+            self.index_lookup(name, value.get_single()?.get_number()?)
         } else {
-            Err(format!("Invalid identifier found {}", node.identifier))
+            Err(format!("Invalid identifier found {}", node.identifier).into())
         }
     }
 
@@ -464,42 +556,33 @@ impl<'a> Traversal for Executor<'a> {
             ref left,
             ref right,
             operator,
+            ..
         } = node;
 
-        let lhs = self.travel(left)?.get_single();
-        let rhs = self.travel(right)?.get_single();
-
-        let ret = match operator {
-            Token::Plus => lhs + rhs,
-            Token::Multiply => lhs * rhs,
-            Token::Minus => lhs - rhs,
-            Token::IntegerDivision => lhs / rhs,
-            Token::Mod => lhs % rhs,
-            Token::Equal => Bool(lhs == rhs),
-            Token::NotEqual => Bool(lhs != rhs),
-            Token::LessThan => Bool(lhs < rhs),
-            Token::GreaterThan => Bool(lhs > rhs),
-            Token::LessEqual => Bool(lhs <= rhs),
-            Token::GreaterEqual => Bool(lhs >= rhs),
-            Token::And => Bool(lhs.get_number() != 0 && rhs.get_number() != 0),
-            Token::Or => Bool(lhs.get_number() != 0 || rhs.get_number() != 0),
-            _ => panic!("Unknown operator found: {}", operator),
-        };
-        return Ok(Single(ret));
+        let lhs = self.travel(left)?.get_single()?;
+        let rhs = self.travel(right)?.get_single()?;
+        Ok(Single(self.binop(lhs, operator, rhs)?))
     }
 
     fn travel_unary_op(&mut self, node: &mut UnaryOpNode) -> NumberResult {
-        let UnaryOpNode { operator, expr } = node;
+        let UnaryOpNode { operator, expr, .. } = node;
         match operator {
             Token::Plus => self.travel(expr),
             Token::Minus => {
-                let ret = self.travel(expr)?.get_single();
+                let ret = self.travel(expr)?.get_single()?;
+                ret.check_neg()?;
                 Ok(Single(-ret))
             }
-            _ => Err(format!("Unexpected Unary Operator found: {}", operator)),
+            Token::BitNot => Ok(Single(self.travel(expr)?.get_single()?.bit_not()?)),
+            _ => Err(format!("Unexpected Unary Operator found: {}", operator).into()),
         }
     }
 
+    fn travel_cast(&mut self, node: &mut CastNode) -> NumberResult {
+        let value = self.travel(&node.expr)?.get_single()?;
+        Ok(Single(value.cast(&node.target)?))
+    }
+
     fn travel_compound(&mut self, node: &mut CompoundNode) -> NumberResult {
         for child in node.children.iter() {
             let ret = self.travel(child)?;
@@ -520,30 +603,30 @@ impl<'a> Traversal for Executor<'a> {
     fn travel_ident(&mut self, node: &mut IdentNode) -> NumberResult {
         if let IdentNode {
             identifier: Id(name),
+            ..
         } = node
         {
             self.lookup(name)
         } else if let IdentNode {
             identifier: ArrayId(name),
+            ..
         } = node
         {
             self.array_lookup(name)
         } else {
-            Err(format!("Invalid identifier found {}", node.identifier))
+            Err(format!("Invalid identifier found {}", node.identifier).into())
         }
     }
 
     fn travel_context_ident(&mut self, node: &mut ContextIdentNode) -> NumberResult {
         if let ContextIdentNode {
             identifier: Cid(name),
+            ..
         } = node
         {
             self.lookup(name)
         } else {
-            Err(format!(
-                "Invalid context identifier found {}",
-                node.identifier
-            ))
+            Err(format!("Invalid context identifier found {}", node.identifier).into())
         }
     }
 
@@ -556,6 +639,9 @@ impl<'a> Traversal for Executor<'a> {
                     if self.is_return(&ret) {
                         return Ok(ret);
                     }
+                    if self.loop_ctl.is_some() {
+                        break;
+                    }
                 }
             } else {
                 for child in node.alternatives.iter() {
@@ -563,6 +649,9 @@ impl<'a> Traversal for Executor<'a> {
                     if self.is_return(&ret) {
                         return Ok(ret);
                     }
+                    if self.loop_ctl.is_some() {
+                        break;
+                    }
                 }
             }
         }
@@ -570,39 +659,70 @@ impl<'a> Traversal for Executor<'a> {
     }
 
     fn travel_loop(&mut self, node: &mut LoopStatNode) -> NumberResult {
-        let mut res = self.travel(&node.condition);
-        while let Ok(Single(cond)) = res {
-            if let Bool(flag) = cond {
-                if flag == true {
-                    for child in node.consequences.iter() {
-                        let ret = self.travel(child)?;
-                        if self.is_return(&ret) {
-                            return Ok(ret);
-                        }
-                    }
-                } else {
+        loop {
+            match self.travel(&node.condition)? {
+                Single(Bool(true)) => {}
+                Single(Bool(false)) => break,
+                cond => {
+                    return Err(format!("loop condition {:?} is not a boolean", cond).into());
+                }
+            }
+            for child in node.consequences.iter() {
+                let ret = self.travel(child)?;
+                if self.is_return(&ret) {
+                    return Ok(ret);
+                }
+                if self.loop_ctl.is_some() {
+                    break;
+                }
+            }
+            if self.loop_ctl.take() == Some(Token::Break) {
+                break;
+            }
+        }
+        Ok(Single(Nil))
+    }
+
+    // The range is evaluated once, assigning to the variable in the body
+    // does not change the iterations.
+    fn travel_for(&mut self, node: &mut ForStatNode) -> NumberResult {
+        let mut index = self.travel(&node.start)?.get_single()?;
+        let end = self.travel(&node.end)?.get_single()?;
+        if matches!(index, Bool(_) | Nil) || matches!(end, Bool(_) | Nil) {
+            return Err(format!("for loop range {:?}..{:?} is not numeric", index, end).into());
+        }
+        while index < end {
+            self.assign_value(&node.ident, Single(index.clone()))?;
+            for child in node.consequences.iter() {
+                let ret = self.travel(child)?;
+                if self.is_return(&ret) {
+                    return Ok(ret);
+                }
+                if self.loop_ctl.is_some() {
                     break;
                 }
             }
-            res = self.travel(&node.condition);
+            if self.loop_ctl.take() == Some(Token::Break) {
+                break;
+            }
+            index = self.binop(index, &Token::Plus, Number::from(1))?;
         }
         Ok(Single(Nil))
     }
 
+    fn travel_loop_ctl(&mut self, node: &mut LoopCtlNode) -> NumberResult {
+        self.loop_ctl = Some(node.token.clone());
+        Ok(Single(Nil))
+    }
+
     fn travel_function(&mut self, _node: &mut FunctionNode) -> NumberResult {
         Ok(Single(Nil))
     }
 
     fn travel_sqrt(&mut self, node: &mut SqrtNode) -> NumberResult {
-        let value_res = self.travel(&node.sqrt_value);
-        if let Ok(Single(value)) = value_res {
-            let res = match value {
-                Number::Felt(number) => Ok(Single(Number::Felt((number as f64).sqrt() as i128))),
-                _ => panic!("wrong sqrt value type"),
-            };
-            res
-        } else {
-            panic!("can not get sqrt value")
+        match self.travel(&node.sqrt_value)?.get_single()? {
+            Number::Felt(number) => Ok(Single(Number::from(isqrt(number.to_canonical_u64())))),
+            value => Err(format!("wrong sqrt value type {:?}", value).into()),
         }
     }
 
@@ -610,8 +730,8 @@ impl<'a> Traversal for Executor<'a> {
         debug!("travel_return");
         if node.returns.len() > 0 {
             let mut ret = Vec::new();
-            for mut node in node.returns.iter() {
-                let res = self.travel(&mut node)?;
+            for node in node.returns.iter() {
+                let res = self.travel(node)?;
                 if let Single(res) = res {
                     ret.push(res);
                 } else if let Multiple(res) = res {
@@ -625,103 +745,166 @@ impl<'a> Traversal for Executor<'a> {
     }
 
     fn travel_multi_assign(&mut self, node: &mut MultiAssignNode) -> NumberResult {
-        let res = self.travel(&node.call)?;
-        let res = res.get_multiple();
+        let res = self.travel(&node.call)?.get_multiple()?;
 
         for (index, ident_node) in node.identifier.iter().enumerate() {
-            let ident;
-            if is_node_type::<IdentNode>(ident_node) {
-                ident = safe_downcast_ref::<IdentNode>(ident_node)
-                    .identifier
-                    .clone();
-            } else if is_node_type::<ContextIdentNode>(ident_node) {
-                ident = safe_downcast_ref::<ContextIdentNode>(ident_node)
-                    .identifier
-                    .clone();
-            } else if is_node_type::<IdentDeclarationNode>(ident_node) {
-                self.travel(&ident_node)?;
-                ident = safe_downcast_ref::<IdentDeclarationNode>(ident_node)
-                    .ident_node
-                    .identifier
-                    .clone();
-            } else {
-                panic!("not support ident node type");
+            if is_node_type::<IdentDeclarationNode>(ident_node) {
+                self.travel(ident_node)?;
             }
-            self.assign_value(&ident, Single(res.get(index).unwrap().clone()))?;
+            let ident = downcast_map::<IdentNode, _>(ident_node, |node| node.identifier.clone())
+                .or_else(|| {
+                    downcast_map::<ContextIdentNode, _>(ident_node, |node| node.identifier.clone())
+                })
+                .or_else(|| {
+                    downcast_map::<IdentDeclarationNode, _>(ident_node, |node| {
+                        node.ident_node.identifier.clone()
+                    })
+                })
+                .ok_or(InterpreterError::from("not support ident node type"))?;
+            let value = res.get(index).cloned().ok_or(format!(
+                "{} values returned for {} idents",
+                res.len(),
+                node.identifier.len()
+            ))?;
+            self.assign_value(&ident, Single(value))?;
         }
         Ok(Single(Nil))
     }
 
     fn travel_malloc(&mut self, node: &mut MallocNode) -> NumberResult {
-        let value_res = self.travel(&node.num_bytes);
-        let hp_name = self.context.get(HP_ADDR_INDEX).unwrap().clone();
-        let hp = self.lookup(&hp_name);
-        if let Ok(Single(value)) = value_res {
-            let res = match value {
-                Number::Felt(number) => Single(hp.unwrap().get_single() + Number::Felt(number)),
-                Number::I32(number) => Single(hp.unwrap().get_single() + Number::I32(number)),
-                _ => panic!("wrong sqrt value type"),
-            };
-            self.assign_value(&Id(hp_name), res.clone())?;
-            Ok(res)
-        } else {
-            panic!("can not get sqrt value")
-        }
+        let value = self.travel(&node.num_bytes)?.get_single()?;
+        let hp_name = self
+            .context
+            .get(HP_ADDR_INDEX)
+            .cloned()
+            .ok_or(InterpreterError::from(
+                "heap pointer not in prophet context",
+            ))?;
+        let hp = self.lookup(&hp_name)?.get_single()?;
+        self.usage
+            .alloc(value.get_number()? as u64, &self.limits)
+            .map_err(InterpreterError::limit)?;
+        let value = match value {
+            Number::U32(_) | Number::U64(_) => value.cast(&Token::Felt)?,
+            _ => value,
+        };
+        let res = match value {
+            Number::Felt(_) | Number::I32(_) => {
+                hp.check_binop(&Token::Plus, &value)?;
+                Single(hp + value)
+            }
+            _ => return Err(format!("wrong malloc size type {:?}", value).into()),
+        };
+        self.assign_value(&Id(hp_name), res.clone())?;
+        Ok(res)
     }
 
     fn travel_printf(&mut self, node: &mut PrintfNode) -> NumberResult {
-        let flag_ret = self.travel(&node.flag)?.get_single().get_number();
+        if self.logs.is_none() {
+            return Ok(Single(Nil));
+        }
+        let flag_ret = self.travel(&node.flag)?.get_single()?.get_number()?;
         if flag_ret == 4 {
-            let addr = self.travel(&node.val_addr)?.get_single().get_number() as u64;
-            let limbs = self.mem.batch_read(addr, 8).unwrap();
-            let u256_str = u32s_be_to_u256(limbs.clone().try_into().unwrap()).unwrap();
-            println!(
+            let addr = self.printf_value(node)?;
+            let limbs: [u64; 8] = self
+                .mem_read(addr, 8)?
+                .try_into()
+                .map_err(|_| InterpreterError::from("u256 needs 8 limbs"))?;
+            let u256_str = u32s_be_to_u256(limbs).map_err(|err| err.to_string())?;
+            self.log(format!(
                 "U256: 0x{:x}, limbs:=[{},{},{},{},{},{},{},{}]",
                 u256_str,
-                limbs.get(0).unwrap(),
-                limbs.get(1).unwrap(),
-                limbs.get(2).unwrap(),
-                limbs.get(3).unwrap(),
-                limbs.get(4).unwrap(),
-                limbs.get(5).unwrap(),
-                limbs.get(6).unwrap(),
-                limbs.get(7).unwrap(),
-            );
+                limbs[0],
+                limbs[1],
+                limbs[2],
+                limbs[3],
+                limbs[4],
+                limbs[5],
+                limbs[6],
+                limbs[7],
+            ));
         } else if flag_ret == 3 {
-            println!(
-                "print value={}",
-                self.travel(&node.val_addr)?.get_single().get_number()
-            );
+            let value = self.printf_value(node)?;
+            self.log(format!("print value={}", value));
         } else if flag_ret == 2 {
-            let addr = self.travel(&node.val_addr)?.get_single().get_number() as u64;
-            let limbs = self.mem.batch_read(addr, 4).unwrap();
-            println!(
+            let addr = self.printf_value(node)?;
+            let limbs = self.mem_read(addr, 4)?;
+            self.log(format!(
                 "print addr:={},{},{},{}",
-                limbs.get(0).unwrap(),
-                limbs.get(1).unwrap(),
-                limbs.get(2).unwrap(),
-                limbs.get(3).unwrap(),
-            );
+                limbs[0], limbs[1], limbs[2], limbs[3],
+            ));
         } else if flag_ret == 1 {
-            let addr = self.travel(&node.val_addr)?.get_single().get_number() as u64;
-            let len = self.mem.read(addr).unwrap();
+            let addr = self.printf_value(node)?;
+            let len = self.mem.read(addr).map_err(|err| err.to_string())?;
             let str: Vec<u8> = self
-                .mem
-                .batch_read(addr + 1, len)
-                .unwrap()
+                .mem_read(addr.saturating_add(1), len)?
                 .iter()
                 .map(|v| *v as u8)
                 .collect();
-            println!("print str={}", String::from_utf8(str).unwrap());
+            self.log(format!("print str={}", String::from_utf8_lossy(&str)));
         } else if flag_ret == 0 {
-            let addr = self.travel(&node.val_addr)?.get_single().get_number() as u64;
-            let len = self.mem.read(addr).unwrap();
+            let addr = self.printf_value(node)?;
+            let len = self.mem.read(addr).map_err(|err| err.to_string())?;
+            let values = self.mem_read(addr.saturating_add(1), len)?;
             (addr + 1..addr + 1 + len)
-                .zip(self.mem.batch_read(addr + 1, len).unwrap())
+                .zip(values)
                 .for_each(|(addr, val)| {
-                    println!("print mem:{}, value:{}", addr, val);
+                    self.log(format!("print mem:{}, value:{}", addr, val));
                 });
         }
         Ok(Single(Nil))
     }
+
+    fn travel_assert(&mut self, node: &mut AssertNode) -> NumberResult {
+        if let Single(Bool(true)) = self.travel(&node.condition)? {
+            return Ok(Single(Nil));
+        }
+        let mut message = format!("assertion failed at {}", node.pos);
+        if let Some(value) = &node.value {
+            let value = self.travel(value)?.get_single()?;
+            message += &format!(", value={}", value.to_string());
+        }
+        self.log(message);
+        Ok(Single(Nil))
+    }
+}
+
+impl<'a> Executor<'a> {
+    fn log(&mut self, message: String) {
+        if let Some(logs) = self.logs.as_mut() {
+            logs.push(message);
+        }
+    }
+
+    fn declare_unsigned(&mut self, name: &str, token: &Token) {
+        if matches!(token, Token::U32 | Token::U64) {
+            self.call_stack.records[self.stack_depth]
+                .unsigned
+                .insert(name.to_string(), token.clone());
+        }
+    }
+
+    fn printf_value(&mut self, node: &PrintfNode) -> Result<u64, InterpreterError> {
+        Ok(self.travel(&node.val_addr)?.get_single()?.get_number()? as u64)
+    }
+
+    // Reads `len` words at `addr` for printing, rejecting ranges past the
+    // output limit or the end of the address space before the memory sees
+    // them.
+    fn mem_read(&mut self, addr: u64, len: u64) -> Result<Vec<u64>, InterpreterError> {
+        self.usage
+            .output(len, &self.limits)
+            .map_err(InterpreterError::limit)?;
+        if addr.checked_add(len).map_or(true, |end| end == u64::MAX) {
+            return Err(format!("memory range {} + {} out of bounds", addr, len).into());
+        }
+        let values = self
+            .mem
+            .batch_read(addr, len)
+            .map_err(|err| err.to_string())?;
+        if values.len() as u64 != len {
+            return Err(format!("read {} of {} words at {}", values.len(), len, addr).into());
+        }
+        Ok(values)
+    }
 }
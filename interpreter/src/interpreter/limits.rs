@@ -0,0 +1,74 @@
+/// Bounds on a single prophet execution. Exceeding any of them stops the
+/// prophet with an [`ErrorStage::Limit`] error, at the same point for every
+/// run of the same prophet and inputs.
+///
+/// [`ErrorStage::Limit`]: crate::error::ErrorStage::Limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProphetLimits {
+    /// Syntax tree nodes evaluated, loop iterations included.
+    pub max_steps: u64,
+    /// Nested function calls below `entry`.
+    pub max_stack_depth: usize,
+    /// Words taken by declared arrays and by `malloc`.
+    pub max_alloc_words: u64,
+    /// Words returned as outputs plus words read by `printf`.
+    pub max_output_len: u64,
+}
+
+impl ProphetLimits {
+    pub fn unlimited() -> Self {
+        ProphetLimits {
+            max_steps: u64::MAX,
+            max_stack_depth: usize::MAX,
+            max_alloc_words: u64::MAX,
+            max_output_len: u64::MAX,
+        }
+    }
+}
+
+impl Default for ProphetLimits {
+    fn default() -> Self {
+        ProphetLimits {
+            max_steps: 1 << 20,
+            max_stack_depth: 64,
+            max_alloc_words: 1 << 16,
+            max_output_len: 1 << 16,
+        }
+    }
+}
+
+/// Usage of one execution, checked against its [`ProphetLimits`].
+#[derive(Debug, Default)]
+pub(crate) struct ProphetUsage {
+    steps: u64,
+    alloc_words: u64,
+    output_len: u64,
+}
+
+impl ProphetUsage {
+    pub(crate) fn step(&mut self, limits: &ProphetLimits) -> Result<(), String> {
+        self.steps = charge(self.steps, 1, limits.max_steps, "step")?;
+        Ok(())
+    }
+
+    pub(crate) fn alloc(&mut self, words: u64, limits: &ProphetLimits) -> Result<(), String> {
+        self.alloc_words = charge(
+            self.alloc_words,
+            words,
+            limits.max_alloc_words,
+            "allocation",
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn output(&mut self, words: u64, limits: &ProphetLimits) -> Result<(), String> {
+        self.output_len = charge(self.output_len, words, limits.max_output_len, "output")?;
+        Ok(())
+    }
+}
+
+fn charge(used: u64, amount: u64, max: u64, what: &str) -> Result<u64, String> {
+    used.checked_add(amount)
+        .filter(|total| *total <= max)
+        .ok_or(format!("{} limit of {} exceeded", what, max))
+}
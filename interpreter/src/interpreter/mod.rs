@@ -1,6 +1,9 @@
 mod executor;
+pub mod limits;
 
+use crate::error::{ErrorStage, InterpreterError};
 use crate::interpreter::executor::Executor;
+use crate::interpreter::limits::ProphetLimits;
 use crate::parser::node::Node;
 use crate::parser::Parser;
 use crate::sema::SymTableGen;
@@ -14,23 +17,350 @@ pub struct Interpreter {
 }
 
 impl Interpreter {
-    pub fn new(text: &str) -> Self {
-        let mut parser = Parser::new(&text);
-        let root_node = parser.parse();
-        Interpreter { root_node }
+    pub fn new(text: &str) -> Result<Self, InterpreterError> {
+        let root_node = Parser::new(text)?.parse()?;
+        Ok(Interpreter { root_node })
+    }
+
+    /// Parses the `%{ ... %}` code of a prophet and checks it once, so the
+    /// result can be executed any number of times with
+    /// [`Interpreter::execute`].
+    pub fn compile(prophet: &OlaProphet) -> Result<Self, InterpreterError> {
+        let code = prophet_body(&prophet.code)
+            .map_err(|msg| InterpreterError::new(ErrorStage::Parse, None, msg))?;
+        let interpreter = Interpreter::new(code)?;
+        interpreter.analyze(prophet)?;
+        Ok(interpreter)
     }
 
     pub fn run(&mut self, prophet: &OlaProphet, values: Vec<u64>, mem: &OlaMemory) -> NumberResult {
-        debug!("sema");
+        self.analyze(prophet)?;
+        self.execute(prophet, values, mem)
+    }
+
+    /// Runs an interpreter that went through semantic analysis, by
+    /// [`Interpreter::compile`] or an earlier [`Interpreter::run`].
+    pub fn execute(&self, prophet: &OlaProphet, values: Vec<u64>, mem: &OlaMemory) -> NumberResult {
+        self.execute_with_limits(prophet, values, mem, &ProphetLimits::default())
+    }
+
+    /// [`Interpreter::execute`] bounded by `limits` instead of the defaults.
+    pub fn execute_with_limits(
+        &self,
+        prophet: &OlaProphet,
+        values: Vec<u64>,
+        mem: &OlaMemory,
+        limits: &ProphetLimits,
+    ) -> NumberResult {
+        self.execute_with_logs(prophet, values, mem, limits, None)
+    }
+
+    /// [`Interpreter::execute_with_limits`] writing a line to `logs` for
+    /// each `printf` and failed `assert`. Without `logs` `printf` is not
+    /// evaluated at all, `assert` is still evaluated but not reported.
+    pub fn execute_with_logs(
+        &self,
+        prophet: &OlaProphet,
+        values: Vec<u64>,
+        mem: &OlaMemory,
+        limits: &ProphetLimits,
+        logs: Option<&mut Vec<String>>,
+    ) -> NumberResult {
+        debug!("executor");
+        let mut exe = Executor::new(prophet, values, mem, limits, logs)?;
         self.root_node
             .write()
             .map_err(|err| format!("failed to lock write lock {}", err))?
-            .traverse(&mut SymTableGen::new(&prophet))?;
-        debug!("executor");
-        let mut exe = Executor::new(&prophet, values, mem);
+            .traverse(&mut exe)
+            .map_err(|err| match err.stage {
+                ErrorStage::Limit => err,
+                _ => err.in_stage(ErrorStage::Runtime),
+            })
+    }
+
+    fn analyze(&self, prophet: &OlaProphet) -> Result<(), InterpreterError> {
+        debug!("sema");
         self.root_node
             .write()
             .map_err(|err| format!("failed to lock write lock {}", err))?
-            .traverse(&mut exe)
+            .traverse(&mut SymTableGen::new(prophet))
+            .map_err(|err| err.in_stage(ErrorStage::Sema))?;
+        Ok(())
+    }
+}
+
+/// The source between `%{` and `%}` of an interpreted prophet.
+pub fn prophet_body(code: &str) -> Result<&str, String> {
+    code.strip_prefix("%{")
+        .and_then(|code| code.strip_suffix("%}"))
+        .ok_or("prophet code is not wrapped in %{ %}".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{ErrorStage, InterpreterError, SourcePos};
+    use crate::interpreter::limits::ProphetLimits;
+    use crate::interpreter::Interpreter;
+    use core::program::binary_program::{OlaProphet, OlaProphetInput, OlaProphetOutput};
+    use core::vm::hardware::OlaMemory;
+
+    fn prophet(code: &str, inputs: &[&str], outputs: &[(&str, usize)]) -> OlaProphet {
+        OlaProphet {
+            host: 0,
+            code: format!("%{{{}%}}", code),
+            ctx: vec![],
+            inputs: inputs
+                .iter()
+                .map(|name| OlaProphetInput {
+                    name: name.to_string(),
+                    length: 1,
+                    is_ref: false,
+                    is_input_output: false,
+                })
+                .collect(),
+            outputs: outputs
+                .iter()
+                .map(|(name, length)| OlaProphetOutput {
+                    name: name.to_string(),
+                    length: *length,
+                    is_ref: false,
+                    is_input_output: false,
+                })
+                .collect(),
+        }
+    }
+
+    fn compile_err(code: &str) -> InterpreterError {
+        match Interpreter::compile(&prophet(code, &["cid.x"], &[("cid.y", 1)])) {
+            Ok(_) => panic!("compiled {:?}", code),
+            Err(err) => err,
+        }
+    }
+
+    fn run(code: &str, values: Vec<u64>) -> Result<Vec<u64>, InterpreterError> {
+        let prophet = prophet(code, &["cid.x", "cid.z"], &[("cid.y", 1)]);
+        let interpreter = Interpreter::compile(&prophet)?;
+        let ret = interpreter.execute(&prophet, values, &OlaMemory::default())?;
+        Ok(ret
+            .get_multiple()?
+            .iter()
+            .map(|value| value.get_number().unwrap() as u64)
+            .collect())
+    }
+
+    #[test]
+    fn test_front_end_errors() {
+        let err = compile_err("entry() {\n    cid.y = cid.x @ 1;\n}");
+        assert_eq!(err.stage, ErrorStage::Lex);
+        assert_eq!(err.pos, Some(SourcePos::new(2, 19)));
+        assert_eq!(
+            err.to_string(),
+            "lex error at line 2, column 19: unknown character '@'"
+        );
+
+        let err = compile_err("entry() {\n    cid.y = cid.x\n    cid.y = 1;\n}");
+        assert_eq!(err.stage, ErrorStage::Parse);
+        assert_eq!(err.pos, Some(SourcePos::new(3, 5)));
+
+        // a stray `;` used to spin the statement list forever
+        let err = compile_err("entry() {\n  ;\n}");
+        assert_eq!(err.stage, ErrorStage::Parse);
+        assert_eq!(err.pos, Some(SourcePos::new(2, 3)));
+
+        let err = compile_err(&format!("entry() {{ cid.y = {}1; }}", "(".repeat(200)));
+        assert_eq!(err.stage, ErrorStage::Parse);
+
+        let err = compile_err("entry() {\n    cid.y = cid.x;\n    cid.y = w + 1;\n}");
+        assert_eq!(err.stage, ErrorStage::Sema);
+        assert_eq!(err.pos, Some(SourcePos::new(3, 13)));
+
+        assert_eq!(compile_err("").stage, ErrorStage::Parse);
+        assert_eq!(
+            compile_err("entry() { cid.y = 1; ").stage,
+            ErrorStage::Parse
+        );
+    }
+
+    #[test]
+    fn test_runtime_errors() {
+        assert_eq!(
+            run("entry() { cid.y = cid.x / cid.z; }", vec![7, 2]),
+            Ok(vec![3])
+        );
+
+        let err = run("entry() {\n    cid.y = cid.x / cid.z;\n}", vec![7, 0]).unwrap_err();
+        assert_eq!(err.stage, ErrorStage::Runtime);
+        assert_eq!(err.pos, Some(SourcePos::new(2, 19)));
+
+        let code = "function f(felt a) -> felt {\n    return f(a);\n}\nentry() {\n    cid.y = f(cid.x);\n}";
+        let err = run(code, vec![1, 2]).unwrap_err();
+        assert_eq!(err.stage, ErrorStage::Runtime);
+        assert!(err.msg.contains("recursive"), "{}", err);
+
+        let err = run("entry() { cid.y = cid.x; }", vec![1]).unwrap_err();
+        assert_eq!(err.stage, ErrorStage::Runtime);
+    }
+
+    #[test]
+    fn test_multi_assign() {
+        let code = "entry() {\n    felt q;\n    felt r;\n    (q, r) = u64_divmod(cid.x, cid.z);\n    cid.y = q + r;\n}";
+        assert_eq!(run(code, vec![17, 5]), Ok(vec![5]));
+    }
+
+    #[test]
+    fn test_bitwise() {
+        let split = |expr: &str| {
+            let code = format!(
+                "entry() {{\n    u64 v = cid.x;\n    u32 hi = (v >> 32) as u32;\n    u32 lo = (v & 4294967295) as u32;\n    cid.y = {};\n}}",
+                expr
+            );
+            run(&code, vec![0x1234_5678_9abc_def0, 0])
+        };
+        assert_eq!(split("hi"), Ok(vec![0x1234_5678]));
+        assert_eq!(split("lo"), Ok(vec![0x9abc_def0]));
+        assert_eq!(split("hi ^ lo"), Ok(vec![0x1234_5678 ^ 0x9abc_def0]));
+        assert_eq!(split("~hi & 255 | 1"), Ok(vec![0x87 | 1]));
+        // bits shifted out are dropped
+        assert_eq!(split("lo << 4"), Ok(vec![0xabcd_ef00]));
+        assert_eq!(
+            split("(hi as u64) << 32 | lo as u64"),
+            Ok(vec![0x1234_5678_9abc_def0])
+        );
+
+        let err = split("hi + lo * 2").unwrap_err();
+        assert_eq!(err.stage, ErrorStage::Runtime);
+        assert_eq!(err.msg, "u32 overflow in 2596069104 * 2");
+        let err = split("lo << 32").unwrap_err();
+        assert_eq!(err.msg, "shift by 32 out of range for 32 bits");
+        assert!(split("hi - lo").is_err());
+        assert!(split("hi + v").is_err());
+        assert!(run("entry() { u32 a = cid.x; cid.y = a; }", vec![1 << 32, 0]).is_err());
+
+        assert_eq!(
+            compile_err("entry() { cid.y = cid.x & 1; }").stage,
+            ErrorStage::Sema
+        );
+        assert_eq!(
+            compile_err("entry() { cid.y = ~1; }").stage,
+            ErrorStage::Sema
+        );
+    }
+
+    #[test]
+    fn test_for_loop() {
+        let code = "entry() {\n    u32 i;\n    u32 sum = 0;\n    for i in 0..cid.x {\n        if i == 3 {\n            continue;\n        }\n        if i == cid.z {\n            break;\n        }\n        sum = sum + i;\n    }\n    cid.y = sum;\n}";
+        assert_eq!(run(code, vec![10, 6]), Ok(vec![12]));
+        assert_eq!(run(code, vec![4, 6]), Ok(vec![3]));
+        assert_eq!(run(code, vec![0, 6]), Ok(vec![0]));
+
+        // `break` leaves the innermost loop only
+        let code = "entry() {\n    felt i;\n    felt j;\n    felt n = 0;\n    for i in 0..cid.x {\n        j = 0;\n        while j < 10 {\n            if j == cid.z {\n                break;\n            }\n            j = j + 1;\n            n = n + 1;\n        }\n    }\n    cid.y = n;\n}";
+        assert_eq!(run(code, vec![3, 2]), Ok(vec![6]));
+
+        let err = compile_err("entry() {\n    break;\n}");
+        assert_eq!(err.stage, ErrorStage::Sema);
+        assert_eq!(err.pos, Some(SourcePos::new(2, 5)));
+        assert_eq!(
+            compile_err("entry() { for k in 0..2 { cid.y = 1; } }").stage,
+            ErrorStage::Sema
+        );
+    }
+
+    #[test]
+    fn test_logs() {
+        let prophet = prophet(
+            "entry() {\n    printf(cid.x, 3);\n    assert(cid.x == 1, cid.x);\n    assert(cid.x > 1);\n    cid.y = cid.x;\n}",
+            &["cid.x"],
+            &[("cid.y", 1)],
+        );
+        let interpreter = Interpreter::compile(&prophet).unwrap();
+        let mem = OlaMemory::default();
+        let limits = ProphetLimits::default();
+
+        let mut logs = vec![];
+        let ret = interpreter.execute_with_logs(&prophet, vec![7], &mem, &limits, Some(&mut logs));
+        assert_eq!(ret.unwrap().get_multiple().unwrap().len(), 1);
+        assert_eq!(
+            logs,
+            vec![
+                "print value=7".to_string(),
+                "assertion failed at line 3, column 5, value=Felt(7)".to_string(),
+            ]
+        );
+
+        let ret = interpreter.execute_with_logs(&prophet, vec![7], &mem, &limits, None);
+        assert_eq!(ret.unwrap().get_multiple().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_assert_without_logs() {
+        // an assert failing to evaluate fails with or without logs
+        let prophet = prophet(
+            "entry() {\n    assert(cid.x / (cid.x - 7) == 1);\n    cid.y = cid.x;\n}",
+            &["cid.x"],
+            &[("cid.y", 1)],
+        );
+        let interpreter = Interpreter::compile(&prophet).unwrap();
+        let mem = OlaMemory::default();
+        let limits = ProphetLimits::default();
+
+        let mut logs = vec![];
+        let with_logs =
+            interpreter.execute_with_logs(&prophet, vec![7], &mem, &limits, Some(&mut logs));
+        let without_logs = interpreter.execute_with_logs(&prophet, vec![7], &mem, &limits, None);
+        assert_eq!(
+            without_logs.unwrap_err().to_string(),
+            with_logs.unwrap_err().to_string()
+        );
+        assert!(logs.is_empty());
+    }
+
+    #[test]
+    fn test_limits() {
+        let prophet = prophet(
+            "function f2(felt a) -> felt {\n    return a + 1;\n}\nfunction f1(felt a) -> felt {\n    return f2(a);\n}\nentry() {\n    felt[4] b;\n    felt i = 0;\n    while i < cid.x {\n        i = i + 1;\n    }\n    cid.y = f1(i);\n}",
+            &["cid.x"],
+            &[("cid.y", 1)],
+        );
+        let interpreter = Interpreter::compile(&prophet).unwrap();
+        let mem = OlaMemory::default();
+        let run = |x: u64, limits: ProphetLimits| {
+            interpreter
+                .execute_with_limits(&prophet, vec![x], &mem, &limits)
+                .map(|ret| ret.get_multiple().unwrap().len())
+        };
+
+        assert_eq!(run(10, ProphetLimits::default()), Ok(1));
+        assert_eq!(run(10, ProphetLimits::unlimited()), Ok(1));
+
+        let limits = ProphetLimits {
+            max_steps: 200,
+            ..ProphetLimits::default()
+        };
+        let err = run(1000, limits).unwrap_err();
+        assert_eq!(err.stage, ErrorStage::Limit);
+        assert_eq!(err.msg, "step limit of 200 exceeded");
+        // the same node fails on every run
+        assert_eq!(run(1000, limits), Err(err));
+
+        let limits = ProphetLimits {
+            max_stack_depth: 1,
+            ..ProphetLimits::default()
+        };
+        let err = run(1, limits).unwrap_err();
+        assert_eq!(err.stage, ErrorStage::Limit);
+        assert_eq!(err.pos, Some(SourcePos::new(5, 12)));
+
+        let limits = ProphetLimits {
+            max_alloc_words: 3,
+            ..ProphetLimits::default()
+        };
+        assert_eq!(run(1, limits).unwrap_err().pos, Some(SourcePos::new(8, 13)));
+
+        let limits = ProphetLimits {
+            max_output_len: 0,
+            ..ProphetLimits::default()
+        };
+        assert_eq!(run(1, limits).unwrap_err().stage, ErrorStage::Limit);
     }
 }
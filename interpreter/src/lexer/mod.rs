@@ -1,35 +1,56 @@
 pub mod token;
 use self::token::Token;
 
+use crate::error::{InterpreterError, SourcePos};
 use crate::lexer::token::Token::{
-    And, Assign, Begin, Colon, Comma, Dot, Else, End, Entry, Equal, Felt, FeltConst, Function,
-    GreaterEqual, GreaterThan, I32Const, Id, If, IntegerDivision, LBracket, LParen, LessEqual,
-    LessThan, Malloc, Minus, Mod, Multiply, NotEqual, Or, Plus, Printf, RBracket, RParen, Return,
-    ReturnDel, Semi, Sqrt, While, EOF, I32,
+    And, Assert, Assign, Begin, BitAnd, BitNot, BitOr, BitXor, Break, Colon, Comma, Continue, Dot,
+    Else, End, Entry, Equal, Felt, FeltConst, For, Function, GreaterEqual, GreaterThan, I32Const,
+    Id, If, IntegerDivision, LBracket, LParen, LessEqual, LessThan, Malloc, Minus, Mod, Multiply,
+    NotEqual, Or, Plus, Printf, RBracket, RParen, Range, Return, ReturnDel, Semi, ShiftLeft,
+    ShiftRight, Sqrt, While, AS, EOF, I32, U32, U64,
 };
 
 #[derive(Clone)]
 pub struct Lexer {
-    text: String,
+    chars: Vec<char>,
     position: usize,
     current_char: Option<char>,
+    line: usize,
+    column: usize,
+    token_pos: SourcePos,
 }
 
 impl Lexer {
     pub fn new(text: &str) -> Self {
         let chars: Vec<char> = text.chars().collect();
+        let current_char = chars.first().cloned();
         Lexer {
-            text: text.to_string(),
+            chars,
             position: 0,
-            current_char: Some(chars[0]),
+            current_char,
+            line: 1,
+            column: 1,
+            token_pos: SourcePos::new(1, 1),
         }
     }
 
+    /// Position of the first character of the token last returned by
+    /// `get_next_token`.
+    pub fn token_pos(&self) -> SourcePos {
+        self.token_pos
+    }
+
     pub fn match_reserved(&self, token: &str) -> (bool, Token) {
         match token {
             "I32" => (true, I32),
+            "U32" => (true, U32),
+            "U64" => (true, U64),
             "FELT" => (true, Felt),
             "WHILE" => (true, While),
+            "FOR" => (true, For),
+            "BREAK" => (true, Break),
+            "CONTINUE" => (true, Continue),
+            "AS" => (true, AS),
             "IF" => (true, If),
             "ELSE" => (true, Else),
             "ENTRY" => (true, Entry),
@@ -38,205 +59,250 @@ impl Lexer {
             "SQRT" => (true, Sqrt),
             "MALLOC" => (true, Malloc),
             "PRINTF" => (true, Printf),
+            "ASSERT" => (true, Assert),
             _ => (false, EOF),
         }
     }
     /// Returns an option to the character following
     /// the current token.
     pub fn peek(&self) -> Option<char> {
-        let position = self.position + 1;
-        if position > self.text.len() - 1 {
-            None
-        } else {
-            Some(self.text.as_bytes()[position] as char)
-        }
+        self.chars.get(self.position + 1).cloned()
     }
     /// Advances the lexer position within the input text,
     /// setting the `current_char` to value found at that
     /// location.
     fn advance(&mut self) {
-        self.position += 1;
-        if self.position > self.text.len() - 1 {
-            self.current_char = None
-        } else {
-            self.current_char = Some(self.text.as_bytes()[self.position] as char)
+        match self.current_char {
+            Some('\n') => {
+                self.line += 1;
+                self.column = 1;
+            }
+            Some(_) => self.column += 1,
+            None => return,
         }
+        self.position += 1;
+        self.current_char = self.chars.get(self.position).cloned();
     }
     fn skip_comment(&mut self) {
-        while self.current_char != Some('\n') {
+        while self.current_char.map_or(false, |char| char != '\n') {
             self.advance()
         }
         self.advance()
     }
     fn skip_whitespace(&mut self) {
-        while self.current_char != None && self.current_char.unwrap().is_whitespace() {
+        while self.current_char.map_or(false, |char| char.is_whitespace()) {
             self.advance()
         }
     }
     /// Handles identifiers and reserved keywords
-    fn id(&mut self) -> Option<Token> {
+    fn id(&mut self) -> Token {
         let mut result = String::new();
-        while self.current_char != None && self.current_char.unwrap().is_alphanumeric()
-            || self.current_char.unwrap() == '.'
-            || self.current_char.unwrap() == '_'
-        {
-            result.push(self.current_char.unwrap());
+        while let Some(char) = self.current_char {
+            if !(char.is_alphanumeric() || char == '.' || char == '_') {
+                break;
+            }
+            // `a..b` is a range, not a dotted name
+            if char == '.' && self.peek() == Some('.') {
+                break;
+            }
+            result.push(char);
             self.advance();
         }
         let uppercase_result = result.to_uppercase();
 
         let (reserved, token) = self.match_reserved(uppercase_result.as_str());
         if reserved {
-            Some(token)
+            token
         } else {
-            Some(Id(result))
+            Id(result)
         }
     }
 
-    fn number(&mut self) -> Option<Token> {
+    fn number(&mut self) -> Result<Token, InterpreterError> {
         let mut digits = String::new();
-        while self.current_char != None && self.current_char.unwrap().is_digit(10) {
-            digits.push(self.current_char.unwrap());
+        while let Some(char) = self.current_char.filter(|char| char.is_ascii_digit()) {
+            digits.push(char);
             self.advance();
         }
         if digits.parse::<i32>().is_ok() {
-            Some(I32Const(digits))
+            Ok(I32Const(digits))
         } else if digits.parse::<u64>().is_ok() {
-            Some(FeltConst(digits))
+            Ok(FeltConst(digits))
         } else {
-            panic!("invalid const number");
+            Err(InterpreterError::lex(
+                self.token_pos,
+                format!("invalid const number {}", digits),
+            ))
         }
     }
 
-    pub fn get_next_token(&mut self) -> Option<Token> {
-        while self.current_char != None {
-            let token = match self.current_char.unwrap() {
+    pub fn get_next_token(&mut self) -> Result<Token, InterpreterError> {
+        while let Some(current_char) = self.current_char {
+            self.token_pos = SourcePos::new(self.line, self.column);
+            let token = match current_char {
                 char if char.is_whitespace() => {
                     self.skip_whitespace();
                     continue;
                 }
-                '-' if self.peek().unwrap() == '>' => {
+                '-' if self.peek() == Some('>') => {
+                    self.advance();
+                    self.advance();
+                    ReturnDel
+                }
+                '=' if self.peek() == Some('=') => {
+                    self.advance();
+                    self.advance();
+                    Equal
+                }
+                '!' if self.peek() == Some('=') => {
                     self.advance();
                     self.advance();
-                    Some(ReturnDel)
+                    NotEqual
                 }
-                '=' if self.peek().unwrap() == '=' => {
+                '<' if self.peek() == Some('<') => {
                     self.advance();
                     self.advance();
-                    Some(Equal)
+                    ShiftLeft
                 }
-                '!' if self.peek().unwrap() == '=' => {
+                '>' if self.peek() == Some('>') => {
                     self.advance();
                     self.advance();
-                    Some(NotEqual)
+                    ShiftRight
                 }
-                '<' if self.peek().unwrap() == '=' => {
+                '<' if self.peek() == Some('=') => {
                     self.advance();
                     self.advance();
-                    Some(LessEqual)
+                    LessEqual
                 }
-                '>' if self.peek().unwrap() == '=' => {
+                '>' if self.peek() == Some('=') => {
                     self.advance();
                     self.advance();
-                    Some(GreaterEqual)
+                    GreaterEqual
                 }
                 '<' => {
                     self.advance();
-                    Some(LessThan)
+                    LessThan
                 }
                 '>' => {
                     self.advance();
-                    Some(GreaterThan)
+                    GreaterThan
                 }
                 '#' => {
                     self.advance();
                     self.skip_comment();
                     continue;
                 }
-                char if char.is_digit(10) => self.number(),
+                char if char.is_ascii_digit() => self.number()?,
                 '+' => {
                     self.advance();
-                    Some(Plus)
+                    Plus
                 }
                 char if char.is_alphanumeric() => self.id(),
-                '_' if self.peek().unwrap().is_alphanumeric() => {
+                '_' if self.peek().map_or(false, |char| char.is_alphanumeric()) => {
                     self.advance();
                     self.id()
                 }
                 '=' => {
                     self.advance();
-                    Some(Assign)
+                    Assign
                 }
                 ':' => {
                     self.advance();
-                    Some(Colon)
+                    Colon
                 }
                 ';' => {
                     self.advance();
-                    Some(Semi)
+                    Semi
                 }
                 ',' => {
                     self.advance();
-                    Some(Comma)
+                    Comma
+                }
+                '.' if self.peek() == Some('.') => {
+                    self.advance();
+                    self.advance();
+                    Range
                 }
                 '.' => {
                     self.advance();
-                    Some(Dot)
+                    Dot
                 }
                 '-' => {
                     self.advance();
-                    Some(Minus)
+                    Minus
                 }
                 '*' => {
                     self.advance();
-                    Some(Multiply)
+                    Multiply
                 }
                 '/' => {
                     self.advance();
-                    Some(IntegerDivision)
+                    IntegerDivision
                 }
                 '%' => {
                     self.advance();
-                    Some(Mod)
+                    Mod
                 }
                 '(' => {
                     self.advance();
-                    Some(LParen)
+                    LParen
                 }
                 ')' => {
                     self.advance();
-                    Some(RParen)
+                    RParen
                 }
                 '{' => {
                     self.advance();
-                    Some(Begin)
+                    Begin
                 }
                 '}' => {
                     self.advance();
-                    Some(End)
+                    End
                 }
                 '[' => {
                     self.advance();
-                    Some(LBracket)
+                    LBracket
                 }
                 ']' => {
                     self.advance();
-                    Some(RBracket)
+                    RBracket
+                }
+                '&' if self.peek() == Some('&') => {
+                    self.advance();
+                    self.advance();
+                    And
                 }
-                '&' if self.peek().unwrap() == '&' => {
+                '|' if self.peek() == Some('|') => {
                     self.advance();
                     self.advance();
-                    Some(And)
+                    Or
                 }
-                '|' if self.peek().unwrap() == '|' => {
+                '&' => {
                     self.advance();
+                    BitAnd
+                }
+                '|' => {
+                    self.advance();
+                    BitOr
+                }
+                '^' => {
                     self.advance();
-                    Some(Or)
+                    BitXor
+                }
+                '~' => {
+                    self.advance();
+                    BitNot
+                }
+                unknown => {
+                    return Err(InterpreterError::lex(
+                        self.token_pos,
+                        format!("unknown character {:?}", unknown),
+                    ))
                 }
-                unknown => panic!("Unknown token found: {}", unknown),
             };
-            return token;
+            return Ok(token);
         }
-        Some(EOF)
+        self.token_pos = SourcePos::new(self.line, self.column);
+        Ok(EOF)
     }
 }
@@ -6,6 +6,8 @@ use std::sync::{Arc, RwLock};
 pub enum Token {
     Felt,
     I32,
+    U32,
+    U64,
     Array(Box<Token>, usize),
     FeltConst(String),
     I32Const(String),
@@ -31,6 +33,12 @@ pub enum Token {
     Else,
     And,
     Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    ShiftLeft,
+    ShiftRight,
     GreaterThan,
     LessThan,
     Equal,
@@ -38,6 +46,10 @@ pub enum Token {
     GreaterEqual,
     NotEqual,
     While,
+    For,
+    Break,
+    Continue,
+    Range,
     Function,
     Return,
     Entry,
@@ -49,6 +61,17 @@ pub enum Token {
     EOF,
     Malloc,
     Printf,
+    Assert,
+}
+
+impl Token {
+    /// Operators defined on `u32` and `u64` only.
+    pub fn is_bitwise(&self) -> bool {
+        matches!(
+            self,
+            Token::BitAnd | Token::BitOr | Token::BitXor | Token::ShiftLeft | Token::ShiftRight
+        )
+    }
 }
 
 impl PartialEq for Token {
@@ -67,6 +90,8 @@ impl<'a> fmt::Display for Token {
         let output = match self {
             Token::Felt => "FELT",
             Token::I32 => "I32",
+            Token::U32 => "U32",
+            Token::U64 => "U64",
             Token::Array(_, _) => &pre_fmt,
             Token::FeltConst(value) => value,
             Token::I32Const(value) => value,
@@ -92,6 +117,12 @@ impl<'a> fmt::Display for Token {
             Token::Else => "else",
             Token::And => "&&",
             Token::Or => "||",
+            Token::BitAnd => "&",
+            Token::BitOr => "|",
+            Token::BitXor => "^",
+            Token::BitNot => "~",
+            Token::ShiftLeft => "<<",
+            Token::ShiftRight => ">>",
             Token::LessThan => "<",
             Token::GreaterThan => ">",
             Token::Equal => "==",
@@ -99,6 +130,10 @@ impl<'a> fmt::Display for Token {
             Token::GreaterEqual => ">=",
             Token::NotEqual => "!=",
             Token::While => "while",
+            Token::For => "for",
+            Token::Break => "break",
+            Token::Continue => "continue",
+            Token::Range => "..",
             Token::Function => "function",
             Token::Return => "return",
             Token::Entry => "entry",
@@ -110,6 +145,7 @@ impl<'a> fmt::Display for Token {
             Token::EOF => "EOF",
             Token::Malloc => "malloc",
             Token::Printf => "printf",
+            Token::Assert => "assert",
         };
         write!(f, "{}", output)
     }
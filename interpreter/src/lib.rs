@@ -1,5 +1,7 @@
+pub mod error;
 pub mod interpreter;
 pub mod lexer;
+pub mod native;
 pub mod parser;
 pub mod sema;
 pub mod utils;
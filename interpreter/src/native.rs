@@ -0,0 +1,154 @@
+use crate::utils::builtins::{builtin_arity, call_builtin, BUILTIN_FUNCTIONS, U256_LIMBS};
+use crate::utils::number::NumberRet::{Multiple, Single};
+use crate::utils::number::{Number, NumberRet};
+use core::vm::hardware::OlaMemory;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+
+/// A prophet written in Rust. It receives the flattened input values, with
+/// `is_ref` inputs already dereferenced, and returns the flattened output
+/// values in declaration order, the same marshaling interpreted prophets use.
+pub type NativeProphetFn =
+    Box<dyn Fn(&[u64], &OlaMemory) -> Result<Vec<u64>, String> + Send + Sync>;
+
+lazy_static! {
+    /// Registry the executors dispatch `native:<id>` prophets to.
+    pub static ref NATIVE_PROPHETS: NativeProphetRegistry = NativeProphetRegistry::default();
+}
+
+pub struct NativeProphetRegistry {
+    prophets: HashMap<String, NativeProphetFn>,
+}
+
+impl NativeProphetRegistry {
+    pub fn new() -> Self {
+        NativeProphetRegistry {
+            prophets: HashMap::new(),
+        }
+    }
+
+    pub fn register<F>(&mut self, id: &str, prophet: F)
+    where
+        F: Fn(&[u64], &OlaMemory) -> Result<Vec<u64>, String> + Send + Sync + 'static,
+    {
+        self.prophets.insert(id.to_string(), Box::new(prophet));
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.prophets.contains_key(id)
+    }
+
+    pub fn ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.prophets.keys().map(|id| id.as_str()).collect();
+        ids.sort();
+        ids
+    }
+
+    pub fn run(&self, id: &str, inputs: &[u64], mem: &OlaMemory) -> Result<Vec<u64>, String> {
+        let prophet = self
+            .prophets
+            .get(id)
+            .ok_or(format!("native prophet {} not registered", id))?;
+        prophet(inputs, mem)
+    }
+}
+
+impl Default for NativeProphetRegistry {
+    /// The builtins of the prophet language plus the `split_hi`/`split_lo`
+    /// helpers compiled Ola-lang emits for u64 values.
+    fn default() -> Self {
+        let mut registry = NativeProphetRegistry::new();
+        registry.register("split_hi", |inputs, _| {
+            let value = single_input("split_hi", inputs)?;
+            Ok(vec![value >> 32])
+        });
+        registry.register("split_lo", |inputs, _| {
+            let value = single_input("split_lo", inputs)?;
+            Ok(vec![value & u32::MAX as u64])
+        });
+        for (name, _) in BUILTIN_FUNCTIONS {
+            registry.register(name, move |inputs, _| run_builtin(name, inputs));
+        }
+        registry
+    }
+}
+
+fn single_input(id: &str, inputs: &[u64]) -> Result<u64, String> {
+    match inputs {
+        [value] => Ok(*value),
+        _ => Err(format!("{} expects 1 input, got {}", id, inputs.len())),
+    }
+}
+
+// u256 arguments take 8 consecutive input values, everything else one.
+fn run_builtin(name: &str, inputs: &[u64]) -> Result<Vec<u64>, String> {
+    let arity = builtin_arity(name).ok_or(format!("unknown builtin function {}", name))?;
+    let width = if name.starts_with("u256_") {
+        U256_LIMBS
+    } else {
+        1
+    };
+    if inputs.len() != arity * width {
+        return Err(format!(
+            "{} expects {} inputs, got {}",
+            name,
+            arity * width,
+            inputs.len()
+        ));
+    }
+    let args: Vec<NumberRet> = inputs
+        .chunks(width)
+        .map(|chunk| {
+            let values: Vec<Number> = chunk.iter().map(|v| Number::from(*v)).collect();
+            if width == 1 {
+                Single(values[0].clone())
+            } else {
+                Multiple(values)
+            }
+        })
+        .collect();
+    let values = match call_builtin(name, args)? {
+        Single(value) => vec![value],
+        Multiple(values) => values,
+    };
+    values.iter().map(|value| value.to_u64()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::native::{NativeProphetRegistry, NATIVE_PROPHETS};
+    use core::vm::hardware::OlaMemory;
+
+    #[test]
+    fn test_native_prophets() {
+        let mem = OlaMemory::default();
+        assert_eq!(
+            NATIVE_PROPHETS.run("split_hi", &[0x1234_5678_9abc_def0], &mem),
+            Ok(vec![0x1234_5678])
+        );
+        assert_eq!(
+            NATIVE_PROPHETS.run("split_lo", &[0x1234_5678_9abc_def0], &mem),
+            Ok(vec![0x9abc_def0])
+        );
+        assert_eq!(
+            NATIVE_PROPHETS.run("u64_divmod", &[100, 7], &mem),
+            Ok(vec![14, 2])
+        );
+        let mut inputs = vec![0, 0, 0, 0, 0, 0, 1, 0];
+        inputs.extend([0, 0, 0, 0, 0, 0, 0, 3]);
+        let mut expected = vec![0, 0, 0, 0, 0, 0, 0, 0x5555_5555];
+        expected.extend([0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(
+            NATIVE_PROPHETS.run("u256_divmod", &inputs, &mem),
+            Ok(expected)
+        );
+        assert!(NATIVE_PROPHETS.run("u256_div", &[1, 2], &mem).is_err());
+        assert!(NATIVE_PROPHETS.run("u64_div", &[1, 0], &mem).is_err());
+        assert!(NATIVE_PROPHETS.run("missing", &[], &mem).is_err());
+
+        let mut registry = NativeProphetRegistry::new();
+        registry.register("sum", |inputs, _| Ok(vec![inputs.iter().sum()]));
+        assert_eq!(registry.ids(), vec!["sum"]);
+        assert_eq!(registry.run("sum", &[1, 2, 3], &mem), Ok(vec![6]));
+    }
+}
@@ -1,16 +1,19 @@
+use crate::error::{InterpreterError, SourcePos};
 use crate::lexer::token::Token;
 use crate::lexer::token::Token::{
-    And, Array, Assign, Begin, Cid, Comma, Else, End, Entry, Equal, Felt, FeltConst, Function,
-    GreaterEqual, GreaterThan, I32Const, Id, If, IndexId, IntegerDivision, LBracket, LParen,
-    LessEqual, LessThan, Malloc, Minus, Mod, Multiply, NotEqual, Or, Plus, Printf, RBracket,
-    RParen, Return, ReturnDel, Semi, Sqrt, While, EOF, I32,
+    And, Array, Assert, Assign, Begin, BitAnd, BitNot, BitOr, BitXor, Break, Cid, Comma, Continue,
+    Else, End, Entry, Equal, Felt, FeltConst, For, Function, GreaterEqual, GreaterThan, I32Const,
+    Id, If, IndexId, IntegerDivision, LBracket, LParen, LessEqual, LessThan, Malloc, Minus, Mod,
+    Multiply, NotEqual, Or, Plus, Printf, RBracket, RParen, Range, Return, ReturnDel, Semi,
+    ShiftLeft, ShiftRight, Sqrt, While, AS, EOF, I32, U32, U64,
 };
 use crate::lexer::Lexer;
 use crate::parser::node::{
-    ArrayNumNode, AssignNode, BinOpNode, BlockNode, CallNode, CompoundNode, CondStatNode,
-    ContextIdentNode, EntryBlockNode, EntryNode, FeltNumNode, FunctionNode, IdentDeclarationNode,
-    IdentIndexNode, IdentNode, IntegerNumNode, LoopStatNode, MallocNode, MultiAssignNode, Node,
-    PrintfNode, ReturnNode, SqrtNode, TypeNode, UnaryOpNode,
+    ArrayNumNode, AssertNode, AssignNode, BinOpNode, BlockNode, CallNode, CastNode, CompoundNode,
+    CondStatNode, ContextIdentNode, EntryBlockNode, EntryNode, FeltNumNode, ForStatNode,
+    FunctionNode, IdentDeclarationNode, IdentIndexNode, IdentNode, IntegerNumNode, LoopCtlNode,
+    LoopStatNode, MallocNode, MultiAssignNode, Node, PrintfNode, ReturnNode, SqrtNode, TypeNode,
+    UnaryOpNode,
 };
 use crate::utils::number::Number;
 use log::debug;
@@ -19,69 +22,107 @@ use std::sync::{Arc, RwLock};
 pub mod node;
 pub mod traversal;
 
+// Bounds the recursion of the parser, and with it the depth of the tree
+// the traversals walk, for hostile input.
+const MAX_NESTING_DEPTH: usize = 64;
+
+type Operand = fn(&mut Parser) -> Result<Arc<RwLock<dyn Node>>, InterpreterError>;
+
 #[macro_export]
 macro_rules! array_type_node {
     ($v: expr, $len: expr) => {
-        $v.consume(&LBracket);
-        $len = match $v.get_current_token() {
-            FeltConst(num) => num,
-            I32Const(num) => num,
-            _ => panic!("not support token type for declare"),
-        };
-        $v.consume(&$v.get_current_token());
-        $v.consume(&RBracket);
+        $v.consume(&LBracket)?;
+        $len = $v.array_len()?;
+        $v.consume(&RBracket)?;
     };
 }
 
 #[derive(Clone)]
 pub struct Parser {
     lexer: Lexer,
-    current_token: Option<Token>,
+    current_token: Token,
+    current_pos: SourcePos,
+    depth: usize,
 }
 
 impl Parser {
-    pub fn new(text: &str) -> Self {
-        let mut lexer = Lexer::new(&text);
-        let current_token = lexer.get_next_token();
+    pub fn new(text: &str) -> Result<Self, InterpreterError> {
+        let mut lexer = Lexer::new(text);
+        let current_token = lexer.get_next_token()?;
+        let current_pos = lexer.token_pos();
 
-        Parser {
+        Ok(Parser {
             lexer,
             current_token,
-        }
+            current_pos,
+            depth: 0,
+        })
     }
     fn get_current_token(&self) -> Token {
-        self.current_token.clone().unwrap()
+        self.current_token.clone()
     }
 
-    fn consume(&mut self, token_type: &Token) {
-        let current_token = self.get_current_token();
+    fn error(&self, msg: String) -> InterpreterError {
+        InterpreterError::parse(self.current_pos, msg)
+    }
 
-        if current_token == *token_type {
-            self.current_token = self.lexer.get_next_token();
+    fn consume(&mut self, token_type: &Token) -> Result<(), InterpreterError> {
+        if self.current_token == *token_type {
+            self.current_token = self.lexer.get_next_token()?;
+            self.current_pos = self.lexer.token_pos();
+            Ok(())
         } else {
-            panic!(
+            Err(self.error(format!(
                 "Unexpected token error: expected {}, received {}",
-                token_type, current_token
-            );
+                token_type, self.current_token
+            )))
         }
     }
 
-    fn entry(&mut self) -> Arc<RwLock<dyn Node>> {
-        let declarations = self.global_declarations();
-        self.consume(&Entry);
-        self.consume(&LParen);
-        self.consume(&RParen);
+    fn enter(&mut self) -> Result<(), InterpreterError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING_DEPTH {
+            return Err(self.error(format!("nesting deeper than {} levels", MAX_NESTING_DEPTH)));
+        }
+        Ok(())
+    }
+
+    fn leave(&mut self, levels: usize) {
+        self.depth -= levels;
+    }
+
+    fn at_type(&self) -> bool {
+        matches!(self.current_token, I32 | Felt | U32 | U64)
+    }
 
-        let entry_block = self.entry_block();
+    fn array_len(&mut self) -> Result<usize, InterpreterError> {
+        let current_token = self.get_current_token();
+        let len = match &current_token {
+            FeltConst(num) | I32Const(num) => num.parse::<usize>().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| self.error(format!("invalid array length {}", current_token)))?;
+        self.consume(&current_token)?;
+        Ok(len)
+    }
+
+    fn entry(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let pos = self.current_pos;
+        let declarations = self.global_declarations()?;
+        self.consume(&Entry)?;
+        self.consume(&LParen)?;
+        self.consume(&RParen)?;
+
+        let entry_block = self.entry_block()?;
         let node = EntryNode::new(declarations, entry_block);
-        Arc::new(RwLock::new(node))
+        Ok(located(pos, node))
     }
 
     fn ident_declaration_assignment(
         &mut self,
         type_node: &TypeNode,
         function_param_flag: bool,
-    ) -> Vec<Arc<RwLock<dyn Node>>> {
+    ) -> Result<Vec<Arc<RwLock<dyn Node>>>, InterpreterError> {
         let mut declarations: Vec<Arc<RwLock<dyn Node>>> = vec![];
         let mut len = Default::default();
         let mut array_flag = false;
@@ -91,6 +132,7 @@ impl Parser {
         }
 
         let current_token = self.get_current_token();
+        let pos = self.current_pos;
 
         // match current_token { }
         if let Id(id) = current_token {
@@ -98,497 +140,643 @@ impl Parser {
             if array_flag {
                 let node = IdentDeclarationNode::new(
                     IdentNode::new(Id(id.clone())),
-                    TypeNode::new(Array(
-                        Box::new(type_node.token.clone()),
-                        len.parse().unwrap(),
-                    )),
+                    TypeNode::new(Array(Box::new(type_node.token.clone()), len)),
                 );
-                declarations.push(Arc::new(RwLock::new(node)));
+                declarations.push(located(pos, node));
 
                 let ident = self.get_current_token();
-                self.consume(&ident);
+                self.consume(&ident)?;
             } else {
-                declarations.extend(self.ident_declaration(&type_node));
+                declarations.extend(self.ident_declaration(type_node)?);
             }
             if !function_param_flag {
                 if self.get_current_token() == Assign {
                     debug!("declarations id assign:{}", id);
-                    let expr = self.assignment_call_statement(Some(Id(id)));
+                    let expr = self.assignment_call_statement(Some(Id(id)), pos)?;
                     declarations.push(expr);
-                    self.consume(&Semi);
+                    self.consume(&Semi)?;
                 } else {
-                    self.consume(&Semi);
-                }
-            } else {
-                if self.get_current_token() == Comma {
-                    self.consume(&Comma);
+                    self.consume(&Semi)?;
                 }
+            } else if self.get_current_token() == Comma {
+                self.consume(&Comma)?;
             }
         } else {
-            panic!("declare no ident:{}", current_token);
+            return Err(self.error(format!("declare no ident:{}", current_token)));
         }
-        declarations
+        Ok(declarations)
     }
 
-    fn global_declarations(&mut self) -> Vec<Arc<RwLock<dyn Node>>> {
+    fn return_type(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let pos = self.current_pos;
+        let type_node = self.type_spec()?;
+        if self.get_current_token() == LBracket {
+            let len;
+            array_type_node!(self, len);
+            let token = Array(Box::new(type_node.token), len);
+            Ok(located(pos, TypeNode::new(token)))
+        } else {
+            Ok(located(pos, type_node))
+        }
+    }
+
+    fn global_declarations(&mut self) -> Result<Vec<Arc<RwLock<dyn Node>>>, InterpreterError> {
         let mut declarations: Vec<Arc<RwLock<dyn Node>>> = vec![];
         loop {
-            if self.get_current_token() == I32 || self.get_current_token() == Felt {
-                let type_node = self.type_spec();
-                declarations.extend(self.ident_declaration_assignment(&type_node, false));
+            if self.at_type() {
+                let type_node = self.type_spec()?;
+                declarations.extend(self.ident_declaration_assignment(&type_node, false)?);
             } else if self.get_current_token() == Function {
-                self.consume(&self.get_current_token());
+                let pos = self.current_pos;
+                self.consume(&self.get_current_token())?;
                 let current_token = self.get_current_token();
                 if let Id(id) = current_token {
                     debug!("function name:{}", id);
-                    self.consume(&self.get_current_token());
-                    self.consume(&LParen);
+                    self.consume(&self.get_current_token())?;
+                    self.consume(&LParen)?;
                     let mut params = Vec::new();
-                    while self.get_current_token() == I32 || self.get_current_token() == Felt {
-                        let type_node = self.type_spec();
-                        params.extend(self.ident_declaration_assignment(&type_node, true));
+                    while self.at_type() {
+                        let type_node = self.type_spec()?;
+                        params.extend(self.ident_declaration_assignment(&type_node, true)?);
                     }
-                    self.consume(&RParen);
+                    self.consume(&RParen)?;
                     let mut returns: Vec<Arc<RwLock<(dyn Node)>>> = vec![];
                     if self.get_current_token() == ReturnDel {
-                        self.consume(&ReturnDel);
+                        self.consume(&ReturnDel)?;
 
                         if self.get_current_token() == LParen {
-                            self.consume(&LParen);
-                            while self.get_current_token() == I32
-                                || self.get_current_token() == Felt
-                            {
-                                let type_node = self.type_spec();
-                                if self.get_current_token() == LBracket {
-                                    let len;
-                                    array_type_node!(self, len);
-                                    let token =
-                                        Array(Box::new(type_node.token), len.parse().unwrap());
-                                    let node = TypeNode::new(token);
-                                    returns.push(Arc::new(RwLock::new(node)));
-                                } else {
-                                    returns.push(Arc::new(RwLock::new(type_node)));
-                                }
+                            self.consume(&LParen)?;
+                            while self.at_type() {
+                                returns.push(self.return_type()?);
                                 if Comma == self.get_current_token() {
-                                    self.consume(&Comma);
+                                    self.consume(&Comma)?;
                                 }
                             }
-                            self.consume(&RParen);
+                            self.consume(&RParen)?;
                         } else {
-                            let type_node = self.type_spec();
-                            if self.get_current_token() == LBracket {
-                                let len;
-                                array_type_node!(self, len);
-                                let token = Array(Box::new(type_node.token), len.parse().unwrap());
-                                let node = TypeNode::new(token);
-                                returns.push(Arc::new(RwLock::new(node)));
-                            } else {
-                                returns.push(Arc::new(RwLock::new(type_node)));
-                            }
+                            returns.push(self.return_type()?);
                             if Comma == self.get_current_token() {
-                                self.consume(&Comma);
+                                self.consume(&Comma)?;
                             }
                         }
                     }
-                    let block = self.block();
+                    let block = self.block()?;
                     let node = FunctionNode::new(Id(id), params, returns, block);
-                    declarations.push(Arc::new(RwLock::new(node)));
+                    declarations.push(located(pos, node));
                 } else {
-                    panic!("function name not found");
+                    return Err(self.error(format!("function name not found: {}", current_token)));
                 }
             } else {
                 break;
             }
         }
-        declarations
+        Ok(declarations)
     }
 
-    fn entry_block(&mut self) -> Arc<RwLock<dyn Node>> {
+    fn entry_block(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
         // block : declarations compound_statement
-        self.consume(&Begin);
-        let declarations = self.declarations();
+        let pos = self.current_pos;
+        self.consume(&Begin)?;
+        let declarations = self.declarations()?;
         debug!("in entry state");
-        let compound_statement = self.compound_statement();
-        self.consume(&End);
+        let compound_statement = self.compound_statement()?;
+        self.consume(&End)?;
         let node = EntryBlockNode::new(declarations, compound_statement);
-        Arc::new(RwLock::new(node))
+        Ok(located(pos, node))
     }
 
-    fn block(&mut self) -> Arc<RwLock<dyn Node>> {
-        self.consume(&Begin);
-        let declarations = self.declarations();
+    fn block(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let pos = self.current_pos;
+        self.consume(&Begin)?;
+        let declarations = self.declarations()?;
         debug!("in state");
-        let compound_statement = self.compound_statement();
-        self.consume(&End);
+        let compound_statement = self.compound_statement()?;
+        self.consume(&End)?;
         let node = BlockNode::new(declarations, compound_statement);
-        Arc::new(RwLock::new(node))
+        Ok(located(pos, node))
     }
 
-    fn compound_statement(&mut self) -> Arc<RwLock<dyn Node>> {
+    fn compound_statement(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
         // compound_statement : Begin statement_list End
-        let nodes = self.statement_list();
+        let pos = self.current_pos;
+        let nodes = self.statement_list()?;
 
-        Arc::new(RwLock::new(CompoundNode::new(nodes)))
+        Ok(located(pos, CompoundNode::new(nodes)))
     }
 
-    fn statement_list(&mut self) -> Vec<Arc<RwLock<dyn Node>>> {
+    fn statement_list(&mut self) -> Result<Vec<Arc<RwLock<dyn Node>>>, InterpreterError> {
+        self.enter()?;
         let mut results = vec![];
 
         loop {
+            let pos = self.current_pos;
             if let Id(id) = self.get_current_token() {
                 debug!("id:{}", id);
-                self.consume(&self.get_current_token());
+                self.consume(&self.get_current_token())?;
                 let mut token = Id(id.clone());
                 if self.get_current_token() == LBracket {
-                    self.consume(&LBracket);
-                    let index = self.add_expr();
+                    self.consume(&LBracket)?;
+                    let index = self.bit_or_expr()?;
                     token = IndexId(id.to_string(), index);
-                    self.consume(&RBracket);
+                    self.consume(&RBracket)?;
                 }
 
-                results.push(self.assignment_call_statement(Some(token)));
+                results.push(self.assignment_call_statement(Some(token), pos)?);
 
                 if let Id(_) = self.get_current_token() {
-                    panic!(
+                    return Err(self.error(format!(
                         "Invalid token in statement list: {}",
                         self.get_current_token()
-                    )
+                    )));
                 }
                 if self.get_current_token() != End {
-                    self.consume(&Semi);
+                    self.consume(&Semi)?;
                 }
             } else if let Cid(_id) = self.get_current_token() {
-                results.push(self.assignment_call_statement(None));
+                results.push(self.assignment_call_statement(None, pos)?);
                 if let Id(_) = self.get_current_token() {
-                    panic!(
+                    return Err(self.error(format!(
                         "Invalid token in statement list: {}",
                         self.get_current_token()
-                    )
+                    )));
                 }
                 if self.get_current_token() != End {
-                    self.consume(&Semi);
+                    self.consume(&Semi)?;
                 }
             } else if If == self.get_current_token() {
-                results.push(self.cond_statement());
+                results.push(self.cond_statement()?);
             } else if While == self.get_current_token() {
-                results.push(self.loop_statement());
+                results.push(self.loop_statement()?);
+            } else if For == self.get_current_token() {
+                results.push(self.for_statement()?);
+            } else if Break == self.get_current_token() || Continue == self.get_current_token() {
+                let token = self.get_current_token();
+                self.consume(&token)?;
+                results.push(located(pos, LoopCtlNode::new(token)));
+                if self.get_current_token() != End {
+                    self.consume(&Semi)?;
+                }
             } else if End == self.get_current_token() {
                 break;
             } else if Return == self.get_current_token() {
-                self.consume(&Return);
+                self.consume(&Return)?;
                 let mut returns = Vec::new();
                 if self.get_current_token() != LParen {
-                    let expr = self.or_expr();
+                    let expr = self.or_expr()?;
                     returns.push(expr);
                 } else {
-                    self.consume(&self.get_current_token());
+                    self.consume(&self.get_current_token())?;
                     while self.get_current_token() != RParen {
-                        let expr = self.or_expr();
+                        let expr = self.or_expr()?;
                         returns.push(expr);
                         if self.get_current_token() == Comma {
-                            self.consume(&self.get_current_token());
+                            self.consume(&self.get_current_token())?;
                         }
                     }
-                    self.consume(&RParen);
+                    self.consume(&RParen)?;
                 }
                 if self.get_current_token() == Semi {
-                    self.consume(&Semi);
+                    self.consume(&Semi)?;
                 }
                 let node = ReturnNode::new(returns);
-                results.push(Arc::new(RwLock::new(node)));
+                results.push(located(pos, node));
                 if self.get_current_token() != End {
-                    self.consume(&Semi);
+                    self.consume(&Semi)?;
                 }
             } else if LParen == self.get_current_token() {
-                self.consume(&LParen);
+                self.consume(&LParen)?;
                 let mut idents = Vec::new();
                 while self.get_current_token() != RParen {
-                    if self.get_current_token() == I32 || self.get_current_token() == Felt {
-                        let type_node = self.type_spec();
-                        idents.extend(self.ident_declaration_assignment(&type_node, true));
+                    if self.at_type() {
+                        let type_node = self.type_spec()?;
+                        idents.extend(self.ident_declaration_assignment(&type_node, true)?);
                     } else if let Id(_) = self.get_current_token() {
-                        idents.push(Arc::new(RwLock::new(IdentNode::new(
-                            self.get_current_token(),
-                        ))));
-                        self.consume(&self.get_current_token());
+                        idents.push(located(
+                            self.current_pos,
+                            IdentNode::new(self.get_current_token()),
+                        ));
+                        self.consume(&self.get_current_token())?;
                         if self.get_current_token() == Comma {
-                            self.consume(&Comma);
+                            self.consume(&Comma)?;
                         }
+                    } else {
+                        return Err(self.error(format!(
+                            "Invalid token in assignment list: {}",
+                            self.get_current_token()
+                        )));
                     }
                 }
-                self.consume(&RParen);
+                self.consume(&RParen)?;
                 if self.get_current_token() == Assign {
-                    self.consume(&Assign);
-                    let call = self.call_statement(None);
+                    self.consume(&Assign)?;
+                    let call = self.call_statement(None, self.current_pos)?;
                     let node = MultiAssignNode::new(idents, Vec::new(), call, Assign);
-                    results.push(Arc::new(RwLock::new(node)));
+                    results.push(located(pos, node));
                 }
                 if self.get_current_token() == Semi {
-                    self.consume(&Semi);
+                    self.consume(&Semi)?;
                 }
             } else if Printf == self.get_current_token() {
-                self.consume(&self.get_current_token());
-                self.consume(&LParen);
-                let start = self.or_expr();
-                self.consume(&Comma);
-                let flag = self.or_expr();
-                self.consume(&RParen);
-                results.push(Arc::new(RwLock::new(PrintfNode::new(start, flag))));
+                self.consume(&self.get_current_token())?;
+                self.consume(&LParen)?;
+                let start = self.or_expr()?;
+                self.consume(&Comma)?;
+                let flag = self.or_expr()?;
+                self.consume(&RParen)?;
+                results.push(located(pos, PrintfNode::new(start, flag)));
+                if self.get_current_token() == Semi {
+                    self.consume(&Semi)?;
+                }
+            } else if Assert == self.get_current_token() {
+                self.consume(&Assert)?;
+                self.consume(&LParen)?;
+                let condition = self.or_expr()?;
+                let mut value = None;
+                if self.get_current_token() == Comma {
+                    self.consume(&Comma)?;
+                    value = Some(self.or_expr()?);
+                }
+                self.consume(&RParen)?;
+                results.push(located(pos, AssertNode::new(condition, value)));
                 if self.get_current_token() == Semi {
-                    self.consume(&Semi);
+                    self.consume(&Semi)?;
                 }
+            } else {
+                return Err(self.error(format!(
+                    "Invalid token in statement list: {}",
+                    self.get_current_token()
+                )));
             }
         }
-        results
+        self.leave(1);
+        Ok(results)
     }
 
-    fn type_spec(&mut self) -> TypeNode {
+    fn type_spec(&mut self) -> Result<TypeNode, InterpreterError> {
         let current_token = self.get_current_token();
         match current_token {
-            I32 | Felt => {
-                self.consume(&current_token);
-                TypeNode::new(current_token)
+            I32 | Felt | U32 | U64 => {
+                let pos = self.current_pos;
+                self.consume(&current_token)?;
+                let mut node = TypeNode::new(current_token);
+                node.set_pos(pos);
+                Ok(node)
             }
-            token => panic!("Unknown token type found {}", token),
+            token => Err(self.error(format!("Unknown token type found {}", token))),
         }
     }
 
-    fn ident_declaration(&mut self, type_node: &TypeNode) -> Vec<Arc<RwLock<dyn Node>>> {
+    fn ident_declaration(
+        &mut self,
+        type_node: &TypeNode,
+    ) -> Result<Vec<Arc<RwLock<dyn Node>>>, InterpreterError> {
         // variable_declaration : Id (Comma Id)* Colon type_spec
         let mut ident_nodes: Vec<IdentNode> = Vec::new();
+        let pos = self.current_pos;
         let identifier = self.get_current_token();
-        self.consume(&identifier);
+        self.consume(&identifier)?;
 
         ident_nodes.push(IdentNode::new(identifier));
 
         let mut declaration_nodes: Vec<Arc<RwLock<dyn Node>>> = vec![];
         for node in ident_nodes {
             let declaration = IdentDeclarationNode::new(node, type_node.clone());
-            declaration_nodes.push(Arc::new(RwLock::new(declaration)));
+            declaration_nodes.push(located(pos, declaration));
         }
-        declaration_nodes
+        Ok(declaration_nodes)
     }
 
-    fn declarations(&mut self) -> Vec<Arc<RwLock<dyn Node>>> {
+    fn declarations(&mut self) -> Result<Vec<Arc<RwLock<dyn Node>>>, InterpreterError> {
         let mut declarations: Vec<Arc<RwLock<dyn Node>>> = vec![];
-        while self.get_current_token() == I32 || self.get_current_token() == Felt {
-            let type_node = self.type_spec();
-            declarations.extend(self.ident_declaration_assignment(&type_node, false));
+        while self.at_type() {
+            let type_node = self.type_spec()?;
+            declarations.extend(self.ident_declaration_assignment(&type_node, false)?);
         }
-        declarations
+        Ok(declarations)
     }
 
-    fn call_statement(&mut self, id: Option<Token>) -> Arc<RwLock<dyn Node>> {
-        let left;
-        if id.is_none() {
-            left = self.get_current_token();
-            self.consume(&left);
-        } else {
-            left = id.unwrap();
-        }
-        self.consume(&LParen);
+    fn call_params(&mut self) -> Result<Vec<Arc<RwLock<dyn Node>>>, InterpreterError> {
+        self.consume(&LParen)?;
         let mut params = Vec::new();
         while self.get_current_token() != RParen {
-            let param = self.or_expr();
+            let param = self.or_expr()?;
             params.push(param);
             if self.get_current_token() == Comma {
-                self.consume(&Comma);
+                self.consume(&Comma)?;
             }
         }
-        self.consume(&RParen);
+        self.consume(&RParen)?;
+        Ok(params)
+    }
+
+    fn call_statement(
+        &mut self,
+        id: Option<Token>,
+        pos: SourcePos,
+    ) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let left = match id {
+            Some(id) => id,
+            None => {
+                let left = self.get_current_token();
+                self.consume(&left)?;
+                left
+            }
+        };
+        let params = self.call_params()?;
         let node = CallNode::new(left, params);
-        Arc::new(RwLock::new(node))
+        Ok(located(pos, node))
     }
 
-    fn assignment_call_statement(&mut self, id: Option<Token>) -> Arc<RwLock<dyn Node>> {
-        let left;
-        if id.is_none() {
-            left = self.get_current_token();
-            self.consume(&left);
-        } else {
-            left = id.unwrap();
-        }
+    fn assignment_call_statement(
+        &mut self,
+        id: Option<Token>,
+        pos: SourcePos,
+    ) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let left = match id {
+            Some(id) => id,
+            None => {
+                let left = self.get_current_token();
+                self.consume(&left)?;
+                left
+            }
+        };
         let current_token = self.get_current_token();
         if current_token == Assign {
-            self.consume(&Assign);
-            let right = self.or_expr();
+            self.consume(&Assign)?;
+            let right = self.or_expr()?;
             let node = AssignNode::new(left, right, current_token);
-            Arc::new(RwLock::new(node))
+            Ok(located(pos, node))
         } else if current_token == LParen {
-            self.call_statement(Some(left))
+            self.call_statement(Some(left), pos)
         } else {
-            panic!("assignment_call_statement mot match:{}", current_token)
+            Err(self.error(format!(
+                "assignment_call_statement mot match:{}",
+                current_token
+            )))
         }
     }
 
-    fn cond_statement(&mut self) -> Arc<RwLock<dyn Node>> {
-        self.consume(&If);
-        let condition = self.or_expr();
+    fn cond_statement(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        self.enter()?;
+        let pos = self.current_pos;
+        self.consume(&If)?;
+        let condition = self.or_expr()?;
 
-        self.consume(&Begin);
-        let true_state = self.statement_list();
-        self.consume(&End);
+        self.consume(&Begin)?;
+        let true_state = self.statement_list()?;
+        self.consume(&End)?;
 
         let mut flase_state = Vec::new();
         if Else == self.get_current_token() {
-            self.consume(&Else);
+            self.consume(&Else)?;
             if If == self.get_current_token() {
-                flase_state.push(self.cond_statement());
+                flase_state.push(self.cond_statement()?);
             } else if Begin == self.get_current_token() {
-                self.consume(&Begin);
-                flase_state.extend(self.statement_list());
-                self.consume(&End);
+                self.consume(&Begin)?;
+                flase_state.extend(self.statement_list()?);
+                self.consume(&End)?;
             } else {
-                panic!("not support condition branch");
+                return Err(self.error(format!(
+                    "not support condition branch: {}",
+                    self.get_current_token()
+                )));
             }
         }
         let node = CondStatNode::new(condition, true_state, flase_state);
+        self.leave(1);
 
-        return Arc::new(RwLock::new(node));
+        Ok(located(pos, node))
     }
 
-    fn loop_statement(&mut self) -> Arc<RwLock<dyn Node>> {
-        self.consume(&While);
-        let condition = self.or_expr();
+    fn loop_statement(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let pos = self.current_pos;
+        self.consume(&While)?;
+        let condition = self.or_expr()?;
 
-        self.consume(&Begin);
-        let consequences = self.statement_list();
-        self.consume(&End);
+        self.consume(&Begin)?;
+        let consequences = self.statement_list()?;
+        self.consume(&End)?;
 
         let node = LoopStatNode::new(condition, consequences);
 
-        Arc::new(RwLock::new(node))
+        Ok(located(pos, node))
     }
 
-    fn identifier(&mut self) -> Arc<RwLock<dyn Node>> {
+    fn for_statement(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        // for_statement : For Id in or_expr Range or_expr Begin statement_list End
+        let pos = self.current_pos;
+        self.consume(&For)?;
+        let ident = self.get_current_token();
+        if !matches!(ident, Id(_)) {
+            return Err(self.error(format!("for loop needs a variable, found {}", ident)));
+        }
+        self.consume(&ident)?;
+        // `in` is not reserved, existing prophets use it as a name
+        self.consume(&Id("in".to_string()))?;
+        let start = self.or_expr()?;
+        self.consume(&Range)?;
+        let end = self.or_expr()?;
+
+        self.consume(&Begin)?;
+        let consequences = self.statement_list()?;
+        self.consume(&End)?;
+
+        let node = ForStatNode::new(ident, start, end, consequences);
+
+        Ok(located(pos, node))
+    }
+
+    fn identifier(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
         let current_token = self.get_current_token();
+        let pos = self.current_pos;
         if let Id(_) = current_token {
-            self.consume(&self.get_current_token());
+            self.consume(&self.get_current_token())?;
             if self.get_current_token() == LParen {
-                self.consume(&self.get_current_token());
-                let mut params = Vec::new();
-                while self.get_current_token() != RParen {
-                    let param = self.or_expr();
-                    params.push(param);
-                    if self.get_current_token() == Comma {
-                        self.consume(&Comma);
-                    }
-                }
-                self.consume(&RParen);
+                let params = self.call_params()?;
                 let node = CallNode::new(current_token, params);
-                Arc::new(RwLock::new(node))
+                Ok(located(pos, node))
             } else if LBracket == self.get_current_token() {
-                self.consume(&LBracket);
-                let index = self.add_expr();
+                self.consume(&LBracket)?;
+                let index = self.bit_or_expr()?;
                 let node = IdentIndexNode::new(current_token, index);
-                self.consume(&RBracket);
-                Arc::new(RwLock::new(node))
+                self.consume(&RBracket)?;
+                Ok(located(pos, node))
             } else {
                 let node = IdentNode::new(current_token);
-                Arc::new(RwLock::new(node))
+                Ok(located(pos, node))
             }
         } else if let Cid(_) = self.get_current_token() {
-            self.consume(&current_token);
+            self.consume(&current_token)?;
             let node = ContextIdentNode::new(current_token);
-            Arc::new(RwLock::new(node))
+            Ok(located(pos, node))
         } else {
-            panic!("Invalid variable: {}", current_token);
+            Err(self.error(format!("Invalid variable: {}", current_token)))
         }
     }
 
-    fn cast_expr(&mut self) -> Arc<RwLock<dyn Node>> {
-        let mut current_token = self.get_current_token();
+    fn cast_expr(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        self.enter()?;
+        let current_token = self.get_current_token();
+        let pos = self.current_pos;
 
-        match current_token {
+        let node = match current_token {
             Plus | Minus => {
-                self.consume(&current_token);
-                let node = UnaryOpNode::new(current_token, self.mul_expr());
-                Arc::new(RwLock::new(node))
+                self.consume(&current_token)?;
+                let node = UnaryOpNode::new(current_token, self.mul_expr()?);
+                located(pos, node)
             }
-            FeltConst(value) => {
-                current_token = self.get_current_token();
-                self.consume(&current_token);
-                Arc::new(RwLock::new(FeltNumNode::new(value.parse::<u64>().unwrap())))
+            BitNot => {
+                self.consume(&current_token)?;
+                let node = UnaryOpNode::new(current_token, self.cast_expr()?);
+                located(pos, node)
             }
-            I32Const(value) => {
-                current_token = self.get_current_token();
-                self.consume(&current_token);
-                Arc::new(RwLock::new(IntegerNumNode::new(
-                    value.parse::<i32>().unwrap(),
-                )))
+            FeltConst(ref value) => {
+                let value = value
+                    .parse::<u64>()
+                    .map_err(|err| self.error(format!("invalid felt {}: {}", value, err)))?;
+                self.consume(&current_token)?;
+                located(pos, FeltNumNode::new(value))
+            }
+            I32Const(ref value) => {
+                let value = value
+                    .parse::<i32>()
+                    .map_err(|err| self.error(format!("invalid i32 {}: {}", value, err)))?;
+                self.consume(&current_token)?;
+                located(pos, IntegerNumNode::new(value))
             }
             Sqrt => {
-                self.consume(&current_token);
-                self.consume(&LParen);
-                let sqrt_value = self.or_expr();
-                self.consume(&RParen);
-                Arc::new(RwLock::new(SqrtNode::new(sqrt_value)))
+                self.consume(&current_token)?;
+                self.consume(&LParen)?;
+                let sqrt_value = self.or_expr()?;
+                self.consume(&RParen)?;
+                located(pos, SqrtNode::new(sqrt_value))
             }
             Malloc => {
-                self.consume(&current_token);
-                self.consume(&LParen);
-                let num_bytes = self.or_expr();
-                self.consume(&RParen);
-                Arc::new(RwLock::new(MallocNode::new(num_bytes)))
+                self.consume(&current_token)?;
+                self.consume(&LParen)?;
+                let num_bytes = self.or_expr()?;
+                self.consume(&RParen)?;
+                located(pos, MallocNode::new(num_bytes))
             }
             LParen => {
-                self.consume(&current_token);
-                let node = self.or_expr();
-                self.consume(&RParen);
+                self.consume(&current_token)?;
+                let node = self.or_expr()?;
+                self.consume(&RParen)?;
                 node
             }
-            Id(_) | Cid(_) => self.identifier(),
-            LBracket => self.array_const(),
+            Id(_) | Cid(_) => self.identifier()?,
+            LBracket => self.array_const()?,
             Printf => {
-                self.consume(&current_token);
-                self.consume(&LParen);
-                let start = self.or_expr();
-                self.consume(&Comma);
-                let flag = self.or_expr();
-                self.consume(&RParen);
-                Arc::new(RwLock::new(PrintfNode::new(start, flag)))
+                self.consume(&current_token)?;
+                self.consume(&LParen)?;
+                let start = self.or_expr()?;
+                self.consume(&Comma)?;
+                let flag = self.or_expr()?;
+                self.consume(&RParen)?;
+                located(pos, PrintfNode::new(start, flag))
+            }
+            _ => {
+                return Err(self.error(format!(
+                    "not support token in cast_expr:{}",
+                    self.get_current_token()
+                )))
             }
-            _ => panic!(
-                "not support token in cast_expr:{}",
-                self.get_current_token()
-            ),
+        };
+        self.leave(1);
+        Ok(node)
+    }
+
+    fn as_expr(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let mut node = self.cast_expr()?;
+        let mut levels = 0;
+        while self.get_current_token() == AS {
+            let pos = self.current_pos;
+            self.enter()?;
+            levels += 1;
+            self.consume(&AS)?;
+            let target = self.type_spec()?.token;
+            node = located(pos, CastNode::new(node, target));
         }
+        self.leave(levels);
+        Ok(node)
     }
 
-    fn mul_expr(&mut self) -> Arc<RwLock<dyn Node>> {
-        let mut node = self.cast_expr();
+    // Operator chains build left-deep trees without recursing here, every
+    // operator counts as a nesting level until the chain ends.
+    fn mul_expr(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let mut node = self.as_expr()?;
         let mut current_token = self.get_current_token();
+        let mut levels = 0;
 
         while current_token == Multiply || current_token == IntegerDivision || current_token == Mod
         {
-            self.consume(&current_token);
-            node = Arc::new(RwLock::new(BinOpNode::new(
-                node,
-                self.cast_expr(),
-                current_token,
-            )));
+            let pos = self.current_pos;
+            self.enter()?;
+            levels += 1;
+            self.consume(&current_token)?;
+            node = located(pos, BinOpNode::new(node, self.as_expr()?, current_token));
             current_token = self.get_current_token();
         }
-        node
+        self.leave(levels);
+        Ok(node)
     }
 
-    fn add_expr(&mut self) -> Arc<RwLock<dyn Node>> {
-        let mut node = self.mul_expr();
+    fn add_expr(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let mut node = self.mul_expr()?;
         let mut current_token = self.get_current_token();
+        let mut levels = 0;
         while current_token == Plus || current_token == Minus {
-            self.consume(&current_token);
-            node = Arc::new(RwLock::new(BinOpNode::new(
-                node,
-                self.mul_expr(),
-                current_token,
-            )));
+            let pos = self.current_pos;
+            self.enter()?;
+            levels += 1;
+            self.consume(&current_token)?;
+            node = located(pos, BinOpNode::new(node, self.mul_expr()?, current_token));
             current_token = self.get_current_token();
         }
-        node
+        self.leave(levels);
+        Ok(node)
+    }
+
+    // Bitwise operators bind as in Rust: shifts below `+ -`, then `&`, `^`
+    // and `|`, all above the comparisons.
+    fn binop_chain(
+        &mut self,
+        operators: &[Token],
+        operand: Operand,
+    ) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let mut node = operand(self)?;
+        let mut current_token = self.get_current_token();
+        let mut levels = 0;
+        while operators.contains(&current_token) {
+            let pos = self.current_pos;
+            self.enter()?;
+            levels += 1;
+            self.consume(&current_token)?;
+            node = located(pos, BinOpNode::new(node, operand(self)?, current_token));
+            current_token = self.get_current_token();
+        }
+        self.leave(levels);
+        Ok(node)
+    }
+
+    fn shift_expr(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        self.binop_chain(&[ShiftLeft, ShiftRight], Self::add_expr)
     }
 
-    fn rel_expr(&mut self) -> Arc<RwLock<dyn Node>> {
-        let left = self.add_expr();
+    fn bit_and_expr(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        self.binop_chain(&[BitAnd], Self::shift_expr)
+    }
+
+    fn bit_xor_expr(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        self.binop_chain(&[BitXor], Self::bit_and_expr)
+    }
+
+    fn bit_or_expr(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        self.binop_chain(&[BitOr], Self::bit_xor_expr)
+    }
+
+    fn rel_expr(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let left = self.bit_or_expr()?;
         let current_token = self.get_current_token();
         if (current_token == GreaterThan)
             || (current_token == NotEqual)
@@ -597,82 +785,100 @@ impl Parser {
             || (current_token == LessThan)
             || (current_token == LessEqual)
         {
-            self.consume(&current_token);
-            let right = self.add_expr();
+            let pos = self.current_pos;
+            self.consume(&current_token)?;
+            let right = self.bit_or_expr()?;
             let node = BinOpNode::new(left, right, current_token);
-            Arc::new(RwLock::new(node))
+            Ok(located(pos, node))
         } else {
-            left
+            Ok(left)
         }
     }
 
-    fn and_expr(&mut self) -> Arc<RwLock<dyn Node>> {
-        let mut node = self.rel_expr();
+    fn and_expr(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let mut node = self.rel_expr()?;
         let mut current_token = self.get_current_token();
+        let mut levels = 0;
         while current_token == And {
-            self.consume(&current_token);
-            node = Arc::new(RwLock::new(BinOpNode::new(
-                node,
-                self.rel_expr(),
-                current_token,
-            )));
+            let pos = self.current_pos;
+            self.enter()?;
+            levels += 1;
+            self.consume(&current_token)?;
+            node = located(pos, BinOpNode::new(node, self.rel_expr()?, current_token));
             current_token = self.get_current_token();
         }
-        node
+        self.leave(levels);
+        Ok(node)
     }
 
-    fn or_expr(&mut self) -> Arc<RwLock<dyn Node>> {
-        let mut node = self.and_expr();
+    fn or_expr(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let mut node = self.and_expr()?;
         let mut current_token = self.get_current_token();
+        let mut levels = 0;
         while current_token == Or {
-            self.consume(&current_token);
-            node = Arc::new(RwLock::new(BinOpNode::new(
-                node,
-                self.and_expr(),
-                current_token,
-            )));
+            let pos = self.current_pos;
+            self.enter()?;
+            levels += 1;
+            self.consume(&current_token)?;
+            node = located(pos, BinOpNode::new(node, self.and_expr()?, current_token));
             current_token = self.get_current_token();
         }
-        node
+        self.leave(levels);
+        Ok(node)
     }
 
-    fn array_const(&mut self) -> Arc<RwLock<dyn Node>> {
-        self.consume(&LBracket);
+    fn array_const(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let pos = self.current_pos;
+        self.consume(&LBracket)?;
         let mut values = Vec::new();
         loop {
             let current_token = self.get_current_token();
-            if let I32Const(value) = current_token {
-                values.push(Number::I32(value.parse().unwrap()));
-                self.consume(&self.get_current_token());
+            if let I32Const(value) = &current_token {
+                let value = value
+                    .parse()
+                    .map_err(|err| self.error(format!("invalid i32 {}: {}", value, err)))?;
+                values.push(Number::I32(value));
+                self.consume(&current_token)?;
 
                 if Comma == self.get_current_token() {
-                    self.consume(&self.get_current_token());
+                    self.consume(&self.get_current_token())?;
                 }
-            } else if let FeltConst(value) = current_token {
-                values.push(Number::Felt(value.parse().unwrap()));
+            } else if let FeltConst(value) = &current_token {
+                let value = value
+                    .parse::<u64>()
+                    .map_err(|err| self.error(format!("invalid felt {}: {}", value, err)))?;
+                values.push(Number::from(value));
 
-                self.consume(&self.get_current_token());
+                self.consume(&current_token)?;
                 if Comma == self.get_current_token() {
-                    self.consume(&self.get_current_token());
+                    self.consume(&self.get_current_token())?;
                 }
             } else if RBracket == self.get_current_token() {
-                self.consume(&RBracket);
+                self.consume(&RBracket)?;
                 break;
             } else {
-                panic!("invalid array const")
+                return Err(self.error(format!("invalid array const: {}", current_token)));
             }
         }
 
         let node = ArrayNumNode::new(values);
-        Arc::new(RwLock::new(node))
+        Ok(located(pos, node))
     }
 
-    pub fn parse(&mut self) -> Arc<RwLock<dyn Node>> {
-        let node = self.entry();
+    pub fn parse(&mut self) -> Result<Arc<RwLock<dyn Node>>, InterpreterError> {
+        let node = self.entry()?;
         let current_token = self.get_current_token();
         if current_token != EOF {
-            panic!("Unexpected token found at end of file: {}", current_token);
+            return Err(self.error(format!(
+                "Unexpected token found at end of file: {}",
+                current_token
+            )));
         }
-        node
+        Ok(node)
     }
 }
+
+fn located<T: Node + 'static>(pos: SourcePos, mut node: T) -> Arc<RwLock<dyn Node>> {
+    node.set_pos(pos);
+    Arc::new(RwLock::new(node))
+}
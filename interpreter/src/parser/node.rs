@@ -3,48 +3,64 @@ use std::fmt;
 use std::fmt::Debug;
 use std::sync::{Arc, RwLock};
 
+use crate::error::SourcePos;
 use crate::lexer::token::Token;
-use crate::parser::traversal::{is_node_type, safe_downcast_ref, Traversal};
+use crate::parser::traversal::{downcast_map, Traversal};
 use crate::sema::symbol::Symbol;
 use crate::utils::number::{Number, NumberResult};
 use node_derive::Node;
 
-pub trait Node {
+pub trait Node: Send + Sync {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
+    /// Where the node is in the prophet body, for error reporting.
+    fn pos(&self) -> SourcePos;
+    fn set_pos(&mut self, pos: SourcePos);
     fn traverse(&mut self, visitor: &mut dyn Traversal) -> NumberResult;
 }
 
 #[derive(Debug, Node)]
 pub struct IntegerNumNode {
     pub value: i32,
+    pub pos: SourcePos,
 }
 
 impl IntegerNumNode {
     pub fn new(value: i32) -> Self {
-        IntegerNumNode { value }
+        IntegerNumNode {
+            value,
+            pos: SourcePos::default(),
+        }
     }
 }
 
 #[derive(Debug, Node)]
 pub struct FeltNumNode {
     pub value: u64,
+    pub pos: SourcePos,
 }
 
 impl FeltNumNode {
     pub fn new(value: u64) -> Self {
-        FeltNumNode { value }
+        FeltNumNode {
+            value,
+            pos: SourcePos::default(),
+        }
     }
 }
 
 #[derive(Debug, Node)]
 pub struct ArrayNumNode {
     pub values: Vec<Number>,
+    pub pos: SourcePos,
 }
 
 impl ArrayNumNode {
     pub fn new(values: Vec<Number>) -> Self {
-        ArrayNumNode { values }
+        ArrayNumNode {
+            values,
+            pos: SourcePos::default(),
+        }
     }
 }
 
@@ -53,6 +69,7 @@ pub struct BinOpNode {
     pub left: Arc<RwLock<dyn Node>>,
     pub right: Arc<RwLock<dyn Node>>,
     pub operator: Token,
+    pub pos: SourcePos,
 }
 
 impl BinOpNode {
@@ -61,30 +78,18 @@ impl BinOpNode {
             left,
             right,
             operator,
+            pos: SourcePos::default(),
         }
     }
 }
 
 pub fn to_string(node: &Arc<RwLock<dyn Node>>) -> String {
-    if is_node_type::<IntegerNumNode>(node) {
-        safe_downcast_ref::<IntegerNumNode>(node).value.to_string()
-    } else if is_node_type::<FeltNumNode>(node) {
-        safe_downcast_ref::<FeltNumNode>(node).value.to_string()
-    } else if is_node_type::<IdentNode>(node) {
-        safe_downcast_ref::<IdentNode>(node).identifier.to_string()
-    } else if is_node_type::<ContextIdentNode>(node) {
-        safe_downcast_ref::<ContextIdentNode>(node)
-            .identifier
-            .to_string()
-    } else {
-        let bin_node = safe_downcast_ref::<BinOpNode>(node);
-        format!(
-            "BinOpNode({} {} {}) ",
-            to_string(&bin_node.left),
-            to_string(&bin_node.right),
-            bin_node.operator
-        )
-    }
+    downcast_map::<IntegerNumNode, _>(node, |node| node.value.to_string())
+        .or_else(|| downcast_map::<FeltNumNode, _>(node, |node| node.value.to_string()))
+        .or_else(|| downcast_map::<IdentNode, _>(node, |node| node.identifier.to_string()))
+        .or_else(|| downcast_map::<ContextIdentNode, _>(node, |node| node.identifier.to_string()))
+        .or_else(|| downcast_map::<BinOpNode, _>(node, |node| format!("{:?}", node)))
+        .unwrap_or_else(|| "Node".to_string())
 }
 
 impl Debug for BinOpNode {
@@ -99,11 +104,16 @@ impl Debug for BinOpNode {
 pub struct UnaryOpNode {
     pub operator: Token,
     pub expr: Arc<RwLock<dyn Node>>,
+    pub pos: SourcePos,
 }
 
 impl UnaryOpNode {
     pub fn new(operator: Token, expr: Arc<RwLock<dyn Node>>) -> Self {
-        UnaryOpNode { operator, expr }
+        UnaryOpNode {
+            operator,
+            expr,
+            pos: SourcePos::default(),
+        }
     }
 }
 
@@ -114,25 +124,58 @@ impl Debug for UnaryOpNode {
     }
 }
 
+/// `expr as target`.
+#[derive(Node)]
+pub struct CastNode {
+    pub expr: Arc<RwLock<dyn Node>>,
+    pub target: Token,
+    pub pos: SourcePos,
+}
+
+impl CastNode {
+    pub fn new(expr: Arc<RwLock<dyn Node>>, target: Token) -> Self {
+        CastNode {
+            expr,
+            target,
+            pos: SourcePos::default(),
+        }
+    }
+}
+
+impl Debug for CastNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expr = to_string(&self.expr);
+        write!(f, "CastNode({} as {}) ", expr, self.target)
+    }
+}
+
 #[derive(Node)]
 pub struct IdentNode {
     pub identifier: Token,
+    pub pos: SourcePos,
 }
 
 impl IdentNode {
     pub fn new(identifier: Token) -> Self {
-        IdentNode { identifier }
+        IdentNode {
+            identifier,
+            pos: SourcePos::default(),
+        }
     }
 }
 
 #[derive(Node)]
 pub struct ContextIdentNode {
     pub identifier: Token,
+    pub pos: SourcePos,
 }
 
 impl ContextIdentNode {
     pub fn new(identifier: Token) -> Self {
-        ContextIdentNode { identifier }
+        ContextIdentNode {
+            identifier,
+            pos: SourcePos::default(),
+        }
     }
 }
 
@@ -141,6 +184,7 @@ pub struct AssignNode {
     pub identifier: Token,
     pub expr: Arc<RwLock<dyn Node>>,
     pub operator: Token,
+    pub pos: SourcePos,
 }
 
 impl AssignNode {
@@ -149,6 +193,7 @@ impl AssignNode {
             identifier,
             expr,
             operator,
+            pos: SourcePos::default(),
         }
     }
 }
@@ -159,6 +204,7 @@ pub struct MultiAssignNode {
     pub expr: Vec<Arc<RwLock<dyn Node>>>,
     pub call: Arc<RwLock<dyn Node>>,
     pub operator: Token,
+    pub pos: SourcePos,
 }
 
 impl MultiAssignNode {
@@ -173,6 +219,7 @@ impl MultiAssignNode {
             expr,
             call,
             operator,
+            pos: SourcePos::default(),
         }
     }
 }
@@ -181,6 +228,7 @@ impl MultiAssignNode {
 pub struct IdentDeclarationNode {
     pub ident_node: IdentNode,
     pub type_node: TypeNode,
+    pub pos: SourcePos,
 }
 
 impl IdentDeclarationNode {
@@ -188,6 +236,7 @@ impl IdentDeclarationNode {
         IdentDeclarationNode {
             ident_node,
             type_node,
+            pos: SourcePos::default(),
         }
     }
 }
@@ -195,11 +244,15 @@ impl IdentDeclarationNode {
 #[derive(Clone, Node)]
 pub struct TypeNode {
     pub token: Token,
+    pub pos: SourcePos,
 }
 
 impl TypeNode {
     pub fn new(token: Token) -> Self {
-        TypeNode { token }
+        TypeNode {
+            token,
+            pos: SourcePos::default(),
+        }
     }
 }
 
@@ -209,6 +262,7 @@ pub struct ArrayIdentNode {
     pub identifier: Token,
     pub array_len: usize,
     pub value: Vec<Number>,
+    pub pos: SourcePos,
 }
 
 impl ArrayIdentNode {
@@ -218,6 +272,7 @@ impl ArrayIdentNode {
             identifier,
             array_len,
             value,
+            pos: SourcePos::default(),
         }
     }
 }
@@ -226,11 +281,16 @@ impl ArrayIdentNode {
 pub struct IdentIndexNode {
     pub identifier: Token,
     pub index: Arc<RwLock<dyn Node>>,
+    pub pos: SourcePos,
 }
 
 impl IdentIndexNode {
     pub fn new(identifier: Token, index: Arc<RwLock<dyn Node>>) -> Self {
-        IdentIndexNode { identifier, index }
+        IdentIndexNode {
+            identifier,
+            index,
+            pos: SourcePos::default(),
+        }
     }
 }
 
@@ -238,6 +298,7 @@ impl IdentIndexNode {
 pub struct BlockNode {
     pub declarations: Vec<Arc<RwLock<dyn Node>>>,
     pub compound_statement: Arc<RwLock<dyn Node>>,
+    pub pos: SourcePos,
 }
 
 impl BlockNode {
@@ -248,6 +309,7 @@ impl BlockNode {
         BlockNode {
             declarations,
             compound_statement,
+            pos: SourcePos::default(),
         }
     }
 }
@@ -256,6 +318,7 @@ impl BlockNode {
 pub struct EntryBlockNode {
     pub declarations: Vec<Arc<RwLock<dyn Node>>>,
     pub compound_statement: Arc<RwLock<dyn Node>>,
+    pub pos: SourcePos,
 }
 
 impl EntryBlockNode {
@@ -266,6 +329,7 @@ impl EntryBlockNode {
         EntryBlockNode {
             declarations,
             compound_statement,
+            pos: SourcePos::default(),
         }
     }
 }
@@ -273,11 +337,15 @@ impl EntryBlockNode {
 #[derive(Clone, Node)]
 pub struct CompoundNode {
     pub children: Vec<Arc<RwLock<dyn Node>>>,
+    pub pos: SourcePos,
 }
 
 impl CompoundNode {
     pub fn new(children: Vec<Arc<RwLock<dyn Node>>>) -> Self {
-        CompoundNode { children }
+        CompoundNode {
+            children,
+            pos: SourcePos::default(),
+        }
     }
 }
 
@@ -286,6 +354,7 @@ pub struct CondStatNode {
     pub condition: Arc<RwLock<dyn Node>>,
     pub consequences: Vec<Arc<RwLock<dyn Node>>>,
     pub alternatives: Vec<Arc<RwLock<dyn Node>>>,
+    pub pos: SourcePos,
 }
 
 impl CondStatNode {
@@ -298,6 +367,7 @@ impl CondStatNode {
             condition,
             consequences,
             alternatives,
+            pos: SourcePos::default(),
         }
     }
 }
@@ -306,6 +376,7 @@ impl CondStatNode {
 pub struct LoopStatNode {
     pub condition: Arc<RwLock<dyn Node>>,
     pub consequences: Vec<Arc<RwLock<dyn Node>>>,
+    pub pos: SourcePos,
 }
 
 impl LoopStatNode {
@@ -313,6 +384,50 @@ impl LoopStatNode {
         LoopStatNode {
             condition,
             consequences,
+            pos: SourcePos::default(),
+        }
+    }
+}
+
+/// `for ident in start..end { consequences }`, `end` excluded.
+#[derive(Node)]
+pub struct ForStatNode {
+    pub ident: Token,
+    pub start: Arc<RwLock<dyn Node>>,
+    pub end: Arc<RwLock<dyn Node>>,
+    pub consequences: Vec<Arc<RwLock<dyn Node>>>,
+    pub pos: SourcePos,
+}
+
+impl ForStatNode {
+    pub fn new(
+        ident: Token,
+        start: Arc<RwLock<dyn Node>>,
+        end: Arc<RwLock<dyn Node>>,
+        consequences: Vec<Arc<RwLock<dyn Node>>>,
+    ) -> Self {
+        ForStatNode {
+            ident,
+            start,
+            end,
+            consequences,
+            pos: SourcePos::default(),
+        }
+    }
+}
+
+/// `break` or `continue` of the innermost loop.
+#[derive(Node)]
+pub struct LoopCtlNode {
+    pub token: Token,
+    pub pos: SourcePos,
+}
+
+impl LoopCtlNode {
+    pub fn new(token: Token) -> Self {
+        LoopCtlNode {
+            token,
+            pos: SourcePos::default(),
         }
     }
 }
@@ -321,6 +436,7 @@ impl LoopStatNode {
 pub struct EntryNode {
     pub global_declarations: Vec<Arc<RwLock<dyn Node>>>,
     pub entry_block: Arc<RwLock<dyn Node>>,
+    pub pos: SourcePos,
 }
 
 impl EntryNode {
@@ -331,6 +447,7 @@ impl EntryNode {
         EntryNode {
             global_declarations,
             entry_block,
+            pos: SourcePos::default(),
         }
     }
 }
@@ -341,6 +458,7 @@ pub struct FunctionNode {
     pub params: Vec<Arc<RwLock<dyn Node>>>,
     pub returns: Vec<Arc<RwLock<dyn Node>>>,
     pub block: Arc<RwLock<dyn Node>>,
+    pub pos: SourcePos,
 }
 
 impl FunctionNode {
@@ -355,6 +473,7 @@ impl FunctionNode {
             params,
             returns,
             block,
+            pos: SourcePos::default(),
         }
     }
 }
@@ -364,6 +483,7 @@ pub struct CallNode {
     pub func_name: Token,
     pub actual_params: Vec<Arc<RwLock<dyn Node>>>,
     pub func_symbol: Option<Arc<RwLock<Symbol>>>,
+    pub pos: SourcePos,
 }
 
 impl CallNode {
@@ -372,6 +492,7 @@ impl CallNode {
             func_name,
             actual_params,
             func_symbol: None,
+            pos: SourcePos::default(),
         }
     }
 }
@@ -379,33 +500,45 @@ impl CallNode {
 #[derive(Node)]
 pub struct SqrtNode {
     pub sqrt_value: Arc<RwLock<dyn Node>>,
+    pub pos: SourcePos,
 }
 
 impl SqrtNode {
     pub fn new(sqrt_value: Arc<RwLock<dyn Node>>) -> Self {
-        SqrtNode { sqrt_value }
+        SqrtNode {
+            sqrt_value,
+            pos: SourcePos::default(),
+        }
     }
 }
 
 #[derive(Node)]
 pub struct ReturnNode {
     pub returns: Vec<Arc<RwLock<dyn Node>>>,
+    pub pos: SourcePos,
 }
 
 impl ReturnNode {
     pub fn new(returns: Vec<Arc<RwLock<dyn Node>>>) -> Self {
-        ReturnNode { returns }
+        ReturnNode {
+            returns,
+            pos: SourcePos::default(),
+        }
     }
 }
 
 #[derive(Node)]
 pub struct MallocNode {
     pub num_bytes: Arc<RwLock<dyn Node>>,
+    pub pos: SourcePos,
 }
 
 impl MallocNode {
     pub fn new(num_bytes: Arc<RwLock<dyn Node>>) -> Self {
-        MallocNode { num_bytes }
+        MallocNode {
+            num_bytes,
+            pos: SourcePos::default(),
+        }
     }
 }
 
@@ -413,10 +546,34 @@ impl MallocNode {
 pub struct PrintfNode {
     pub flag: Arc<RwLock<dyn Node>>,
     pub val_addr: Arc<RwLock<dyn Node>>,
+    pub pos: SourcePos,
+}
+
+/// `assert(condition)` or `assert(condition, value)`, logs a diagnostic
+/// when the condition is false and carries on.
+#[derive(Node)]
+pub struct AssertNode {
+    pub condition: Arc<RwLock<dyn Node>>,
+    pub value: Option<Arc<RwLock<dyn Node>>>,
+    pub pos: SourcePos,
+}
+
+impl AssertNode {
+    pub fn new(condition: Arc<RwLock<dyn Node>>, value: Option<Arc<RwLock<dyn Node>>>) -> Self {
+        AssertNode {
+            condition,
+            value,
+            pos: SourcePos::default(),
+        }
+    }
 }
 
 impl PrintfNode {
     pub fn new(val_addr: Arc<RwLock<dyn Node>>, flag: Arc<RwLock<dyn Node>>) -> Self {
-        PrintfNode { val_addr, flag }
+        PrintfNode {
+            val_addr,
+            flag,
+            pos: SourcePos::default(),
+        }
     }
 }
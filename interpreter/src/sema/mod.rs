@@ -1,21 +1,24 @@
+use crate::error::InterpreterError;
 use crate::lexer::token::Token;
 use crate::lexer::token::Token::{Array, ArrayId, Cid, Felt, Id};
 use crate::parser::node::{
-    ArrayIdentNode, ArrayNumNode, AssignNode, BinOpNode, BlockNode, CallNode, CompoundNode,
-    CondStatNode, ContextIdentNode, EntryBlockNode, EntryNode, FeltNumNode, FunctionNode,
-    IdentDeclarationNode, IdentIndexNode, IdentNode, IntegerNumNode, LoopStatNode, MallocNode,
-    MultiAssignNode, Node, PrintfNode, ReturnNode, SqrtNode, TypeNode, UnaryOpNode,
+    ArrayIdentNode, ArrayNumNode, AssertNode, AssignNode, BinOpNode, BlockNode, CallNode, CastNode,
+    CompoundNode, CondStatNode, ContextIdentNode, EntryBlockNode, EntryNode, FeltNumNode,
+    ForStatNode, FunctionNode, IdentDeclarationNode, IdentIndexNode, IdentNode, IntegerNumNode,
+    LoopCtlNode, LoopStatNode, MallocNode, MultiAssignNode, PrintfNode, ReturnNode, SqrtNode,
+    TypeNode, UnaryOpNode,
 };
-use crate::parser::traversal::{is_node_type, safe_downcast_ref, Traversal};
+use crate::parser::traversal::{downcast_map, is_node_type, Traversal};
 use crate::sema::symbol::Symbol::{BuiltInSymbol, FuncSymbol, IdentSymbol};
-use crate::sema::symbol::{BuiltIn, SymbolTable};
+use crate::sema::symbol::{BuiltIn, Symbol, SymbolTable};
+use crate::utils::builtins::builtin_arity;
 use crate::utils::number::Number::Nil;
 use crate::utils::number::NumberRet::{Multiple, Single};
-use crate::utils::number::{number_from_token, Number, NumberResult};
+use crate::utils::number::{number_from_token, Number, NumberResult, NumberRet};
 use core::program::binary_program::OlaProphet;
 use log::debug;
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 pub mod symbol;
 
@@ -26,11 +29,7 @@ macro_rules! inf_var_insert {
             let variable = IdentSymbol($input.name.to_string(), BuiltIn(Felt), None);
             $current_scope.insert(variable);
         } else {
-            let variable = IdentSymbol(
-                $input.name.to_string(),
-                BuiltIn(Array(Box::new(Felt), $input.length)),
-                None,
-            );
+            let variable = IdentSymbol($input.name.to_string(), BuiltIn(Felt), Some($input.length));
             $current_scope.insert(variable);
         }
     };
@@ -39,19 +38,14 @@ macro_rules! inf_var_insert {
 #[derive(Clone)]
 pub struct SymTableGen {
     current_scope: Arc<RwLock<SymbolTable>>,
+    depth: usize,
+    // loops enclosing the current statement, `break` and `continue` need one
+    loops: usize,
 }
 
 impl SymTableGen {
     pub fn new(prophet: &OlaProphet) -> Self {
-        let gen = SymTableGen {
-            current_scope: Arc::new(RwLock::new(SymbolTable::new(
-                "Global Scope".to_string(),
-                1,
-                None,
-            ))),
-        };
-
-        let mut current_scope = gen.current_scope.write().unwrap();
+        let mut current_scope = SymbolTable::new("Global Scope".to_string(), 1, None);
         for input in prophet.inputs.iter() {
             inf_var_insert!(input, current_scope);
         }
@@ -64,12 +58,46 @@ impl SymTableGen {
         for output in prophet.outputs.iter() {
             inf_var_insert!(output, current_scope);
         }
-        drop(current_scope);
-        gen
+        SymTableGen {
+            current_scope: Arc::new(RwLock::new(current_scope)),
+            depth: 0,
+            loops: 0,
+        }
+    }
+
+    fn scope(&self) -> Result<RwLockReadGuard<SymbolTable>, InterpreterError> {
+        self.current_scope
+            .read()
+            .map_err(|_| InterpreterError::from("symbol table lock is poisoned"))
+    }
+
+    fn scope_mut(&self) -> Result<RwLockWriteGuard<SymbolTable>, InterpreterError> {
+        self.current_scope
+            .write()
+            .map_err(|_| InterpreterError::from("symbol table lock is poisoned"))
+    }
+
+    fn lookup(&self, name: &str) -> Result<Option<Symbol>, InterpreterError> {
+        Ok(self.scope()?.lookup(name))
+    }
+}
+
+// Type of the first element stands for a whole array.
+fn element_type(ret: NumberRet) -> Result<Number, InterpreterError> {
+    match ret {
+        Single(num) => Ok(num),
+        Multiple(nums) => nums
+            .first()
+            .cloned()
+            .ok_or(InterpreterError::from("empty value has no type")),
     }
 }
 
 impl Traversal for SymTableGen {
+    fn travel_depth(&mut self) -> &mut usize {
+        &mut self.depth
+    }
+
     fn travel_entry(&mut self, node: &mut EntryNode) -> NumberResult {
         for declaration in node.global_declarations.iter() {
             self.travel(declaration)?;
@@ -85,7 +113,7 @@ impl Traversal for SymTableGen {
 
     fn travel_entry_block(&mut self, node: &mut EntryBlockNode) -> NumberResult {
         let cur = self.current_scope.clone();
-        let scope_level = cur.read().unwrap().scope_level;
+        let scope_level = self.scope()?.scope_level;
         let cur_scope = SymbolTable::new(Token::Entry.to_string(), scope_level + 1, Some(cur));
 
         self.current_scope = Arc::new(RwLock::new(cur_scope));
@@ -97,29 +125,27 @@ impl Traversal for SymTableGen {
 
     fn travel_declaration(&mut self, node: &mut IdentDeclarationNode) -> NumberResult {
         let IdentDeclarationNode {
-            ident_node: IdentNode { identifier },
-            type_node: TypeNode { token },
+            ident_node: IdentNode { identifier, .. },
+            type_node: TypeNode { token, .. },
+            ..
         } = node;
 
         if let Id(name) = identifier {
-            if self.current_scope.read().unwrap().lookup(name).is_some() {
-                return Err(format!(
-                    "Found duplicate variable declaration for '{}'!",
-                    name
-                ));
+            if self.lookup(name)?.is_some() {
+                return Err(format!("Found duplicate variable declaration for '{}'!", name).into());
             }
             debug!("insert id name:{}", name);
-            let mut current_scope = self.current_scope.write().unwrap();
+            let mut current_scope = self.scope_mut()?;
             if let Array(builtin_token, len) = token {
-                if let BuiltInSymbol(builtin) = current_scope.get(&builtin_token) {
+                if let Some(BuiltInSymbol(builtin)) = current_scope.get(builtin_token) {
                     let variable = IdentSymbol(name.to_string(), builtin, Some(*len));
                     current_scope.insert(variable);
                 }
-            } else if let BuiltInSymbol(builtin) = current_scope.get(&token) {
+            } else if let Some(BuiltInSymbol(builtin)) = current_scope.get(token) {
                 let variable = IdentSymbol(name.to_string(), builtin, None);
                 current_scope.insert(variable);
             } else {
-                panic!("Invalid builtin type {}", token);
+                return Err(format!("Invalid builtin type {}", token).into());
             }
         }
         Ok(Single(Nil))
@@ -137,21 +163,26 @@ impl Traversal for SymTableGen {
     }
 
     fn travel_felt(&mut self, _node: &mut FeltNumNode) -> NumberResult {
-        Ok(Single(Number::Felt(0)))
+        Ok(Single(Number::from(0u64)))
     }
 
     fn travel_array(&mut self, node: &mut ArrayNumNode) -> NumberResult {
-        Ok(Single(Number::from(&node.values[0].number_type())))
+        let first = node
+            .values
+            .first()
+            .ok_or(InterpreterError::from("empty array const"))?;
+        Ok(Single(Number::from(&first.number_type()?)))
     }
 
     fn travel_ident_index(&mut self, node: &mut IdentIndexNode) -> NumberResult {
         if let IdentIndexNode {
             identifier: Id(name),
             index,
+            ..
         } = node
         {
-            if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                Err(format!("identifier Undeclared variable {} found.", name))
+            if self.lookup(name)?.is_none() {
+                Err(format!("identifier Undeclared variable {} found.", name).into())
             } else {
                 let value = self.travel(index)?;
                 Ok(value)
@@ -160,27 +191,41 @@ impl Traversal for SymTableGen {
             Err(format!(
                 "Invalid identifier found travel_context_ident{}",
                 node.identifier
-            ))
+            )
+            .into())
         }
     }
 
     fn travel_binop(&mut self, node: &mut BinOpNode) -> NumberResult {
-        let left = self.travel(&node.left)?;
-        let right = self.travel(&node.right)?;
-        let left_type = match left {
-            Single(num) => num,
-            Multiple(nums) => nums[0].clone(),
-        };
-
-        let right_type = match right {
-            Single(num) => num,
-            Multiple(nums) => nums[0].clone(),
+        let left_type = element_type(self.travel(&node.left)?)?;
+        let right_type = element_type(self.travel(&node.right)?)?;
+        // the shift amount does not take the type of the shifted value
+        let binop_type = match node.operator {
+            Token::ShiftLeft | Token::ShiftRight => left_type.number_type()?,
+            _ => left_type.binop_number_type(&right_type)?,
         };
-        let binop_type = left_type.binop_number_type(&right_type);
+        if node.operator.is_bitwise() && !matches!(binop_type, Token::U32 | Token::U64) {
+            return Err(format!("operator {} needs u32 or u64 operands", node.operator).into());
+        }
         Ok(Single(Number::from(&binop_type)))
     }
     fn travel_unary_op(&mut self, node: &mut UnaryOpNode) -> NumberResult {
-        self.travel(&node.expr)
+        let ret = self.travel(&node.expr)?;
+        let unsigned = matches!(&ret, Single(num) if num.is_unsigned());
+        match node.operator {
+            Token::BitNot if !unsigned => {
+                Err("operator ~ needs a u32 or u64 operand".to_string().into())
+            }
+            Token::Minus if unsigned => Err("cannot negate an unsigned value".to_string().into()),
+            _ => Ok(ret),
+        }
+    }
+
+    fn travel_cast(&mut self, node: &mut CastNode) -> NumberResult {
+        match self.travel(&node.expr)? {
+            Single(_) => Ok(Single(Number::from(&node.target))),
+            Multiple(_) => Err(format!("cannot cast an array to {}", node.target).into()),
+        }
     }
 
     fn travel_compound(&mut self, node: &mut CompoundNode) -> NumberResult {
@@ -193,19 +238,18 @@ impl Traversal for SymTableGen {
     fn travel_assign(&mut self, node: &mut AssignNode) -> NumberResult {
         debug!("sema assign id:{}", node.identifier);
         if let Id(name) = &mut node.identifier {
-            if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                return Err(format!("assign Undeclared variable {} found.", name));
-            } else {
-                let symbol = self.current_scope.read().unwrap().lookup(&name).unwrap();
-                if let IdentSymbol(_ident, BuiltIn(_token), size) = symbol {
-                    if size.is_some() {
-                        node.identifier = ArrayId(name.to_string());
-                    }
+            match self.lookup(name)? {
+                None => {
+                    return Err(format!("assign Undeclared variable {} found.", name).into());
+                }
+                Some(IdentSymbol(_ident, BuiltIn(_token), Some(_))) => {
+                    node.identifier = ArrayId(name.to_string());
                 }
+                Some(_) => {}
             }
         } else if let Cid(name) = &node.identifier {
-            if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                return Err(format!("assign Undeclared variable {} found.", name));
+            if self.lookup(name)?.is_none() {
+                return Err(format!("assign Undeclared variable {} found.", name).into());
             }
         }
         self.travel(&node.expr)
@@ -214,39 +258,31 @@ impl Traversal for SymTableGen {
     fn travel_ident(&mut self, node: &mut IdentNode) -> NumberResult {
         if let IdentNode {
             identifier: Id(name),
+            ..
         } = node
         {
-            let ident = self.current_scope.read().unwrap().lookup(&name);
-            if ident.is_none() {
-                Err(format!("identifier Undeclared variable {} found.", name))
-            } else {
-                if let Some(IdentSymbol(_ident, BuiltIn(token), size)) = ident {
-                    if size.is_some() {
-                        node.identifier = ArrayId(name.to_string());
-                    }
-                    if size.is_some() {
-                        return Ok(Single(number_from_token(&token, size.unwrap())));
-                    }
-                    Ok(Single(Number::from(&token)))
-                } else {
-                    panic!("ident not support symbol type")
+            match self.lookup(name)? {
+                None => Err(format!("identifier Undeclared variable {} found.", name).into()),
+                Some(IdentSymbol(_ident, BuiltIn(token), Some(size))) => {
+                    node.identifier = ArrayId(name.to_string());
+                    Ok(Single(number_from_token(&token, size)))
                 }
+                Some(IdentSymbol(_ident, BuiltIn(token), None)) => Ok(Single(Number::from(&token))),
+                Some(symbol) => Err(format!("{} is not a variable", symbol.name()).into()),
             }
         } else {
-            Err(format!(
-                "Invalid identifier found travel_ident{}",
-                node.identifier
-            ))
+            Err(format!("Invalid identifier found travel_ident{}", node.identifier).into())
         }
     }
 
     fn travel_context_ident(&mut self, node: &mut ContextIdentNode) -> NumberResult {
         if let ContextIdentNode {
             identifier: Cid(name),
+            ..
         } = node
         {
-            if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                Err(format!("identifier Undeclared variable {} found.", name))
+            if self.lookup(name)?.is_none() {
+                Err(format!("identifier Undeclared variable {} found.", name).into())
             } else {
                 Ok(Single(Nil))
             }
@@ -254,7 +290,8 @@ impl Traversal for SymTableGen {
             Err(format!(
                 "Invalid identifier found travel_context_ident{}",
                 node.identifier
-            ))
+            )
+            .into())
         }
     }
 
@@ -274,23 +311,54 @@ impl Traversal for SymTableGen {
 
     fn travel_loop(&mut self, node: &mut LoopStatNode) -> NumberResult {
         self.travel(&node.condition)?;
+        self.loops += 1;
         for expr in node.consequences.iter() {
             self.travel(expr)?;
         }
+        self.loops -= 1;
 
         Ok(Single(Nil))
     }
 
+    fn travel_for(&mut self, node: &mut ForStatNode) -> NumberResult {
+        let name = node.ident.to_string();
+        match self.lookup(&name)? {
+            Some(IdentSymbol(_ident, _builtin, None)) => {}
+            Some(_) => return Err(format!("for loop variable {} is not a scalar", name).into()),
+            None => return Err(format!("for loop Undeclared variable {} found.", name).into()),
+        }
+        self.travel(&node.start)?;
+        self.travel(&node.end)?;
+        self.loops += 1;
+        for expr in node.consequences.iter() {
+            self.travel(expr)?;
+        }
+        self.loops -= 1;
+
+        Ok(Single(Nil))
+    }
+
+    fn travel_loop_ctl(&mut self, node: &mut LoopCtlNode) -> NumberResult {
+        if self.loops == 0 {
+            return Err(format!("{} outside of a loop", node.token).into());
+        }
+        Ok(Single(Nil))
+    }
+
     fn travel_function(&mut self, node: &mut FunctionNode) -> NumberResult {
         if let Id(func_name) = &node.func_name {
             let mut param_symbols = Vec::new();
             let mut param_scope = HashMap::new();
             for param_node in &node.params {
-                let mut param = param_node.write().unwrap();
+                let mut param = param_node
+                    .write()
+                    .map_err(|_| InterpreterError::from("syntax tree lock is poisoned"))?;
                 let param = param
                     .as_any_mut()
                     .downcast_mut::<IdentDeclarationNode>()
-                    .unwrap();
+                    .ok_or(InterpreterError::from(
+                        "function parameter is not a declaration",
+                    ))?;
                 let name = param.ident_node.identifier.to_string();
 
                 let ident_type = BuiltIn(param.type_node.token.clone());
@@ -310,53 +378,70 @@ impl Traversal for SymTableGen {
                 param_scope.insert(name.clone(), symbol);
             }
             let func_symbol = FuncSymbol(func_name.to_string(), param_symbols, node.block.clone());
-            self.current_scope
-                .write()
-                .unwrap()
+            self.scope_mut()?
                 .symbols
                 .insert(func_name.to_string(), func_symbol);
             let cur = self.current_scope.clone();
-            let scope_level = cur.read().unwrap().scope_level;
+            let scope_level = self.scope()?.scope_level;
             let mut cur_scope = SymbolTable::new(func_name.to_string(), scope_level + 1, Some(cur));
             cur_scope.symbols = param_scope;
             self.current_scope = Arc::new(RwLock::new(cur_scope));
             self.travel(&node.block)?;
-            let enclosing_scope = self.current_scope.read().unwrap().enclosing_scope.clone();
-            self.current_scope = enclosing_scope.unwrap();
+            let enclosing_scope = self.scope()?.enclosing_scope.clone();
+            self.current_scope =
+                enclosing_scope.ok_or(InterpreterError::from("function scope has no parent"))?;
         }
         Ok(Single(Nil))
     }
 
     fn travel_call(&mut self, node: &mut CallNode) -> NumberResult {
-        let symbol = self
-            .current_scope
-            .read()
-            .unwrap()
-            .lookup(&node.func_name.to_string());
+        let symbol = self.lookup(&node.func_name.to_string())?;
 
         let mut actual_types = Vec::new();
         for param in node.actual_params.iter() {
             let res = self.travel(param)?;
             let param_type = match res {
                 Single(num) => num,
-                Multiple(nums) => number_from_token(&nums[0].number_type(), nums.len()),
+                Multiple(nums) => {
+                    let len = nums.len();
+                    number_from_token(&element_type(Multiple(nums))?.number_type()?, len)
+                }
             };
 
             actual_types.push(param_type);
         }
         if let Some(func_symbol) = symbol {
             if let FuncSymbol(name, params, body) = func_symbol {
-                for (index, item) in params.iter().enumerate() {
-                    if !Number::from(&item.1 .0).eq(&actual_types.get(index).unwrap()) {
-                        panic!("function params type not match")
+                if params.len() != actual_types.len() {
+                    return Err(format!(
+                        "function {} expects {} arguments, got {}",
+                        name,
+                        params.len(),
+                        actual_types.len()
+                    )
+                    .into());
+                }
+                for (item, actual_type) in params.iter().zip(actual_types.iter()) {
+                    if !Number::from(&item.1 .0).eq(actual_type) {
+                        return Err(format!("function {} params type not match", name).into());
                     }
                 }
                 node.func_symbol = Some(Arc::new(RwLock::new(FuncSymbol(name, params, body))));
             } else {
-                panic!("not support symbol for function")
+                return Err(format!("{} is not a function", node.func_name).into());
+            }
+        } else if let Some(arity) = builtin_arity(&node.func_name.to_string()) {
+            if arity != actual_types.len() {
+                return Err(format!(
+                    "builtin function {} expects {} arguments, got {}",
+                    node.func_name,
+                    arity,
+                    actual_types.len()
+                )
+                .into());
             }
         } else {
-            panic!("not found function");
+            return Err(format!("not found function {}", node.func_name).into());
         }
         Ok(Single(Nil))
     }
@@ -368,21 +453,26 @@ impl Traversal for SymTableGen {
     fn travel_return(&mut self, node: &mut ReturnNode) -> NumberResult {
         for ret in &node.returns {
             if is_node_type::<IdentNode>(ret) {
-                let mut ident = ret.write().unwrap();
-                let ident = ident.as_any_mut().downcast_mut::<IdentNode>().unwrap();
+                let mut ident = ret
+                    .write()
+                    .map_err(|_| InterpreterError::from("syntax tree lock is poisoned"))?;
+                let ident = match ident.as_any_mut().downcast_mut::<IdentNode>() {
+                    Some(ident) => ident,
+                    None => continue,
+                };
 
                 let name = ident.identifier.clone().to_string();
-                if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                    return Err(format!("assign Undeclared variable {} found.", name));
-                } else {
-                    if let IdentSymbol(name, BuiltIn(_token), size) =
-                        self.current_scope.read().unwrap().lookup(&name).unwrap()
-                    {
-                        if size.is_some() {
-                            ident.identifier = ArrayId(name.to_string());
-                        }
+                match self.lookup(&name)? {
+                    None => {
+                        return Err(format!("assign Undeclared variable {} found.", name).into());
+                    }
+                    Some(IdentSymbol(name, BuiltIn(_token), Some(_))) => {
+                        ident.identifier = ArrayId(name.to_string());
                     }
+                    Some(_) => {}
                 }
+            } else {
+                self.travel(ret)?;
             }
         }
         Ok(Single(Nil))
@@ -390,19 +480,13 @@ impl Traversal for SymTableGen {
 
     fn travel_multi_assign(&mut self, node: &mut MultiAssignNode) -> NumberResult {
         for node in node.identifier.iter() {
-            if is_node_type::<IdentNode>(node) {
-                let ident = &safe_downcast_ref::<IdentNode>(node).identifier.clone();
-                let name = ident.to_string();
-                if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                    return Err(format!("assign Undeclared variable {} found.", name));
-                }
-            } else if is_node_type::<ContextIdentNode>(node) {
-                let ident = &safe_downcast_ref::<ContextIdentNode>(node)
-                    .identifier
-                    .clone();
-                let name = ident.to_string();
-                if self.current_scope.read().unwrap().lookup(&name).is_none() {
-                    return Err(format!("assign Undeclared variable {} found.", name));
+            let name = downcast_map::<IdentNode, _>(node, |node| node.identifier.to_string())
+                .or_else(|| {
+                    downcast_map::<ContextIdentNode, _>(node, |node| node.identifier.to_string())
+                });
+            if let Some(name) = name {
+                if self.lookup(&name)?.is_none() {
+                    return Err(format!("assign Undeclared variable {} found.", name).into());
                 }
             } else {
                 self.travel(node)?;
@@ -421,4 +505,12 @@ impl Traversal for SymTableGen {
         let ret = self.travel(&node.val_addr);
         ret
     }
+
+    fn travel_assert(&mut self, node: &mut AssertNode) -> NumberResult {
+        self.travel(&node.condition)?;
+        if let Some(value) = &node.value {
+            self.travel(value)?;
+        }
+        Ok(Single(Nil))
+    }
 }
@@ -8,15 +8,6 @@ use std::sync::{Arc, RwLock};
 #[derive(Clone, PartialEq)]
 pub struct BuiltIn(pub Token);
 
-impl BuiltIn {
-    pub fn new(name: Token) -> Self {
-        match name {
-            Token::I32 | Token::Felt => BuiltIn(name),
-            _ => panic!("Invalid symbol value found {}", name),
-        }
-    }
-}
-
 #[derive(Clone)]
 pub enum Symbol {
     BuiltInSymbol(BuiltIn),
@@ -24,6 +15,15 @@ pub enum Symbol {
     FuncSymbol(String, Vec<(String, BuiltIn)>, Arc<RwLock<dyn Node>>),
 }
 
+impl Symbol {
+    pub fn name(&self) -> String {
+        match self {
+            BuiltInSymbol(builtin) => builtin.to_string(),
+            IdentSymbol(name, _, _) | FuncSymbol(name, _, _) => name.clone(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct SymbolTable {
     pub scope_name: String,
@@ -54,39 +54,25 @@ impl SymbolTable {
             .insert(builtin.0.to_string(), BuiltInSymbol(builtin));
     }
     // Returns the builtin type for the given token reference.
-    pub fn get(&self, name: &Token) -> Symbol {
-        let symbol = self.lookup(&name.to_string());
-        if symbol.is_some() {
-            symbol.unwrap()
-        } else {
-            panic!("token {} not found", name)
-        }
+    pub fn get(&self, name: &Token) -> Option<Symbol> {
+        self.lookup(&name.to_string())
     }
     pub fn insert(&mut self, symbol: Symbol) {
-        if let IdentSymbol(key, _kind, _) = symbol.clone() {
-            self.symbols.insert(key, symbol);
-        } else {
-            panic!("{}", format!("Error, Invalid Symbol! {}", symbol));
-        }
+        self.symbols.insert(symbol.name(), symbol);
     }
     pub fn lookup(&self, key: &str) -> Option<Symbol> {
         match self.symbols.get(key) {
             None => {
-                if self.enclosing_scope.is_some() {
-                    let scope = self.enclosing_scope.as_ref()?.read().unwrap();
-                    scope.lookup(key)
-                } else {
-                    None
-                }
+                let scope = self.enclosing_scope.as_ref()?.read().ok()?;
+                scope.lookup(key)
             }
             Some(symbol) => Some(symbol.clone()),
         }
     }
     fn initialise_builtins(&mut self) {
-        let u32_type = BuiltIn::new(Token::I32);
-        let felt_type = BuiltIn::new(Token::Felt);
-        self.set(u32_type);
-        self.set(felt_type);
+        for token in [Token::I32, Token::Felt, Token::U32, Token::U64] {
+            self.set(BuiltIn(token));
+        }
     }
 }
 
@@ -123,7 +109,7 @@ impl fmt::Display for SymbolTable {
         println!("Scope: {}, Level: {}", &self.scope_name, &self.scope_level);
 
         for (key, val) in &self.symbols {
-            writeln!(f, "{{ {} => {} }}", key, val).unwrap();
+            writeln!(f, "{{ {} => {} }}", key, val)?;
         }
         Ok(())
     }
@@ -0,0 +1,401 @@
+use crate::utils::number::NumberRet::{Multiple, Single};
+use crate::utils::number::{Number, NumberResult, NumberRet};
+use core::util::converts::{u256_to_u32s_be, u32s_be_to_u256};
+use ethereum_types::U256;
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::{PrimeField, PrimeField64};
+
+// Functions callable from prophet code without a definition. u256 values are
+// 8 u32 limbs, most significant first, the layout compiled Ola-lang uses.
+// `*_divmod` returns the quotient followed by the remainder. `felt_div` is
+// the field division `a * b^-1`, unlike `/` on felts, and `felt_exp` raises a
+// felt to the canonical value of its second argument.
+pub const BUILTIN_FUNCTIONS: [(&str, usize); 15] = [
+    ("isqrt", 1),
+    ("field_sqrt", 1),
+    ("felt_inv", 1),
+    ("felt_div", 2),
+    ("felt_exp", 2),
+    ("modinv", 2),
+    ("u32_div", 2),
+    ("u32_mod", 2),
+    ("u32_divmod", 2),
+    ("u64_div", 2),
+    ("u64_mod", 2),
+    ("u64_divmod", 2),
+    ("u256_div", 2),
+    ("u256_mod", 2),
+    ("u256_divmod", 2),
+];
+
+pub const U256_LIMBS: usize = 8;
+
+/// Number of arguments of a builtin, `None` if `name` is not a builtin.
+pub fn builtin_arity(name: &str) -> Option<usize> {
+    BUILTIN_FUNCTIONS
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, arity)| *arity)
+}
+
+pub fn call_builtin(name: &str, args: Vec<NumberRet>) -> NumberResult {
+    match builtin_arity(name) {
+        Some(arity) if arity == args.len() => {}
+        Some(arity) => {
+            return Err(format!("{} expects {} arguments, got {}", name, arity, args.len()).into())
+        }
+        None => return Err(format!("unknown builtin function {}", name).into()),
+    }
+    match name {
+        "isqrt" => Ok(Single(Number::from(isqrt(
+            single_arg(name, &args[0])?.to_u64()?,
+        )))),
+        "field_sqrt" => Ok(Single(Number::from(field_sqrt(
+            single_arg(name, &args[0])?.to_felt()?,
+        )?))),
+        "felt_inv" => Ok(Single(single_arg(name, &args[0])?.inverse()?)),
+        "felt_div" => Ok(Single(
+            single_arg(name, &args[0])?.field_div(&single_arg(name, &args[1])?)?,
+        )),
+        "felt_exp" => Ok(Single(
+            single_arg(name, &args[0])?.exp(single_arg(name, &args[1])?.to_u64()?)?,
+        )),
+        "modinv" => Ok(Single(Number::from(modinv(
+            single_arg(name, &args[0])?.to_u64()?,
+            single_arg(name, &args[1])?.to_u64()?,
+        )?))),
+        "u32_div" | "u32_mod" | "u32_divmod" => {
+            let a = single_arg(name, &args[0])?.to_u32()? as u64;
+            let b = single_arg(name, &args[1])?.to_u32()? as u64;
+            divmod_ret(name, a, b)
+        }
+        "u64_div" | "u64_mod" | "u64_divmod" => {
+            let a = single_arg(name, &args[0])?.to_u64()?;
+            let b = single_arg(name, &args[1])?.to_u64()?;
+            divmod_ret(name, a, b)
+        }
+        "u256_div" | "u256_mod" | "u256_divmod" => {
+            let a = u256_arg(name, &args[0])?;
+            let b = u256_arg(name, &args[1])?;
+            if b.is_zero() {
+                return Err(format!("{}: division by zero", name).into());
+            }
+            let (q, r) = a.div_mod(b);
+            let limbs = match name {
+                "u256_div" => u256_limbs(q),
+                "u256_mod" => u256_limbs(r),
+                _ => [u256_limbs(q), u256_limbs(r)].concat(),
+            };
+            Ok(Multiple(limbs))
+        }
+        _ => Err(format!("unknown builtin function {}", name).into()),
+    }
+}
+
+/// Floor of the square root, exact for the whole u64 range.
+pub fn isqrt(value: u64) -> u64 {
+    if value < 2 {
+        return value;
+    }
+    // f64 is only a starting guess, it is off by a few above 2^52
+    let mut root = (value as f64).sqrt() as u64;
+    while (root as u128) * (root as u128) > value as u128 {
+        root -= 1;
+    }
+    while ((root + 1) as u128) * ((root + 1) as u128) <= value as u128 {
+        root += 1;
+    }
+    root
+}
+
+/// Square root in the Goldilocks field. Of the two roots the one with the
+/// smaller canonical value is returned so the result is deterministic.
+pub fn field_sqrt(value: GoldilocksField) -> Result<GoldilocksField, String> {
+    let root = value.sqrt().ok_or(format!(
+        "{} is not a quadratic residue",
+        value.to_canonical_u64()
+    ))?;
+    let neg = -root;
+    if neg.to_canonical_u64() < root.to_canonical_u64() {
+        Ok(neg)
+    } else {
+        Ok(root)
+    }
+}
+
+/// Inverse of `value` modulo `modulus` by the extended Euclidean algorithm.
+pub fn modinv(value: u64, modulus: u64) -> Result<u64, String> {
+    if modulus < 2 {
+        return Err(format!("modinv: invalid modulus {}", modulus));
+    }
+    let (mut old_r, mut r) = ((value % modulus) as i128, modulus as i128);
+    let (mut old_s, mut s) = (1i128, 0i128);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+    }
+    if old_r != 1 {
+        return Err(format!(
+            "modinv: {} has no inverse modulo {}",
+            value, modulus
+        ));
+    }
+    Ok(old_s.rem_euclid(modulus as i128) as u64)
+}
+
+fn divmod_ret(name: &str, a: u64, b: u64) -> NumberResult {
+    if b == 0 {
+        return Err(format!("{}: division by zero", name).into());
+    }
+    if name.ends_with("_divmod") {
+        Ok(Multiple(vec![Number::from(a / b), Number::from(a % b)]))
+    } else if name.ends_with("_div") {
+        Ok(Single(Number::from(a / b)))
+    } else {
+        Ok(Single(Number::from(a % b)))
+    }
+}
+
+fn single_arg(name: &str, arg: &NumberRet) -> Result<Number, String> {
+    match arg {
+        Single(value) => Ok(value.clone()),
+        Multiple(values) if values.len() == 1 => Ok(values[0].clone()),
+        Multiple(values) => Err(format!(
+            "{} expects a single value, got {} values",
+            name,
+            values.len()
+        )),
+    }
+}
+
+fn u256_arg(name: &str, arg: &NumberRet) -> Result<U256, String> {
+    let values = match arg {
+        Multiple(values) if values.len() == U256_LIMBS => values,
+        _ => return Err(format!("{} expects {} u32 limbs", name, U256_LIMBS)),
+    };
+    let mut limbs = [0u64; U256_LIMBS];
+    for (limb, value) in limbs.iter_mut().zip(values) {
+        *limb = value.to_u32()? as u64;
+    }
+    u32s_be_to_u256(limbs).map_err(|err| format!("{}: {}", name, err))
+}
+
+fn u256_limbs(value: U256) -> Vec<Number> {
+    u256_to_u32s_be(value)
+        .iter()
+        .map(|limb| Number::from(*limb))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::Interpreter;
+    use crate::utils::builtins::{call_builtin, field_sqrt, isqrt, modinv};
+    use crate::utils::number::Number;
+    use crate::utils::number::NumberRet::{Multiple, Single};
+    use core::program::binary_program::{OlaProphet, OlaProphetInput, OlaProphetOutput};
+    use core::vm::hardware::OlaMemory;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::{Field, Field64, PrimeField64};
+
+    fn limbs(values: [u64; 8]) -> Vec<Number> {
+        values.iter().map(|v| Number::from(*v)).collect()
+    }
+
+    fn to_u64s(values: Vec<Number>) -> Vec<u64> {
+        values.iter().map(|v| v.to_u64().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_isqrt_exact() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(15), 3);
+        assert_eq!(isqrt(16), 4);
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64);
+        // f64 rounds these to the next root
+        let r = (1u64 << 32) - 1;
+        assert_eq!(isqrt(r * r + 2 * r), r);
+        let p = GoldilocksField::ORDER - 1;
+        let root = isqrt(p);
+        assert!(root as u128 * root as u128 <= p as u128);
+        assert!((root + 1) as u128 * (root + 1) as u128 > p as u128);
+    }
+
+    #[test]
+    fn test_field_sqrt_and_modinv() {
+        let x = GoldilocksField::from_canonical_u64(0x1234_5678_9abc);
+        let square = x * x;
+        let root = field_sqrt(square).unwrap();
+        assert_eq!(root * root, square);
+        assert!(root.to_canonical_u64() <= (-root).to_canonical_u64());
+        assert!(field_sqrt(GoldilocksField::MULTIPLICATIVE_GROUP_GENERATOR).is_err());
+
+        assert_eq!(modinv(3, 11), Ok(4));
+        assert_eq!(modinv(10, 17).unwrap() * 10 % 17, 1);
+        assert!(modinv(6, 9).is_err());
+        let inv = modinv(7, GoldilocksField::ORDER).unwrap();
+        assert_eq!(
+            GoldilocksField::from_canonical_u64(inv) * GoldilocksField::from_canonical_u64(7),
+            GoldilocksField::ONE
+        );
+    }
+
+    #[test]
+    fn test_felt_div_and_exp() {
+        let felt = |value: u64| Single(Number::from(value));
+        let ret = call_builtin("felt_div", vec![felt(5), felt(7)])
+            .unwrap()
+            .get_single()
+            .unwrap();
+        assert_eq!(
+            ret.to_felt().unwrap() * GoldilocksField::from_canonical_u64(7),
+            GoldilocksField::from_canonical_u64(5)
+        );
+        assert!(call_builtin("felt_div", vec![felt(5), felt(0)]).is_err());
+
+        let ret = call_builtin("felt_exp", vec![felt(3), felt(GoldilocksField::ORDER - 1)])
+            .unwrap()
+            .get_single()
+            .unwrap();
+        assert_eq!(ret.to_u64(), Ok(1));
+        let ret = call_builtin("felt_exp", vec![felt(2), felt(64)])
+            .unwrap()
+            .get_single()
+            .unwrap();
+        assert_eq!(ret.to_u64(), Ok(0xffff_ffff));
+        assert!(call_builtin("felt_exp", vec![felt(2)]).is_err());
+    }
+
+    #[test]
+    fn test_integer_divmod() {
+        let ret = call_builtin(
+            "u64_divmod",
+            vec![
+                Single(Number::from(0xffff_ffff_0000_0000u64)),
+                Single(Number::from(7u64)),
+            ],
+        )
+        .unwrap();
+        let expected = 0xffff_ffff_0000_0000u64;
+        assert_eq!(
+            to_u64s(ret.get_multiple().unwrap()),
+            vec![expected / 7, expected % 7]
+        );
+        assert!(call_builtin(
+            "u32_div",
+            vec![Single(Number::from(1u64 << 32)), Single(Number::from(3u64))]
+        )
+        .is_err());
+        assert!(call_builtin(
+            "u32_mod",
+            vec![Single(Number::from(3u64)), Single(Number::from(0u64))]
+        )
+        .is_err());
+
+        // (2^128 + 5) / (2^64 + 1)
+        let a = limbs([0, 0, 0, 1, 0, 0, 0, 5]);
+        let b = limbs([0, 0, 0, 0, 0, 1, 0, 1]);
+        let ret = call_builtin("u256_divmod", vec![Multiple(a), Multiple(b)]).unwrap();
+        assert_eq!(
+            to_u64s(ret.get_multiple().unwrap()),
+            vec![
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0xffff_ffff,
+                0xffff_ffff,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                6
+            ]
+        );
+        let max = limbs([0xffff_ffff; 8]);
+        let ret = call_builtin(
+            "u256_mod",
+            vec![
+                Multiple(max.clone()),
+                Multiple(limbs([0, 0, 0, 0, 0, 0, 0, 10])),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            to_u64s(ret.get_multiple().unwrap()),
+            vec![0, 0, 0, 0, 0, 0, 0, 5]
+        );
+        assert!(call_builtin("u256_div", vec![Multiple(max), Multiple(limbs([0; 8]))]).is_err());
+    }
+
+    #[test]
+    fn test_builtins_from_prophet() {
+        let code = r#"
+    entry() {
+        cid.q = u256_div(cid.a, cid.b);
+        cid.r = isqrt(cid.x);
+        cid.qr = u64_divmod(cid.x, cid.y);
+        cid.d = felt_div(cid.x, cid.y);
+        cid.e = felt_exp(cid.y, 3);
+    }
+"#;
+        let prophet = OlaProphet {
+            host: 0,
+            code: format!("%{{{}%}}", code),
+            ctx: vec![],
+            inputs: ["cid.a", "cid.b", "cid.x", "cid.y"]
+                .iter()
+                .zip([8, 8, 1, 1])
+                .map(|(name, length)| OlaProphetInput {
+                    name: name.to_string(),
+                    length,
+                    is_ref: false,
+                    is_input_output: false,
+                })
+                .collect(),
+            outputs: ["cid.q", "cid.r", "cid.qr", "cid.d", "cid.e"]
+                .iter()
+                .zip([8, 1, 2, 1, 1])
+                .map(|(name, length)| OlaProphetOutput {
+                    name: name.to_string(),
+                    length,
+                    is_ref: false,
+                    is_input_output: false,
+                })
+                .collect(),
+        };
+        let x = (1u64 << 53) + 1;
+        let mut values = vec![0, 0, 0, 0, 0, 0, 1, 0];
+        values.extend([0, 0, 0, 0, 0, 0, 0, 3]);
+        values.extend([x, 10]);
+        let mem = OlaMemory::default();
+        let mut interpreter = Interpreter::new(code).unwrap();
+        let ret = interpreter.run(&prophet, values, &mem).unwrap();
+        let quotient =
+            GoldilocksField::from_canonical_u64(x) / GoldilocksField::from_canonical_u64(10);
+        assert_eq!(
+            to_u64s(ret.get_multiple().unwrap()),
+            vec![
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0x5555_5555,
+                isqrt(x),
+                x / 10,
+                x % 10,
+                quotient.to_canonical_u64(),
+                1000
+            ]
+        );
+    }
+}
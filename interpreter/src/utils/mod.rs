@@ -1 +1,2 @@
+pub mod builtins;
 pub mod number;
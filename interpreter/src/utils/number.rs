@@ -1,12 +1,19 @@
+use crate::error::InterpreterError;
 use crate::lexer::token::Token;
-use crate::utils::number::Number::{Bool, Felt, Nil, I32};
+use crate::utils::number::Number::{Bool, Felt, Nil, I32, U32, U64};
 use crate::utils::number::NumberRet::{Multiple, Single};
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::{Field, Field64, PrimeField64};
 use regex::Regex;
 use std::cmp::Ordering;
 use std::ops;
 use std::ops::Not;
 use std::str::FromStr;
 
+// `+`, `-` and `*` on felts are Goldilocks field operations; `/` and `%` are
+// the integer quotient and remainder of the canonical representatives, which
+// is what compiled prophets such as `split_hi` rely on. Field division is
+// `Number::field_div`.
 #[macro_export]
 macro_rules! number_binop {
     ($v: expr, $op: tt, $rhs: ident, $op_desc: tt) => {
@@ -15,49 +22,96 @@ macro_rules! number_binop {
             I32(left) => match $rhs {
                 Nil => $v,
                 I32(right) => I32(left $op right),
-                Felt(right) => Felt(left as i128 $op right),
+                Felt(right) => Felt(felt_from_i32(left) $op right),
                 _ => panic!("{} not use bool", $op_desc),
             },
             Felt(left) => match $rhs {
                 Nil => $v,
-                I32(right) => Felt(left $op right as i128),
+                I32(right) => Felt(left $op felt_from_i32(right)),
                 Felt(right) => Felt(left $op right),
                 _ => panic!("{} not use bool", $op_desc),
             },
             _ => panic!("{} not use bool", $op_desc),
         }
     };
+    ($v: expr, $op: tt, $rhs: ident, $op_desc: tt, integer $int_op: ident) => {
+        match $v {
+            Nil => $rhs,
+            I32(left) => match $rhs {
+                Nil => $v,
+                I32(right) => I32(left $op right),
+                Felt(right) => Felt(felt_integer_op(felt_from_i32(left), right, u64::$int_op)),
+                _ => panic!("{} not use bool", $op_desc),
+            },
+            Felt(left) => match $rhs {
+                Nil => $v,
+                I32(right) => Felt(felt_integer_op(left, felt_from_i32(right), u64::$int_op)),
+                Felt(right) => Felt(felt_integer_op(left, right, u64::$int_op)),
+                _ => panic!("{} not use bool", $op_desc),
+            },
+            _ => panic!("{} not use bool", $op_desc),
+        }
+    };
     ($v: expr, $op: tt, $rhs: tt,  $op_desc: tt, $cmp: tt) => {
         match $v {
             Nil => match $rhs {
                 Nil => 0 $op 0,
-                I32(_) => 0 $op 1 ,
-                Felt(_) => 0 $op 1,
+                I32(_) | Felt(_) | U32(_) | U64(_) => 0 $op 1,
                 _ => panic!("{} not use bool",  $op_desc),
             },
             I32(left) => match $rhs {
                 Nil => 0 $op 1,
                 I32(right) => left $op right,
-                Felt(right) => (*left as i128) $op *right,
+                Felt(right) => (*left as i128) $op (right.to_canonical_u64() as i128),
+                U32(_) | U64(_) => (*left as i128) $op $rhs.integer_value(),
                 _ => panic!("{} not use bool",  $op_desc),
             },
             Felt(left) => match $rhs {
                 Nil => 0 $op 1,
-                I32(right) => *left $op (*right as i128),
-                Felt(right) => *left $op *right,
+                I32(right) => (left.to_canonical_u64() as i128) $op (*right as i128),
+                Felt(right) => left.to_canonical_u64() $op right.to_canonical_u64(),
+                U32(_) | U64(_) => (left.to_canonical_u64() as i128) $op $rhs.integer_value(),
                 _ => panic!("{} not use bool", $op_desc),
             },
+            U32(_) | U64(_) => match $rhs {
+                Nil => 0 $op 1,
+                Bool(_) => panic!("{} not use bool", $op_desc),
+                _ => $v.integer_value() $op $rhs.integer_value(),
+            },
             _ => panic!("{} not use bool", $op_desc),
         }
     };
 }
 
+pub fn felt_from_i32(value: i32) -> GoldilocksField {
+    if value >= 0 {
+        GoldilocksField::from_canonical_u64(value as u64)
+    } else {
+        -GoldilocksField::from_canonical_u64(value.unsigned_abs() as u64)
+    }
+}
+
+fn felt_from_i128(value: i128) -> GoldilocksField {
+    let reduced = value.rem_euclid(GoldilocksField::ORDER as i128);
+    GoldilocksField::from_canonical_u64(reduced as u64)
+}
+
+fn felt_integer_op(
+    left: GoldilocksField,
+    right: GoldilocksField,
+    op: fn(u64, u64) -> u64,
+) -> GoldilocksField {
+    GoldilocksField::from_canonical_u64(op(left.to_canonical_u64(), right.to_canonical_u64()))
+}
+
 #[derive(Debug, Clone)]
 pub enum Number {
     Nil,
     I32(i32),
-    Felt(i128),
+    Felt(GoldilocksField),
     Bool(bool),
+    U32(u32),
+    U64(u64),
 }
 
 #[derive(Debug, Clone)]
@@ -67,50 +121,72 @@ pub enum NumberRet {
 }
 
 impl NumberRet {
-    pub fn get_single(self) -> Number {
+    pub fn get_single(self) -> Result<Number, String> {
         match self {
-            Single(value) => value,
-            Multiple(values) => {
+            Single(value) => Ok(value),
+            Multiple(mut values) => {
                 if values.len() == 1 {
-                    return values.get(0).unwrap().clone();
+                    Ok(values.remove(0))
                 } else {
-                    panic!("binop not support multi value")
+                    Err(format!(
+                        "expected a single value, got {} values",
+                        values.len()
+                    ))
                 }
             }
         }
     }
 
-    pub fn get_multiple(self) -> Vec<Number> {
+    pub fn get_multiple(self) -> Result<Vec<Number>, String> {
         match self {
-            Single(_) => panic!("is single value"),
-            Multiple(values) => values,
+            Single(value) => Err(format!("expected multiple values, got {:?}", value)),
+            Multiple(values) => Ok(values),
         }
     }
 }
 
-pub type NumberResult = Result<NumberRet, String>;
+pub type NumberResult = Result<NumberRet, InterpreterError>;
 
-fn convert(text: &str) -> Number {
-    let reg = Regex::new(r"^I32\((?P<u32>[-+]?\d+)\)|^Felt\((?P<felt>[-+]?\d+)\)").unwrap();
+fn convert(text: &str) -> Result<Number, String> {
+    let reg = Regex::new(
+        r"^I32\((?P<u32>[-+]?\d+)\)|^Felt\((?P<felt>[-+]?\d+)\)|^U32\((?P<uint32>\d+)\)|^U64\((?P<uint64>\d+)\)",
+    )
+    .map_err(|err| err.to_string())?;
 
-    let cap = reg.captures(text).unwrap();
+    let cap = reg
+        .captures(text)
+        .ok_or(format!("invalid number {}", text))?;
     let int_as_str = cap.name("u32").map_or("", |m| m.as_str());
     let felt_as_str = cap.name("felt").map_or("", |m| m.as_str());
+    if let Some(value) = cap.name("uint32") {
+        return value
+            .as_str()
+            .parse()
+            .map(U32)
+            .map_err(|err| err.to_string());
+    }
+    if let Some(value) = cap.name("uint64") {
+        return value
+            .as_str()
+            .parse()
+            .map(U64)
+            .map_err(|err| err.to_string());
+    }
     if !int_as_str.is_empty() {
-        let value = int_as_str.parse::<i32>().unwrap();
-        I32(value)
+        let value = int_as_str.parse::<i32>().map_err(|err| err.to_string())?;
+        Ok(I32(value))
     } else if !felt_as_str.is_empty() {
-        let value = felt_as_str.parse::<i128>().unwrap();
-        Felt(value)
+        let value = felt_as_str.parse::<i128>().map_err(|err| err.to_string())?;
+        Ok(Felt(felt_from_i128(value)))
     } else {
-        Nil
+        Ok(Nil)
     }
 }
 
 impl FromStr for Number {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(convert(s))
+        convert(s)
     }
 }
 
@@ -119,8 +195,10 @@ impl ToString for Number {
         match self {
             Nil => "Nil".into(),
             I32(value) => format!("I32({})", value),
-            Felt(value) => format!("Felt({})", value),
+            Felt(value) => format!("Felt({})", value.to_canonical_u64()),
             Bool(value) => format!("Bool({})", value),
+            U32(value) => format!("U32({})", value),
+            U64(value) => format!("U64({})", value),
         }
     }
 }
@@ -132,8 +210,188 @@ impl ops::Neg for Number {
         match self {
             Nil => Nil,
             I32(value) => I32(-value),
-            Felt(value) => Felt(value.not()),
+            Felt(value) => Felt(-value),
             Bool(value) => Bool(value.not()),
+            // rejected by `check_neg`
+            U32(value) => U32(value.wrapping_neg()),
+            U64(value) => U64(value.wrapping_neg()),
+        }
+    }
+}
+
+impl Number {
+    /// Rejects the operands `number_binop!` and `-` panic on: booleans
+    /// outside `&&` and `||`, division by zero and `I32` overflow.
+    pub fn check_binop(&self, operator: &Token, rhs: &Number) -> Result<(), String> {
+        if *operator == Token::And || *operator == Token::Or {
+            return Ok(());
+        }
+        if matches!(self, Bool(_)) || matches!(rhs, Bool(_)) {
+            return Err(format!("operator {} does not take bool operands", operator));
+        }
+        let divides = *operator == Token::IntegerDivision || *operator == Token::Mod;
+        let zero = match rhs {
+            I32(value) => *value == 0,
+            Felt(value) => value.to_canonical_u64() == 0,
+            U32(value) => *value == 0,
+            U64(value) => *value == 0,
+            _ => false,
+        };
+        if divides && zero {
+            return Err(format!("operator {} by zero", operator));
+        }
+        if let (I32(left), I32(right)) = (self, rhs) {
+            let value = match operator {
+                Token::Plus => left.checked_add(*right),
+                Token::Minus => left.checked_sub(*right),
+                Token::Multiply => left.checked_mul(*right),
+                Token::IntegerDivision => left.checked_div(*right),
+                Token::Mod => left.checked_rem(*right),
+                _ => Some(0),
+            };
+            if value.is_none() {
+                return Err(format!("i32 overflow in {} {} {}", left, operator, right));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check_neg(&self) -> Result<(), String> {
+        match self {
+            I32(i32::MIN) => Err(format!("i32 overflow in -({})", i32::MIN)),
+            U32(_) | U64(_) => Err(format!("cannot negate unsigned {}", self.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self, U32(_) | U64(_))
+    }
+
+    // Exact value for comparisons across types, booleans and nil are zero.
+    fn integer_value(&self) -> i128 {
+        match self {
+            I32(value) => *value as i128,
+            Felt(value) => value.to_canonical_u64() as i128,
+            U32(value) => *value as i128,
+            U64(value) => *value as i128,
+            Bool(_) | Nil => 0,
+        }
+    }
+
+    /// The value as an unsigned integer no larger than `max`, an `I32` or
+    /// felt that does not fit is an error.
+    fn unsigned_value(&self, max: u64, type_name: &str) -> Result<u64, String> {
+        let value = match self {
+            I32(value) => u64::try_from(*value).ok(),
+            Felt(value) => Some(value.to_canonical_u64()),
+            U32(value) => Some(*value as u64),
+            U64(value) => Some(*value),
+            Bool(value) => Some(*value as u64),
+            Nil => return Err("Nil has no value".to_string()),
+        };
+        value.filter(|value| *value <= max).ok_or(format!(
+            "{} out of {} range",
+            self.to_string(),
+            type_name
+        ))
+    }
+
+    /// `u32` and `u64` arithmetic, bitwise operators and shifts. The other
+    /// operand takes the unsigned type, an `i32` or felt that does not fit
+    /// is an error, as is mixing `u32` with `u64`. `+`, `-` and `*` fail on
+    /// overflow instead of wrapping. `&`, `|` and `^` need an unsigned
+    /// operand, `<<` and `>>` an unsigned left operand and an amount below
+    /// its bit width; bits shifted out are dropped.
+    pub fn unsigned_binop(&self, operator: &Token, rhs: &Number) -> Result<Number, String> {
+        if *operator == Token::ShiftLeft || *operator == Token::ShiftRight {
+            let (bits, max) = match self {
+                U32(_) => (32, u32::MAX as u64),
+                U64(_) => (64, u64::MAX),
+                _ => {
+                    return Err(format!(
+                        "operator {} needs a u32 or u64 left operand, got {}",
+                        operator,
+                        self.to_string()
+                    ))
+                }
+            };
+            let value = self.unsigned_value(max, "shift")?;
+            let amount = rhs.unsigned_value(u64::MAX, "shift amount")?;
+            if amount >= bits {
+                return Err(format!(
+                    "shift by {} out of range for {} bits",
+                    amount, bits
+                ));
+            }
+            let value = if *operator == Token::ShiftLeft {
+                (value << amount) & max
+            } else {
+                value >> amount
+            };
+            return Ok(Self::unsigned(bits, value));
+        }
+
+        let (bits, max, type_name) = match (self, rhs) {
+            (U32(_), U64(_)) | (U64(_), U32(_)) => {
+                return Err(format!("operator {} mixes u32 and u64 operands", operator))
+            }
+            (U32(_), _) | (_, U32(_)) => (32, u32::MAX as u64, "u32"),
+            (U64(_), _) | (_, U64(_)) => (64, u64::MAX, "u64"),
+            _ => return Err(format!("operator {} needs a u32 or u64 operand", operator)),
+        };
+        let left = self.unsigned_value(max, type_name)?;
+        let right = rhs.unsigned_value(max, type_name)?;
+        let value = match operator {
+            Token::Plus => left.checked_add(right),
+            Token::Minus => left.checked_sub(right),
+            Token::Multiply => left.checked_mul(right),
+            Token::IntegerDivision => left.checked_div(right),
+            Token::Mod => left.checked_rem(right),
+            Token::BitAnd => Some(left & right),
+            Token::BitOr => Some(left | right),
+            Token::BitXor => Some(left ^ right),
+            _ => return Err(format!("Unknown operator found: {}", operator)),
+        }
+        .filter(|value| *value <= max)
+        .ok_or(format!(
+            "{} overflow in {} {} {}",
+            type_name, left, operator, right
+        ))?;
+        Ok(Self::unsigned(bits, value))
+    }
+
+    fn unsigned(bits: u64, value: u64) -> Number {
+        if bits == 32 {
+            U32(value as u32)
+        } else {
+            U64(value)
+        }
+    }
+
+    pub fn bit_not(&self) -> Result<Number, String> {
+        match self {
+            U32(value) => Ok(U32(!value)),
+            U64(value) => Ok(U64(!value)),
+            _ => Err(format!(
+                "operator ~ needs a u32 or u64 operand, got {}",
+                self.to_string()
+            )),
+        }
+    }
+
+    /// `as` conversion. Values that do not fit the target type are errors,
+    /// nothing is truncated; negative `I32` values become their field
+    /// negation as felts, as in felt arithmetic.
+    pub fn cast(&self, target: &Token) -> Result<Number, String> {
+        match target {
+            Token::Felt => self.to_felt().map(Felt),
+            Token::I32 => i32::try_from(self.integer_value())
+                .map(I32)
+                .map_err(|_| format!("{} out of i32 range", self.to_string())),
+            Token::U32 => Ok(U32(self.unsigned_value(u32::MAX as u64, "u32")? as u32)),
+            Token::U64 => Ok(U64(self.unsigned_value(u64::MAX, "u64")?)),
+            _ => Err(format!("cannot cast to {}", target)),
         }
     }
 }
@@ -162,14 +420,14 @@ impl ops::Sub for Number {
 impl ops::Div for Number {
     type Output = Number;
     fn div(self, rhs: Number) -> Number {
-        number_binop!(self, /, rhs, "divide")
+        number_binop!(self, /, rhs, "divide", integer div)
     }
 }
 
 impl ops::Rem for Number {
     type Output = Number;
     fn rem(self, rhs: Number) -> Number {
-        number_binop!(self, %, rhs, "rem")
+        number_binop!(self, %, rhs, "rem", integer rem)
     }
 }
 
@@ -213,7 +471,13 @@ impl From<i32> for Number {
 
 impl From<u64> for Number {
     fn from(num: u64) -> Self {
-        Felt(num as i128)
+        Felt(GoldilocksField::from_noncanonical_u64(num))
+    }
+}
+
+impl From<GoldilocksField> for Number {
+    fn from(num: GoldilocksField) -> Self {
+        Felt(num)
     }
 }
 
@@ -221,58 +485,184 @@ impl From<&Token> for Number {
     fn from(token: &Token) -> Self {
         match token {
             Token::I32 => I32(0),
-            Token::Felt => Felt(0),
+            Token::Felt => Felt(GoldilocksField::ZERO),
+            Token::U32 => U32(0),
+            Token::U64 => U64(0),
             Token::Array(token, len) => number_from_token(token, *len),
-            _ => panic!("not support token to Number:{}", token),
+            // not a type, left for the callers to reject
+            _ => Nil,
         }
     }
 }
 
 pub fn number_from_token(token: &Token, len: usize) -> Number {
     match token {
-        Token::Felt => Number::Felt(len as i128),
+        Token::Felt => Number::Felt(GoldilocksField::from_canonical_usize(len)),
         Token::I32 => Number::I32(len as i32),
-        _ => panic!("wrong type"),
+        Token::U32 => Number::U32(len as u32),
+        Token::U64 => Number::U64(len as u64),
+        _ => Nil,
     }
 }
 
 impl Number {
-    pub fn number_type(&self) -> Token {
+    pub fn number_type(&self) -> Result<Token, String> {
         match self {
-            Felt(_) => Token::Felt,
-            I32(_) => Token::I32,
-            Bool(_) => Token::I32,
-            Nil => panic!("wrong type"),
+            Felt(_) => Ok(Token::Felt),
+            I32(_) => Ok(Token::I32),
+            Bool(_) => Ok(Token::I32),
+            U32(_) => Ok(Token::U32),
+            U64(_) => Ok(Token::U64),
+            Nil => Err("Nil has no type".to_string()),
         }
     }
 
-    pub fn binop_number_type(&self, rhs: &Number) -> Token {
+    pub fn binop_number_type(&self, rhs: &Number) -> Result<Token, String> {
+        match (self, rhs) {
+            (U32(_), U64(_)) | (U64(_), U32(_)) => {
+                return Err("u32 op u64 not support, cast one side".to_string())
+            }
+            (U32(_) | U64(_), Bool(_) | Nil) | (Bool(_) | Nil, U32(_) | U64(_)) => {
+                return Err(format!("{:?} op {:?} not support", self, rhs))
+            }
+            (U32(_), _) | (_, U32(_)) => return Ok(Token::U32),
+            (U64(_), _) | (_, U64(_)) => return Ok(Token::U64),
+            _ => {}
+        }
         match self {
             Felt(_) => match rhs {
-                I32(_) => Token::Felt,
-                Felt(_) => Token::Felt,
-                _ => panic!("felt op {:?} not support", rhs),
+                I32(_) => Ok(Token::Felt),
+                Felt(_) => Ok(Token::Felt),
+                _ => Err(format!("felt op {:?} not support", rhs)),
             },
             I32(_) => match rhs {
-                I32(_) => Token::I32,
-                Felt(_) => Token::Felt,
-                _ => panic!("i32 op {:?} not support", rhs),
+                I32(_) => Ok(Token::I32),
+                Felt(_) => Ok(Token::Felt),
+                _ => Err(format!("i32 op {:?} not support", rhs)),
             },
             Bool(_) => match rhs {
-                Bool(_) => Token::Felt,
-                _ => panic!("bool op {:?} not support", rhs),
+                Bool(_) => Ok(Token::Felt),
+                _ => Err(format!("bool op {:?} not support", rhs)),
             },
-            Nil => panic!("Nil not support"),
+            Nil => Err("Nil not support".to_string()),
+            // handled above
+            U32(_) | U64(_) => Err(format!("{:?} op {:?} not support", self, rhs)),
         }
     }
 
-    pub fn get_number(&self) -> usize {
-        let value = match self {
-            Felt(num) => *num as usize,
-            I32(num) => *num as usize,
-            Bool(num) => *num as usize,
-            Nil => panic!("wrong type"),
-        };
-        value
+    pub fn get_number(&self) -> Result<usize, String> {
+        match self {
+            Felt(num) => Ok(num.to_canonical_u64() as usize),
+            I32(num) => Ok(*num as usize),
+            Bool(num) => Ok(*num as usize),
+            U32(num) => Ok(*num as usize),
+            U64(_) => Ok(self.to_felt()?.to_canonical_u64() as usize),
+            Nil => Err("Nil has no value".to_string()),
+        }
+    }
+
+    pub fn to_felt(&self) -> Result<GoldilocksField, String> {
+        match self {
+            Felt(num) => Ok(*num),
+            I32(num) => Ok(felt_from_i32(*num)),
+            Bool(num) => Ok(GoldilocksField::from_bool(*num)),
+            U32(num) => Ok(GoldilocksField::from_canonical_u32(*num)),
+            U64(num) if *num < GoldilocksField::ORDER => {
+                Ok(GoldilocksField::from_canonical_u64(*num))
+            }
+            U64(num) => Err(format!("u64 value {} does not fit a felt", num)),
+            Nil => Err("Nil has no felt value".to_string()),
+        }
+    }
+
+    /// Canonical value in `[0, FIELD_ORDER)`. Negative `I32` values are
+    /// rejected instead of being wrapped into the field.
+    pub fn to_u64(&self) -> Result<u64, String> {
+        match self {
+            I32(num) if *num < 0 => Err(format!("negative value {} is not a u64", num)),
+            _ => Ok(self.to_felt()?.to_canonical_u64()),
+        }
+    }
+
+    pub fn to_u32(&self) -> Result<u32, String> {
+        let value = self.to_u64()?;
+        u32::try_from(value).map_err(|_| format!("value {} out of u32 range", value))
+    }
+
+    pub fn from_u32(value: u32) -> Number {
+        Felt(GoldilocksField::from_canonical_u32(value))
+    }
+
+    pub fn inverse(&self) -> Result<Number, String> {
+        self.to_felt()?
+            .try_inverse()
+            .map(Felt)
+            .ok_or("felt zero has no inverse".to_string())
+    }
+
+    pub fn field_div(&self, rhs: &Number) -> Result<Number, String> {
+        Ok(Felt(self.to_felt()? * rhs.inverse()?.to_felt()?))
+    }
+
+    pub fn exp(&self, power: u64) -> Result<Number, String> {
+        Ok(Felt(self.to_felt()?.exp_u64(power)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::number::Number;
+    use crate::utils::number::Number::{Felt, I32};
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::{Field, Field64};
+
+    #[test]
+    fn test_felt_field_arithmetic() {
+        let p_minus_one = Number::from(GoldilocksField::ORDER - 1);
+        assert_eq!((p_minus_one.clone() + Number::from(2u64)).to_u64(), Ok(1));
+        assert_eq!(
+            (Number::from(0u64) - Number::from(1u64)).to_u64(),
+            Ok(GoldilocksField::ORDER - 1)
+        );
+        assert_eq!((p_minus_one.clone() * p_minus_one.clone()).to_u64(), Ok(1));
+        assert_eq!(
+            (-Number::from(5u64)).to_u64(),
+            Ok(GoldilocksField::ORDER - 5)
+        );
+        assert_eq!(
+            (Number::from(3u64) + I32(-4)).to_u64(),
+            p_minus_one.to_u64()
+        );
+
+        let big = Number::from(0x1234_5678_9abc_def0u64);
+        assert_eq!(
+            (big.clone() / Number::from(4294967296u64)).to_u64(),
+            Ok(0x1234_5678)
+        );
+        assert_eq!(
+            (big.clone() % Number::from(4294967296u64)).to_u64(),
+            Ok(0x9abc_def0)
+        );
+
+        let seven = Number::from(7u64);
+        let inv = seven.inverse().unwrap();
+        assert_eq!((inv * seven.clone()).to_u64(), Ok(1));
+        let q = Number::from(5u64).field_div(&seven).unwrap();
+        assert_eq!((q * seven.clone()).to_u64(), Ok(5));
+        assert!(Number::from(0u64).inverse().is_err());
+        assert_eq!(
+            Number::from(2u64).exp(64).unwrap().to_u64(),
+            Ok(0xffff_ffff)
+        );
+
+        assert_eq!(Number::from(u32::MAX as u64).to_u32(), Ok(u32::MAX));
+        assert!(Number::from(u32::MAX as u64 + 1).to_u32().is_err());
+        assert!(I32(-1).to_u64().is_err());
+        assert!(Number::from(GoldilocksField::ORDER - 1) > Number::from(1u64));
+        assert_eq!(
+            "Felt(-1)".parse::<Number>().unwrap().to_u64(),
+            Ok(GoldilocksField::ORDER - 1)
+        );
+        assert!(matches!(Number::from(GoldilocksField::NEG_ONE), Felt(_)));
     }
 }
@@ -15,6 +15,7 @@ pub fn node_derive(input: TokenStream) -> TokenStream {
         "ArrayNumNode" => quote!(travel.travel_array(self)),
         "BinOpNode" => quote!(travel.travel_binop(self)),
         "UnaryOpNode" => quote!(travel.travel_unary_op(self)),
+        "CastNode" => quote!(travel.travel_cast(self)),
         "IdentNode" => quote!(travel.travel_ident(self)),
         "IdentIndexNode" => quote!(travel.travel_ident_index(self)),
         "ContextIdentNode" => quote!(travel.travel_context_ident(self)),
@@ -27,6 +28,8 @@ pub fn node_derive(input: TokenStream) -> TokenStream {
         "CompoundNode" => quote!(travel.travel_compound(self)),
         "CondStatNode" => quote!(travel.travel_cond(self)),
         "LoopStatNode" => quote!(travel.travel_loop(self)),
+        "ForStatNode" => quote!(travel.travel_for(self)),
+        "LoopCtlNode" => quote!(travel.travel_loop_ctl(self)),
         "EntryNode" => quote!(travel.travel_entry(self)),
         "FunctionNode" => quote!(travel.travel_function(self)),
         "CallNode" => quote!(travel.travel_call(self)),
@@ -35,6 +38,7 @@ pub fn node_derive(input: TokenStream) -> TokenStream {
         "MultiAssignNode" => quote!(travel.travel_multi_assign(self)),
         "MallocNode" => quote!(travel.travel_malloc(self)),
         "PrintfNode" => quote!(travel.travel_printf(self)),
+        "AssertNode" => quote!(travel.travel_assert(self)),
         _ => panic!(""),
     };
 
@@ -46,6 +50,12 @@ pub fn node_derive(input: TokenStream) -> TokenStream {
             fn as_any_mut(&mut self) -> &mut dyn Any {
                 self
             }
+            fn pos(&self) -> SourcePos {
+                self.pos
+            }
+            fn set_pos(&mut self, pos: SourcePos) {
+                self.pos = pos;
+            }
             fn traverse(&mut self, travel: &mut dyn Traversal) -> NumberResult {
                 #quot
             }
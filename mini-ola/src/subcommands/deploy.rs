@@ -1,5 +1,5 @@
 use core::{
-    program::binary_program::BinaryProgram,
+    program::{binary_program::BinaryProgram, compact::CompactProgram, verifier::verify_program},
     state::utils::get_prog_hash_cf_key_from_contract_addr,
     storage::db::{Database, RocksDB, SequencerColumnFamily},
     types::{storage::u8_arr_to_field_arr, Field, GoldilocksField},
@@ -8,7 +8,6 @@ use std::{fs::File, path::PathBuf};
 
 use anyhow::Ok;
 use clap::Parser;
-use plonky2::hash::utils::poseidon_hash_bytes;
 use rand::{thread_rng, Rng};
 use rocksdb::WriteBatch;
 
@@ -30,8 +29,13 @@ pub struct Deploy {
 impl Deploy {
     pub fn run(self) -> anyhow::Result<()> {
         let program: BinaryProgram = serde_json::from_reader(File::open(self.contract)?)?;
-        let program_bytes = bincode::serialize(&program)?;
-        let program_hash = poseidon_hash_bytes(program_bytes.as_ref()).to_vec();
+        let compact = CompactProgram::from_binary_program(&program)?;
+        if let Err(violations) = verify_program(&compact) {
+            let report: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+            anyhow::bail!("Program verification failed: {}", report.join("\n"))
+        }
+        let program_bytes = compact.encode();
+        let program_hash = compact.hash().to_vec();
 
         let target_address: [u8; 32] = if let Some(addr) = self.address {
             address_from_hex_be(addr.as_str()).unwrap()
@@ -75,3 +79,36 @@ impl Deploy {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::{program::binary_program::BinaryProgram, vm::opcodes::OlaOpcode};
+    use std::{fs::File, path::PathBuf};
+
+    use super::Deploy;
+
+    #[test]
+    fn test_deploy_rejects_malformed_program() {
+        let dir = std::env::temp_dir().join(format!("mini_ola_deploy_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // a lone ret never reaches an end instruction
+        let program = BinaryProgram {
+            bytecode: format!("0x{:0>16x}", OlaOpcode::RET.binary_bit_mask()),
+            prophets: vec![],
+            debug_info: None,
+        };
+        let contract: PathBuf = dir.join("contract.json");
+        serde_json::to_writer(File::create(&contract).unwrap(), &program).unwrap();
+
+        let db = dir.join("db");
+        let deploy = Deploy {
+            db: Some(db.clone()),
+            address: None,
+            contract,
+        };
+        let err = deploy.run().unwrap_err();
+        assert!(err.to_string().contains("no end instruction"), "{}", err);
+        assert!(!db.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,9 @@
 use clap::{CommandFactory, Parser, Subcommand};
 use colored::Colorize;
-use subcommands::{call::Call, deploy::Deploy, deploy_sys::DeploySys, invoke::Invoke};
+use subcommands::{
+    call::Call, deploy::Deploy, deploy_sys::DeploySys, disasm::Disasm, invoke::Invoke,
+    prophet_check::ProphetCheck,
+};
 
 mod subcommands;
 mod utils;
@@ -24,6 +27,10 @@ enum Subcommands {
     Invoke(Invoke),
     #[clap(about = "Make a state query.")]
     Call(Call),
+    #[clap(about = "Disassemble a contract binary into re-assemblable asm.")]
+    Disasm(Disasm),
+    #[clap(about = "Report prophet outputs a contract uses before checking them.")]
+    ProphetCheck(ProphetCheck),
 }
 
 fn main() {
@@ -45,6 +52,8 @@ fn run_command(cli: Cli) -> anyhow::Result<()> {
             Subcommands::Deploy(cmd) => cmd.run(),
             Subcommands::Invoke(cmd) => cmd.run(),
             Subcommands::Call(cmd) => cmd.run(),
+            Subcommands::Disasm(cmd) => cmd.run(),
+            Subcommands::ProphetCheck(cmd) => cmd.run(),
         },
     }
 }
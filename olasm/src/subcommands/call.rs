@@ -120,6 +120,13 @@ impl Call {
         let mut tx_exe_manager: TxExeManager =
             TxExeManager::new(ExecuteMode::Debug, block_info, tx, &mut storage, to, 0);
         let result = tx_exe_manager.call()?;
+        let prophet_logs = tx_exe_manager.prophet_logs();
+        if !prophet_logs.is_empty() {
+            println!("Prophet logs:");
+            for log in prophet_logs {
+                println!("{}", log);
+            }
+        }
         println!("Call success with result: {:?}", result);
         Ok(())
     }
@@ -0,0 +1,51 @@
+use std::{collections::BTreeMap, fs::File, path::PathBuf};
+
+use clap::Parser;
+use core::program::{binary_program::BinaryProgram, decoder::disassemble_binary_program};
+
+use crate::utils::ExpandedPathbufParser;
+
+#[derive(Debug, Parser)]
+pub struct Disasm {
+    #[clap(
+        long,
+        help = "Path of the debug info (pc to source asm map) to recover label names"
+    )]
+    debug: Option<PathBuf>,
+    #[clap(
+        long,
+        help = "Path to write the disassembled asm json, print to stdout if absent"
+    )]
+    output: Option<PathBuf>,
+    #[clap(long, help = "Only print the asm text without the prophets")]
+    asm_only: bool,
+    #[clap(
+        value_parser = ExpandedPathbufParser,
+        help = "Path to contract binary file"
+    )]
+    contract: PathBuf,
+}
+
+impl Disasm {
+    pub fn run(self) -> anyhow::Result<()> {
+        let program_file = File::open(self.contract)?;
+        let mut program: BinaryProgram = serde_json::from_reader(program_file)?;
+        if let Some(debug_path) = self.debug {
+            let debug_file = File::open(debug_path)?;
+            let debug_info: BTreeMap<usize, String> = serde_json::from_reader(debug_file)?;
+            program.debug_info = Some(debug_info);
+        }
+
+        let disassembled = disassemble_binary_program(program).map_err(|e| anyhow::anyhow!(e))?;
+        let out = if self.asm_only {
+            disassembled.program
+        } else {
+            serde_json::to_string_pretty(&disassembled)?
+        };
+        match self.output {
+            Some(path) => std::fs::write(path, out)?,
+            None => println!("{}", out),
+        }
+        Ok(())
+    }
+}
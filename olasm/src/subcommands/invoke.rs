@@ -164,6 +164,12 @@ impl Invoke {
                 println!("{:?}", event);
             }
         }
+        if !result.prophet_logs.is_empty() {
+            println!("Prophet logs:");
+            for log in result.prophet_logs {
+                println!("{}", log);
+            }
+        }
         Ok(())
     }
 }
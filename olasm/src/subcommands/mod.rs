@@ -1,5 +1,7 @@
 pub mod call;
 pub mod deploy;
 pub mod deploy_sys;
+pub mod disasm;
 pub mod invoke;
 pub mod parser;
+pub mod prophet_check;
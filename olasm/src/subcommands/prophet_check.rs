@@ -0,0 +1,30 @@
+use std::{fs::File, path::PathBuf};
+
+use clap::Parser;
+use core::program::{binary_program::BinaryProgram, prophet_check::check_binary_prophet_outputs};
+
+use crate::utils::ExpandedPathbufParser;
+
+#[derive(Debug, Parser)]
+pub struct ProphetCheck {
+    #[clap(
+        value_parser = ExpandedPathbufParser,
+        help = "Path to contract binary file"
+    )]
+    contract: PathBuf,
+}
+
+impl ProphetCheck {
+    pub fn run(self) -> anyhow::Result<()> {
+        let program_file = File::open(self.contract)?;
+        let program: BinaryProgram = serde_json::from_reader(program_file)?;
+        let warnings = check_binary_prophet_outputs(&program).map_err(|e| anyhow::anyhow!(e))?;
+        if warnings.is_empty() {
+            println!("No unchecked prophet outputs.");
+        }
+        for warning in warnings {
+            println!("warning: {}", warning);
+        }
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@ use plonky2_field::packed::PackedField;
 use plonky2_field::polynomial::{PolynomialCoeffs, PolynomialValues};
 use plonky2_field::types::Field;
 use plonky2_util::{log2_strict, reverse_index_bits_in_place};
+use serde::{Deserialize, Serialize};
 
 use crate::fri::proof::FriProof;
 use crate::fri::prover::fri_proof;
@@ -28,6 +29,8 @@ pub const SALT_SIZE: usize = 4;
 
 /// Represents a FRI oracle, i.e. a batch of polynomials which have been
 /// Merklized.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PolynomialBatch<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
 {
     pub polynomials: Vec<PolynomialCoeffs<F>>,
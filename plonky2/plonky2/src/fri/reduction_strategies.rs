@@ -56,18 +56,34 @@ impl FriReductionStrategy {
             }
         }
     }
+
+    /// An upper bound on the arity bits of any reduction step, whatever the
+    /// degree.
+    pub fn max_arity_bits(&self) -> usize {
+        match self {
+            FriReductionStrategy::Fixed(reduction_arity_bits) => {
+                reduction_arity_bits.iter().copied().max().unwrap_or(0)
+            }
+            &FriReductionStrategy::ConstantArityBits(arity_bits, _) => arity_bits,
+            FriReductionStrategy::MinSize(opt_max_arity_bits) => {
+                opt_max_arity_bits.unwrap_or(DEFAULT_MAX_ARITY_BITS)
+            }
+        }
+    }
 }
 
+/// 2^4 is the largest arity we see in optimal reduction sequences in practice.
+/// For 2^5 to occur in an optimal sequence, we would need a really massive
+/// polynomial.
+const DEFAULT_MAX_ARITY_BITS: usize = 4;
+
 fn min_size_arity_bits(
     degree_bits: usize,
     rate_bits: usize,
     num_queries: usize,
     opt_max_arity_bits: Option<usize>,
 ) -> Vec<usize> {
-    // 2^4 is the largest arity we see in optimal reduction sequences in practice.
-    // For 2^5 to occur in an optimal sequence, we would need a really massive
-    // polynomial.
-    let max_arity_bits = opt_max_arity_bits.unwrap_or(4);
+    let max_arity_bits = opt_max_arity_bits.unwrap_or(DEFAULT_MAX_ARITY_BITS);
 
     let start = Instant::now();
     let (mut arity_bits, fri_proof_size) =